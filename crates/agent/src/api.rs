@@ -8,7 +8,7 @@ use anyhow::bail;
 use chrono::Utc;
 use futures::StreamExt;
 use http::StatusCode;
-use reqwest::IntoUrl;
+use reqwest::{IntoUrl, Response};
 use sha2::{Digest, Sha256};
 use snops_common::{
     binaries::{BinaryEntry, BinarySource},
@@ -17,7 +17,8 @@ use snops_common::{
     util::sha256_file,
 };
 use tokio::{fs::File, io::AsyncWriteExt};
-use tracing::info;
+use tracing::{info, warn};
+use url::Url;
 
 use crate::transfers::{self, TransferTx};
 
@@ -31,8 +32,9 @@ pub async fn download_file(
     to: impl AsRef<Path>,
     transfer_tx: TransferTx,
 ) -> anyhow::Result<Option<(File, String, u64)>> {
+    let url = url.into_url()?;
     let desc = url.as_str().to_owned();
-    let req = client.get(url).send().await?;
+    let req = send_with_fallback(client, url).await?;
     if req.status() == StatusCode::NOT_FOUND {
         return Ok(None);
     }
@@ -99,6 +101,41 @@ pub async fn download_file(
     Ok(Some((file, sha256, downloaded)))
 }
 
+/// Send a download request. When the control plane redirected the request to
+/// a peer agent that could not serve the file, the file is requested from the
+/// control plane directly.
+async fn send_with_fallback(client: &reqwest::Client, url: Url) -> reqwest::Result<Response> {
+    let res = client.get(url.clone()).send().await;
+
+    let failed_at = match &res {
+        Ok(res) if res.status().is_server_error() => Some(res.url().clone()),
+        Ok(_) => None,
+        Err(e) => e.url().cloned(),
+    };
+    match failed_at {
+        Some(at) if is_peer_redirect(&url, &at) => {
+            warn!("peer {at} failed to serve {url}, downloading from the control plane");
+            client.get(with_direct_query(url)).send().await
+        }
+        _ => res,
+    }
+}
+
+/// Whether a request identified by the agent query was redirected away from
+/// the host it was sent to
+fn is_peer_redirect(url: &Url, at: &Url) -> bool {
+    url.query_pairs().any(|(k, _)| k == "agent")
+        && (url.host_str(), url.port_or_known_default())
+            != (at.host_str(), at.port_or_known_default())
+}
+
+/// Ask the control plane to serve a file itself rather than redirecting to a
+/// peer agent
+pub fn with_direct_query(mut url: Url) -> Url {
+    url.query_pairs_mut().append_pair("direct", "true");
+    url
+}
+
 pub async fn check_binary(
     binary: &BinaryEntry,
     base_url: &str,
@@ -263,3 +300,74 @@ pub async fn get_file_issues(
         .then_some(BadFileReason::Stale)
         .or_else(|| (remote_content_length != local_content_length).then_some(BadFileReason::Size)))
 }
+
+#[cfg(test)]
+mod tests {
+    use axum::{
+        Router,
+        extract::RawQuery,
+        response::{IntoResponse, Redirect},
+        routing::get,
+    };
+    use tokio::sync::mpsc;
+
+    use super::*;
+
+    #[test]
+    fn peer_redirects() {
+        let cp: Url = "http://10.0.0.1:1234/content/storage/testnet/s/genesis.block?agent=a"
+            .parse()
+            .unwrap();
+        let peer: Url = "http://10.0.0.2:8080/content/sha256/00".parse().unwrap();
+        assert!(is_peer_redirect(&cp, &peer));
+        assert!(!is_peer_redirect(&cp, &cp));
+
+        // downloads that do not identify the agent are never redirected to peers
+        let url: Url = "http://10.0.0.1:1234/snarkos".parse().unwrap();
+        assert!(!is_peer_redirect(&url, &peer));
+
+        assert_eq!(with_direct_query(cp).query(), Some("agent=a&direct=true"));
+    }
+
+    #[tokio::test]
+    async fn falls_back_to_control_plane_when_peer_is_down() {
+        // bind then drop a listener to get a port nothing listens on
+        let dead_peer = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+
+        let app = Router::new().route(
+            "/content/file",
+            get(move |RawQuery(query): RawQuery| async move {
+                if query.is_some_and(|q| q.contains("direct=true")) {
+                    "file contents".into_response()
+                } else {
+                    Redirect::temporary(&format!("http://{dead_peer}/content/sha256/00"))
+                        .into_response()
+                }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let dir = tempfile::tempdir().unwrap();
+        let dst = dir.path().join("file");
+        let (tx, _rx) = mpsc::unbounded_channel();
+        let (_, sha256, len) = download_file(
+            0,
+            &reqwest::Client::new(),
+            format!("http://{addr}/content/file?agent=a"),
+            &dst,
+            tx,
+        )
+        .await
+        .unwrap()
+        .unwrap();
+
+        assert_eq!(std::fs::read(&dst).unwrap(), b"file contents");
+        assert_eq!(len, 13);
+        assert_eq!(sha256, format!("{:x}", Sha256::digest(b"file contents")));
+    }
+}
@@ -0,0 +1,330 @@
+use std::{
+    io,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicU64, Ordering},
+    time::SystemTime,
+};
+
+use chrono::{DateTime, Utc};
+use clap::Parser;
+use tracing::{trace, warn};
+
+/// Distinguishes the temporary files of concurrent inserts
+static TMP_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// A content-addressed cache of downloaded files (binaries, genesis blocks),
+/// keyed by their sha256. Entries are immutable, so several agents on the same
+/// host can safely share a cache directory.
+///
+/// Any file downloaded with a known sha256 is cached. Ledgers are not, as
+/// agents build their ledgers by syncing from peers rather than downloading
+/// them.
+///
+/// The least recently used entries are evicted when the cache grows past its
+/// maximum size.
+#[derive(Debug, Clone)]
+pub struct DownloadCache {
+    dir: PathBuf,
+    max_size: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct CacheEntry {
+    pub sha256: String,
+    pub size: u64,
+    pub last_used: SystemTime,
+}
+
+impl DownloadCache {
+    pub fn new(dir: PathBuf, max_size: u64) -> Self {
+        Self { dir, max_size }
+    }
+
+    /// Path of the cache entry for a sha256. The hash is validated so it can't
+    /// escape the cache directory.
+    fn entry_path(&self, sha256: &str) -> Option<PathBuf> {
+        let sha256 = sha256.to_ascii_lowercase();
+        (sha256.len() == 64 && sha256.chars().all(|c| c.is_ascii_hexdigit()))
+            .then(|| self.dir.join(sha256))
+    }
+
+    /// Path of a cached file, if it is in the cache
+    pub async fn find(&self, sha256: &str) -> Option<PathBuf> {
+        let path = self.entry_path(sha256)?;
+        tokio::fs::try_exists(&path)
+            .await
+            .is_ok_and(|exists| exists)
+            .then_some(path)
+    }
+
+    /// Copy a cached file to `dst`. Returns false if the file is not cached.
+    pub async fn get(&self, sha256: &str, dst: &Path) -> io::Result<bool> {
+        let Some(path) = self.entry_path(sha256) else {
+            return Ok(false);
+        };
+        if !tokio::fs::try_exists(&path).await? {
+            return Ok(false);
+        }
+
+        // copy rather than hard link, as downloads write over the destination
+        tokio::fs::copy(&path, dst).await?;
+        touch(&path).await;
+        trace!("restored {} from cache ({sha256})", dst.display());
+        Ok(true)
+    }
+
+    /// Add a file with a known sha256 to the cache, then evict old entries if
+    /// the cache is too large.
+    pub async fn insert(&self, sha256: &str, src: &Path) -> io::Result<()> {
+        let Some(path) = self.entry_path(sha256) else {
+            return Ok(());
+        };
+        if tokio::fs::try_exists(&path).await? {
+            touch(&path).await;
+            return Ok(());
+        }
+
+        tokio::fs::create_dir_all(&self.dir).await?;
+
+        // write to a temporary file first so other agents sharing the cache
+        // never see a partial entry. the counter keeps concurrent inserts of
+        // the same file within this process apart
+        let tmp = self.dir.join(format!(
+            ".{sha256}.{}.{}.tmp",
+            std::process::id(),
+            TMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        if let Err(e) = tokio::fs::copy(src, &tmp).await {
+            let _ = tokio::fs::remove_file(&tmp).await;
+            return Err(e);
+        }
+        tokio::fs::rename(&tmp, &path).await?;
+        trace!("cached {} as {sha256}", src.display());
+
+        self.prune(self.max_size).await?;
+        Ok(())
+    }
+
+    /// List cache entries from least to most recently used
+    pub async fn entries(&self) -> io::Result<Vec<CacheEntry>> {
+        let mut entries = vec![];
+        let mut dir = match tokio::fs::read_dir(&self.dir).await {
+            Ok(dir) => dir,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(entries),
+            Err(e) => return Err(e),
+        };
+
+        while let Some(entry) = dir.next_entry().await? {
+            let Some(sha256) = entry.file_name().to_str().map(str::to_owned) else {
+                continue;
+            };
+            // skip temporary files and anything else that isn't an entry
+            if self.entry_path(&sha256).is_none() {
+                continue;
+            }
+            let meta = entry.metadata().await?;
+            entries.push(CacheEntry {
+                sha256,
+                size: meta.len(),
+                last_used: meta.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+            });
+        }
+
+        entries.sort_by_key(|e| e.last_used);
+        Ok(entries)
+    }
+
+    /// Evict the least recently used entries until the cache is at most
+    /// `max_size` bytes. Returns the number of entries and bytes removed.
+    pub async fn prune(&self, max_size: u64) -> io::Result<(usize, u64)> {
+        let entries = self.entries().await?;
+        let mut total: u64 = entries.iter().map(|e| e.size).sum();
+        let mut removed = (0, 0);
+
+        for entry in entries {
+            if total <= max_size {
+                break;
+            }
+
+            match tokio::fs::remove_file(self.dir.join(&entry.sha256)).await {
+                Ok(()) => {
+                    removed.0 += 1;
+                    removed.1 += entry.size;
+                }
+                // another agent sharing the cache may have evicted it first
+                Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+                Err(e) => return Err(e),
+            }
+            total -= entry.size;
+        }
+
+        Ok(removed)
+    }
+}
+
+/// Mark an entry as recently used
+async fn touch(path: &Path) {
+    let path = path.to_owned();
+    let res = tokio::task::spawn_blocking(move || {
+        std::fs::File::options()
+            .append(true)
+            .open(&path)?
+            .set_modified(SystemTime::now())
+    })
+    .await;
+
+    if let Ok(Err(e)) = res {
+        warn!("failed to update cache entry access time: {e}");
+    }
+}
+
+/// Inspect or prune the agent's download cache
+#[derive(Debug, Parser)]
+pub enum CacheCommands {
+    /// List cached files from least to most recently used
+    List,
+    /// Evict the least recently used files until the cache fits in a size
+    Prune {
+        /// Target size in MiB, defaults to the agent's cache size
+        #[clap(long)]
+        max_size: Option<u64>,
+    },
+    /// Remove every cached file
+    Clear,
+}
+
+impl CacheCommands {
+    pub async fn run(self, cache: DownloadCache) -> io::Result<()> {
+        match self {
+            CacheCommands::List => {
+                let entries = cache.entries().await?;
+                let total: u64 = entries.iter().map(|e| e.size).sum();
+                for entry in entries {
+                    println!(
+                        "{} {:>12} {}",
+                        entry.sha256,
+                        entry.size,
+                        DateTime::<Utc>::from(entry.last_used).naive_local()
+                    );
+                }
+                println!(
+                    "total: {total} bytes of {} bytes in {}",
+                    cache.max_size,
+                    cache.dir.display()
+                );
+            }
+            CacheCommands::Prune { max_size } => {
+                let max_size = max_size.map(|s| s * 1024 * 1024).unwrap_or(cache.max_size);
+                let (count, size) = cache.prune(max_size).await?;
+                println!("removed {count} files ({size} bytes)");
+            }
+            CacheCommands::Clear => {
+                let (count, size) = cache.prune(0).await?;
+                println!("removed {count} files ({size} bytes)");
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Arc, time::Duration};
+
+    use super::*;
+
+    fn sha(c: char) -> String {
+        std::iter::repeat_n(c, 64).collect()
+    }
+
+    fn set_last_used(cache: &DownloadCache, sha256: &str, secs: u64) {
+        std::fs::File::options()
+            .append(true)
+            .open(cache.dir.join(sha256))
+            .unwrap()
+            .set_modified(SystemTime::UNIX_EPOCH + Duration::from_secs(secs))
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn evicts_least_recently_used() {
+        let root = tempfile::tempdir().unwrap();
+        let cache = DownloadCache::new(root.path().join("cache"), 12);
+
+        let src = root.path().join("src");
+        tokio::fs::write(&src, [0u8; 4]).await.unwrap();
+
+        for (secs, c) in [(1000, 'a'), (2000, 'b'), (3000, 'c')] {
+            cache.insert(&sha(c), &src).await.unwrap();
+            set_last_used(&cache, &sha(c), secs);
+        }
+
+        // use `a` so `b` becomes the least recently used
+        let dst = root.path().join("dst");
+        assert!(cache.get(&sha('a'), &dst).await.unwrap());
+        cache.insert(&sha('d'), &src).await.unwrap();
+
+        let cached: Vec<_> = cache
+            .entries()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|e| e.sha256)
+            .collect();
+        assert_eq!(cached.len(), 3);
+        assert_eq!(cached[0], sha('c'));
+        assert!(cached.contains(&sha('a')) && cached.contains(&sha('d')));
+        assert!(!cache.get(&sha('b'), &dst).await.unwrap());
+        assert!(!cache.get("../escape", &dst).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn concurrent_inserts_of_the_same_file() {
+        let root = tempfile::tempdir().unwrap();
+        let cache = Arc::new(DownloadCache::new(root.path().join("cache"), 1024));
+
+        let src = root.path().join("src");
+        tokio::fs::write(&src, [7u8; 64]).await.unwrap();
+
+        let inserts = (0..8)
+            .map(|_| {
+                let cache = Arc::clone(&cache);
+                let src = src.clone();
+                tokio::spawn(async move { cache.insert(&sha('e'), &src).await })
+            })
+            .collect::<Vec<_>>();
+        for insert in inserts {
+            insert.await.unwrap().unwrap();
+        }
+
+        let entries = cache.entries().await.unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].size, 64);
+
+        // no temporary files are left behind
+        let mut dir = tokio::fs::read_dir(&cache.dir).await.unwrap();
+        let mut files = 0;
+        while dir.next_entry().await.unwrap().is_some() {
+            files += 1;
+        }
+        assert_eq!(files, 1);
+    }
+
+    #[tokio::test]
+    async fn prune_to_size() {
+        let root = tempfile::tempdir().unwrap();
+        let cache = DownloadCache::new(root.path().join("cache"), 1024);
+
+        let src = root.path().join("src");
+        tokio::fs::write(&src, [0u8; 4]).await.unwrap();
+        for (secs, c) in [(1000, 'a'), (2000, 'b'), (3000, 'c')] {
+            cache.insert(&sha(c), &src).await.unwrap();
+            set_last_used(&cache, &sha(c), secs);
+        }
+
+        assert_eq!(cache.prune(8).await.unwrap(), (1, 4));
+        assert!(cache.find(&sha('a')).await.is_none());
+        assert_eq!(cache.prune(0).await.unwrap(), (2, 8));
+        assert!(cache.entries().await.unwrap().is_empty());
+    }
+}
@@ -13,22 +13,28 @@ use http::Uri;
 use snops_common::state::{AgentId, AgentModeOptions, NetworkId, PortConfig, StorageId};
 use tracing::{info, warn};
 
-use crate::net;
+use crate::{
+    cache::{CacheCommands, DownloadCache},
+    net,
+};
 
 pub const ENV_ENDPOINT: &str = "SNOPS_ENDPOINT";
 pub const ENV_ENDPOINT_DEFAULT: &str = "127.0.0.1:1234";
+/// Cores reserved for each authorization a compute agent executes at once
+const CORES_PER_COMPUTE_SLOT: u32 = 8;
 
 // TODO: allow agents to define preferred internal/external addrs
 
 #[derive(Debug, Parser)]
+#[clap(subcommand_negates_reqs = true)]
 pub struct Cli {
     #[arg(long, env = ENV_ENDPOINT)]
     /// Control plane endpoint address (IP, or wss://host, http://host)
     pub endpoint: Option<String>,
 
     /// Agent ID, used to identify the agent in the network.
-    #[arg(long)]
-    pub id: AgentId,
+    #[arg(long, required = true)]
+    pub id: Option<AgentId>,
 
     /// Locally provided private key file, used for envs where private keys are
     /// locally provided
@@ -44,6 +50,16 @@ pub struct Cli {
     #[arg(long, default_value = "./snops-data")]
     pub path: PathBuf,
 
+    /// Path to the content-addressed download cache, defaults to `cache` in
+    /// the data directory. Agents on the same host can share a cache.
+    #[arg(long, env = "SNOPS_AGENT_CACHE")]
+    pub cache_path: Option<PathBuf>,
+
+    /// Maximum size of the download cache in MiB. Least recently used files
+    /// are evicted past this size.
+    #[arg(long, env = "SNOPS_AGENT_CACHE_SIZE", default_value_t = 8192)]
+    pub cache_size: u64,
+
     /// Enable the agent to fetch its external address. Necessary to determine
     /// which agents are on shared networks, and for
     /// external-to-external connections
@@ -62,18 +78,24 @@ pub struct Cli {
     #[clap(flatten)]
     pub modes: AgentModeOptions,
 
+    /// Number of authorizations the agent executes at once when functioning
+    /// as a compute target. Defaults to one for every 8 available cores.
+    #[arg(long, env = "SNOPS_AGENT_COMPUTE_SLOTS")]
+    pub compute_slots: Option<u32>,
+
     #[clap(short, long, default_value_t = false)]
     /// Run the agent in quiet mode, suppressing most node output
     pub quiet: bool,
 
-    #[cfg(any(feature = "clipages", feature = "mangen"))]
     #[clap(subcommand)]
-    pub command: Commands,
+    pub command: Option<Commands>,
 }
 
-#[cfg(any(feature = "clipages", feature = "mangen"))]
 #[derive(Debug, Parser)]
 pub enum Commands {
+    /// Inspect or prune the download cache
+    #[clap(subcommand)]
+    Cache(CacheCommands),
     #[cfg(feature = "mangen")]
     Man(snops_common::mangen::Mangen),
     #[cfg(feature = "clipages")]
@@ -81,9 +103,29 @@ pub enum Commands {
 }
 
 impl Cli {
-    #[cfg(any(feature = "clipages", feature = "mangen"))]
-    pub fn run(self) {
-        match self.command {
+    /// Number of authorizations the agent can execute at once
+    pub fn compute_slots(&self) -> u32 {
+        self.compute_slots.unwrap_or_else(|| {
+            std::thread::available_parallelism()
+                .map(|cores| cores.get() as u32 / CORES_PER_COMPUTE_SLOT)
+                .unwrap_or_default()
+                .max(1)
+        })
+    }
+
+    /// Run a subcommand and exit, or return if no subcommand was given
+    pub async fn run(&mut self) {
+        let Some(command) = self.command.take() else {
+            return;
+        };
+
+        match command {
+            Commands::Cache(cache_cmd) => {
+                if let Err(e) = cache_cmd.run(self.cache()).await {
+                    eprintln!("cache error: {e}");
+                    std::process::exit(1);
+                }
+            }
             #[cfg(feature = "mangen")]
             Commands::Man(mangen) => {
                 mangen
@@ -103,6 +145,16 @@ impl Cli {
         std::process::exit(0);
     }
 
+    /// The agent's content-addressed download cache
+    pub fn cache(&self) -> DownloadCache {
+        DownloadCache::new(
+            self.cache_path
+                .clone()
+                .unwrap_or_else(|| self.path.join("cache")),
+            self.cache_size * 1024 * 1024,
+        )
+    }
+
     pub fn get_local_ip(&self) -> IpAddr {
         if self.bind_addr.is_unspecified() {
             IpAddr::V4(Ipv4Addr::LOCALHOST)
@@ -124,8 +176,10 @@ impl Cli {
         // Add agent version
         query.push_str(&format!("&version={}", env!("CARGO_PKG_VERSION")));
 
-        // add &id=
-        query.push_str(&format!("&id={}", self.id));
+        // add &id= (always present when running the agent)
+        if let Some(id) = self.id {
+            query.push_str(&format!("&id={id}"));
+        }
 
         // add local pk flag
         if let Some(file) = self.private_key_file.as_ref() {
@@ -136,6 +190,11 @@ impl Cli {
             }
         }
 
+        // add compute slots for compute agents
+        if self.modes.compute {
+            query.push_str(&format!("&compute_slots={}", self.compute_slots()));
+        }
+
         // add &labels= if id is present
         if let Some(labels) = &self.labels {
             info!("Using labels: {:?}", labels);
@@ -1,4 +1,5 @@
 mod api;
+mod cache;
 mod cli;
 mod client;
 mod db;
@@ -38,13 +39,12 @@ async fn main() {
         .install_default()
         .expect("Failed to install rustls crypto provider");
 
-    // For documentation purposes will exit after running the command.
-    #[cfg(any(feature = "clipages", feature = "mangen"))]
-    Cli::parse().run();
+    let mut args = Cli::parse();
 
-    let (_guard, reload_handler) = init_logging();
+    // Subcommands (cache management, documentation) exit after running.
+    args.run().await;
 
-    let args = Cli::parse();
+    let (_guard, reload_handler) = init_logging();
 
     let (internal_addrs, external_addr) = args.addrs();
 
@@ -82,6 +82,7 @@ async fn main() {
         _started: Instant::now(),
         external_addr,
         internal_addrs,
+        cache: args.cache(),
         cli: args,
         endpoint,
         queue_reconcile_tx,
@@ -132,6 +133,20 @@ async fn main() {
         }
     });
 
+    // Start the content server so other agents can download cached files
+    if state.cli.ports.content != 0 {
+        let content_addr = (state.cli.bind_addr, state.cli.ports.content);
+        let content_listener = tokio::net::TcpListener::bind(content_addr)
+            .await
+            .expect("failed to bind content server");
+        let content_state = Arc::clone(&state);
+        tokio::spawn(async move {
+            if let Err(e) = server::start_content(content_listener, content_state).await {
+                error!("content server crashed: {e:?}");
+            }
+        });
+    }
+
     // Get the interrupt signals to break the stream connection
     let mut interrupt = Signals::term_or_interrupt();
 
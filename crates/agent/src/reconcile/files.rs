@@ -40,6 +40,15 @@ pub fn get_genesis_route(endpoint: &str, network: NetworkId, storage_id: Storage
     format!("{endpoint}/content/storage/{network}/{storage_id}/{SNARKOS_GENESIS_FILE}")
 }
 
+/// Identify this agent when downloading from the control plane, so the
+/// download can be redirected to a peer agent that has the file
+pub fn with_agent_query(mut url: Url, state: &GlobalState) -> Url {
+    if let Some(id) = state.cli.id {
+        url.query_pairs_mut().append_pair("agent", &id.to_string());
+    }
+    url
+}
+
 /// This reconciler creates a directory if it does not exist
 pub struct DirectoryReconciler<'a>(pub &'a Path);
 impl Reconcile<(), ReconcileError> for DirectoryReconciler<'_> {
@@ -91,6 +100,11 @@ impl FileReconciler {
         self
     }
 
+    pub fn with_sha256(mut self, sha256: Option<String>) -> Self {
+        self.check_sha256 = sha256;
+        self
+    }
+
     pub fn with_binary(mut self, binary: &BinaryEntry) -> Self {
         self.permissions = Some(0o755);
         self.check_sha256 = binary.sha256.clone();
@@ -174,7 +188,7 @@ impl Reconcile<bool, ReconcileError> for FileReconciler {
             dashmap::Entry::Vacant(_) => false,
         };
 
-        let file_problems = get_file_issues(
+        let mut file_problems = get_file_issues(
             &client,
             self.src.as_str(),
             self.dst.as_path(),
@@ -184,6 +198,27 @@ impl Reconcile<bool, ReconcileError> for FileReconciler {
         )
         .await?;
 
+        // restore the file from the download cache when its hash is known
+        if let (Some(sha256), true, false) =
+            (&self.check_sha256, file_problems.is_some(), is_complete)
+        {
+            match self.state.cache.get(sha256, &self.dst).await {
+                Ok(true) => {
+                    file_problems = get_file_issues(
+                        &client,
+                        self.src.as_str(),
+                        self.dst.as_path(),
+                        self.check_size,
+                        Some(sha256),
+                        self.offline,
+                    )
+                    .await?;
+                }
+                Ok(false) => {}
+                Err(e) => warn!("failed to restore {} from cache: {e}", self.dst.display()),
+            }
+        }
+
         // There is an issue with the file being complete and not existing
         if is_complete && !self.dst.exists() {
             // Clear the download
@@ -227,6 +262,17 @@ impl Reconcile<bool, ReconcileError> for FileReconciler {
         // Everything is good. Ensure file permissions
         if file_problems.is_none() {
             self.check_and_set_mode()?;
+
+            // cache completed downloads so they don't need to be fetched again
+            if let (Some(sha256), true) = (&self.check_sha256, is_complete) {
+                if self.state.cache.find(sha256).await.is_none() {
+                    match self.state.cache.insert(sha256, &self.dst).await {
+                        // let the control plane send other agents here for this file
+                        Ok(()) => self.state.post_cached_files().await,
+                        Err(e) => warn!("failed to cache {}: {e}", self.dst.display()),
+                    }
+                }
+            }
             trace!("File reconcile complete: {}", self.dst.display());
             return Ok(ReconcileStatus::with(true));
         }
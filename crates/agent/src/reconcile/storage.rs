@@ -10,7 +10,7 @@ use snops_common::{
     binaries::{BinaryEntry, BinarySource},
     constant::{
         LEDGER_BASE_DIR, LEDGER_PERSIST_DIR, NODE_DATA_DIR, SNARKOS_FILE, SNARKOS_GENESIS_FILE,
-        VERSION_FILE,
+        TRANSFERRED_CHECKPOINT_DIR, VERSION_FILE,
     },
     rpc::error::ReconcileError,
     state::{HeightRequest, InternedId, ReconcileCondition, ReconcileStatus, TransferId},
@@ -19,7 +19,10 @@ use tokio::{process::Command, sync::Mutex, task::AbortHandle};
 use tracing::{error, info, trace};
 use url::Url;
 
-use super::{DirectoryReconciler, FileReconciler, Reconcile, default_binary, get_genesis_route};
+use super::{
+    DirectoryReconciler, FileReconciler, Reconcile, default_binary, get_genesis_route,
+    with_agent_query,
+};
 use crate::state::GlobalState;
 
 /// Download a specific binary file needed to run the node
@@ -72,8 +75,10 @@ impl Reconcile<(), ReconcileError> for BinaryReconciler<'_> {
             BinarySource::Url(url) => url.clone(),
             BinarySource::Path(path) => {
                 let url = format!("{}{}", &state.endpoint, path.display());
-                url.parse::<reqwest::Url>()
-                    .map_err(|e| ReconcileError::UrlParseError(url, e.to_string()))?
+                let url = url
+                    .parse::<reqwest::Url>()
+                    .map_err(|e| ReconcileError::UrlParseError(url, e.to_string()))?;
+                with_agent_query(url, state)
             }
         };
 
@@ -154,14 +159,16 @@ impl Reconcile<(), ReconcileError> for GenesisReconciler<'_> {
         **ok_at = None;
 
         let genesis_url = get_genesis_route(&state.endpoint, env_info.network, env_info.storage.id);
+        let genesis_url = genesis_url
+            .parse::<Url>()
+            .map_err(|e| ReconcileError::UrlParseError(genesis_url.to_string(), e.to_string()))?;
         let mut file_rec = FileReconciler::new(
             Arc::clone(&self.state),
-            genesis_url.parse::<Url>().map_err(|e| {
-                ReconcileError::UrlParseError(genesis_url.to_string(), e.to_string())
-            })?,
+            with_agent_query(genesis_url, &self.state),
             genesis_file,
         )
         .with_offline(!self.state.is_ws_online())
+        .with_sha256(env_info.storage.genesis_sha256.clone())
         .with_tx_id(**transfer);
         let file_res = file_rec.reconcile().await?;
 
@@ -205,18 +212,22 @@ pub struct LedgerReconciler<'a> {
     pub modify_handle: &'a mut Option<(AbortHandle, Arc<Mutex<Option<LedgerModifyResult>>>)>,
 }
 
+/// The directory a node's ledger is unpacked into, and the name of the ledger
+/// directory within it. Checkpoints are stored next to the ledger directory.
+pub fn ledger_untar_paths(state: &GlobalState, env_info: &AgentEnvInfo) -> (PathBuf, &'static str) {
+    if env_info.storage.persist {
+        (
+            state.cli.storage_path(env_info.network, env_info.storage.id),
+            LEDGER_PERSIST_DIR,
+        )
+    } else {
+        (state.cli.path.join(NODE_DATA_DIR), LEDGER_BASE_DIR)
+    }
+}
+
 impl LedgerReconciler<'_> {
     pub fn untar_paths(&self) -> (PathBuf, &'static str) {
-        if self.env_info.storage.persist {
-            (
-                self.state
-                    .cli
-                    .storage_path(self.env_info.network, self.env_info.storage.id),
-                LEDGER_PERSIST_DIR,
-            )
-        } else {
-            (self.state.cli.path.join(NODE_DATA_DIR), LEDGER_BASE_DIR)
-        }
+        ledger_untar_paths(&self.state, &self.env_info)
     }
 
     pub fn ledger_path(&self) -> PathBuf {
@@ -233,31 +244,43 @@ impl LedgerReconciler<'_> {
         // If there's a retention policy, load the checkpoint manager
         // this is so we can wipe all leftover checkpoints for non-persisted storage
         // after resets or new environments
-        let manager = self
+        let policy = self
             .env_info
             .storage
             .retention_policy
             .clone()
-            .map(|policy| {
-                trace!("loading checkpoints from {untar_base:?}...");
-                CheckpointManager::load(ledger_path.clone(), policy).map_err(|e| {
-                    error!("failed to load checkpoints: {e}");
-                    ReconcileError::CheckpointLoadError(e.to_string())
-                })
-            })
-            .transpose()?
             .ok_or(ReconcileError::MissingRetentionPolicy(self.target_height.1))?;
+        let load = |path: PathBuf| {
+            trace!("loading checkpoints from {path:?}...");
+            CheckpointManager::load(path, policy.clone()).map_err(|e| {
+                error!("failed to load checkpoints: {e}");
+                ReconcileError::CheckpointLoadError(e.to_string())
+            })
+        };
 
         // Determine which checkpoint to use by the next available height/time
-        match self.target_height.1 {
-            HeightRequest::Absolute(height) => manager.nearest_with_height(height),
-            HeightRequest::Checkpoint(span) => manager.nearest_with_span(span),
-            // top cannot be a target height
-            _ => None,
+        let find = |manager: &CheckpointManager| {
+            match self.target_height.1 {
+                HeightRequest::Absolute(height) => manager.nearest_with_height(height),
+                HeightRequest::Checkpoint(span) => manager.nearest_with_span(span),
+                // top cannot be a target height
+                _ => None,
+            }
+            .map(|(_, path)| path.clone())
+        };
+
+        if let Some(path) = find(&load(ledger_path)?) {
+            return Ok(path);
         }
-        .map(|(_, path)| path)
-        .ok_or(ReconcileError::NoAvailableCheckpoints(self.target_height.1))
-        .cloned()
+
+        // Fall back to the checkpoints transferred from the agent that previously
+        // ran this node. They apply once the ledger has synced past them.
+        let transferred = load(
+            untar_base
+                .join(TRANSFERRED_CHECKPOINT_DIR)
+                .join(ledger_dir),
+        )?;
+        find(&transferred).ok_or(ReconcileError::NoAvailableCheckpoints(self.target_height.1))
     }
 
     pub fn spawn_modify(
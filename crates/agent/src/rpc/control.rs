@@ -1,17 +1,20 @@
 //! Control plane-to-agent RPC.
 
-use std::net::IpAddr;
+use std::{net::IpAddr, path::PathBuf};
 
+use snops_checkpoint::{CheckpointHeader, CheckpointManager, RetentionPolicy, path_from_height};
 use snops_common::{
+    action_models::FeeBreakdown,
     aot_cmds::AotCmd,
+    constant::TRANSFERRED_CHECKPOINT_DIR,
     define_rpc_mux,
-    prelude::snarkos_status::SnarkOSLiteBlock,
+    prelude::{ledger_digest::LedgerDigest, snarkos_status::SnarkOSLiteBlock},
     rpc::{
         control::{
             ControlServiceClient, ControlServiceRequest, ControlServiceResponse,
             agent::{
                 AgentMetric, AgentService, AgentServiceRequest, AgentServiceResponse, AgentStatus,
-                Handshake,
+                AuthorizeRequest, EstimateFeeRequest, Handshake,
             },
         },
         error::{AgentError, SnarkosRequestError},
@@ -22,7 +25,11 @@ use tarpc::context::Context;
 use tracing::{error, info, trace};
 
 use crate::{
-    api, log::make_env_filter, metrics::MetricComputer, reconcile::default_binary, state::AppState,
+    api,
+    log::make_env_filter,
+    metrics::MetricComputer,
+    reconcile::{default_binary, storage::ledger_untar_paths},
+    state::AppState,
 };
 
 define_rpc_mux!(child;
@@ -37,6 +44,73 @@ pub struct AgentRpcServer {
     pub version: &'static str,
 }
 
+impl AgentRpcServer {
+    /// The ledger path of the node in the given environment, or of the running
+    /// node when no environment is given
+    async fn ledger_path(&self, env_id: Option<EnvId>) -> Result<PathBuf, AgentError> {
+        let env_id = match env_id {
+            Some(env_id) => env_id,
+            None => self
+                .state
+                .get_agent_state()
+                .await
+                .env()
+                .ok_or(AgentError::InvalidState)?,
+        };
+        let info = self
+            .state
+            .get_env_info(env_id)
+            .await
+            .map_err(|e| AgentError::FailedToGetEnvInfo(e.to_string()))?;
+
+        let (untar_base, ledger_dir) = ledger_untar_paths(&self.state, &info);
+        Ok(untar_base.join(ledger_dir))
+    }
+
+    /// Download the aot binary used for compute in the given environment
+    async fn compute_binary(&self, env_id: EnvId) -> Result<PathBuf, AgentError> {
+        // TODO: maybe in the env config store a branch label for the binary so it won't
+        // be put in storage and won't overwrite itself
+
+        // TODO: compute agents wiping out env info when alternating environments
+        let info = self
+            .state
+            .get_env_info(env_id)
+            .await
+            .map_err(|e| AgentError::FailedToGetEnvInfo(e.to_string()))?;
+
+        let aot_bin = self
+            .state
+            .cli
+            .path
+            .join(format!("snarkos-aot-{env_id}-compute"));
+
+        let default_entry = default_binary(&info);
+
+        // download the snarkOS binary
+        api::check_binary(
+            // attempt to use the specified "compute" binary
+            info.storage
+                .binaries
+                .get(&InternedId::compute_id())
+                // fallback to the default binary
+                .or_else(|| info.storage.binaries.get(&InternedId::default()))
+                // fallback to the default entry
+                .unwrap_or(&default_entry),
+            &self.state.endpoint,
+            &aot_bin,
+            self.state.transfer_tx(),
+        )
+        .await
+        .map_err(|e| {
+            error!("failed obtain compute binary: {e}");
+            AgentError::ProcessFailed
+        })?;
+
+        Ok(aot_bin)
+    }
+}
+
 impl AgentService for AgentRpcServer {
     async fn kill(self, _: Context) {
         info!("Kill RPC invoked...");
@@ -86,6 +160,9 @@ impl AgentService for AgentRpcServer {
             error!("failed to send transfer statuses: {err}");
         }
 
+        // emit the files this agent can serve to other agents
+        self.state.post_cached_files().await;
+
         info!("Received control-plane handshake");
 
         // Re-fetch peer addresses to ensure no addresses changed while offline
@@ -232,43 +309,7 @@ impl AgentService for AgentRpcServer {
     ) -> Result<String, AgentError> {
         info!("Executing authorization for {env_id}...");
 
-        // TODO: maybe in the env config store a branch label for the binary so it won't
-        // be put in storage and won't overwrite itself
-
-        // TODO: compute agents wiping out env info when alternating environments
-        let info = self
-            .state
-            .get_env_info(env_id)
-            .await
-            .map_err(|e| AgentError::FailedToGetEnvInfo(e.to_string()))?;
-
-        let aot_bin = self
-            .state
-            .cli
-            .path
-            .join(format!("snarkos-aot-{env_id}-compute"));
-
-        let default_entry = default_binary(&info);
-
-        // download the snarkOS binary
-        api::check_binary(
-            // attempt to use the specified "compute" binary
-            info.storage
-                .binaries
-                .get(&InternedId::compute_id())
-                // fallback to the default binary
-                .or_else(|| info.storage.binaries.get(&InternedId::default()))
-                // fallback to the default entry
-                .unwrap_or(&default_entry),
-            &self.state.endpoint,
-            &aot_bin,
-            self.state.transfer_tx(),
-        )
-        .await
-        .map_err(|e| {
-            error!("failed obtain compute binary: {e}");
-            AgentError::ProcessFailed
-        })?;
+        let aot_bin = self.compute_binary(env_id).await?;
 
         let start = std::time::Instant::now();
         match AotCmd::new(aot_bin, network)
@@ -299,6 +340,98 @@ impl AgentService for AgentRpcServer {
         }
     }
 
+    async fn authorize_program(
+        self,
+        _: Context,
+        env_id: EnvId,
+        network: NetworkId,
+        request: AuthorizeRequest,
+    ) -> Result<String, AgentError> {
+        info!(
+            "Authorizing {}/{} for {env_id}...",
+            request.program, request.function
+        );
+
+        let aot_bin = self.compute_binary(env_id).await?;
+        let query = format!("{}{}", self.state.endpoint, request.query);
+
+        let start = std::time::Instant::now();
+        match AotCmd::new(aot_bin, network)
+            .authorize_program(
+                &request.private_key,
+                request.fee_private_key.as_ref(),
+                &request.program,
+                &request.function,
+                &request.inputs,
+                Some(&query),
+                request.priority_fee,
+                None,
+                request.cost_v1,
+            )
+            .await
+        {
+            Ok(mut auth) => {
+                // Truncate the output to the first {
+                if let Some(index) = auth.find("{") {
+                    auth = auth.split_off(index);
+                }
+
+                info!("Authorized in {}ms", start.elapsed().as_millis());
+                Ok(auth)
+            }
+            Err(e) => {
+                error!("failed to authorize: {e}");
+                Err(AgentError::ProcessFailed)
+            }
+        }
+    }
+
+    async fn estimate_fee(
+        self,
+        _: Context,
+        env_id: EnvId,
+        network: NetworkId,
+        request: EstimateFeeRequest,
+    ) -> Result<FeeBreakdown, AgentError> {
+        let aot = AotCmd::new(self.compute_binary(env_id).await?, network);
+
+        let res = match request {
+            EstimateFeeRequest::Program(request) => {
+                info!(
+                    "Estimating fee of {}/{} for {env_id}...",
+                    request.program, request.function
+                );
+                let query = format!("{}{}", self.state.endpoint, request.query);
+                aot.estimate_program_fee(
+                    &request.private_key,
+                    &request.program,
+                    &request.function,
+                    &request.inputs,
+                    Some(&query),
+                    request.priority_fee,
+                    request.cost_v1,
+                )
+                .await
+            }
+            EstimateFeeRequest::Deploy {
+                private_key,
+                program,
+                query,
+                priority_fee,
+            } => {
+                info!("Estimating deployment fee for {env_id}...");
+                let query = format!("{}{query}", self.state.endpoint);
+                aot.estimate_deploy_fee(&private_key, &program, Some(&query), priority_fee)
+                    .await
+            }
+        };
+
+        res.map_err(|e| {
+            error!("failed to estimate fee: {e}");
+            AgentError::ProcessFailed
+        })
+    }
+
     async fn set_log_level(self, _: Context, level: String) -> Result<(), AgentError> {
         tracing::debug!("setting log level to {level}");
         let level: tracing_subscriber::filter::LevelFilter = level
@@ -312,6 +445,67 @@ impl AgentService for AgentRpcServer {
         Ok(())
     }
 
+    async fn get_checkpoints(self, _: Context) -> Result<Vec<u32>, AgentError> {
+        let ledger_path = self.ledger_path(None).await?;
+        let manager = CheckpointManager::load(ledger_path, RetentionPolicy::default())
+            .map_err(|e| AgentError::FailedToReadCheckpoint(e.to_string()))?;
+        Ok(manager.checkpoints().map(|(h, _)| h.block_height).collect())
+    }
+
+    async fn get_checkpoint(self, _: Context, height: u32) -> Result<Vec<u8>, AgentError> {
+        let ledger_path = self.ledger_path(None).await?;
+        let path = path_from_height(&ledger_path, height).ok_or(AgentError::CheckpointNotFound)?;
+        match tokio::fs::read(&path).await {
+            Ok(bytes) => Ok(bytes),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                Err(AgentError::CheckpointNotFound)
+            }
+            Err(e) => Err(AgentError::FailedToReadCheckpoint(e.to_string())),
+        }
+    }
+
+    async fn put_checkpoint(
+        self,
+        _: Context,
+        env_id: EnvId,
+        height: u32,
+        checkpoint: Vec<u8>,
+    ) -> Result<(), AgentError> {
+        // reject anything that isn't a checkpoint for the claimed height
+        let header = CheckpointHeader::read_bytes(checkpoint.as_slice())
+            .map_err(|e| AgentError::FailedToReadCheckpoint(e.to_string()))?;
+        if header.block_height != height {
+            return Err(AgentError::FailedToReadCheckpoint(format!(
+                "checkpoint is for height {}, not {height}",
+                header.block_height
+            )));
+        }
+
+        // transferred checkpoints are kept apart from the node's own, as the
+        // node removes checkpoints taller than its ledger when it starts
+        let ledger_path = self.ledger_path(Some(env_id)).await?;
+        let (Some(untar_base), Some(ledger_dir)) = (ledger_path.parent(), ledger_path.file_name())
+        else {
+            return Err(AgentError::InvalidState);
+        };
+        let dir = untar_base.join(TRANSFERRED_CHECKPOINT_DIR);
+        let path =
+            path_from_height(&dir.join(ledger_dir), height).ok_or(AgentError::InvalidState)?;
+
+        let write = async {
+            tokio::fs::create_dir_all(&dir).await?;
+            let tmp = path.with_extension("tmp");
+            tokio::fs::write(&tmp, &checkpoint).await?;
+            tokio::fs::rename(&tmp, &path).await
+        };
+        write
+            .await
+            .map_err(|e| AgentError::FailedToWriteCheckpoint(e.to_string()))?;
+
+        info!("received checkpoint for height {height}");
+        Ok(())
+    }
+
     async fn set_aot_log_level(self, ctx: Context, verbosity: u8) -> Result<(), AgentError> {
         tracing::debug!("agent setting aot log verbosity to {verbosity:?}");
         self.state
@@ -351,6 +545,16 @@ impl AgentService for AgentRpcServer {
             .map_err(|e| AgentError::FailedToMakeRequest(format!("find tx: {e:?}")))?
     }
 
+    async fn get_ledger_digest(self, ctx: Context) -> Result<LedgerDigest, AgentError> {
+        self.state
+            .get_node_client()
+            .await
+            .ok_or(AgentError::NodeClientNotSet)?
+            .get_ledger_digest(ctx)
+            .await
+            .map_err(|e| AgentError::FailedToMakeRequest(format!("ledger digest: {e:?}")))?
+    }
+
     async fn get_status(self, ctx: Context) -> Result<AgentStatus, AgentError> {
         let aot_online = match self.state.get_node_client().await {
             Some(c) => c.status(ctx).await.is_ok(),
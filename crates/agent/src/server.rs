@@ -4,19 +4,22 @@ use anyhow::Result;
 use axum::{
     Router,
     extract::{
-        State, WebSocketUpgrade,
+        Path, Request, State, WebSocketUpgrade,
         ws::{Message, WebSocket},
     },
     response::{IntoResponse, Response},
     routing::get,
 };
 use futures::StreamExt;
+use http::StatusCode;
 use snops_common::rpc::{
     RpcTransport,
     agent::{AgentNodeService, node::NodeServiceClient},
 };
 use tarpc::server::Channel;
 use tokio::select;
+use tower::Service;
+use tower_http::services::ServeFile;
 use tracing::{error, info, warn};
 
 use crate::{
@@ -38,6 +41,31 @@ pub async fn start(listener: tokio::net::TcpListener, state: AppState) -> Result
     Ok(())
 }
 
+/// Start the server that serves cached files to other agents
+pub async fn start_content(listener: tokio::net::TcpListener, state: AppState) -> Result<()> {
+    let app = Router::new()
+        .route("/content/sha256/:sha256", get(serve_cached_file))
+        .with_state(Arc::clone(&state));
+    info!("Starting content server on: {}", listener.local_addr()?);
+
+    axum::serve(listener, app).await?;
+
+    Ok(())
+}
+
+/// Serve a file from the download cache. Cached files have been verified
+/// against their sha256 when they were downloaded.
+async fn serve_cached_file(
+    Path(sha256): Path<String>,
+    State(state): State<AppState>,
+    req: Request,
+) -> Response {
+    match state.cache.find(&sha256).await {
+        Some(path) => ServeFile::new(path).call(req).await.into_response(),
+        None => StatusCode::NOT_FOUND.into_response(),
+    }
+}
+
 async fn node_ws_handler(ws: WebSocketUpgrade, State(state): State<AppState>) -> Response {
     ws.on_upgrade(|socket| handle_socket(socket, state))
         .into_response()
@@ -21,7 +21,10 @@ use tarpc::context;
 use tokio::sync::{RwLock, mpsc::Sender, oneshot};
 use tracing::{error, info};
 
-use crate::{cli::Cli, db::Database, log::ReloadHandler, metrics::Metrics, transfers::TransferTx};
+use crate::{
+    cache::DownloadCache, cli::Cli, db::Database, log::ReloadHandler, metrics::Metrics,
+    transfers::TransferTx,
+};
 
 pub const NODE_GRACEFUL_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(30);
 
@@ -38,6 +41,7 @@ pub struct GlobalState {
     pub internal_addrs: Vec<IpAddr>,
     pub agent_rpc_port: u16,
     pub cli: Cli,
+    pub cache: DownloadCache,
     pub endpoint: String,
     pub loki: Mutex<Option<Url>>,
     /// Desired state the agent should be in. After each reconciliation, the
@@ -70,6 +74,29 @@ impl GlobalState {
         self.client.read().await.clone()
     }
 
+    /// Tell the control plane which cached files this agent can serve to other
+    /// agents
+    pub async fn post_cached_files(&self) {
+        if self.cli.ports.content == 0 {
+            return;
+        }
+        let Some(client) = self.get_ws_client().await else {
+            return;
+        };
+
+        let hashes = match self.cache.entries().await {
+            Ok(entries) => entries.into_iter().map(|e| e.sha256).collect(),
+            Err(e) => {
+                error!("failed to list cached files: {e}");
+                return;
+            }
+        };
+
+        if let Err(e) = client.post_cached_files(context::current(), hashes).await {
+            error!("failed to send cached files: {e}");
+        }
+    }
+
     pub async fn get_agent_state(&self) -> Arc<AgentState> {
         self.agent_state.read().await.clone()
     }
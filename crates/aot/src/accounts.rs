@@ -1,6 +1,6 @@
-use std::{collections::HashSet, fs, path::PathBuf};
+use std::{collections::HashSet, fs, path::PathBuf, str::FromStr};
 
-use anyhow::{Result, ensure};
+use anyhow::{Result, bail, ensure};
 use bech32::ToBase32;
 use clap::Parser;
 use colored::Colorize;
@@ -8,9 +8,10 @@ use indexmap::IndexMap;
 use rand::SeedableRng;
 use rand_chacha::ChaChaRng;
 use rayon::iter::{IntoParallelIterator, ParallelIterator};
+use serde_json::Value;
 use snarkvm::{console::program::Network, prelude::Itertools, utilities::ToBytes};
 
-use crate::{Address, PrivateKey};
+use crate::{Address, PrivateKey, ViewKey};
 
 /// Given a seed and a count, generate a number of accounts.
 #[derive(Debug, Clone, Parser)]
@@ -31,6 +32,55 @@ pub struct GenAccounts {
     /// If unpassed or used with --vanity, uses a random seed
     #[clap(name = "seed", short, long)]
     pub seed: Option<u64>,
+
+    /// Import existing private keys from a JSON file instead of generating
+    /// them. The file can be a list of private keys or a map of addresses to
+    /// private keys, such as the output of this command or the committee and
+    /// additional accounts outputs of `genesis`.
+    #[clap(long, conflicts_with_all = ["vanity", "seed"])]
+    pub import: Option<PathBuf>,
+
+    /// Where to write a manifest of the accounts' addresses and view keys,
+    /// which is safe to share.
+    #[clap(long)]
+    pub manifest_output: Option<PathBuf>,
+}
+
+/// Read private keys from a JSON list of keys or a map of addresses to keys.
+/// Map values can be a key or a list starting with a key.
+fn import_accounts<N: Network>(path: &PathBuf) -> Result<IndexMap<Address<N>, PrivateKey<N>>> {
+    parse_accounts(&fs::read_to_string(path)?)
+}
+
+fn parse_accounts<N: Network>(json: &str) -> Result<IndexMap<Address<N>, PrivateKey<N>>> {
+    let parse_key = |value: &Value| match value {
+        Value::String(key) => Ok(PrivateKey::<N>::from_str(key)?),
+        Value::Array(values) => match values.first() {
+            Some(Value::String(key)) => Ok(PrivateKey::<N>::from_str(key)?),
+            _ => bail!("expected a list starting with a private key"),
+        },
+        _ => bail!("expected a private key"),
+    };
+
+    let keys = match serde_json::from_str(json)? {
+        Value::Array(keys) => keys.iter().map(parse_key).collect::<Result<Vec<_>>>()?,
+        Value::Object(accounts) => accounts
+            .iter()
+            .map(|(addr, value)| {
+                let key = parse_key(value)?;
+                ensure!(
+                    Address::try_from(&key)?.to_string() == *addr,
+                    "private key for {addr} does not match its address"
+                );
+                Ok(key)
+            })
+            .collect::<Result<Vec<_>>>()?,
+        _ => bail!("expected a list of private keys or a map of addresses to private keys"),
+    };
+
+    keys.into_iter()
+        .map(|key| Ok((Address::try_from(&key)?, key)))
+        .collect()
 }
 
 pub const BECH32M_CHARSET: &str = "0123456789acdefghjklmnpqrstuvwxyz";
@@ -61,6 +111,65 @@ impl bech32::WriteBase32 for VanityCheck<'_> {
 
 impl GenAccounts {
     pub fn parse<N: Network>(self) -> Result<()> {
+        let accounts = match &self.import {
+            Some(path) => import_accounts::<N>(path)?,
+            None => self.generate()?,
+        };
+
+        if let Some(manifest_file) = &self.manifest_output {
+            let manifest = accounts
+                .iter()
+                .map(|(addr, key)| Ok((*addr, ViewKey::try_from(key)?)))
+                .collect::<Result<IndexMap<_, _>>>()?;
+            fs::write(manifest_file, serde_json::to_string_pretty(&manifest)?)?;
+
+            println!(
+                "Account manifest written to {}.",
+                manifest_file.display().to_string().yellow()
+            );
+        }
+
+        match self.output {
+            // Write the accounts JSON file.
+            Some(accounts_file) => {
+                let file = fs::File::options()
+                    .append(false)
+                    .create(true)
+                    .truncate(true)
+                    .write(true)
+                    .open(&accounts_file)?;
+                serde_json::to_writer_pretty(file, &accounts)?;
+
+                println!(
+                    "Accounts written to {}.",
+                    accounts_file.display().to_string().yellow()
+                );
+            }
+
+            // Only a manifest was requested, keep the private keys out of stdout.
+            None if self.manifest_output.is_some() => {}
+
+            // Write the accounts to stdout if no file was passed.
+            None => {
+                let verb = match self.import {
+                    Some(_) => "Imported",
+                    None => "Generated",
+                };
+                println!("{verb} {} accounts:", accounts.len());
+                for (addr, key) in accounts {
+                    println!(
+                        "\t{}: {}",
+                        addr.to_string().yellow(),
+                        key.to_string().cyan()
+                    );
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn generate<N: Network>(&self) -> Result<IndexMap<Address<N>, PrivateKey<N>>> {
         let mut rng = self
             .seed
             .map(ChaChaRng::seed_from_u64)
@@ -81,8 +190,7 @@ impl GenAccounts {
             Ok(prefix)
         }).transpose()?;
 
-        // Add additional accounts to the public balances
-        let accounts: IndexMap<Address<N>, PrivateKey<N>> = (0..self.count)
+        (0..self.count)
             .map(|_| {
                 if let Some(vanity) = &vanity {
                     loop {
@@ -107,38 +215,65 @@ impl GenAccounts {
                     Ok((addr, key))
                 }
             })
-            .collect::<Result<IndexMap<_, _>>>()?;
+            .collect()
+    }
+}
 
-        match self.output {
-            // Write the accounts JSON file.
-            Some(accounts_file) => {
-                let file = fs::File::options()
-                    .append(false)
-                    .create(true)
-                    .truncate(true)
-                    .write(true)
-                    .open(&accounts_file)?;
-                serde_json::to_writer_pretty(file, &accounts)?;
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use snarkvm::console::network::MainnetV0;
 
-                println!(
-                    "Accounts written to {}.",
-                    accounts_file.display().to_string().yellow()
-                );
-            }
+    use super::*;
 
-            // Write the accounts to stdout if no file was passed.
-            None => {
-                println!("Generated {} accounts:", self.count,);
-                for (addr, key) in accounts {
-                    println!(
-                        "\t{}: {}",
-                        addr.to_string().yellow(),
-                        key.to_string().cyan()
-                    );
-                }
-            }
-        }
+    type N = MainnetV0;
 
-        Ok(())
+    fn keys() -> Vec<PrivateKey<N>> {
+        let rng = &mut ChaChaRng::seed_from_u64(1);
+        (0..2).map(|_| PrivateKey::new(rng).unwrap()).collect()
+    }
+
+    fn addr(key: &PrivateKey<N>) -> Address<N> {
+        Address::try_from(key).unwrap()
+    }
+
+    #[test]
+    fn imports_a_list_of_keys() {
+        let keys = keys();
+        let json = json!([keys[0].to_string(), keys[1].to_string()]).to_string();
+
+        let accounts = parse_accounts::<N>(&json).unwrap();
+        assert_eq!(accounts.len(), 2);
+        assert_eq!(accounts[&addr(&keys[0])], keys[0]);
+        assert_eq!(accounts[&addr(&keys[1])], keys[1]);
+    }
+
+    #[test]
+    fn imports_a_map_of_addresses() {
+        let keys = keys();
+        // values can be a key, or a list starting with a key like the
+        // additional accounts written by `genesis`
+        let json = json!({
+            addr(&keys[0]).to_string(): keys[0].to_string(),
+            addr(&keys[1]).to_string(): [keys[1].to_string(), 100],
+        })
+        .to_string();
+
+        let accounts = parse_accounts::<N>(&json).unwrap();
+        assert_eq!(
+            accounts.keys().collect::<Vec<_>>(),
+            [&addr(&keys[0]), &addr(&keys[1])]
+        );
+    }
+
+    #[test]
+    fn rejects_mismatched_and_malformed_keys() {
+        let keys = keys();
+        let mismatched = json!({ addr(&keys[0]).to_string(): keys[1].to_string() }).to_string();
+        assert!(parse_accounts::<N>(&mismatched).is_err());
+
+        assert!(parse_accounts::<N>(r#"["not a key"]"#).is_err());
+        assert!(parse_accounts::<N>(r#"[1]"#).is_err());
+        assert!(parse_accounts::<N>(r#""a string""#).is_err());
     }
 }
@@ -138,6 +138,16 @@ pub fn estimate_cost<N: Network>(
     func: &Authorization<N>,
     use_cost_v2: bool,
 ) -> Result<u64> {
+    let (storage_cost, finalize_cost) = estimate_cost_breakdown(process, func, use_cost_v2)?;
+    Ok(storage_cost + finalize_cost)
+}
+
+/// Estimate the storage and finalize costs of an execution.
+pub fn estimate_cost_breakdown<N: Network>(
+    process: &Process<N>,
+    func: &Authorization<N>,
+    use_cost_v2: bool,
+) -> Result<(u64, u64)> {
     let transitions = func.transitions();
 
     let storage_cost = {
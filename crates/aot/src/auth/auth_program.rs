@@ -2,7 +2,9 @@ use anyhow::{Result, bail};
 use clap::Args;
 use snarkvm::{console::program::Locator, synthesizer::Process};
 
-use super::{auth_fee::estimate_cost, query};
+use snops_common::action_models::FeeBreakdown;
+
+use super::{auth_fee::estimate_cost_breakdown, query};
 use crate::{Authorization, Key, Network, Value};
 
 #[derive(Debug, Args)]
@@ -32,8 +34,9 @@ pub struct AuthorizeProgram<N: Network> {
 }
 
 impl<N: Network> AuthorizeProgram<N> {
-    /// Initializes a new authorization.
-    pub fn parse(self) -> Result<(Authorization<N>, u64)> {
+    /// Initializes a new authorization, along with its estimated fee without a
+    /// priority fee.
+    pub fn parse(self) -> Result<(Authorization<N>, FeeBreakdown)> {
         let private_key = self.key.try_get()?;
 
         let mut process = Process::load()?;
@@ -54,8 +57,59 @@ impl<N: Network> AuthorizeProgram<N> {
                 &mut super::rng_from_seed(self.seed),
             )?;
 
-        let cost = estimate_cost(&process, &auth, !self.cost_v1)?;
+        let (storage, finalize) = estimate_cost_breakdown(&process, &auth, !self.cost_v1)?;
+        let fee = FeeBreakdown {
+            storage,
+            finalize,
+            ..Default::default()
+        };
+
+        Ok((auth, fee))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use snarkvm::console::network::MainnetV0;
+
+    use super::*;
+    use crate::{Address, PrivateKey, auth::auth_fee::estimate_cost};
+
+    type N = MainnetV0;
+
+    #[test]
+    fn fee_breakdown_adds_up_to_the_estimated_cost() {
+        let key = PrivateKey::<N>::new(&mut rand::thread_rng()).unwrap();
+        let to = Address::try_from(key).unwrap();
+        let (auth, fee) = AuthorizeProgram::<N> {
+            key: Key {
+                private_key: Some(key),
+                private_key_file: None,
+            },
+            options: AuthProgramOptions {
+                query: None,
+                locator: Locator::from_str("credits.aleo/transfer_public").unwrap(),
+                inputs: vec![
+                    Value::from_str(&to.to_string()).unwrap(),
+                    Value::from_str("1u64").unwrap(),
+                ],
+            },
+            seed: Some(1),
+            cost_v1: false,
+        }
+        .parse()
+        .unwrap();
+
+        // a dry run of an execution only has storage and finalize costs
+        assert!(fee.storage > 0);
+        assert!(fee.finalize > 0);
+        assert_eq!(fee.synthesis + fee.namespace + fee.priority, 0);
 
-        Ok((auth, cost))
+        // the fee is authorized for the same cost the dry run reports
+        let process = Process::<N>::load().unwrap();
+        assert_eq!(fee.base(), fee.storage + fee.finalize);
+        assert_eq!(fee.base(), estimate_cost(&process, &auth, true).unwrap());
     }
 }
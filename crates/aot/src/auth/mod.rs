@@ -5,6 +5,7 @@ use clap::{Args, Subcommand};
 use rand::SeedableRng;
 use rand_chacha::ChaChaRng;
 use snarkvm::synthesizer::{Process, process::deployment_cost};
+use snops_common::action_models::FeeBreakdown;
 
 use crate::{Key, Network};
 
@@ -15,6 +16,7 @@ pub mod auth_id;
 pub mod auth_program;
 pub mod execute;
 pub mod query;
+pub mod records;
 
 pub fn rng_from_seed(seed: Option<u64>) -> ChaChaRng {
     if let Some(seed) = seed {
@@ -35,6 +37,7 @@ pub enum AuthCommand<N: Network> {
     Id(AuthArgs<N>),
     Cost(CostCommand<N>),
     Deploy(AuthDeployCommand<N>),
+    Records(records::FindRecords<N>),
 }
 
 /// Estimate the cost of a program execution or deployment.
@@ -71,6 +74,9 @@ pub struct AuthProgramCommand<N: Network> {
     /// Enable cost v1 for the transaction cost estimation (v2 by default)
     #[clap(long, default_value_t = false)]
     pub cost_v1: bool,
+    /// Print the estimated fee breakdown as JSON instead of the authorization
+    #[clap(long)]
+    pub estimate_fee: bool,
 }
 
 /// Deploy a program to the network.
@@ -93,6 +99,9 @@ pub struct AuthDeployCommand<N: Network> {
     /// Enable cost v1 for the transaction cost estimation (v2 by default)
     #[clap(long, default_value_t = false)]
     pub cost_v1: bool,
+    /// Print the estimated fee breakdown as JSON instead of the authorization
+    #[clap(long)]
+    pub estimate_fee: bool,
 }
 
 impl<N: Network> AuthCommand<N> {
@@ -160,11 +169,12 @@ impl<N: Network> AuthCommand<N> {
                 fee_opts,
                 seed,
                 cost_v1,
+                estimate_fee,
             }) => {
                 let query = program_opts.query.clone();
 
                 // authorize the program execution without a fee
-                let (auth, cost) = auth_program::AuthorizeProgram {
+                let (auth, fee) = auth_program::AuthorizeProgram {
                     key: key.clone(),
                     options: program_opts,
                     seed,
@@ -172,6 +182,15 @@ impl<N: Network> AuthCommand<N> {
                 }
                 .parse()?;
 
+                if estimate_fee {
+                    let fee = FeeBreakdown {
+                        priority: fee_opts.priority_fee,
+                        ..fee
+                    };
+                    println!("{}", serde_json::to_string(&fee)?);
+                    return Ok(());
+                }
+
                 if skip_fee {
                     println!("{}", serde_json::to_string(&auth)?);
                     return Ok(());
@@ -185,7 +204,7 @@ impl<N: Network> AuthCommand<N> {
                     deployment: None,
                     query,
                     id: Some(auth.to_execution_id()?),
-                    cost: Some(cost),
+                    cost: Some(fee.base()),
                     seed,
                     cost_v1,
                 }
@@ -208,6 +227,7 @@ impl<N: Network> AuthCommand<N> {
                 fee_opts,
                 seed,
                 cost_v1,
+                estimate_fee,
             }) => {
                 // authorize the deployment without a fee
                 let AuthBlob::Deploy {
@@ -222,6 +242,19 @@ impl<N: Network> AuthCommand<N> {
                     unreachable!("authorize deploy never returns a program auth")
                 };
 
+                if estimate_fee {
+                    let (_, (storage, synthesis, namespace)) = deployment_cost(&deployment)?;
+                    let fee = FeeBreakdown {
+                        storage,
+                        synthesis,
+                        namespace,
+                        priority: fee_opts.priority_fee,
+                        ..Default::default()
+                    };
+                    println!("{}", serde_json::to_string(&fee)?);
+                    return Ok(());
+                }
+
                 if skip_fee {
                     println!(
                         "{}",
@@ -259,6 +292,8 @@ impl<N: Network> AuthCommand<N> {
                 );
                 Ok(())
             }
+            // records command finds the unspent records owned by a key
+            AuthCommand::Records(command) => command.parse(),
         }
     }
 }
@@ -0,0 +1,43 @@
+use anyhow::{Result, bail};
+use clap::Args;
+use serde_json::{Value, json};
+
+use crate::{Key, Network, ViewKey};
+
+/// Find the unspent records owned by a private key through a ledger query
+/// service, printing them as JSON.
+#[derive(Debug, Args)]
+pub struct FindRecords<N: Network> {
+    #[clap(flatten)]
+    pub key: Key<N>,
+    /// The ledger query service to scan for records.
+    #[clap(env, short, long)]
+    pub query: String,
+    /// Only include records with at least this many microcredits.
+    #[clap(long, default_value_t = 0)]
+    pub min_microcredits: u64,
+}
+
+impl<N: Network> FindRecords<N> {
+    pub fn parse(self) -> Result<()> {
+        let view_key = ViewKey::try_from(self.key.try_get()?)?;
+
+        // the query service scans the ledger the first time it sees a view key
+        let res = reqwest::blocking::Client::new()
+            .post(format!("{}/records", self.query))
+            .json(&json!({
+                "view_key": view_key,
+                "min_microcredits": self.min_microcredits,
+            }))
+            .send()?;
+
+        let status = res.status();
+        let body: Value = res.json()?;
+        if !status.is_success() {
+            bail!("failed to find records ({status}): {}", body["error"]);
+        }
+
+        println!("{}", serde_json::to_string(&body["records"])?);
+        Ok(())
+    }
+}
@@ -9,14 +9,16 @@ use rand::{CryptoRng, Rng, SeedableRng};
 use rand_chacha::ChaChaRng;
 use serde::{Serialize, de::DeserializeOwned};
 use snarkvm::{
+    console::program::ProgramOwner,
     ledger::{
         Header, Ratify, Solutions,
         committee::MIN_VALIDATOR_STAKE,
         store::{ConsensusStore, helpers::memory::ConsensusMemory},
     },
-    synthesizer::program::FinalizeGlobalState,
+    synthesizer::{Process, Program, process::deployment_cost, program::FinalizeGlobalState},
     utilities::ToBytes,
 };
+use snops_common::program_bundle::{read_bundle, sort_by_imports};
 
 use crate::{
     Address, Block, CTRecord, Committee, DbLedger, MemVM, Network, NetworkId, PTRecord, PrivateKey,
@@ -108,11 +110,49 @@ pub struct Genesis<N: Network> {
     #[clap(long)]
     pub bonded_commissions: Option<AddressMap<N, u8>>,
 
+    /// An optional map from address to public balance, added to the balances
+    /// of the committee and additional accounts. Only `credits.aleo/account`
+    /// can be seeded; mappings of other programs are only written by finalize
+    /// and must be initialized by transactions after genesis.
+    #[clap(long)]
+    pub public_balances: Option<AddressMap<N, u64>>,
+
+    /// Programs to deploy in the genesis block, paid for by the genesis key.
+    /// Can be a `.aleo` file, a directory of `.aleo` files, or a JSON bundle
+    /// manifest. Programs are deployed after the programs they import.
+    #[clap(long)]
+    pub programs: Option<PathBuf>,
+
     /// Optionally initialize a ledger as well.
     #[clap(long)]
     pub ledger: Option<PathBuf>,
 }
 
+/// Add `extra` to `balances`, failing if the total would exceed `supply`, the
+/// starting supply left after the committee stake.
+fn add_public_balances<N: Network>(
+    balances: &mut IndexMap<Address<N>, u64>,
+    extra: &IndexMap<Address<N>, u64>,
+    supply: u64,
+) -> Result<()> {
+    for (addr, balance) in extra {
+        let entry = balances.entry(*addr).or_default();
+        *entry = entry
+            .checked_add(*balance)
+            .ok_or_else(|| anyhow!("public balance of {addr} overflows"))?;
+    }
+
+    let total = balances
+        .values()
+        .try_fold(0u64, |acc, x| acc.checked_add(*x))
+        .ok_or_else(|| anyhow!("public balances overflow"))?;
+    ensure!(
+        total <= supply,
+        "public balances exceed the starting supply: {total} > {supply}"
+    );
+    Ok(())
+}
+
 /// Returns a new genesis block for a quorum chain.
 pub fn genesis_quorum<R: Rng + CryptoRng, N: Network>(
     vm: &MemVM<N>,
@@ -314,6 +354,15 @@ impl<N: Network> Genesis<N> {
             })
             .collect::<Result<IndexMap<_, _>>>()?;
 
+        // Add the requested public balances
+        if let Some(extra) = &self.public_balances {
+            add_public_balances(
+                &mut public_balances,
+                &extra.0,
+                N::STARTING_SUPPLY.saturating_sub(committee.total_stake()),
+            )?;
+        }
+
         // Calculate the public balance per validator.
         let remaining_balance = N::STARTING_SUPPLY
             .saturating_sub(committee.total_stake())
@@ -356,13 +405,14 @@ impl<N: Network> Genesis<N> {
                 .into_iter()
                 .map(|(addr, (key, balance, _))| {
                     let record_tx: Transaction<N> =
-                        public_transaction::<N, ConsensusMemory<_>, N::Circuit>(
+                        public_transaction::<N, ConsensusMemory<_>, N::Circuit, _>(
                             "transfer_public_to_private",
                             &vm,
                             addr,
                             record_balance,
                             key,
                             None,
+                            &mut rng,
                         )?;
                     // Cannot fail because transfer_public_to_private always emits a
                     // record.
@@ -378,6 +428,38 @@ impl<N: Network> Genesis<N> {
 
         // endregion: Genesis Records
 
+        // region: Genesis Programs
+        if let Some(path) = &self.programs {
+            let programs = read_bundle(path)?
+                .iter()
+                .map(|source| Ok(source.parse::<Program<N>>()?))
+                .collect::<Result<Vec<_>>>()?;
+            let programs = sort_by_imports(
+                programs,
+                |program| program.id().to_string(),
+                |program| program.imports().keys().map(|id| id.to_string()).collect(),
+            )?;
+
+            // deployments are built in a separate process because the programs
+            // are only added to the VM when the genesis block is finalized
+            let mut process = Process::<N>::load()?;
+            for program in programs {
+                let deployment = process.deploy::<N::Circuit, _>(&program, &mut rng)?;
+                process.add_program(&program)?;
+
+                let deployment_id = deployment.to_deployment_id()?;
+                let (cost, _) = deployment_cost(&deployment)?;
+                let fee_auth =
+                    vm.authorize_fee_public(&genesis_key, cost, 0, deployment_id, &mut rng)?;
+                let fee = vm.execute_fee_authorization(fee_auth, None, &mut rng)?;
+                let owner = ProgramOwner::new(&genesis_key, deployment_id, &mut rng)?;
+
+                tracing::info!("deploying {} in the genesis block", program.id());
+                txs.push(Transaction::from_deployment(owner, deployment, fee)?);
+            }
+        }
+        // endregion: Genesis Programs
+
         // Initialize the genesis block.
         let block = genesis_quorum(
             &vm,
@@ -517,3 +599,42 @@ impl<N: Network> Genesis<N> {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use snarkvm::console::network::MainnetV0;
+
+    use super::*;
+
+    type N = MainnetV0;
+
+    fn addrs() -> Vec<Address<N>> {
+        let rng = &mut ChaChaRng::seed_from_u64(1);
+        (0..2)
+            .map(|_| Address::try_from(&PrivateKey::<N>::new(rng).unwrap()).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn public_balances_are_added_to_existing_balances() {
+        let addrs = addrs();
+        let mut balances = IndexMap::from([(addrs[0], 10)]);
+        let extra = IndexMap::from([(addrs[0], 5), (addrs[1], 7)]);
+
+        add_public_balances(&mut balances, &extra, 100).unwrap();
+        assert_eq!(balances[&addrs[0]], 15);
+        assert_eq!(balances[&addrs[1]], 7);
+    }
+
+    #[test]
+    fn public_balances_cannot_exceed_the_supply() {
+        let addrs = addrs();
+        let mut balances = IndexMap::from([(addrs[0], 10)]);
+
+        let extra = IndexMap::from([(addrs[1], 91)]);
+        assert!(add_public_balances(&mut balances.clone(), &extra, 100).is_err());
+
+        let extra = IndexMap::from([(addrs[0], u64::MAX)]);
+        assert!(add_public_balances(&mut balances, &extra, u64::MAX).is_err());
+    }
+}
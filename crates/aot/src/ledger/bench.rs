@@ -0,0 +1,188 @@
+use std::{fs, path::Path, time::Duration};
+
+use anyhow::Result;
+use clap::ValueEnum;
+use serde::Serialize;
+use serde_json::json;
+
+/// Measurements from applying one block to a ledger.
+#[derive(Debug, Clone, Serialize)]
+pub struct BlockTiming {
+    pub height: u32,
+    pub transactions: usize,
+    pub aborted_transactions: usize,
+    pub bytes: usize,
+    /// Time spent verifying the block, in milliseconds.
+    pub verify_ms: f64,
+    /// Time spent finalizing the block into the ledger, in milliseconds.
+    pub finalize_ms: f64,
+}
+
+impl BlockTiming {
+    pub fn new(
+        height: u32,
+        transactions: usize,
+        aborted_transactions: usize,
+        bytes: usize,
+        verify: Duration,
+        finalize: Duration,
+    ) -> Self {
+        Self {
+            height,
+            transactions,
+            aborted_transactions,
+            bytes,
+            verify_ms: verify.as_secs_f64() * 1000.0,
+            finalize_ms: finalize.as_secs_f64() * 1000.0,
+        }
+    }
+}
+
+/// Totals across a replay.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ReplaySummary {
+    pub blocks: usize,
+    pub transactions: usize,
+    pub verify_ms: f64,
+    pub finalize_ms: f64,
+    pub transactions_per_second: f64,
+}
+
+impl ReplaySummary {
+    pub fn new(timings: &[BlockTiming]) -> Self {
+        let mut summary = timings.iter().fold(Self::default(), |mut acc, t| {
+            acc.blocks += 1;
+            acc.transactions += t.transactions;
+            acc.verify_ms += t.verify_ms;
+            acc.finalize_ms += t.finalize_ms;
+            acc
+        });
+
+        let total_secs = (summary.verify_ms + summary.finalize_ms) / 1000.0;
+        if total_secs > 0.0 {
+            summary.transactions_per_second = summary.transactions as f64 / total_secs;
+        }
+        summary
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, ValueEnum)]
+pub enum BenchFormat {
+    #[default]
+    Csv,
+    Json,
+}
+
+/// Write per-block timings to a file.
+pub fn write_timings(path: &Path, format: BenchFormat, timings: &[BlockTiming]) -> Result<()> {
+    let out = match format {
+        BenchFormat::Csv => {
+            let mut out =
+                "height,transactions,aborted_transactions,bytes,verify_ms,finalize_ms\n".to_owned();
+            for t in timings {
+                out.push_str(&format!(
+                    "{},{},{},{},{:.3},{:.3}\n",
+                    t.height,
+                    t.transactions,
+                    t.aborted_transactions,
+                    t.bytes,
+                    t.verify_ms,
+                    t.finalize_ms
+                ));
+            }
+            out
+        }
+        BenchFormat::Json => serde_json::to_string_pretty(&json!({
+            "blocks": timings,
+            "summary": ReplaySummary::new(timings),
+        }))?,
+    };
+
+    fs::write(path, out)?;
+    Ok(())
+}
+
+/// Run `f` with a flamegraph of its spans written to `path` as folded stacks.
+#[cfg(feature = "flame")]
+pub fn with_flamegraph<T>(path: &Path, f: impl FnOnce() -> Result<T>) -> Result<T> {
+    use tracing_subscriber::layer::SubscriberExt;
+
+    let (layer, guard) = tracing_flame::FlameLayer::with_file(path)?;
+    let subscriber = tracing_subscriber::registry().with(layer);
+    let res = tracing::subscriber::with_default(subscriber, f);
+    guard.flush()?;
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn timings() -> Vec<BlockTiming> {
+        vec![
+            BlockTiming::new(
+                1,
+                3,
+                1,
+                100,
+                Duration::from_millis(500),
+                Duration::from_millis(250),
+            ),
+            BlockTiming::new(
+                2,
+                5,
+                0,
+                200,
+                Duration::from_millis(1000),
+                Duration::from_millis(250),
+            ),
+        ]
+    }
+
+    #[test]
+    fn summary_totals_timings() {
+        let summary = ReplaySummary::new(&timings());
+        assert_eq!(summary.blocks, 2);
+        assert_eq!(summary.transactions, 8);
+        assert_eq!(summary.verify_ms, 1500.0);
+        assert_eq!(summary.finalize_ms, 500.0);
+        // 8 transactions over 2 seconds
+        assert_eq!(summary.transactions_per_second, 4.0);
+    }
+
+    #[test]
+    fn empty_summary_has_no_throughput() {
+        let summary = ReplaySummary::new(&[]);
+        assert_eq!(summary.blocks, 0);
+        assert_eq!(summary.transactions_per_second, 0.0);
+    }
+
+    #[test]
+    fn writes_csv_timings() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("bench.csv");
+        write_timings(&path, BenchFormat::Csv, &timings()).unwrap();
+
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "height,transactions,aborted_transactions,bytes,verify_ms,finalize_ms\n\
+             1,3,1,100,500.000,250.000\n\
+             2,5,0,200,1000.000,250.000\n"
+        );
+    }
+
+    #[test]
+    fn writes_json_timings() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("bench.json");
+        write_timings(&path, BenchFormat::Json, &timings()).unwrap();
+
+        let json: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(json["blocks"].as_array().unwrap().len(), 2);
+        assert_eq!(json["blocks"][1]["height"], 2);
+        assert_eq!(json["blocks"][0]["aborted_transactions"], 1);
+        assert_eq!(json["summary"]["transactions"], 8);
+        assert_eq!(json["summary"]["transactions_per_second"], 4.0);
+    }
+}
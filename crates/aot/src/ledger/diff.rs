@@ -0,0 +1,175 @@
+use std::{
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+use aleo_std::StorageMode;
+use anyhow::{Result, bail};
+use clap::Args;
+use snarkvm::ledger::store::{
+    BlockStorage, FinalizeStorage,
+    helpers::{
+        MapRead,
+        rocksdb::{BlockDB, FinalizeDB},
+    },
+};
+use snops_common::state::ledger_digest::{LedgerDiff, LedgerDigest};
+
+use crate::Network;
+
+/// Compare two ledgers, finding the first height at which their blocks differ
+/// and the finalize mapping keys with different values.
+#[derive(Debug, Args)]
+pub struct Diff {
+    /// The first ledger.
+    pub a: PathBuf,
+    /// The second ledger.
+    pub b: PathBuf,
+    /// Print the differences as JSON.
+    #[arg(long)]
+    pub json: bool,
+}
+
+impl Diff {
+    pub fn parse<N: Network>(self, genesis: Option<&Path>) -> Result<()> {
+        // the rocksdb handle is global to the process, so the second ledger is
+        // read by a child `ledger digest` process
+        let b = child_ledger_digest(genesis, &self.b)?;
+        let a = ledger_digest::<N>(StorageMode::Custom(self.a.clone()), true)?;
+        let diff = a.diff(&b);
+
+        if self.json {
+            println!("{}", serde_json::to_string_pretty(&diff)?);
+        } else {
+            print_diff(&diff);
+        }
+
+        Ok(())
+    }
+}
+
+/// Read a ledger's digest, including transactions, by running `ledger digest`
+/// with the current executable.
+fn child_ledger_digest(genesis: Option<&Path>, ledger: &Path) -> Result<LedgerDigest> {
+    let mut command = Command::new(std::env::current_exe()?);
+    command.arg("ledger");
+    if let Some(genesis) = genesis {
+        command.arg("--genesis").arg(genesis);
+    }
+    let output = command
+        .arg("--ledger")
+        .arg(ledger)
+        .arg("digest")
+        .stdin(Stdio::null())
+        .stderr(Stdio::inherit())
+        .output()?;
+
+    if !output.status.success() {
+        bail!(
+            "failed to read ledger {}: {}",
+            ledger.display(),
+            output.status
+        );
+    }
+    Ok(serde_json::from_slice(&output.stdout)?)
+}
+
+/// Print a ledger's digest as JSON, for `ledger diff`.
+pub fn print_digest<N: Network>(ledger: PathBuf) -> Result<()> {
+    let digest = ledger_digest::<N>(StorageMode::Custom(ledger), true)?;
+    println!("{}", serde_json::to_string(&digest)?);
+    Ok(())
+}
+
+fn print_diff(diff: &LedgerDiff) {
+    println!("heights: {} / {}", diff.heights.0, diff.heights.1);
+
+    match (diff.diverged_at, &diff.block_hashes) {
+        (Some(height), Some((a, b))) => {
+            println!("diverged at height {height}");
+            println!("  a: {a}");
+            println!("  b: {b}");
+        }
+        _ => println!(
+            "blocks match up to height {}",
+            diff.heights.0.min(diff.heights.1)
+        ),
+    }
+
+    if let Some((a, b)) = &diff.transactions {
+        for tx in a {
+            println!("  transaction only in a: {tx}");
+        }
+        for tx in b {
+            println!("  transaction only in b: {tx}");
+        }
+    }
+
+    if diff.mappings.is_empty() {
+        println!("finalize mappings match");
+    }
+    for (mapping, keys) in &diff.mappings {
+        println!("mapping {mapping}:");
+        for key in &keys.only_a {
+            println!("  only in a: {key}");
+        }
+        for key in &keys.only_b {
+            println!("  only in b: {key}");
+        }
+        for key in &keys.changed {
+            println!("  changed: {key}");
+        }
+        if keys.summarized {
+            println!("  entries differ");
+        }
+    }
+}
+
+/// Summarize a ledger's block hashes and finalize mappings.
+pub fn ledger_digest<N: Network>(
+    storage_mode: StorageMode,
+    transactions: bool,
+) -> Result<LedgerDigest> {
+    let blocks = BlockDB::<N>::open(storage_mode.clone())?;
+    let finalize = FinalizeDB::<N>::open(storage_mode)?;
+    digest(&blocks, &finalize, transactions)
+}
+
+/// Summarize a ledger from its block and finalize stores, walking the
+/// key-values the same way checkpoints do.
+pub fn digest<N: Network>(
+    blocks: &BlockDB<N>,
+    finalize: &FinalizeDB<N>,
+    transactions: bool,
+) -> Result<LedgerDigest> {
+    let mut digest = LedgerDigest::default();
+
+    let mut height = 0;
+    while let Some(hash) = blocks.get_block_hash(height)? {
+        if transactions {
+            if let Some(txs) = blocks.get_block_transactions(&hash)? {
+                if !txs.is_empty() {
+                    let ids = txs.transaction_ids().map(|id| id.to_string()).collect();
+                    digest.transactions.insert(height, ids);
+                }
+            }
+        }
+        digest.block_hashes.push(hash.to_string());
+        height += 1;
+    }
+
+    for (program, mappings) in finalize.program_id_map().iter_confirmed() {
+        for mapping in mappings.iter() {
+            let entries = finalize
+                .get_mapping_confirmed(*program, *mapping)?
+                .into_iter()
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect();
+            digest
+                .mappings
+                .insert(format!("{program}/{mapping}"), entries);
+        }
+    }
+
+    Ok(digest)
+}
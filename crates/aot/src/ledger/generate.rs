@@ -0,0 +1,510 @@
+use std::{collections::BTreeMap, path::PathBuf, str::FromStr};
+
+use anyhow::{Result, anyhow, bail, ensure};
+use clap::Args;
+use indexmap::{IndexMap, IndexSet};
+use rand::{CryptoRng, Rng, SeedableRng};
+use rand_chacha::ChaChaRng;
+use rayon::prelude::*;
+use serde::Deserialize;
+use snarkvm::{
+    console::{
+        program::{Identifier, ProgramID},
+        types::Field,
+    },
+    ledger::{
+        Block,
+        authority::Authority,
+        narwhal::{BatchCertificate, BatchHeader, Data, Subdag, Transmission, TransmissionID},
+    },
+    synthesizer::Program,
+};
+use tracing::{info, warn};
+
+use crate::{Address, DbLedger, Network, PrivateKey, Transaction, Value, ledger::util};
+
+/// Generates blocks ahead of time, growing a ledger without running a network.
+///
+/// Blocks are built as quorum blocks whose batch certificates are signed by
+/// the committee keys written by `genesis --committee-output`, so the
+/// resulting ledger can be loaded by regular nodes.
+#[derive(Debug, Args)]
+pub struct Generate {
+    /// The committee JSON written by `genesis --committee-output`. Every
+    /// member of the genesis committee must have a private key in this file.
+    #[arg(short, long)]
+    pub committee: PathBuf,
+    /// The number of blocks to generate.
+    #[arg(short, long)]
+    pub blocks: u32,
+    /// A JSON workload describing the transactions included in each block.
+    #[arg(short, long)]
+    pub workload: Option<PathBuf>,
+    /// The number of `transfer_public` transactions per block. Overrides the
+    /// workload file.
+    #[arg(long)]
+    pub transfers_per_block: Option<usize>,
+    /// The seed to use when generating transactions and signatures. The same
+    /// seed, ledger, committee and workload always generate the same blocks.
+    #[arg(long)]
+    pub seed: Option<u64>,
+    /// The number of seconds between the timestamps of generated blocks.
+    /// Timestamps follow the latest block rather than the clock so that
+    /// generation is reproducible.
+    #[arg(long, default_value_t = 1)]
+    pub block_interval: u32,
+}
+
+/// The transactions to include in each generated block.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Workload {
+    /// The number of `transfer_public` transactions per block.
+    #[serde(default)]
+    pub transfers_per_block: usize,
+    /// The amount of microcredits sent by each transfer.
+    #[serde(default = "default_transfer_amount")]
+    pub transfer_amount: u64,
+    /// Paths to programs to deploy, one per block, in order. Programs must
+    /// come after the programs they import.
+    #[serde(default)]
+    pub deploy: Vec<PathBuf>,
+    /// Functions to execute in every block once their program is deployed.
+    #[serde(default)]
+    pub execute: Vec<WorkloadExecution>,
+}
+
+fn default_transfer_amount() -> u64 {
+    1
+}
+
+impl Default for Workload {
+    fn default() -> Self {
+        Self {
+            transfers_per_block: 0,
+            transfer_amount: default_transfer_amount(),
+            deploy: vec![],
+            execute: vec![],
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WorkloadExecution {
+    /// The program and function to execute, e.g. `hello.aleo/main`.
+    pub locator: String,
+    /// The inputs to the function.
+    #[serde(default)]
+    pub inputs: Vec<String>,
+    /// The number of executions per block.
+    #[serde(default = "default_per_block")]
+    pub per_block: usize,
+}
+
+fn default_per_block() -> usize {
+    1
+}
+
+/// A transaction to prove for the next block.
+enum Job<N: Network> {
+    Transfer {
+        key: PrivateKey<N>,
+        to: Address<N>,
+        amount: u64,
+    },
+    Deploy {
+        key: PrivateKey<N>,
+        program: Box<Program<N>>,
+    },
+    Execute {
+        key: PrivateKey<N>,
+        program: ProgramID<N>,
+        function: Identifier<N>,
+        inputs: Vec<Value<N>>,
+    },
+}
+
+impl Generate {
+    pub fn parse<N: Network>(self, ledger: &DbLedger<N>) -> Result<()> {
+        let mut rng = ChaChaRng::seed_from_u64(self.seed.unwrap_or(1234567890u64));
+
+        // committee members' keys, as written by `genesis --committee-output`
+        let members: IndexMap<Address<N>, (PrivateKey<N>, u64)> =
+            serde_json::from_reader(std::fs::File::open(&self.committee)?)?;
+        let keys: IndexMap<Address<N>, PrivateKey<N>> = members
+            .into_iter()
+            .map(|(addr, (key, _))| (addr, key))
+            .collect();
+        ensure!(!keys.is_empty(), "committee file has no members");
+
+        let mut workload = match &self.workload {
+            Some(path) => serde_json::from_reader(std::fs::File::open(path)?)?,
+            None => Workload::default(),
+        };
+        if let Some(transfers) = self.transfers_per_block {
+            workload.transfers_per_block = transfers;
+        }
+
+        let mut deployments = workload
+            .deploy
+            .iter()
+            .map(|path| Ok(Program::<N>::from_str(&std::fs::read_to_string(path)?)?))
+            .collect::<Result<Vec<_>>>()?
+            .into_iter();
+        let executions = workload
+            .execute
+            .iter()
+            .map(|exec| {
+                let (program, function) = exec
+                    .locator
+                    .split_once('/')
+                    .ok_or_else(|| anyhow!("invalid locator `{}`", exec.locator))?;
+                let inputs = exec
+                    .inputs
+                    .iter()
+                    .map(|input| Value::from_str(input))
+                    .collect::<Result<Vec<_>>>()?;
+                Ok((
+                    ProgramID::from_str(program)?,
+                    Identifier::from_str(function)?,
+                    inputs,
+                    exec.per_block,
+                ))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        // certificates from the last anchor round that are not yet committed
+        let mut pending = vec![];
+        let start_height = ledger.latest_height();
+
+        for i in 0..self.blocks {
+            let mut jobs = vec![];
+            let mut next_key = {
+                let mut index = i as usize;
+                let keys = keys.values().copied().collect::<Vec<_>>();
+                move || {
+                    index += 1;
+                    keys[index % keys.len()]
+                }
+            };
+
+            if let Some(program) = deployments.next() {
+                jobs.push(Job::Deploy {
+                    key: next_key(),
+                    program: Box::new(program),
+                });
+            }
+
+            {
+                let process = ledger.vm().process();
+                let process = process.read();
+                for (program, function, inputs, per_block) in &executions {
+                    if !process.contains_program(program) {
+                        continue;
+                    }
+                    for _ in 0..*per_block {
+                        jobs.push(Job::Execute {
+                            key: next_key(),
+                            program: *program,
+                            function: *function,
+                            inputs: inputs.clone(),
+                        });
+                    }
+                }
+            }
+
+            for _ in 0..workload.transfers_per_block {
+                let to = *keys
+                    .get_index(rng.gen_range(0..keys.len()))
+                    .expect("committee is not empty")
+                    .0;
+                jobs.push(Job::Transfer {
+                    key: next_key(),
+                    to,
+                    amount: workload.transfer_amount,
+                });
+            }
+
+            let transactions = prove_jobs(ledger, jobs, &mut rng)?;
+            let tx_count = transactions.len();
+
+            let block = prepare_quorum_block(
+                ledger,
+                &keys,
+                transactions,
+                &mut pending,
+                self.block_interval,
+                &mut rng,
+            )?;
+            ledger.check_next_block(&block, &mut rng)?;
+            ledger.advance_to_next_block(&block)?;
+
+            if !block.aborted_transaction_ids().is_empty() {
+                warn!(
+                    "block {} aborted {} transactions",
+                    block.height(),
+                    block.aborted_transaction_ids().len()
+                );
+            }
+            info!(
+                "generated block {} ({}/{}) with {tx_count} transactions",
+                block.height(),
+                i + 1,
+                self.blocks
+            );
+        }
+
+        println!(
+            "Generated {} blocks ({start_height} -> {}), latest block hash: {}",
+            self.blocks,
+            ledger.latest_height(),
+            ledger.latest_hash()
+        );
+
+        Ok(())
+    }
+}
+
+/// Prove the transactions for a block in parallel. Transactions that fail to
+/// prove are skipped rather than failing the whole generation.
+fn prove_jobs<N: Network>(
+    ledger: &DbLedger<N>,
+    jobs: Vec<Job<N>>,
+    rng: &mut ChaChaRng,
+) -> Result<Vec<Transaction<N>>> {
+    Ok(seed_jobs(jobs, rng)
+        .into_par_iter()
+        .filter_map(|(job, seed)| {
+            let rng = &mut ChaChaRng::seed_from_u64(seed);
+            let res = match job {
+                Job::Transfer { key, to, amount } => util::make_transaction_proof::<
+                    _,
+                    _,
+                    N::Circuit,
+                    _,
+                >(
+                    ledger.vm(), to, amount, key, None, rng
+                ),
+                Job::Deploy { key, program } => {
+                    ledger.vm().deploy(&key, &program, None, 0, None, rng)
+                }
+                Job::Execute {
+                    key,
+                    program,
+                    function,
+                    inputs,
+                } => ledger.vm().execute(
+                    &key,
+                    (program, function),
+                    inputs.into_iter(),
+                    None,
+                    0,
+                    None,
+                    rng,
+                ),
+            };
+
+            res.inspect_err(|e| warn!("failed to generate transaction: {e}"))
+                .ok()
+        })
+        .collect())
+}
+
+/// Pair each job with a seed for its own rng, so jobs proven in parallel are
+/// still deterministic for a given seed.
+fn seed_jobs<T>(jobs: Vec<T>, rng: &mut ChaChaRng) -> Vec<(T, u64)> {
+    jobs.into_iter()
+        .map(|job| (job, rng.r#gen::<u64>()))
+        .collect()
+}
+
+/// The timestamp of the block after one with the `latest` timestamp.
+fn next_timestamp(latest: i64, interval: u32) -> i64 {
+    latest + i64::from(interval.max(1))
+}
+
+/// Build the next quorum block, with a subdag made of a round of certificates
+/// from every committee member followed by the leader's certificate, which
+/// carries the block's transactions.
+fn prepare_quorum_block<N: Network, R: Rng + CryptoRng>(
+    ledger: &DbLedger<N>,
+    keys: &IndexMap<Address<N>, PrivateKey<N>>,
+    transactions: Vec<Transaction<N>>,
+    pending: &mut Vec<BatchCertificate<N>>,
+    interval: u32,
+    rng: &mut R,
+) -> Result<Block<N>> {
+    let latest = ledger.latest_block();
+    let latest_round = latest.round();
+    ensure!(
+        latest_round % 2 == 0,
+        "latest block round {latest_round} is not an anchor round"
+    );
+    let odd_round = latest_round + 1;
+    let anchor_round = latest_round + 2;
+
+    // batch timestamps must move forward from the latest block
+    let timestamp = next_timestamp(latest.timestamp(), interval);
+
+    // the certificates from the previous anchor round
+    let mut previous_ids = IndexSet::new();
+    match latest.authority() {
+        Authority::Quorum(subdag) => {
+            previous_ids.insert(subdag.leader_certificate().id());
+        }
+        Authority::Beacon(_) if latest_round == 0 => {}
+        Authority::Beacon(_) => bail!("cannot extend a ledger of beacon blocks"),
+    }
+    previous_ids.extend(pending.iter().map(|c| c.id()));
+
+    let odd_certs = keys
+        .values()
+        .map(|key| {
+            certify(
+                ledger,
+                keys,
+                key,
+                odd_round,
+                timestamp,
+                IndexSet::new(),
+                previous_ids.clone(),
+                rng,
+            )
+        })
+        .collect::<Result<IndexSet<_>>>()?;
+    let odd_ids: IndexSet<_> = odd_certs.iter().map(|c| c.id()).collect();
+
+    let mut transmissions = IndexMap::new();
+    for tx in transactions {
+        let checksum = Data::Object(tx.clone()).to_checksum::<N>()?;
+        transmissions.insert(
+            TransmissionID::Transaction(tx.id(), checksum),
+            Transmission::from(tx),
+        );
+    }
+
+    let committee = ledger
+        .get_committee_lookback_for_round(anchor_round)?
+        .ok_or_else(|| anyhow!("no committee for round {anchor_round}"))?;
+    let leader = committee.get_leader(anchor_round)?;
+
+    let mut leader_cert = None;
+    let mut anchor_certs = vec![];
+    for (addr, key) in keys {
+        let transmission_ids = match *addr == leader {
+            true => transmissions.keys().copied().collect(),
+            false => IndexSet::new(),
+        };
+        let cert = certify(
+            ledger,
+            keys,
+            key,
+            anchor_round,
+            timestamp,
+            transmission_ids,
+            odd_ids.clone(),
+            rng,
+        )?;
+        match *addr == leader {
+            true => leader_cert = Some(cert),
+            false => anchor_certs.push(cert),
+        }
+    }
+    let leader_cert = leader_cert
+        .ok_or_else(|| anyhow!("missing key for round {anchor_round} leader {leader}"))?;
+
+    // uncommitted certificates from the previous anchor round are committed
+    // along with this block
+    let mut subdag = BTreeMap::new();
+    let previous = std::mem::replace(pending, anchor_certs);
+    if !previous.is_empty() {
+        subdag.insert(latest_round, previous.into_iter().collect());
+    }
+    subdag.insert(odd_round, odd_certs);
+    subdag.insert(anchor_round, [leader_cert].into());
+
+    ledger.prepare_advance_to_next_quorum_block(Subdag::from(subdag)?, transmissions, rng)
+}
+
+/// Create a batch certificate authored by `key` and signed by every other
+/// committee member.
+#[allow(clippy::too_many_arguments)]
+fn certify<N: Network, R: Rng + CryptoRng>(
+    ledger: &DbLedger<N>,
+    keys: &IndexMap<Address<N>, PrivateKey<N>>,
+    key: &PrivateKey<N>,
+    round: u64,
+    timestamp: i64,
+    transmission_ids: IndexSet<TransmissionID<N>>,
+    previous_ids: IndexSet<Field<N>>,
+    rng: &mut R,
+) -> Result<BatchCertificate<N>> {
+    let committee = ledger
+        .get_committee_lookback_for_round(round)?
+        .ok_or_else(|| anyhow!("no committee for round {round}"))?;
+    for member in committee.members().keys() {
+        ensure!(
+            keys.contains_key(member),
+            "missing private key for committee member {member}"
+        );
+    }
+
+    let header = BatchHeader::new(
+        key,
+        round,
+        timestamp,
+        committee.id(),
+        transmission_ids,
+        previous_ids,
+        rng,
+    )?;
+
+    let author = Address::try_from(key)?;
+    let signatures = keys
+        .iter()
+        .filter(|(addr, _)| **addr != author)
+        .map(|(_, key)| key.sign(&[header.batch_id()], rng))
+        .collect::<Result<IndexSet<_>>>()?;
+
+    BatchCertificate::from(header, signatures)
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{Rng, SeedableRng};
+    use rand_chacha::ChaChaRng;
+
+    use super::{next_timestamp, seed_jobs};
+
+    /// Everything drawn from the rng while planning a block: transfer
+    /// recipients followed by the per-job proving seeds.
+    fn plan(seed: u64) -> (Vec<usize>, Vec<(usize, u64)>) {
+        let mut rng = ChaChaRng::seed_from_u64(seed);
+        let recipients = (0..8).map(|_| rng.gen_range(0..4)).collect();
+        let jobs = seed_jobs((0..8).collect(), &mut rng);
+        (recipients, jobs)
+    }
+
+    #[test]
+    fn same_seed_same_output() {
+        assert_eq!(plan(1234567890), plan(1234567890));
+        assert_ne!(plan(1234567890), plan(1));
+    }
+
+    #[test]
+    fn job_seeds_are_distinct() {
+        let (_, jobs) = plan(7);
+        let mut seeds = jobs.iter().map(|(_, seed)| *seed).collect::<Vec<_>>();
+        seeds.sort();
+        seeds.dedup();
+        assert_eq!(seeds.len(), jobs.len());
+    }
+
+    #[test]
+    fn timestamps_follow_the_latest_block() {
+        assert_eq!(next_timestamp(1_700_000_000, 10), 1_700_000_010);
+        // timestamps always move forward
+        assert_eq!(next_timestamp(1_700_000_000, 0), 1_700_000_001);
+    }
+}
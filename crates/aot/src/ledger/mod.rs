@@ -12,10 +12,15 @@ use crate::{
     cli::ReloadHandler,
 };
 
+pub mod bench;
 pub mod checkpoint;
+pub mod diff;
+pub mod generate;
 pub mod hash;
 pub mod init;
 pub mod query;
+pub mod records;
+pub mod replay;
 pub mod truncate;
 pub mod util;
 pub mod view;
@@ -45,10 +50,17 @@ pub enum Commands<N: Network> {
     View(view::View<N>),
     #[clap(flatten)]
     Truncate(truncate::Truncate),
+    Replay(replay::Replay),
     Execute(Box<Execute<N>>),
+    Generate(generate::Generate),
     Query(query::LedgerQuery<N>),
     /// Hash the ledger.
     Hash,
+    /// Compare two ledgers.
+    Diff(diff::Diff),
+    /// Print the ledger's digest as JSON. Used by `diff`.
+    #[clap(hide = true)]
+    Digest,
     #[clap(subcommand)]
     Checkpoint(CheckpointCommand),
 }
@@ -60,7 +72,7 @@ impl<N: Network> Ledger<N> {
             genesis, ledger, ..
         } = self;
 
-        let genesis_block = if let Some(path) = genesis {
+        let genesis_block = if let Some(path) = &genesis {
             Block::read_le(std::fs::File::open(path)?)?
         } else {
             Block::read_le(N::genesis_bytes())?
@@ -78,6 +90,7 @@ impl<N: Network> Ledger<N> {
             }
 
             Commands::Truncate(truncate) => truncate.parse::<N>(genesis_block, ledger),
+            Commands::Replay(replay) => replay.parse::<N>(genesis_block, ledger),
             Commands::Execute(execute) => {
                 let ledger = util::open_ledger(genesis_block, ledger)?;
                 let tx = execute_local(
@@ -90,6 +103,11 @@ impl<N: Network> Ledger<N> {
                 Ok(())
             }
 
+            Commands::Generate(generate) => {
+                let ledger = util::open_ledger(genesis_block, ledger)?;
+                generate.parse::<N>(&ledger)
+            }
+
             // TODO this log handler only affects the query server not snarkos
             Commands::Query(query) => {
                 let ledger = util::open_ledger(genesis_block, ledger)?;
@@ -97,6 +115,8 @@ impl<N: Network> Ledger<N> {
             }
 
             Commands::Hash => hash::hash_ledger(ledger),
+            Commands::Diff(diff) => diff.parse::<N>(genesis.as_deref()),
+            Commands::Digest => diff::print_digest::<N>(ledger),
             Commands::Checkpoint(command) => command.parse::<N>(genesis_block, ledger),
         }
     }
@@ -4,23 +4,25 @@ use std::{
     net::{IpAddr, SocketAddr},
     ops::Deref,
     path::PathBuf,
-    sync::Arc,
+    sync::{Arc, RwLock},
 };
 
 use anyhow::Result;
 use axum::{
     Json, Router,
-    extract::{self, Query, State},
+    extract::{self, Path, Query, State},
     response::IntoResponse,
     routing::{get, post},
 };
 use clap::Args;
 use reqwest::StatusCode;
+use serde::Deserialize;
 use serde_json::json;
 use tracing_appender::non_blocking::NonBlocking;
 
+use super::records::{RecordIndex, RecordScan};
 use crate::{
-    Block, DbLedger, Network, Transaction,
+    Address, Block, DbLedger, Network, Transaction, ViewKey,
     cli::{ReloadHandler, make_env_filter},
 };
 
@@ -57,6 +59,21 @@ struct LedgerState<N: Network> {
     ledger: DbLedger<N>,
     appender: Option<NonBlocking>,
     log_level_handler: ReloadHandler,
+    records: RwLock<RecordIndex<N>>,
+}
+
+#[derive(Deserialize)]
+#[serde(bound = "N: Network")]
+struct RecordsRequest<N: Network> {
+    view_key: ViewKey<N>,
+    #[serde(default)]
+    min_microcredits: u64,
+}
+
+#[derive(Deserialize)]
+struct RecordsQuery {
+    #[serde(default)]
+    min_microcredits: u64,
 }
 
 type AppState<N> = Arc<LedgerState<N>>;
@@ -82,6 +99,7 @@ impl<N: Network> LedgerQuery<N> {
             ledger: ledger.clone(),
             appender,
             log_level_handler,
+            records: RwLock::new(RecordIndex::new(ledger.latest_height())),
         };
 
         let network = N::str_id();
@@ -109,8 +127,8 @@ impl<N: Network> LedgerQuery<N> {
             )
             .route("/block", post(Self::add_block))
             .route("/log", post(Self::set_log_level))
-            // TODO: for ahead of time ledger generation, support a /beacon_block endpoint to write
-            // beacon block TODO: api to get and decrypt records for a private key
+            .route("/records", post(Self::watch_records))
+            .route("/records/:address", get(Self::get_records))
             .with_state(Arc::new(state));
 
         let listener = tokio::net::TcpListener::bind(SocketAddr::new(self.bind, self.port)).await?;
@@ -140,6 +158,12 @@ impl<N: Network> LedgerQuery<N> {
             return StatusCode::BAD_REQUEST;
         };
 
+        // reserve the records this transaction spends so they aren't handed out
+        // again before it is confirmed
+        if let Ok(mut records) = state.records.write() {
+            records.add_transaction(state.ledger.latest_height(), &payload);
+        }
+
         match state.appender.clone() {
             Some(mut a) => match write!(a, "{}", tx_json) {
                 Ok(_) => StatusCode::OK,
@@ -180,11 +204,100 @@ impl<N: Network> LedgerQuery<N> {
             );
         }
 
-        match state.ledger.advance_to_next_block(&payload) {
-            Ok(_) => (StatusCode::OK, Json(json!({"status": "ok"}))),
-            Err(e) => (
+        if let Err(e) = state.ledger.advance_to_next_block(&payload) {
+            return (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 Json(json!({"error": format!("failed to advance block: {e}")})),
+            );
+        }
+
+        let res = match state.records.write() {
+            Ok(mut records) => records.add_block(&payload),
+            Err(_) => Err(anyhow::anyhow!("record index lock poisoned")),
+        };
+        if let Err(e) = res {
+            tracing::error!("failed to index records in block {}: {e}", payload.height());
+        }
+
+        (StatusCode::OK, Json(json!({"status": "ok"})))
+    }
+
+    /// Watch a view key's records, scanning the ledger the first time the view
+    /// key is seen, and return its unspent records.
+    ///
+    /// The scan runs on a blocking thread without holding the index lock, so
+    /// blocks and other requests are not held up while the ledger is scanned.
+    async fn watch_records(
+        state: State<AppState<N>>,
+        Json(req): Json<RecordsRequest<N>>,
+    ) -> impl IntoResponse {
+        let address = req.view_key.to_address();
+        let watched = match state.records.read() {
+            Ok(records) => records.is_watched(&address),
+            Err(_) => {
+                return (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(json!({"error": "record index lock poisoned"})),
+                );
+            }
+        };
+
+        if !watched {
+            let state = Arc::clone(&state.0);
+            let res = tokio::task::spawn_blocking(move || {
+                let scan = RecordScan::new(&state.ledger, req.view_key)?;
+                let mut records = state
+                    .records
+                    .write()
+                    .map_err(|_| anyhow::anyhow!("record index lock poisoned"))?;
+                records.insert_scan(scan, |height| state.ledger.get_block(height))
+            })
+            .await
+            .map_err(anyhow::Error::from)
+            .and_then(|res| res);
+
+            if let Err(e) = res {
+                return (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(json!({"error": format!("failed to scan records: {e}")})),
+                );
+            }
+        }
+
+        let Ok(records) = state.records.read() else {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({"error": "record index lock poisoned"})),
+            );
+        };
+
+        (
+            StatusCode::OK,
+            Json(json!({
+                "address": address,
+                "records": records.unspent(&address, req.min_microcredits),
+            })),
+        )
+    }
+
+    /// Unspent records for an address whose view key is already watched.
+    async fn get_records(
+        state: State<AppState<N>>,
+        Path(address): Path<Address<N>>,
+        Query(query): Query<RecordsQuery>,
+    ) -> impl IntoResponse {
+        let Ok(records) = state.records.read() else {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({"error": "record index lock poisoned"})),
+            );
+        };
+
+        match records.unspent(&address, query.min_microcredits) {
+            Some(unspent) => (StatusCode::OK, Json(json!(unspent))),
+            None => (
+                StatusCode::NOT_FOUND,
+                Json(json!({"error": "address is not watched"})),
             ),
         }
     }
@@ -0,0 +1,349 @@
+use std::collections::{HashMap, HashSet};
+
+use anyhow::Result;
+use indexmap::IndexMap;
+use serde::Serialize;
+use snarkvm::{
+    console::{
+        account::GraphKey,
+        program::{Entry, Identifier, Literal, Plaintext},
+        types::Field,
+    },
+    ledger::RecordsFilter,
+};
+
+use crate::{Address, Block, DbLedger, Network, PTRecord, Transaction, ViewKey};
+
+/// How many blocks a record spent by a broadcasted transaction stays reserved
+/// while waiting for the transaction to be confirmed.
+const PENDING_SPEND_BLOCKS: u32 = 10;
+
+/// An unspent record owned by a watched view key.
+#[derive(Debug, Clone, Serialize)]
+#[serde(bound = "N: Network")]
+pub struct OwnedRecord<N: Network> {
+    pub commitment: Field<N>,
+    pub tag: Field<N>,
+    pub microcredits: Option<u64>,
+    pub record: PTRecord<N>,
+}
+
+struct Watched<N: Network> {
+    view_key: ViewKey<N>,
+    sk_tag: Field<N>,
+    records: IndexMap<Field<N>, OwnedRecord<N>>,
+}
+
+/// The unspent records found by scanning the ledger for a view key, to be
+/// merged into a [`RecordIndex`] with [`RecordIndex::insert_scan`].
+///
+/// Scanning reads the whole ledger, so it is done without access to the index.
+pub struct RecordScan<N: Network> {
+    address: Address<N>,
+    /// The ledger height before the scan started. Blocks after this height
+    /// may have been missed by the scan.
+    height: u32,
+    watched: Watched<N>,
+}
+
+impl<N: Network> RecordScan<N> {
+    /// Scan the ledger for a view key's unspent records.
+    pub fn new(ledger: &DbLedger<N>, view_key: ViewKey<N>) -> Result<Self> {
+        let height = ledger.latest_height();
+        let sk_tag = GraphKey::try_from(&view_key)?.sk_tag();
+        let records = ledger
+            .find_records(&view_key, RecordsFilter::Unspent)?
+            .map(|(commitment, record)| {
+                let record = owned_record(sk_tag, commitment, record)?;
+                Ok((commitment, record))
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(Self {
+            address: view_key.to_address(),
+            height,
+            watched: Watched {
+                view_key,
+                sk_tag,
+                records,
+            },
+        })
+    }
+
+    pub fn address(&self) -> Address<N> {
+        self.address
+    }
+}
+
+/// An incremental index of the unspent records owned by a set of view keys.
+///
+/// Records are found by scanning the ledger when a view key is first watched,
+/// then kept up to date as blocks are added. Records are identified as spent by
+/// their tags, which are the view key counterpart of serial numbers and are
+/// included in every transition that consumes a record.
+pub struct RecordIndex<N: Network> {
+    watched: HashMap<Address<N>, Watched<N>>,
+    /// Tags spent by broadcasted transactions that are not yet in a block,
+    /// and the height they were broadcasted at.
+    pending: HashMap<Field<N>, u32>,
+    /// The height of the latest block added to the index.
+    height: u32,
+}
+
+impl<N: Network> RecordIndex<N> {
+    /// Create an empty index for a ledger at the given height.
+    pub fn new(height: u32) -> Self {
+        Self {
+            watched: HashMap::new(),
+            pending: HashMap::new(),
+            height,
+        }
+    }
+
+    pub fn is_watched(&self, address: &Address<N>) -> bool {
+        self.watched.contains_key(address)
+    }
+
+    /// Start tracking records for a scanned view key. Blocks added to the
+    /// index while the scan was running are fetched with `get_block` and
+    /// applied to the scanned records. Returns the view key's address.
+    pub fn insert_scan(
+        &mut self,
+        mut scan: RecordScan<N>,
+        get_block: impl Fn(u32) -> Result<Block<N>>,
+    ) -> Result<Address<N>> {
+        // the view key was watched by a concurrent scan
+        if self.is_watched(&scan.address) {
+            return Ok(scan.address);
+        }
+
+        for height in scan.height.saturating_add(1)..=self.height {
+            scan.watched.add_block(&get_block(height)?)?;
+        }
+
+        self.watched.insert(scan.address, scan.watched);
+        Ok(scan.address)
+    }
+
+    /// Unspent records for a watched address that are not spent by a pending
+    /// transaction, or `None` if the address is not watched.
+    pub fn unspent(
+        &self,
+        address: &Address<N>,
+        min_microcredits: u64,
+    ) -> Option<Vec<&OwnedRecord<N>>> {
+        let watched = self.watched.get(address)?;
+        Some(
+            watched
+                .records
+                .values()
+                .filter(|r| !self.pending.contains_key(&r.tag))
+                .filter(|r| {
+                    min_microcredits == 0 || r.microcredits.unwrap_or(0) >= min_microcredits
+                })
+                .collect(),
+        )
+    }
+
+    /// Reserve the records consumed by a broadcasted transaction until it is
+    /// confirmed, or for [`PENDING_SPEND_BLOCKS`] blocks.
+    pub fn add_transaction(&mut self, height: u32, tx: &Transaction<N>) {
+        self.reserve(height, tx.transitions().flat_map(|t| t.tags()).copied());
+    }
+
+    fn reserve(&mut self, height: u32, tags: impl IntoIterator<Item = Field<N>>) {
+        for tag in tags {
+            self.pending.insert(tag, height);
+        }
+    }
+
+    /// Update the index with the records created and spent in a block.
+    pub fn add_block(&mut self, block: &Block<N>) -> Result<()> {
+        for watched in self.watched.values_mut() {
+            watched.add_block(block)?;
+        }
+
+        let tags: HashSet<_> = block
+            .transitions()
+            .flat_map(|t| t.tags())
+            .copied()
+            .collect();
+        self.release(block.height(), &tags);
+        self.height = self.height.max(block.height());
+
+        Ok(())
+    }
+
+    /// Drop reservations for tags spent at `height` and reservations that
+    /// have expired.
+    fn release(&mut self, height: u32, spent: &HashSet<Field<N>>) {
+        self.pending.retain(|tag, broadcasted| {
+            !spent.contains(tag) && height.saturating_sub(*broadcasted) < PENDING_SPEND_BLOCKS
+        });
+    }
+}
+
+impl<N: Network> Watched<N> {
+    fn add_block(&mut self, block: &Block<N>) -> Result<()> {
+        let tags: HashSet<_> = block.transitions().flat_map(|t| t.tags()).collect();
+        self.records.retain(|_, r| !tags.contains(&r.tag));
+
+        for (commitment, record) in block.records() {
+            if !record.is_owner(&self.view_key) {
+                continue;
+            }
+            let record = record.decrypt(&self.view_key)?;
+            let record = owned_record(self.sk_tag, *commitment, record)?;
+            // the record may be created and spent in the same block
+            if !tags.contains(&record.tag) {
+                self.records.insert(*commitment, record);
+            }
+        }
+        Ok(())
+    }
+}
+
+fn owned_record<N: Network>(
+    sk_tag: Field<N>,
+    commitment: Field<N>,
+    record: PTRecord<N>,
+) -> Result<OwnedRecord<N>> {
+    let microcredits = match record.data().get(&Identifier::try_from("microcredits")?) {
+        Some(Entry::Private(Plaintext::Literal(Literal::U64(amount), _))) => Some(**amount),
+        _ => None,
+    };
+
+    Ok(OwnedRecord {
+        commitment,
+        tag: PTRecord::<N>::tag(sk_tag, commitment)?,
+        microcredits,
+        record,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::Cell, str::FromStr};
+
+    use anyhow::anyhow;
+    use rand::SeedableRng;
+    use rand_chacha::ChaChaRng;
+    use snarkvm::console::network::MainnetV0;
+
+    use super::*;
+    use crate::PrivateKey;
+
+    type N = MainnetV0;
+
+    fn view_key(seed: u64) -> ViewKey<N> {
+        let key = PrivateKey::<N>::new(&mut ChaChaRng::seed_from_u64(seed)).unwrap();
+        ViewKey::try_from(&key).unwrap()
+    }
+
+    /// A scan at `height` that found records with the given amounts.
+    fn scan(view_key: ViewKey<N>, height: u32, amounts: &[u64]) -> RecordScan<N> {
+        let address = view_key.to_address();
+        let sk_tag = GraphKey::try_from(&view_key).unwrap().sk_tag();
+        let records: IndexMap<_, _> = amounts
+            .iter()
+            .enumerate()
+            .map(|(i, amount)| {
+                let record = PTRecord::<N>::from_str(&format!(
+                    "{{ owner: {address}.private, microcredits: {amount}u64.private, _nonce: 0group.public }}"
+                ))
+                .unwrap();
+                let commitment = Field::from_u64(i as u64 + 1);
+                (commitment, owned_record(sk_tag, commitment, record).unwrap())
+            })
+            .collect();
+
+        RecordScan {
+            address,
+            height,
+            watched: Watched {
+                view_key,
+                sk_tag,
+                records,
+            },
+        }
+    }
+
+    fn no_blocks(height: u32) -> Result<Block<N>> {
+        Err(anyhow!("unexpected block {height}"))
+    }
+
+    fn microcredits(index: &RecordIndex<N>, address: &Address<N>, min: u64) -> Vec<u64> {
+        index
+            .unspent(address, min)
+            .unwrap()
+            .into_iter()
+            .map(|r| r.microcredits.unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn unspent_filters_by_amount() {
+        let mut index = RecordIndex::new(5);
+        let address = index
+            .insert_scan(scan(view_key(1), 5, &[10, 20, 30]), no_blocks)
+            .unwrap();
+
+        assert!(index.is_watched(&address));
+        assert_eq!(microcredits(&index, &address, 0), [10, 20, 30]);
+        assert_eq!(microcredits(&index, &address, 20), [20, 30]);
+        assert!(index.unspent(&view_key(2).to_address(), 0).is_none());
+    }
+
+    #[test]
+    fn pending_spends_are_hidden_until_released() {
+        let mut index = RecordIndex::new(5);
+        let address = index
+            .insert_scan(scan(view_key(1), 5, &[10, 20]), no_blocks)
+            .unwrap();
+        let tag = index.unspent(&address, 0).unwrap()[0].tag;
+
+        index.reserve(5, [tag]);
+        assert_eq!(microcredits(&index, &address, 0), [20]);
+
+        // reservations last for PENDING_SPEND_BLOCKS blocks
+        index.release(5 + PENDING_SPEND_BLOCKS - 1, &HashSet::new());
+        assert_eq!(microcredits(&index, &address, 0), [20]);
+        index.release(5 + PENDING_SPEND_BLOCKS, &HashSet::new());
+        assert_eq!(microcredits(&index, &address, 0), [10, 20]);
+
+        // or until the tag is spent in a block
+        index.reserve(6, [tag]);
+        index.release(7, &[tag].into());
+        assert!(index.pending.is_empty());
+    }
+
+    #[test]
+    fn concurrent_scans_keep_the_first() {
+        let mut index = RecordIndex::new(5);
+        index
+            .insert_scan(scan(view_key(1), 5, &[10]), no_blocks)
+            .unwrap();
+        let address = index
+            .insert_scan(scan(view_key(1), 5, &[10, 20]), no_blocks)
+            .unwrap();
+        assert_eq!(microcredits(&index, &address, 0), [10]);
+    }
+
+    #[test]
+    fn scans_catch_up_with_blocks_added_during_the_scan() {
+        let mut index = RecordIndex::new(7);
+        let requested = Cell::new(vec![]);
+        let res = index.insert_scan(scan(view_key(1), 5, &[10]), |height| {
+            let mut heights = requested.take();
+            heights.push(height);
+            requested.set(heights);
+            no_blocks(height)
+        });
+
+        // the first missed block is requested, and a failed catch-up leaves
+        // the view key unwatched
+        assert!(res.is_err());
+        assert_eq!(requested.take(), [6]);
+        assert!(!index.is_watched(&view_key(1).to_address()));
+    }
+}
@@ -0,0 +1,218 @@
+use std::{
+    fs,
+    os::fd::{AsRawFd, RawFd},
+    path::PathBuf,
+    time::Instant,
+};
+
+use anyhow::{Result, bail};
+use clap::Args;
+use nix::{
+    sys::wait::{WaitStatus, waitpid},
+    unistd::{self, ForkResult},
+};
+use snarkvm::{
+    console::program::Network,
+    ledger::Block,
+    utilities::{FromBytes, ToBytes},
+};
+use snops_checkpoint::{CheckpointManager, RetentionPolicy};
+use tracing::info;
+
+use super::bench::{self, BenchFormat, BlockTiming, ReplaySummary};
+use crate::{DbLedger, ledger::util};
+
+/// Replays blocks from a ledger into a fresh ledger (`<ledger>.new`) up to a
+/// specific height or amount to rollback to. Replays every block when neither
+/// is passed. An existing `<ledger>.new` is removed first.
+#[derive(Debug, Args)]
+pub struct Replay {
+    /// The height to replay to.
+    #[arg(long)]
+    height: Option<u32>,
+    /// The amount of blocks to rollback to.
+    #[arg(long)]
+    amount: Option<u32>,
+    /// How many blocks to skip when reading.
+    #[arg(long, default_value_t = 1)]
+    skip: u32,
+    /// When checkpoint is enabled, checkpoints.
+    #[arg(short, long, default_value_t = false)]
+    checkpoint: bool,
+    /// Verify each block before applying it, and write each block's verify
+    /// and finalize times to this file.
+    #[arg(long)]
+    bench: Option<PathBuf>,
+    /// The format of the benchmark file.
+    #[arg(long, value_enum, default_value_t)]
+    bench_format: BenchFormat,
+    /// Write a flamegraph of the replay to this file as folded stacks.
+    #[cfg(feature = "flame")]
+    #[arg(long)]
+    flame: Option<PathBuf>,
+}
+
+impl Replay {
+    pub fn parse<N: Network>(self, genesis: Block<N>, ledger: PathBuf) -> Result<()> {
+        // always replay into a fresh ledger so timings and checkpoints don't
+        // depend on an earlier run
+        let new_ledger = ledger.with_extension("new");
+        if new_ledger.exists() {
+            info!("removing existing ledger {}", new_ledger.display());
+            fs::remove_dir_all(&new_ledger)?;
+        }
+
+        let (read_fd, write_fd) = unistd::pipe()?;
+
+        match unsafe { unistd::fork() }? {
+            ForkResult::Parent { child, .. } => {
+                unistd::close(read_fd.as_raw_fd())?;
+
+                let db_ledger: DbLedger<N> = util::open_ledger(genesis, ledger)?;
+
+                let target_height = match (self.height, self.amount) {
+                    (Some(height), _) => height,
+                    (None, Some(amount)) => db_ledger.latest_height().saturating_sub(amount),
+                    (None, None) => db_ledger.latest_height() + 1,
+                };
+
+                for i in self.skip..target_height {
+                    let block = db_ledger.get_block(i)?;
+                    let buf = block.to_bytes_le()?;
+                    unistd::write(&write_fd, &(buf.len() as u32).to_le_bytes())?;
+                    unistd::write(&write_fd, &buf)?;
+                }
+
+                unistd::write(&write_fd, &(0u32).to_le_bytes())?;
+                unistd::close(write_fd.as_raw_fd())?;
+
+                match waitpid(child, None)? {
+                    WaitStatus::Exited(_, 0) => {}
+                    WaitStatus::Exited(_, code) => {
+                        bail!("replay process exited with status {code}")
+                    }
+                    status => bail!("replay process did not exit: {status:?}"),
+                }
+            }
+            ForkResult::Child => {
+                unistd::close(write_fd.as_raw_fd())?;
+
+                let mut manager = self
+                    .checkpoint
+                    .then(|| {
+                        CheckpointManager::load(new_ledger.clone(), RetentionPolicy::default())
+                    })
+                    .transpose()?;
+
+                let db_ledger: DbLedger<N> = util::open_ledger(genesis, new_ledger)?;
+
+                // wipe out existing incompatible checkpoints
+                if let Some(manager) = manager.as_mut() {
+                    manager.cull_incompatible::<N>()?;
+                }
+
+                let read_fd = read_fd.as_raw_fd();
+                let apply = || self.apply_blocks(read_fd, &db_ledger, manager.as_mut());
+
+                #[cfg(feature = "flame")]
+                let timings = match &self.flame {
+                    Some(path) => bench::with_flamegraph(path, apply)?,
+                    None => apply()?,
+                };
+                #[cfg(not(feature = "flame"))]
+                let timings = apply()?;
+
+                if let Some(path) = &self.bench {
+                    bench::write_timings(path, self.bench_format, &timings)?;
+
+                    let summary = ReplaySummary::new(&timings);
+                    println!(
+                        "Replayed {} blocks with {} transactions: {:.0}ms verifying, {:.0}ms finalizing, {:.2} tx/s",
+                        summary.blocks,
+                        summary.transactions,
+                        summary.verify_ms,
+                        summary.finalize_ms,
+                        summary.transactions_per_second
+                    );
+                }
+
+                unistd::close(read_fd.as_raw_fd())?;
+                unsafe {
+                    nix::libc::_exit(0);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Apply blocks read from the parent process until it sends an empty
+    /// block, returning the time spent on each block.
+    fn apply_blocks<N: Network>(
+        &self,
+        read_fd: RawFd,
+        db_ledger: &DbLedger<N>,
+        mut manager: Option<&mut CheckpointManager>,
+    ) -> Result<Vec<BlockTiming>> {
+        let mut timings = vec![];
+        let mut rng = rand::thread_rng();
+
+        loop {
+            let mut size_buf = [0u8; 4];
+            unistd::read(read_fd, &mut size_buf)?;
+            let amount = u32::from_le_bytes(size_buf);
+            if amount == 0 {
+                break;
+            }
+
+            let mut buf = vec![0u8; amount as usize];
+            let mut read = 0;
+            while read < amount as usize {
+                read += unistd::read(read_fd, &mut buf[read..])?;
+            }
+            let block = Block::from_bytes_le(&buf)?;
+            if db_ledger.latest_height() + 1 != block.height() {
+                println!(
+                    "Skipping block {}, waiting for {}",
+                    block.height(),
+                    db_ledger.latest_height() + 1,
+                );
+                continue;
+            }
+
+            println!(
+                "Reading block {}... {}",
+                db_ledger.latest_height() + 1,
+                buf.len()
+            );
+
+            let height = block.height();
+            let start = Instant::now();
+            if self.bench.is_some() {
+                let _span = tracing::info_span!("verify", height).entered();
+                db_ledger.check_next_block(&block, &mut rng)?;
+            }
+            let verified = Instant::now();
+            {
+                let _span = tracing::info_span!("finalize", height).entered();
+                db_ledger.advance_to_next_block(&block)?;
+            }
+
+            timings.push(BlockTiming::new(
+                height,
+                block.transactions().len(),
+                block.aborted_transaction_ids().len(),
+                buf.len(),
+                verified - start,
+                verified.elapsed(),
+            ));
+
+            // if checkpoints are enabled, check if this block should be added
+            if let Some(manager) = manager.as_mut() {
+                manager.poll::<N>()?;
+            }
+        }
+
+        Ok(timings)
+    }
+}
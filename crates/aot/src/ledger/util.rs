@@ -24,14 +24,18 @@ pub fn open_ledger<N: Network, C: ConsensusStorage<N>>(
     Ledger::load(genesis_block, StorageMode::Custom(ledger_path))
 }
 
-pub fn prove_credits<N: Network, C: ConsensusStorage<N>, A: Aleo<Network = N>>(
+pub fn prove_credits<
+    N: Network,
+    C: ConsensusStorage<N>,
+    A: Aleo<Network = N>,
+    R: Rng + CryptoRng,
+>(
     locator: &'static str,
     vm: &VM<N, C>,
     private_key: PrivateKey<N>,
     inputs: impl IntoIterator<IntoIter = impl ExactSizeIterator<Item = impl TryInto<Value<N>>>>,
+    rng: &mut R,
 ) -> Result<Execution<N>> {
-    let rng = &mut rand::thread_rng();
-
     // authorize the transfer execution
     let auth = vm.authorize(
         &private_key,
@@ -47,14 +51,13 @@ pub fn prove_credits<N: Network, C: ConsensusStorage<N>, A: Aleo<Network = N>>(
     trace.prove_execution::<A, _>(&format!("credits.aleo/{locator}"), VarunaVersion::V1, rng)
 }
 
-pub fn prove_fee<N: Network, C: ConsensusStorage<N>, A: Aleo<Network = N>>(
+pub fn prove_fee<N: Network, C: ConsensusStorage<N>, A: Aleo<Network = N>, R: Rng + CryptoRng>(
     vm: &VM<N, C>,
     private_key: &PrivateKey<N>,
     min_fee: u64,
     execution_id: Field<N>,
+    rng: &mut R,
 ) -> Result<Fee<N>> {
-    let rng = &mut rand::thread_rng();
-
     // authorize the fee execution
     let auth = vm.authorize_fee_public(private_key, min_fee, 0, execution_id, rng)?;
 
@@ -64,23 +67,25 @@ pub fn prove_fee<N: Network, C: ConsensusStorage<N>, A: Aleo<Network = N>>(
     trace.prove_fee::<A, _>(VarunaVersion::V1, rng)
 }
 
-pub fn public_transaction<N: Network, C: ConsensusStorage<N>, A: Aleo<Network = N>>(
+pub fn public_transaction<
+    N: Network,
+    C: ConsensusStorage<N>,
+    A: Aleo<Network = N>,
+    R: Rng + CryptoRng,
+>(
     locator: &'static str,
     vm: &VM<N, C>,
     address: Address<N>,
     amount_microcredits: u64,
     private_key: PrivateKey<N>,
     private_key_fee: Option<PrivateKey<N>>,
+    rng: &mut R,
 ) -> Result<Transaction<N>> {
-    // let rng = &mut rand::thread_rng();
-
-    // let query = Query::from(vm.block_store());
-
     // fee key falls back to the private key
     let private_key_fee = private_key_fee.unwrap_or(private_key);
 
     // proof for the execution of the transfer function
-    let execution = prove_credits::<_, _, A>(
+    let execution = prove_credits::<_, _, A, _>(
         locator,
         vm,
         private_key,
@@ -88,32 +93,46 @@ pub fn public_transaction<N: Network, C: ConsensusStorage<N>, A: Aleo<Network =
             Value::from_str(address.to_string().as_str())?,
             Value::from(Literal::U64(U64::new(amount_microcredits))),
         ],
+        rng,
     )?;
 
     // compute fee for the execution
     let (min_fee, _) = execution_cost_v2(&vm.process().read(), &execution)?;
 
     // proof for the fee, authorizing the execution
-    let fee = prove_fee::<_, _, A>(vm, &private_key_fee, min_fee, execution.to_execution_id()?)?;
+    let fee = prove_fee::<_, _, A, _>(
+        vm,
+        &private_key_fee,
+        min_fee,
+        execution.to_execution_id()?,
+        rng,
+    )?;
 
     // assemble the transaction
     Transaction::<N>::from_execution(execution, Some(fee))
 }
 
-pub fn make_transaction_proof<N: Network, C: ConsensusStorage<N>, A: Aleo<Network = N>>(
+pub fn make_transaction_proof<
+    N: Network,
+    C: ConsensusStorage<N>,
+    A: Aleo<Network = N>,
+    R: Rng + CryptoRng,
+>(
     vm: &VM<N, C>,
     address: Address<N>,
     amount_microcredits: u64,
     private_key: PrivateKey<N>,
     private_key_fee: Option<PrivateKey<N>>,
+    rng: &mut R,
 ) -> Result<Transaction<N>> {
-    public_transaction::<_, _, A>(
+    public_transaction::<_, _, A, _>(
         "transfer_public",
         vm,
         address,
         amount_microcredits,
         private_key,
         private_key_fee,
+        rng,
     )
 }
 
@@ -125,14 +144,16 @@ pub fn _make_transaction_proof_private<N: Network, C: ConsensusStorage<N>, A: Al
     private_key_fee: Option<PrivateKey<N>>,
 ) -> Result<(Transaction<N>, Vec<Transaction<N>>)> {
     let vm = ledger.vm();
+    let mut rng = ChaChaRng::from_rng(thread_rng())?;
 
-    let record_tx = public_transaction::<_, _, A>(
+    let record_tx = public_transaction::<_, _, A, _>(
         "transfer_public_to_private",
         vm,
         Address::try_from(private_key)?,
         amounts.iter().sum(),
         private_key,
         private_key_fee,
+        &mut rng,
     )?;
 
     // fee key falls back to the private key
@@ -143,8 +164,6 @@ pub fn _make_transaction_proof_private<N: Network, C: ConsensusStorage<N>, A: Al
     // Decrypt the record
     let record = record_enc.decrypt(&ViewKey::try_from(private_key)?)?;
 
-    let mut rng = ChaChaRng::from_rng(thread_rng())?;
-
     let target_block = ledger.prepare_advance_to_next_beacon_block(
         &private_key,
         vec![],
@@ -159,7 +178,7 @@ pub fn _make_transaction_proof_private<N: Network, C: ConsensusStorage<N>, A: Al
         .into_iter()
         .map(|amount| {
             // proof for the execution of the transfer function
-            let execution = prove_credits::<_, _, A>(
+            let execution = prove_credits::<_, _, A, _>(
                 "transfer_private",
                 vm,
                 private_key,
@@ -168,14 +187,20 @@ pub fn _make_transaction_proof_private<N: Network, C: ConsensusStorage<N>, A: Al
                     Value::from_str(address.to_string().as_str())?,
                     Value::from(Literal::U64(U64::new(amount))),
                 ],
+                &mut rng,
             )?;
 
             // compute fee for the execution
             let (min_fee, _) = execution_cost_v2(&vm.process().read(), &execution)?;
 
             // proof for the fee, authorizing the execution
-            let fee =
-                prove_fee::<_, _, A>(vm, &private_key_fee, min_fee, execution.to_execution_id()?)?;
+            let fee = prove_fee::<_, _, A, _>(
+                vm,
+                &private_key_fee,
+                min_fee,
+                execution.to_execution_id()?,
+                &mut rng,
+            )?;
 
             // assemble the transaction
             Transaction::<N>::from_execution(execution, Some(fee))
@@ -0,0 +1,97 @@
+use std::{io::Read, path::PathBuf};
+
+use anyhow::Result;
+use clap::Args;
+use snarkvm::synthesizer::{Process, Program, process::deployment_cost};
+use snops_common::program_bundle::{BundleProgram, read_bundle, sort_by_imports};
+
+use crate::{Network, auth::query};
+
+/// Order a bundle of programs by their imports and estimate the cost of
+/// deploying each of them.
+#[derive(Debug, Args)]
+pub struct BundleCommand {
+    /// Query to load imports that are not in the bundle with.
+    #[clap(short, long)]
+    pub query: Option<String>,
+    /// A `.aleo` file, a directory of `.aleo` files, a JSON manifest
+    /// (`{"programs": ["a.aleo", ...]}`), or `-` for a JSON array of program
+    /// sources in stdin.
+    pub bundle: PathBuf,
+    /// Skip estimating deployment costs.
+    #[clap(long)]
+    pub no_cost: bool,
+    /// Output as JSON
+    #[clap(long, short)]
+    pub json: bool,
+}
+
+impl BundleCommand {
+    pub fn parse<N: Network>(self) -> Result<()> {
+        let sources = if self.bundle.as_os_str() == "-" {
+            let mut buf = String::new();
+            std::io::stdin().read_to_string(&mut buf)?;
+            serde_json::from_str::<Vec<String>>(&buf)?
+        } else {
+            read_bundle(&self.bundle)?
+        };
+
+        let programs = bundle::<N>(&sources, self.query.as_deref(), !self.no_cost)?;
+
+        if self.json {
+            println!("{}", serde_json::to_string(&programs)?);
+        } else {
+            for program in programs {
+                match program.cost {
+                    Some(cost) => println!("{} {cost}", program.id),
+                    None => println!("{}", program.id),
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Parse and sort program sources so each program comes after its imports,
+/// optionally estimating the deployment cost of each program.
+pub fn bundle<N: Network>(
+    sources: &[String],
+    query: Option<&str>,
+    cost: bool,
+) -> Result<Vec<BundleProgram>> {
+    let programs = sources
+        .iter()
+        .map(|source| Ok((source.parse::<Program<N>>()?, source)))
+        .collect::<Result<Vec<_>>>()?;
+
+    let programs = sort_by_imports(
+        programs,
+        |(program, _)| program.id().to_string(),
+        |(program, _)| program.imports().keys().map(|id| id.to_string()).collect(),
+    )?;
+
+    let mut process = cost.then(Process::<N>::load).transpose()?;
+    programs
+        .into_iter()
+        .map(|(program, source)| {
+            let cost = match process.as_mut() {
+                Some(process) => {
+                    // programs earlier in the bundle are already in the process
+                    query::get_process_imports(process, &program, query)?;
+                    let deployment =
+                        process.deploy::<N::Circuit, _>(&program, &mut rand::thread_rng())?;
+                    process.add_program(&program)?;
+                    Some(deployment_cost(&deployment)?.0)
+                }
+                None => None,
+            };
+
+            Ok(BundleProgram {
+                id: program.id().to_string(),
+                imports: program.imports().keys().map(|id| id.to_string()).collect(),
+                cost,
+                program: source.clone(),
+            })
+        })
+        .collect()
+}
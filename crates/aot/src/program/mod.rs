@@ -5,6 +5,7 @@ use serde_json::json;
 use snarkvm::synthesizer::Program;
 
 use crate::Network;
+pub mod bundle;
 pub mod cost;
 
 /// A command to help gather information about a program, including its cost and
@@ -19,6 +20,7 @@ pub enum ProgramCommand<N: Network> {
     /// List the inputs of a given program.
     Imports(ProgramInfo<N>),
     Cost(cost::CostCommand<N>),
+    Bundle(bundle::BundleCommand),
 }
 
 impl<N: Network> ProgramCommand<N> {
@@ -80,6 +82,7 @@ impl<N: Network> ProgramCommand<N> {
                 println!("{}", command.parse()?);
                 Ok(())
             }
+            ProgramCommand::Bundle(command) => command.parse::<N>(),
         }
     }
 }
@@ -14,8 +14,8 @@ use snarkos_node::{
 };
 use snarkvm::{
     ledger::store::{
-        BlockStorage, CommitteeStorage,
-        helpers::rocksdb::{BlockDB, CommitteeDB},
+        BlockStorage, CommitteeStorage, FinalizeStorage,
+        helpers::rocksdb::{BlockDB, CommitteeDB, FinalizeDB},
     },
     prelude::Block,
     utilities::FromBytes,
@@ -238,6 +238,7 @@ impl<N: Network> Runner<N> {
             let blocks = BlockDB::<N>::open(storage_mode.clone())?;
             // copy the block db to the agent's rpc server
             agent.set_block_db(blocks.clone());
+            agent.set_finalize_db(FinalizeDB::<N>::open(storage_mode.clone())?);
 
             // check for height changes and poll the manager when a new block comes in
             let mut last_height = committee.current_height()?;
@@ -7,7 +7,10 @@ use std::{
 use futures_util::{SinkExt, StreamExt};
 use http::Uri;
 use node::{MuxedMessageIncoming, MuxedMessageOutgoing, NodeRpcServer};
-use snarkvm::ledger::store::{BlockStorage, helpers::rocksdb::BlockDB};
+use snarkvm::ledger::store::{
+    BlockStorage,
+    helpers::rocksdb::{BlockDB, FinalizeDB},
+};
 use snops_common::{
     rpc::{
         PING_INTERVAL_SEC, PING_LENGTH, RpcTransport,
@@ -30,6 +33,7 @@ pub enum RpcClient<N: Network> {
         _port: u16,
         client: AgentNodeServiceClient,
         server_block_db: Arc<RwLock<Option<BlockDB<N>>>>,
+        server_finalize_db: Arc<RwLock<Option<FinalizeDB<N>>>>,
     },
     Disabled,
 }
@@ -41,6 +45,7 @@ impl<N: Network> RpcClient<N> {
         };
 
         let block_db = Arc::new(RwLock::new(None));
+        let finalize_db = Arc::new(RwLock::new(None));
 
         let start_time = Instant::now();
 
@@ -60,6 +65,7 @@ impl<N: Network> RpcClient<N> {
                     NodeRpcServer {
                         log_level_handler,
                         block_db: Arc::clone(&block_db),
+                        finalize_db: Arc::clone(&finalize_db),
                     }
                     .serve(),
                 )
@@ -204,6 +210,7 @@ impl<N: Network> RpcClient<N> {
             _port: port,
             client,
             server_block_db: block_db,
+            server_finalize_db: finalize_db,
         }
     }
 
@@ -218,6 +225,18 @@ impl<N: Network> RpcClient<N> {
             *server_block_db = Some(block_db);
         }
     }
+
+    pub fn set_finalize_db(&self, finalize_db: FinalizeDB<N>) {
+        if let Self::Enabled {
+            server_finalize_db, ..
+        } = self
+        {
+            let mut server_finalize_db = server_finalize_db
+                .write()
+                .expect("failed to lock rpc's finalize db");
+            *server_finalize_db = Some(finalize_db);
+        }
+    }
 }
 
 impl<N: Network> RpcClient<N> {
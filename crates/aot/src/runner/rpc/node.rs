@@ -2,7 +2,10 @@
 
 use std::sync::{Arc, RwLock};
 
-use snarkvm::ledger::store::{BlockStorage, helpers::rocksdb::BlockDB};
+use snarkvm::ledger::store::{
+    BlockStorage,
+    helpers::rocksdb::{BlockDB, FinalizeDB},
+};
 use snops_common::{
     define_rpc_mux,
     rpc::{
@@ -12,13 +15,17 @@ use snops_common::{
         },
         error::AgentError,
     },
-    state::snarkos_status::SnarkOSLiteBlock,
+    state::{
+        ledger_digest::{LedgerDigest, MAX_DIGEST_MAPPING_ENTRIES},
+        snarkos_status::SnarkOSLiteBlock,
+    },
 };
 use tarpc::context;
 
 use crate::{
     Network,
     cli::{ReloadHandler, make_env_filter},
+    ledger::diff,
     runner::rpc::get_block_info_for_height,
 };
 
@@ -31,6 +38,7 @@ define_rpc_mux!(child;
 pub struct NodeRpcServer<N: Network> {
     pub log_level_handler: ReloadHandler,
     pub block_db: Arc<RwLock<Option<BlockDB<N>>>>,
+    pub finalize_db: Arc<RwLock<Option<FinalizeDB<N>>>>,
 }
 
 impl<N: Network> NodeService for NodeRpcServer<N> {
@@ -108,4 +116,24 @@ impl<N: Network> NodeService for NodeRpcServer<N> {
             transactions: tx_ids,
         }))
     }
+
+    async fn get_ledger_digest(self, _: context::Context) -> Result<LedgerDigest, AgentError> {
+        // walking the ledger can take a while, so it is kept off the runtime
+        tokio::task::spawn_blocking(move || {
+            let block_guard = self.block_db.read();
+            let finalize_guard = self.finalize_db.read();
+            let (Ok(Some(block_db)), Ok(Some(finalize_db))) =
+                (block_guard.as_deref(), finalize_guard.as_deref())
+            else {
+                return Err(AgentError::NodeClientNotReady);
+            };
+
+            let mut digest = diff::digest(block_db, finalize_db, false)
+                .map_err(|e| AgentError::FailedToMakeRequest(format!("ledger digest: {e:?}")))?;
+            digest.bound_mappings(MAX_DIGEST_MAPPING_ENTRIES);
+            Ok(digest)
+        })
+        .await
+        .map_err(|e| AgentError::FailedToMakeRequest(format!("ledger digest: {e}")))?
+    }
 }
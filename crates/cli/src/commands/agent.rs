@@ -61,6 +61,12 @@ enum AgentCommands {
     Info,
     /// Kill the specific agent
     Kill,
+    /// Take the specific agent out of rotation for envs and compute.
+    Cordon,
+    /// Return a cordoned agent to rotation.
+    Uncordon,
+    /// Cordon the specific agent and move its node to another agent.
+    Drain,
 
     /// List all agents.
     /// Ignores the agent id.
@@ -144,6 +150,21 @@ impl Agent {
 
                 client.post(ep).send().await?
             }
+            Cordon => {
+                let ep = format!("{url}/api/v1/agents/{}/cordon", self.id);
+
+                client.post(ep).send().await?
+            }
+            Uncordon => {
+                let ep = format!("{url}/api/v1/agents/{}/uncordon", self.id);
+
+                client.post(ep).send().await?
+            }
+            Drain => {
+                let ep = format!("{url}/api/v1/agents/{}/drain", self.id);
+
+                client.post(ep).send().await?
+            }
             Status => {
                 let ep = format!("{url}/api/v1/agents/{}/status", self.id);
 
@@ -1,4 +1,4 @@
-use std::{collections::HashMap, str::FromStr, sync::Arc};
+use std::{collections::HashMap, io::Write, path::PathBuf, str::FromStr, sync::Arc};
 
 use anyhow::Result;
 use clap::Parser;
@@ -7,11 +7,12 @@ use reqwest::{Client, RequestBuilder, Response};
 use serde_json::{Value, json};
 use snops_cli::events::EventsClient;
 use snops_common::{
-    action_models::{AleoValue, WithTargets},
+    action_models::{AleoValue, FeeBreakdown, InputTemplate, LedgerDiffAction, WithTargets},
     events::{Event, EventKind, TransactionEvent},
     key_source::KeySource,
     node_targets::{NodeTarget, NodeTargetError, NodeTargets},
-    state::{CannonId, EnvId, HeightRequest, InternedId},
+    program_bundle::read_bundle,
+    state::{CannonId, EnvId, HeightRequest, InternedId, NodeKey},
 };
 
 use crate::commands::env::post_and_wait;
@@ -106,18 +107,62 @@ pub enum Action {
         /// The optional priority fee to use.
         #[clap(long)]
         priority_fee: Option<u32>,
-        /// The fee record to use if you want to pay the fee privately.
+        /// The fee record to use if you want to pay the fee privately, or
+        /// `auto` to use an unspent record owned by the fee key.
         #[clap(long)]
         fee_record: Option<String>,
         /// When present, don't wait for transaction execution before returning
         #[clap(long = "async")]
         async_mode: bool,
+        /// Estimate the fee of the transaction without sending it
+        #[clap(long, conflicts_with = "confirm")]
+        dry_run: bool,
+        /// Show the estimated fee and ask for confirmation before sending the
+        /// transaction
+        #[clap(long)]
+        confirm: bool,
         /// `transfer_public` OR `credits.aleo/transfer_public`.
         locator: String,
         /// list of program inputs.
         #[clap(num_args = 1, value_delimiter = ' ')]
         inputs: Vec<AleoValue>,
     },
+    /// Execute a function many times with templated inputs. Authorizations
+    /// are generated on compute agents in parallel.
+    BatchExecute {
+        /// Private key template to sign each transaction with, eg.
+        /// `accounts.$` or `accounts.{i}`. Defaults to `committee.0`
+        #[clap(long)]
+        private_key: Option<InputTemplate>,
+        /// Private key to use for the fees. Defaults to the same as
+        /// --private-key
+        #[clap(long)]
+        fee_private_key: Option<KeySource>,
+        /// Desired cannon to fire the transactions
+        #[clap(long, short)]
+        cannon: Option<CannonId>,
+        /// The optional priority fee to use for each transaction.
+        #[clap(long)]
+        priority_fee: Option<u32>,
+        /// Number of transactions to execute.
+        #[clap(long, short = 'n')]
+        count: u32,
+        /// How many authorizations to generate at once. Defaults to the
+        /// number of compute slots across the compute agents with the
+        /// cannon's labels.
+        #[clap(long)]
+        concurrency: Option<usize>,
+        /// When present, return once the batch is started instead of printing
+        /// its progress
+        #[clap(long = "async")]
+        async_mode: bool,
+        /// `transfer_public` OR `credits.aleo/transfer_public`.
+        locator: String,
+        /// list of program input templates. `{i}` is replaced with the
+        /// transaction index and `{rand:MIN..MAX}` with a random integer.
+        #[clap(num_args = 1, value_delimiter = ' ')]
+        inputs: Vec<InputTemplate>,
+    },
     /// Deploy an aleo program to the environment.
     Deploy {
         /// Private key to use, can be `committee.0` to use committee member 0's
@@ -134,15 +179,58 @@ pub enum Action {
         /// The optional priority fee to use.
         #[clap(long)]
         priority_fee: Option<u32>,
-        /// The fee record to use if you want to pay the fee privately.
+        /// The fee record to use if you want to pay the fee privately, or
+        /// `auto` to use an unspent record owned by the fee key.
         #[clap(long)]
         fee_record: Option<String>,
         /// When present, don't wait for transaction execution before returning
         #[clap(long = "async")]
         async_mode: bool,
+        /// Estimate the fee of the transaction without sending it
+        #[clap(long, conflicts_with = "confirm")]
+        dry_run: bool,
+        /// Show the estimated fee and ask for confirmation before sending the
+        /// transaction
+        #[clap(long)]
+        confirm: bool,
         /// Path to program or program content in stdin
         program: FileOrStdin<String>,
     },
+    /// Deploy a bundle of aleo programs to the environment, ordered by their
+    /// imports. Each deployment waits for the previous one to be confirmed.
+    DeployBundle {
+        /// Private key to use, can be `committee.0` to use committee member 0's
+        /// key
+        #[clap(long, short)]
+        private_key: Option<KeySource>,
+        /// Private key to use for the fees. Defaults to the same as
+        /// --private-key
+        #[clap(long)]
+        fee_private_key: Option<KeySource>,
+        /// Desired cannon to fire the transactions
+        #[clap(long, short)]
+        cannon: Option<CannonId>,
+        /// The optional priority fee to use for each deployment.
+        #[clap(long)]
+        priority_fee: Option<u32>,
+        /// When present, return the deployment order without waiting for the
+        /// deployments
+        #[clap(long = "async")]
+        async_mode: bool,
+        /// A `.aleo` file, a directory of `.aleo` files, or a JSON manifest
+        /// (`{"programs": ["a.aleo", ...]}`)
+        bundle: PathBuf,
+    },
+    /// Compare the ledgers of the target nodes, finding where they diverge.
+    LedgerDiff {
+        /// The node to compare the others against. Defaults to the node with
+        /// the highest block height.
+        #[clap(long, short)]
+        reference: Option<NodeKey>,
+        /// The nodes to compare. (eg. `validator/any`)
+        #[clap(num_args = 1, value_delimiter = ' ')]
+        nodes: Vec<NodeTarget>,
+    },
     /// Configure the state of the target nodes.
     Config {
         /// Configure the online state of the target nodes.
@@ -239,6 +327,8 @@ impl Action {
                 locator,
                 inputs,
                 async_mode,
+                dry_run,
+                confirm,
             } => {
                 let ep = format!("{url}/api/v1/env/{}/action/execute", env_id);
 
@@ -272,6 +362,14 @@ impl Action {
                     json["program"] = program.into();
                 }
 
+                if dry_run {
+                    json["dry_run"] = true.into();
+                    return Ok(client.post(ep).json(&json).send().await?);
+                }
+                if confirm && !confirm_fee(&client, &ep, &json).await? {
+                    std::process::exit(1);
+                }
+
                 let req = client.post(ep).query(&[("async", "true")]).json(&json);
                 if async_mode {
                     req.send().await?
@@ -287,6 +385,8 @@ impl Action {
                 priority_fee,
                 fee_record,
                 async_mode,
+                dry_run,
+                confirm,
                 program,
             } => {
                 let ep = format!("{url}/api/v1/env/{}/action/deploy", env_id);
@@ -311,6 +411,14 @@ impl Action {
                     json["fee_record"] = fee_record.into();
                 }
 
+                if dry_run {
+                    json["dry_run"] = true.into();
+                    return Ok(client.post(ep).json(&json).send().await?);
+                }
+                if confirm && !confirm_fee(&client, &ep, &json).await? {
+                    std::process::exit(1);
+                }
+
                 let req = client.post(ep).query(&[("async", "true")]).json(&json);
                 if async_mode {
                     req.send().await?
@@ -319,6 +427,110 @@ impl Action {
                     std::process::exit(0);
                 }
             }
+            BatchExecute {
+                private_key,
+                fee_private_key,
+                cannon,
+                priority_fee,
+                count,
+                concurrency,
+                async_mode,
+                locator,
+                inputs,
+            } => {
+                let ep = format!("{url}/api/v1/env/{env_id}/action/batch_execute");
+
+                let (program, function) = locator
+                    .split_once('/')
+                    .map(|(program, function)| (Some(program), function))
+                    .unwrap_or((None, &locator));
+
+                let mut json = json!({
+                    "function": function,
+                    "inputs": inputs,
+                    "count": count,
+                });
+
+                if let Some(private_key) = private_key {
+                    json["private_key"] = private_key.to_string().into();
+                }
+                if let Some(fee_private_key) = fee_private_key {
+                    json["fee_private_key"] = fee_private_key.to_string().into();
+                }
+                if let Some(cannon) = cannon {
+                    json["cannon"] = cannon.to_string().into();
+                }
+                if let Some(priority_fee) = priority_fee {
+                    json["priority_fee"] = priority_fee.into();
+                }
+                if let Some(concurrency) = concurrency {
+                    json["concurrency"] = concurrency.into();
+                }
+                if let Some(program) = program {
+                    json["program"] = program.into();
+                }
+
+                let mut req = client.post(ep).json(&json);
+                if async_mode {
+                    req = req.query(&[("async", "true")]);
+                }
+                let mut res = req.send().await?;
+                if async_mode || !res.status().is_success() {
+                    res
+                } else {
+                    // print progress lines as the transactions are sent
+                    let mut stdout = std::io::stdout();
+                    while let Some(chunk) = res.chunk().await? {
+                        stdout.write_all(&chunk)?;
+                        stdout.flush()?;
+                    }
+                    std::process::exit(0);
+                }
+            }
+            DeployBundle {
+                private_key,
+                fee_private_key,
+                cannon,
+                priority_fee,
+                async_mode,
+                bundle,
+            } => {
+                let ep = format!("{url}/api/v1/env/{env_id}/action/deploy_bundle");
+
+                let mut json = json!({
+                    "programs": read_bundle(&bundle)?,
+                });
+
+                if let Some(private_key) = private_key {
+                    json["private_key"] = private_key.to_string().into();
+                }
+                if let Some(fee_private_key) = fee_private_key {
+                    json["fee_private_key"] = fee_private_key.to_string().into();
+                }
+                if let Some(cannon) = cannon {
+                    json["cannon"] = cannon.to_string().into();
+                }
+                if let Some(priority_fee) = priority_fee {
+                    json["priority_fee"] = priority_fee.into();
+                }
+
+                let mut req = client.post(ep).json(&json);
+                if async_mode {
+                    req = req.query(&[("async", "true")]);
+                }
+                req.send().await?
+            }
+            LedgerDiff { reference, nodes } => {
+                let ep = format!("{url}/api/v1/env/{env_id}/action/ledger_diff");
+                client
+                    .post(ep)
+                    .json(&WithTargets {
+                        nodes: nodes.into(),
+                        data: LedgerDiffAction { reference },
+                    })
+                    .send()
+                    .await?
+            }
             Config {
                 online,
                 height,
@@ -380,6 +592,48 @@ impl Action {
     }
 }
 
+/// Print the estimated fee of an execute or deploy action and ask whether to
+/// send the transaction
+async fn confirm_fee(client: &Client, ep: &str, json: &Value) -> Result<bool> {
+    let mut json = json.clone();
+    json["dry_run"] = true.into();
+
+    let res = client.post(ep).json(&json).send().await?;
+    let status = res.status();
+    let text = res.text().await?;
+    if !status.is_success() {
+        let value = serde_json::from_str(&text).unwrap_or(Value::String(text));
+        eprintln!("failed to estimate fee: {status}");
+        println!("{}", serde_json::to_string_pretty(&value)?);
+        return Ok(false);
+    }
+
+    let fee: FeeBreakdown =
+        serde_json::from_value(serde_json::from_str::<Value>(&text)?["fee"].take())?;
+    for (name, amount) in [
+        ("storage", fee.storage),
+        ("finalize", fee.finalize),
+        ("synthesis", fee.synthesis),
+        ("namespace", fee.namespace),
+        ("priority", fee.priority),
+    ] {
+        if amount > 0 {
+            eprintln!("{name:>10}: {amount} microcredits");
+        }
+    }
+    eprintln!(
+        "{:>10}: {} microcredits ({} credits)",
+        "total",
+        fee.total(),
+        fee.total() as f64 / 1_000_000.0
+    );
+
+    eprint!("send transaction? [y/N] ");
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer)?;
+    Ok(matches!(answer.trim(), "y" | "Y" | "yes"))
+}
+
 pub async fn post_and_wait_tx(url: &str, req: RequestBuilder) -> Result<()> {
     use snops_common::events::EventFilter::*;
     let res = req.send().await?;
@@ -406,6 +660,7 @@ pub async fn post_and_wait_tx(url: &str, req: RequestBuilder) -> Result<()> {
 
     let mut tx = None;
     let mut block_hash = None;
+    let mut outcome = None;
     let mut broadcast_height = None;
     let mut broadcast_time = None;
 
@@ -465,11 +720,26 @@ pub async fn post_and_wait_tx(url: &str, req: RequestBuilder) -> Result<()> {
                 broadcast_height = height;
                 broadcast_time = Some(timestamp);
             }
-            TransactionEvent::Confirmed { hash } => {
-                eprintln!("confirmed with hash {hash}");
+            TransactionEvent::Accepted { hash } => {
+                eprintln!("accepted in block {hash}");
+                outcome = Some("accepted");
+                block_hash = Some(hash);
+                break;
+            }
+            TransactionEvent::Rejected { hash } => {
+                eprintln!("rejected in block {hash}");
+                outcome = Some("rejected");
+                block_hash = Some(hash);
+                break;
+            }
+            TransactionEvent::Aborted { hash } => {
+                eprintln!("aborted in block {hash}");
+                outcome = Some("aborted");
                 block_hash = Some(hash);
                 break;
             }
+            // only emitted after one of the outcomes above
+            TransactionEvent::Confirmed { .. } => {}
         }
     }
     println!(
@@ -479,6 +749,7 @@ pub async fn post_and_wait_tx(url: &str, req: RequestBuilder) -> Result<()> {
             "broadcast_height": broadcast_height,
             "broadcast_time": broadcast_time,
             "block_hash": block_hash,
+            "outcome": outcome,
         }))?
     );
     events.close().await
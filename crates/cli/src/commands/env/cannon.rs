@@ -0,0 +1,100 @@
+use anyhow::Result;
+use clap::Parser;
+use reqwest::{Client, Response};
+use snops_common::state::{CannonId, EnvId};
+
+/// Inspect and control an env's transaction cannons.
+#[derive(Debug, Parser)]
+pub enum Cannon {
+    /// List the env's cannons and their queue counts.
+    #[clap(alias = "ls")]
+    List,
+    /// Get a cannon's status, queue counts, source, and sink.
+    Info {
+        /// The cannon's id.
+        #[clap(default_value = "default")]
+        cannon: CannonId,
+    },
+    /// Page through a cannon's tracked transactions.
+    #[clap(alias = "txs")]
+    Transactions {
+        /// The cannon's id.
+        #[clap(default_value = "default")]
+        cannon: CannonId,
+        /// Only list transactions in this state: authorized, executing,
+        /// unsent, or broadcasted.
+        #[clap(long, short)]
+        status: Option<String>,
+        /// The number of transactions to skip.
+        #[clap(long, default_value_t = 0)]
+        offset: usize,
+        /// The maximum number of transactions to list.
+        #[clap(long, default_value_t = 100)]
+        limit: usize,
+    },
+    /// Stop executing and broadcasting transactions. New transactions are
+    /// queued until the cannon is resumed.
+    Pause {
+        /// The cannon's id.
+        #[clap(default_value = "default")]
+        cannon: CannonId,
+    },
+    /// Resume a paused or draining cannon.
+    Resume {
+        /// The cannon's id.
+        #[clap(default_value = "default")]
+        cannon: CannonId,
+    },
+    /// Finish a cannon's tracked transactions while rejecting new ones.
+    Drain {
+        /// The cannon's id.
+        #[clap(default_value = "default")]
+        cannon: CannonId,
+    },
+    /// Remove a cannon's tracked transactions.
+    Flush {
+        /// The cannon's id.
+        #[clap(default_value = "default")]
+        cannon: CannonId,
+        /// Only remove transactions in this state: authorized, executing,
+        /// unsent, or broadcasted.
+        #[clap(long, short)]
+        status: Option<String>,
+    },
+}
+
+impl Cannon {
+    pub async fn execute(self, url: &str, env_id: EnvId, client: Client) -> Result<Response> {
+        let ep = format!("{url}/api/v1/env/{env_id}/cannons");
+
+        use Cannon::*;
+        Ok(match self {
+            List => client.get(ep).send().await?,
+            Info { cannon } => client.get(format!("{ep}/{cannon}")).send().await?,
+            Transactions {
+                cannon,
+                status,
+                offset,
+                limit,
+            } => {
+                let mut req = client
+                    .get(format!("{ep}/{cannon}/transactions"))
+                    .query(&[("offset", offset), ("limit", limit)]);
+                if let Some(status) = status {
+                    req = req.query(&[("status", status)]);
+                }
+                req.send().await?
+            }
+            Pause { cannon } => client.post(format!("{ep}/{cannon}/pause")).send().await?,
+            Resume { cannon } => client.post(format!("{ep}/{cannon}/resume")).send().await?,
+            Drain { cannon } => client.post(format!("{ep}/{cannon}/drain")).send().await?,
+            Flush { cannon, status } => {
+                let mut req = client.post(format!("{ep}/{cannon}/flush"));
+                if let Some(status) = status {
+                    req = req.query(&[("status", status)]);
+                }
+                req.send().await?
+            }
+        })
+    }
+}
@@ -15,6 +15,7 @@ use snops_common::{
 };
 
 mod action;
+mod cannon;
 
 /// For interacting with snop environments.
 #[derive(Debug, Parser)]
@@ -43,6 +44,9 @@ enum EnvCommands {
 
     /// List an env's agents
     Agents,
+    /// Inspect and control the env's transaction cannons.
+    #[clap(subcommand)]
+    Cannon(cannon::Cannon),
     // execute and broadcast
     Auth {
         /// When present, don't wait for transaction execution before returning
@@ -51,6 +55,18 @@ enum EnvCommands {
         /// Desired cannon to fire the transaction
         #[clap(long, short, default_value = "default")]
         cannon: CannonId,
+        /// Priority of the transaction in the cannon's queues: low, normal, or
+        /// high.
+        #[clap(long)]
+        priority: Option<String>,
+        /// Abort the authorization if it has not been executed by this RFC 3339
+        /// time.
+        #[clap(long)]
+        deadline: Option<String>,
+        /// A transaction that must be confirmed before the authorization is
+        /// executed. Can be repeated.
+        #[clap(long)]
+        after: Vec<String>,
         /// Authorization to execute and broadcast
         auth: FileOrStdin<Authorization>,
     },
@@ -72,6 +88,21 @@ enum EnvCommands {
     /// Get the latest height from all agents in the env.
     Height,
 
+    /// Get the transactions the env's cannons have finished with.
+    History {
+        /// Only include transactions from this cannon.
+        #[clap(long, short)]
+        cannon: Option<CannonId>,
+        /// Only include transactions with this outcome: accepted, rejected,
+        /// aborted, recorded, execute_exceeded, broadcast_exceeded, flushed,
+        /// expired, or dependency_failed.
+        #[clap(long, short)]
+        outcome: Option<String>,
+        /// The output format: json, jsonl, or csv.
+        #[clap(long, short, default_value = "json")]
+        format: String,
+    },
+
     /// Lookup a transaction's block by a transaction id.
     #[clap(alias = "tx")]
     Transaction { id: String },
@@ -87,6 +118,12 @@ enum EnvCommands {
     /// Get an env's latest block/state root info.
     Info,
 
+    /// Get the addresses and view keys of a storage keyset.
+    Keyset {
+        /// The keyset's name, eg. `accounts`.
+        name: InternedId,
+    },
+
     /// List all environments.
     /// Ignores the env id.
     #[clap(alias = "ls")]
@@ -140,6 +177,7 @@ impl Env {
         use EnvCommands::*;
         Ok(match self.command {
             Action(action) => action.execute(url, id, client).await?,
+            Cannon(cannon) => cannon.execute(url, id, client).await?,
             Agent { key } => {
                 let ep = format!("{url}/api/v1/env/{id}/agents/{key}");
 
@@ -153,6 +191,9 @@ impl Env {
             Auth {
                 async_mode,
                 cannon,
+                priority,
+                deadline,
+                after,
                 auth,
             } => {
                 let ep = format!("{url}/api/v1/env/{id}/cannons/{cannon}/auth");
@@ -162,6 +203,15 @@ impl Env {
                 if async_mode {
                     req = req.query(&[("async", "true")]);
                 }
+                if let Some(priority) = priority {
+                    req = req.query(&[("priority", priority)]);
+                }
+                if let Some(deadline) = deadline {
+                    req = req.query(&[("deadline", deadline)]);
+                }
+                if !after.is_empty() {
+                    req = req.query(&[("after", after.join(","))]);
+                }
 
                 if async_mode {
                     req.send().await?
@@ -246,6 +296,11 @@ impl Env {
                 println!("{}", client.get(ep).send().await?.text().await?);
                 std::process::exit(0);
             }
+            Keyset { name } => {
+                let ep = format!("{url}/api/v1/env/{id}/keysets/{name}");
+
+                client.get(ep).send().await?
+            }
             Storage => {
                 let ep = format!("{url}/api/v1/env/{id}/storage");
 
@@ -266,6 +321,29 @@ impl Env {
 
                 client.get(ep).send().await?
             }
+            History {
+                cannon,
+                outcome,
+                format,
+            } => {
+                let ep = format!("{url}/api/v1/env/{id}/history");
+
+                let mut req = client.get(ep).query(&[("format", &format)]);
+                if let Some(cannon) = cannon {
+                    req = req.query(&[("cannon", cannon.to_string())]);
+                }
+                if let Some(outcome) = outcome {
+                    req = req.query(&[("outcome", outcome)]);
+                }
+                let res = req.send().await?;
+
+                // exports are printed as is
+                if format != "json" && res.status().is_success() {
+                    print!("{}", res.text().await?);
+                    std::process::exit(0);
+                }
+                res
+            }
         })
     }
 }
@@ -1,12 +1,14 @@
-use std::str::FromStr;
+use std::{fmt, str::FromStr};
 
 use indexmap::{IndexMap, IndexSet};
+use rand::Rng;
 use serde::{Deserialize, Serialize};
+use thiserror::Error;
 
 use crate::{
     key_source::KeySource,
     node_targets::{NodeTarget, NodeTargets},
-    state::HeightRequest,
+    state::{HeightRequest, NodeKey},
 };
 
 #[derive(Deserialize, Serialize, Clone)]
@@ -33,10 +35,18 @@ impl From<NodeTargets> for WithTargets<()> {
     }
 }
 
+/// A `fee_record` that selects an unspent credits record owned by the fee key
+/// with enough microcredits to pay the fee
+pub const AUTO_FEE_RECORD: &str = "auto";
+
 fn committee_0_key() -> KeySource {
     KeySource::Committee(Some(0))
 }
 
+fn committee_0_template() -> InputTemplate {
+    InputTemplate("committee.0".to_owned())
+}
+
 fn credits_aleo() -> String {
     "credits.aleo".to_owned()
 }
@@ -68,9 +78,52 @@ pub struct ExecuteAction {
     /// The optional priority fee
     #[serde(default)]
     pub priority_fee: Option<u64>,
-    /// The optional fee record for a private fee
+    /// The optional fee record for a private fee, or [`AUTO_FEE_RECORD`] to
+    /// select an unspent record owned by the fee key
     #[serde(default)]
     pub fee_record: Option<String>,
+    /// Estimate the fee of the transaction instead of broadcasting it
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub struct BatchExecuteAction {
+    /// The private key template to sign each transaction with, eg.
+    /// `accounts.$` for a random account or `accounts.{i}` for one account
+    /// per transaction. Defaults to committee member 0's key.
+    #[serde(default = "committee_0_template")]
+    pub private_key: InputTemplate,
+    /// A private key to use for the fees. If not provided, the fees will be
+    /// paid from the `private_key`
+    pub fee_private_key: Option<KeySource>,
+    /// The program to execute. Defaults to `credits.aleo`
+    #[serde(default = "credits_aleo")]
+    pub program: String,
+    /// The function to call
+    pub function: String,
+    /// The cannon id of who to execute the transactions
+    #[serde(default = "default_str")]
+    pub cannon: String,
+    /// The number of transactions to execute, at most
+    /// [`BatchExecuteAction::MAX_COUNT`]
+    pub count: u32,
+    /// Templates for the inputs to the function, rendered for each
+    /// transaction
+    pub inputs: Vec<InputTemplate>,
+    /// The optional priority fee for each transaction
+    #[serde(default)]
+    pub priority_fee: Option<u64>,
+    /// How many authorizations to generate at once. Defaults to the number of
+    /// compute slots across the compute agents with the cannon's labels.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub concurrency: Option<usize>,
+}
+
+impl BatchExecuteAction {
+    /// The most transactions a single batch can execute
+    pub const MAX_COUNT: u32 = 10_000;
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -91,9 +144,72 @@ pub struct DeployAction {
     /// The optional priority fee
     #[serde(default)]
     pub priority_fee: Option<u64>,
-    /// The optional fee record for a private fee
+    /// The optional fee record for a private fee, or [`AUTO_FEE_RECORD`] to
+    /// select an unspent record owned by the fee key
     #[serde(default)]
     pub fee_record: Option<String>,
+    /// Estimate the fee of the transaction instead of broadcasting it
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub struct DeployBundleAction {
+    /// The private key to use for the transactions. If not provided, the
+    /// transactions will be signed with the committee member 0's key.
+    #[serde(default = "committee_0_key")]
+    pub private_key: KeySource,
+    /// A private key to use for the fees. If not provided, the fees will be
+    /// paid from the `private_key`
+    pub fee_private_key: Option<KeySource>,
+    /// The programs to deploy, in any order. Programs are deployed after the
+    /// programs they import.
+    pub programs: Vec<String>,
+    /// The cannon id of who to execute the transactions
+    #[serde(default = "default_str")]
+    pub cannon: String,
+    /// The optional priority fee for each deployment
+    #[serde(default)]
+    pub priority_fee: Option<u64>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub struct LedgerDiffAction {
+    /// The node the other nodes' ledgers are compared to. Defaults to the node
+    /// with the highest block height.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reference: Option<NodeKey>,
+}
+
+/// The estimated fee of a transaction, in microcredits
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FeeBreakdown {
+    /// Cost of storing the transaction
+    pub storage: u64,
+    /// Cost of finalizing an execution
+    #[serde(default)]
+    pub finalize: u64,
+    /// Cost of synthesizing a deployment's circuits
+    #[serde(default)]
+    pub synthesis: u64,
+    /// Cost of a deployment's program name
+    #[serde(default)]
+    pub namespace: u64,
+    #[serde(default)]
+    pub priority: u64,
+}
+
+impl FeeBreakdown {
+    /// The fee without the priority fee
+    pub fn base(&self) -> u64 {
+        self.storage + self.finalize + self.synthesis + self.namespace
+    }
+
+    pub fn total(&self) -> u64 {
+        self.base() + self.priority
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -115,6 +231,101 @@ impl FromStr for AleoValue {
     }
 }
 
+#[derive(Debug, Error)]
+pub enum InputTemplateError {
+    #[error("invalid random range `{0}`, expected `min..max` or `min..=max`")]
+    InvalidRange(String),
+    #[error("empty random range `{0}`")]
+    EmptyRange(String),
+}
+
+/// An input value with placeholders that are filled in for each transaction
+/// in a batch:
+///
+/// - `{i}` is replaced with the index of the transaction
+/// - `{rand:MIN..MAX}` or `{rand:MIN..=MAX}` is replaced with a random integer
+///   in the range
+///
+/// The rendered value is parsed like any other input, so key sources such as
+/// `accounts.$` or `accounts.{i}` resolve to storage accounts. Braces that are
+/// not placeholders, such as in struct literals, are left as-is.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct InputTemplate(pub String);
+
+impl FromStr for InputTemplate {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Self(s.to_owned()))
+    }
+}
+
+impl fmt::Display for InputTemplate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl InputTemplate {
+    /// Render the template for the transaction at `index`
+    pub fn render(&self, index: u32, rng: &mut impl Rng) -> Result<AleoValue, InputTemplateError> {
+        let mut out = String::with_capacity(self.0.len());
+        let mut rest = self.0.as_str();
+
+        while let Some(start) = rest.find('{') {
+            out.push_str(&rest[..start]);
+            rest = &rest[start..];
+
+            let placeholder = rest.find('}').map(|end| (&rest[1..end], end));
+            match placeholder {
+                Some(("i", end)) => {
+                    out.push_str(&index.to_string());
+                    rest = &rest[end + 1..];
+                }
+                Some((inner, end)) if inner.starts_with("rand:") => {
+                    out.push_str(&random_in_range(&inner[5..], rng)?.to_string());
+                    rest = &rest[end + 1..];
+                }
+                // not a placeholder, placeholders may still be nested inside
+                _ => {
+                    out.push('{');
+                    rest = &rest[1..];
+                }
+            }
+        }
+        out.push_str(rest);
+
+        Ok(match KeySource::from_str(&out) {
+            Ok(key) => AleoValue::Key(key),
+            Err(_) => AleoValue::Other(out),
+        })
+    }
+}
+
+fn random_in_range(range: &str, rng: &mut impl Rng) -> Result<u128, InputTemplateError> {
+    let invalid = || InputTemplateError::InvalidRange(range.to_owned());
+
+    let (min, max, inclusive) = match range.split_once("..=") {
+        Some((min, max)) => (min, max, true),
+        None => {
+            let (min, max) = range.split_once("..").ok_or_else(invalid)?;
+            (min, max, false)
+        }
+    };
+    let min: u128 = min.trim().parse().map_err(|_| invalid())?;
+    let max: u128 = max.trim().parse().map_err(|_| invalid())?;
+
+    if max < min || (max == min && !inclusive) {
+        return Err(InputTemplateError::EmptyRange(range.to_owned()));
+    }
+    Ok(if inclusive {
+        rng.gen_range(min..=max)
+    } else {
+        rng.gen_range(min..max)
+    })
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Reconfig {
     #[serde(default, skip_serializing_if = "Option::is_none")]
@@ -134,3 +345,74 @@ pub struct Reconfig {
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub del_env: Option<IndexSet<String>>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(template: &str, index: u32) -> Result<AleoValue, InputTemplateError> {
+        InputTemplate(template.to_owned()).render(index, &mut rand::thread_rng())
+    }
+
+    fn other(value: AleoValue) -> String {
+        match value {
+            AleoValue::Other(value) => value,
+            AleoValue::Key(key) => panic!("expected a literal, got key {key}"),
+        }
+    }
+
+    #[test]
+    fn renders_placeholders() {
+        assert_eq!(other(render("{i}u64", 7).unwrap()), "7u64");
+        assert!(matches!(
+            render("accounts.{i}", 3).unwrap(),
+            AleoValue::Key(KeySource::Named(_, Some(3)))
+        ));
+        assert!(matches!(
+            render("accounts.$", 3).unwrap(),
+            AleoValue::Key(KeySource::Named(_, None))
+        ));
+
+        for i in 0..20 {
+            let value = other(render("{rand:10..=12}u64", i).unwrap());
+            let amount: u64 = value.trim_end_matches("u64").parse().unwrap();
+            assert!((10..=12).contains(&amount));
+        }
+
+        // braces that aren't placeholders are kept
+        assert_eq!(
+            other(render("{ a: {i}u8, b: 1u8 }", 2).unwrap()),
+            "{ a: 2u8, b: 1u8 }"
+        );
+    }
+
+    #[test]
+    fn fee_breakdown_totals() {
+        let fee = FeeBreakdown {
+            storage: 1,
+            finalize: 2,
+            synthesis: 4,
+            namespace: 8,
+            priority: 16,
+        };
+        assert_eq!(fee.base(), 15);
+        assert_eq!(fee.total(), 31);
+
+        // fields missing from an estimate default to zero
+        let fee: FeeBreakdown = serde_json::from_str(r#"{"storage":5,"priority":1}"#).unwrap();
+        assert_eq!(fee.base(), 5);
+        assert_eq!(fee.total(), 6);
+    }
+
+    #[test]
+    fn rejects_bad_ranges() {
+        assert!(matches!(
+            render("{rand:5..5}u64", 0),
+            Err(InputTemplateError::EmptyRange(_))
+        ));
+        assert!(matches!(
+            render("{rand:a..5}u64", 0),
+            Err(InputTemplateError::InvalidRange(_))
+        ));
+    }
+}
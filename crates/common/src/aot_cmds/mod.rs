@@ -1,5 +1,6 @@
 use std::{io, path::PathBuf, process::Stdio};
 
+use serde::Deserialize;
 use tokio::{
     io::AsyncWriteExt,
     process::{Child, Command},
@@ -10,10 +11,21 @@ pub use error::AotCmdError;
 
 use self::error::CommandError;
 use crate::{
+    action_models::FeeBreakdown,
     constant::{LEDGER_BASE_DIR, SNARKOS_GENESIS_FILE},
+    program_bundle::BundleProgram,
     state::{Authorization, NetworkId},
 };
 
+/// An unspent record found by `aot auth records`
+#[derive(Debug, Clone, Deserialize)]
+pub struct UnspentRecord {
+    pub commitment: String,
+    pub microcredits: Option<u64>,
+    /// The record plaintext
+    pub record: String,
+}
+
 pub struct AotCmd {
     bin: PathBuf,
     network: NetworkId,
@@ -52,6 +64,12 @@ impl AotCmd {
         Ok(unsafe { String::from_utf8_unchecked(bytes) })
     }
 
+    fn parse_fee(bytes: Vec<u8>) -> Result<FeeBreakdown, AotCmdError> {
+        // skip anything printed before the json, such as parameter downloads
+        let start = bytes.iter().position(|b| *b == b'{').unwrap_or(0);
+        Ok(serde_json::from_slice(&bytes[start..])?)
+    }
+
     fn _parse_string_option(bytes: Vec<u8>) -> Result<Option<String>, AotCmdError> {
         let string = unsafe { String::from_utf8_unchecked(bytes) };
         Ok(if string.is_empty() {
@@ -62,7 +80,7 @@ impl AotCmd {
     }
 
     #[allow(clippy::too_many_arguments)]
-    pub async fn authorize_program(
+    fn auth_program_command(
         &self,
         private_key: &str,
         fee_private_key: Option<&String>,
@@ -73,7 +91,7 @@ impl AotCmd {
         priority_fee: Option<u64>,
         fee_record: Option<&String>,
         cost_v1: bool,
-    ) -> Result<String, AotCmdError> {
+    ) -> Command {
         let mut command = Command::new(&self.bin);
         command
             .stdout(std::io::stdout())
@@ -107,6 +125,33 @@ impl AotCmd {
         command
             .arg(format!("{program_id}/{function_name}"))
             .args(inputs);
+        command
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub async fn authorize_program(
+        &self,
+        private_key: &str,
+        fee_private_key: Option<&String>,
+        program_id: &str,
+        function_name: &str,
+        inputs: &[String],
+        query: Option<&String>,
+        priority_fee: Option<u64>,
+        fee_record: Option<&String>,
+        cost_v1: bool,
+    ) -> Result<String, AotCmdError> {
+        let mut command = self.auth_program_command(
+            private_key,
+            fee_private_key,
+            program_id,
+            function_name,
+            inputs,
+            query,
+            priority_fee,
+            fee_record,
+            cost_v1,
+        );
 
         Self::handle_output(
             command.output().await,
@@ -116,17 +161,49 @@ impl AotCmd {
         )
     }
 
+    /// Estimate the fee of a program execution without authorizing the fee
     #[allow(clippy::too_many_arguments)]
-    pub async fn authorize_deploy(
+    pub async fn estimate_program_fee(
+        &self,
+        private_key: &str,
+        program_id: &str,
+        function_name: &str,
+        inputs: &[String],
+        query: Option<&String>,
+        priority_fee: Option<u64>,
+        cost_v1: bool,
+    ) -> Result<FeeBreakdown, AotCmdError> {
+        let mut command = self.auth_program_command(
+            private_key,
+            None,
+            program_id,
+            function_name,
+            inputs,
+            query,
+            priority_fee,
+            None,
+            cost_v1,
+        );
+        command.stdout(Stdio::piped()).arg("--estimate-fee");
+
+        Self::handle_output(
+            command.output().await,
+            "output",
+            "aot auth program",
+            Self::parse_fee,
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn auth_deploy_command(
         &self,
         private_key: &str,
         fee_private_key: Option<&String>,
-        program: &str,
         query: Option<&String>,
         priority_fee: Option<u64>,
         fee_record: Option<&String>,
         cost_v1: bool,
-    ) -> Result<String, AotCmdError> {
+    ) -> Command {
         let mut command = Command::new(&self.bin);
         command
             .stdin(Stdio::piped())
@@ -158,6 +235,14 @@ impl AotCmd {
             command.arg("--cost-v1");
         }
 
+        command
+    }
+
+    /// Spawn an `aot auth deploy` command, writing the program to its stdin
+    async fn run_auth_deploy(
+        mut command: Command,
+        program: &str,
+    ) -> Result<std::process::Output, AotCmdError> {
         command.arg("-");
 
         let mut child = command
@@ -171,14 +256,103 @@ impl AotCmd {
                 .map_err(|e| CommandError::action("writing to", "aot auth deploy stdin", e))?;
         }
 
+        Ok(child
+            .wait_with_output()
+            .await
+            .map_err(|e| CommandError::action("output", "aot auth deploy", e))?)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub async fn authorize_deploy(
+        &self,
+        private_key: &str,
+        fee_private_key: Option<&String>,
+        program: &str,
+        query: Option<&String>,
+        priority_fee: Option<u64>,
+        fee_record: Option<&String>,
+        cost_v1: bool,
+    ) -> Result<String, AotCmdError> {
+        let command = self.auth_deploy_command(
+            private_key,
+            fee_private_key,
+            query,
+            priority_fee,
+            fee_record,
+            cost_v1,
+        );
+
         Self::handle_output(
-            child.wait_with_output().await,
+            Ok(Self::run_auth_deploy(command, program).await?),
             "output",
             "aot auth deploy",
             Self::parse_string,
         )
     }
 
+    /// Estimate the fee of a deployment without authorizing the fee
+    pub async fn estimate_deploy_fee(
+        &self,
+        private_key: &str,
+        program: &str,
+        query: Option<&String>,
+        priority_fee: Option<u64>,
+    ) -> Result<FeeBreakdown, AotCmdError> {
+        let mut command =
+            self.auth_deploy_command(private_key, None, query, priority_fee, None, false);
+        command.arg("--estimate-fee");
+
+        Self::handle_output(
+            Ok(Self::run_auth_deploy(command, program).await?),
+            "output",
+            "aot auth deploy",
+            Self::parse_fee,
+        )
+    }
+
+    /// Sort program sources by their imports and estimate their deployment
+    /// costs.
+    pub async fn program_bundle(
+        &self,
+        programs: &[String],
+        query: Option<&String>,
+    ) -> Result<Vec<BundleProgram>, AotCmdError> {
+        let mut command = Command::new(&self.bin);
+        command
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(std::io::stderr())
+            .env("NETWORK", self.network.to_string())
+            .arg("program")
+            .arg("bundle")
+            .arg("--json");
+
+        if let Some(query) = query {
+            command.arg("--query").arg(query);
+        }
+
+        command.arg("-");
+
+        let mut child = command
+            .spawn()
+            .map_err(|e| CommandError::action("spawning", "aot program bundle", e))?;
+
+        if let Some(mut stdin) = child.stdin.take() {
+            let programs = serde_json::to_vec(programs)?;
+            stdin
+                .write_all(&programs)
+                .await
+                .map_err(|e| CommandError::action("writing to", "aot program bundle stdin", e))?;
+        }
+
+        Self::handle_output(
+            child.wait_with_output().await,
+            "output",
+            "aot program bundle",
+            |bytes| Ok(serde_json::from_slice(&bytes)?),
+        )
+    }
+
     pub async fn authorize_program_only(
         &self,
         private_key: &str,
@@ -299,6 +473,37 @@ impl AotCmd {
         .map(|s| s.trim().to_string())
     }
 
+    /// Find the unspent records owned by a private key through a ledger query
+    /// service
+    pub async fn find_records(
+        &self,
+        private_key: &str,
+        query: &str,
+        min_microcredits: u64,
+    ) -> Result<Vec<UnspentRecord>, AotCmdError> {
+        let mut command = Command::new(&self.bin);
+        command
+            .env("NETWORK", self.network.to_string())
+            .arg("auth")
+            .arg("records")
+            .arg("--private-key")
+            .arg(private_key)
+            .arg("--query")
+            .arg(query)
+            .arg("--min-microcredits")
+            .arg(min_microcredits.to_string());
+
+        Self::handle_output(
+            command.output().await,
+            "output",
+            "aot auth records",
+            |bytes| {
+                let start = bytes.iter().position(|b| *b == b'[').unwrap_or(0);
+                Ok(serde_json::from_slice(&bytes[start..])?)
+            },
+        )
+    }
+
     pub fn ledger_query(&self, storage_path: PathBuf, port: u16) -> Result<Child, CommandError> {
         let mut command = Command::new(&self.bin);
         command
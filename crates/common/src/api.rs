@@ -43,6 +43,9 @@ pub struct StorageInfo {
     pub version: u16,
     /// Whether to use the network's native genesis block
     pub native_genesis: bool,
+    /// The sha256 of the genesis block, used to find it in an agent's download
+    /// cache
+    pub genesis_sha256: Option<String>,
     /// A map of the snarkos binary ids to a potential download url (when None,
     /// download from the control plane)
     pub binaries: IndexMap<InternedId, BinaryEntry>,
@@ -248,7 +251,7 @@ impl DataFormat for StorageInfo {
     type Header = StorageInfoHeader;
 
     const LATEST_HEADER: Self::Header = StorageInfoHeader {
-        version: 2,
+        version: 3,
         retention_policy: RetentionPolicy::LATEST_HEADER,
         binaries: BinaryEntry::LATEST_HEADER,
     };
@@ -263,6 +266,7 @@ impl DataFormat for StorageInfo {
         written += self.version.write_data(writer)?;
         written += self.native_genesis.write_data(writer)?;
         written += self.binaries.write_data(writer)?;
+        written += self.genesis_sha256.write_data(writer)?;
         Ok(written)
     }
 
@@ -292,12 +296,18 @@ impl DataFormat for StorageInfo {
         let native_genesis = bool::read_data(reader, &())?;
         let binaries =
             IndexMap::<InternedId, BinaryEntry>::read_data(reader, &((), header.binaries))?;
+        let genesis_sha256 = if header.version > 2 {
+            Option::<String>::read_data(reader, &())?
+        } else {
+            None
+        };
         Ok(Self {
             id,
             retention_policy,
             persist,
             version,
             native_genesis,
+            genesis_sha256,
             binaries,
         })
     }
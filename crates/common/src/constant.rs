@@ -13,6 +13,9 @@ pub const SNARKOS_GENESIS_FILE: &str = "genesis.block";
 pub const LEDGER_BASE_DIR: &str = "ledger";
 /// The directory name for persisted ledgers within the storage dir.
 pub const LEDGER_PERSIST_DIR: &str = "persist";
+/// Directory name for checkpoints transferred from another agent's ledger,
+/// next to the ledger directory.
+pub const TRANSFERRED_CHECKPOINT_DIR: &str = "transferred";
 /// File containing a version counter for a ledger
 pub const VERSION_FILE: &str = "version";
 /// Directory name for the node's data.
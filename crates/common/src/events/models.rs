@@ -62,6 +62,10 @@ pub enum AgentEvent {
     NodeStatus(NodeStatus),
     /// An agent emits a block update
     BlockInfo(LatestBlockInfo),
+    /// A node on a disconnected agent was moved to another agent
+    NodeFailover { to: AgentId },
+    /// A node on a disconnected agent could not be moved to another agent
+    NodeFailoverFailed { reason: String },
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -88,7 +92,15 @@ pub enum TransactionEvent {
     },
     /// The transaction broadcast has exceeded the maximum number of attempts
     BroadcastExceeded { attempts: u32 },
-    /// The transaction has been confirmed by the network
+    /// The transaction was included in a block and executed successfully
+    Accepted { hash: String },
+    /// The transaction was included in a block, but its execution failed and
+    /// only its fee was consumed
+    Rejected { hash: String },
+    /// The transaction was aborted by the block that included it
+    Aborted { hash: String },
+    /// Deprecated: the transaction was included in a block. Emitted after
+    /// `Accepted`, `Rejected`, or `Aborted` for subscribers that predate them
     Confirmed { hash: String },
 }
 
@@ -100,6 +112,17 @@ pub enum TransactionAbortReason {
         transaction_status: TransactionSendState,
     },
     MissingAuthorization,
+    /// The transaction was removed by flushing the cannon
+    Flushed,
+    /// The authorization was not executed before its deadline
+    DeadlineExceeded {
+        deadline: DateTime<Utc>,
+    },
+    /// A transaction the authorization depends on was not confirmed
+    DependencyFailed {
+        dependency: Arc<String>,
+        outcome: String,
+    },
 }
 
 #[derive(Clone, Copy, Debug, PartialEq)]
@@ -112,6 +135,8 @@ pub enum EventKindFilter {
     AgentReconcileError,
     AgentNodeStatus,
     AgentBlockInfo,
+    AgentNodeFailover,
+    AgentNodeFailoverFailed,
     TransactionAuthorizationReceived,
     TransactionExecuteAborted,
     TransactionExecuteAwaitingCompute,
@@ -121,6 +146,11 @@ pub enum EventKindFilter {
     TransactionExecuteComplete,
     TransactionBroadcasted,
     TransactionBroadcastExceeded,
+    TransactionAccepted,
+    TransactionRejected,
+    TransactionAborted,
+    /// Deprecated: use `TransactionAccepted`, `TransactionRejected`, or
+    /// `TransactionAborted`
     TransactionConfirmed,
 }
 
@@ -140,6 +170,8 @@ impl EventKind {
             Agent(ReconcileError(_)) => AgentReconcileError,
             Agent(NodeStatus(_)) => AgentNodeStatus,
             Agent(BlockInfo(_)) => AgentBlockInfo,
+            Agent(NodeFailover { .. }) => AgentNodeFailover,
+            Agent(NodeFailoverFailed { .. }) => AgentNodeFailoverFailed,
             Transaction(AuthorizationReceived { .. }) => TransactionAuthorizationReceived,
             Transaction(ExecuteAborted(_)) => TransactionExecuteAborted,
             Transaction(ExecuteAwaitingCompute) => TransactionExecuteAwaitingCompute,
@@ -149,6 +181,9 @@ impl EventKind {
             Transaction(ExecuteComplete { .. }) => TransactionExecuteComplete,
             Transaction(Broadcasted { .. }) => TransactionBroadcasted,
             Transaction(BroadcastExceeded { .. }) => TransactionBroadcastExceeded,
+            Transaction(Accepted { .. }) => TransactionAccepted,
+            Transaction(Rejected { .. }) => TransactionRejected,
+            Transaction(Aborted { .. }) => TransactionAborted,
             Transaction(Confirmed { .. }) => TransactionConfirmed,
         }
     }
@@ -168,6 +203,8 @@ impl FromStr for EventKindFilter {
             "agent-reconcile-error" => Ok(Self::AgentReconcileError),
             "agent-node-status" => Ok(Self::AgentNodeStatus),
             "agent-block-info" => Ok(Self::AgentBlockInfo),
+            "agent-node-failover" => Ok(Self::AgentNodeFailover),
+            "agent-node-failover-failed" => Ok(Self::AgentNodeFailoverFailed),
             "transaction-authorization-received" => Ok(Self::TransactionAuthorizationReceived),
             "transaction-execute-aborted" => Ok(Self::TransactionExecuteAborted),
             "transaction-execute-awaiting-compute" => Ok(Self::TransactionExecuteAwaitingCompute),
@@ -177,6 +214,9 @@ impl FromStr for EventKindFilter {
             "transaction-execute-complete" => Ok(Self::TransactionExecuteComplete),
             "transaction-broadcasted" => Ok(Self::TransactionBroadcasted),
             "transaction-broadcast-exceeded" => Ok(Self::TransactionBroadcastExceeded),
+            "transaction-accepted" => Ok(Self::TransactionAccepted),
+            "transaction-rejected" => Ok(Self::TransactionRejected),
+            "transaction-aborted" => Ok(Self::TransactionAborted),
             "transaction-confirmed" => Ok(Self::TransactionConfirmed),
             _ => Err(format!("invalid event kind: {s}")),
         }
@@ -196,6 +236,8 @@ impl Display for EventKindFilter {
             AgentReconcileError => "agent-reconcile-error",
             AgentNodeStatus => "agent-node-status",
             AgentBlockInfo => "agent-block-info",
+            AgentNodeFailover => "agent-node-failover",
+            AgentNodeFailoverFailed => "agent-node-failover-failed",
             TransactionAuthorizationReceived => "transaction-authorization-received",
             TransactionExecuteAborted => "transaction-execute-aborted",
             TransactionExecuteAwaitingCompute => "transaction-execute-awaiting-compute",
@@ -205,6 +247,9 @@ impl Display for EventKindFilter {
             TransactionExecuteComplete => "transaction-execute-complete",
             TransactionBroadcasted => "transaction-broadcasted",
             TransactionBroadcastExceeded => "transaction-broadcast-exceeded",
+            TransactionAccepted => "transaction-accepted",
+            TransactionRejected => "transaction-rejected",
+            TransactionAborted => "transaction-aborted",
             TransactionConfirmed => "transaction-confirmed",
         };
 
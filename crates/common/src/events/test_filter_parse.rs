@@ -41,6 +41,11 @@ fn test_each_filter() -> Result<(), EventFilterParseError> {
     );
     eq!("cannon-is(default)", CannonIs(InternedId::default()));
     eq!("event-is(agent-connected)", EventIs(AgentConnected));
+    // deprecated alias of the transaction outcome events
+    eq!(
+        "event-is(transaction-confirmed)",
+        EventIs(TransactionConfirmed)
+    );
     eq!(
         "node-key-is(client/foo)",
         NodeKeyIs("client/foo".parse().unwrap())
@@ -193,6 +198,7 @@ fn test_str() {
     test!("transaction-is(foo)");
     test!("cannon-is(default)");
     test!("event-is(agent-connected)");
+    test!("event-is(transaction-confirmed)");
     test!("node-key-is(client/foo)");
     test!("node-target-is(client/any)");
     test!("node-target-is(client/any, validator/any)");
@@ -13,6 +13,7 @@ pub mod events;
 pub mod format;
 pub mod key_source;
 pub mod node_targets;
+pub mod program_bundle;
 pub mod util;
 
 #[cfg(feature = "clipages")]
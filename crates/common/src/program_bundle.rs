@@ -0,0 +1,168 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// A program in a deployment bundle, in deployment order.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BundleProgram {
+    /// The program id (eg. `token.aleo`)
+    pub id: String,
+    /// Ids of the programs this program imports
+    #[serde(default)]
+    pub imports: Vec<String>,
+    /// The estimated deployment cost in microcredits
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cost: Option<u64>,
+    /// The program source
+    pub program: String,
+}
+
+/// A manifest listing the programs in a bundle. Paths are relative to the
+/// manifest.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BundleManifest {
+    pub programs: Vec<PathBuf>,
+}
+
+#[derive(Debug, Error)]
+pub enum BundleError {
+    #[error("failed to read {}: {1}", .0.display())]
+    Read(PathBuf, #[source] io::Error),
+    #[error("failed to parse bundle manifest {}: {1}", .0.display())]
+    Manifest(PathBuf, #[source] serde_json::Error),
+    #[error("no programs found in {}", .0.display())]
+    Empty(PathBuf),
+    #[error("program `{0}` appears more than once in the bundle")]
+    DuplicateProgram(String),
+    #[error("programs have cyclic imports: {}", .0.join(", "))]
+    ImportCycle(Vec<String>),
+}
+
+/// Read the program sources of a bundle. The path can be a single `.aleo`
+/// file, a directory of `.aleo` files, or a JSON [`BundleManifest`].
+pub fn read_bundle(path: &Path) -> Result<Vec<String>, BundleError> {
+    let read =
+        |path: &Path| fs::read_to_string(path).map_err(|e| BundleError::Read(path.into(), e));
+
+    let paths = if path.is_dir() {
+        let mut paths = fs::read_dir(path)
+            .map_err(|e| BundleError::Read(path.into(), e))?
+            .filter_map(|entry| Some(entry.ok()?.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "aleo"))
+            .collect::<Vec<_>>();
+        // directory order is not stable
+        paths.sort();
+        paths
+    } else if path.extension().is_some_and(|ext| ext == "aleo") {
+        vec![path.to_owned()]
+    } else {
+        let manifest: BundleManifest = serde_json::from_str(&read(path)?)
+            .map_err(|e| BundleError::Manifest(path.into(), e))?;
+        let base = path.parent().unwrap_or(Path::new("."));
+        manifest.programs.iter().map(|p| base.join(p)).collect()
+    };
+
+    if paths.is_empty() {
+        return Err(BundleError::Empty(path.into()));
+    }
+    paths.iter().map(|path| read(path)).collect()
+}
+
+/// Sort items so every item comes after the items it imports. Imports that are
+/// not in the bundle are assumed to be deployed already. Items without a
+/// dependency between them keep their original order.
+pub fn sort_by_imports<T>(
+    items: Vec<T>,
+    id: impl Fn(&T) -> String,
+    imports: impl Fn(&T) -> Vec<String>,
+) -> Result<Vec<T>, BundleError> {
+    let ids = items.iter().map(&id).collect::<Vec<_>>();
+    let mut index = HashMap::new();
+    for (i, id) in ids.iter().enumerate() {
+        if index.insert(id.clone(), i).is_some() {
+            return Err(BundleError::DuplicateProgram(id.clone()));
+        }
+    }
+
+    // imports of each item that are also in the bundle
+    let mut pending = items
+        .iter()
+        .map(|item| {
+            imports(item)
+                .into_iter()
+                .filter_map(|import| index.get(&import).copied())
+                .collect::<HashSet<_>>()
+        })
+        .collect::<Vec<_>>();
+
+    let mut order = Vec::with_capacity(items.len());
+    let mut done = vec![false; items.len()];
+    while order.len() < items.len() {
+        let Some(next) = (0..items.len()).find(|&i| !done[i] && pending[i].is_empty()) else {
+            let cycle = (0..items.len())
+                .filter(|&i| !done[i])
+                .map(|i| ids[i].clone())
+                .collect();
+            return Err(BundleError::ImportCycle(cycle));
+        };
+
+        done[next] = true;
+        order.push(next);
+        for deps in &mut pending {
+            deps.remove(&next);
+        }
+    }
+
+    let mut items = items.into_iter().map(Some).collect::<Vec<_>>();
+    Ok(order.into_iter().filter_map(|i| items[i].take()).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sort(items: &[(&str, &[&str])]) -> Result<Vec<String>, BundleError> {
+        sort_by_imports(
+            items.to_vec(),
+            |(id, _)| id.to_string(),
+            |(_, imports)| imports.iter().map(|i| i.to_string()).collect(),
+        )
+        .map(|items| items.into_iter().map(|(id, _)| id.to_owned()).collect())
+    }
+
+    #[test]
+    fn sorts_programs_by_imports() {
+        let order = sort(&[
+            (
+                "swap.aleo",
+                &["token_a.aleo", "token_b.aleo", "credits.aleo"],
+            ),
+            ("token_b.aleo", &["registry.aleo"]),
+            ("token_a.aleo", &["registry.aleo"]),
+            ("registry.aleo", &[]),
+        ])
+        .unwrap();
+        assert_eq!(
+            order,
+            ["registry.aleo", "token_b.aleo", "token_a.aleo", "swap.aleo"]
+        );
+    }
+
+    #[test]
+    fn rejects_cycles_and_duplicates() {
+        assert!(matches!(
+            sort(&[("a.aleo", &["b.aleo"]), ("b.aleo", &["a.aleo"]), ("c.aleo", &[])]),
+            Err(BundleError::ImportCycle(ids)) if ids == ["a.aleo", "b.aleo"]
+        ));
+        assert!(matches!(
+            sort(&[("a.aleo", &[]), ("a.aleo", &[])]),
+            Err(BundleError::DuplicateProgram(id)) if id == "a.aleo"
+        ));
+    }
+}
@@ -1,4 +1,7 @@
-use crate::{rpc::error::AgentError, state::snarkos_status::SnarkOSLiteBlock};
+use crate::{
+    rpc::error::AgentError,
+    state::{ledger_digest::LedgerDigest, snarkos_status::SnarkOSLiteBlock},
+};
 
 #[tarpc::service]
 pub trait NodeService {
@@ -7,4 +10,5 @@ pub trait NodeService {
     async fn set_log_level(verbosity: u8) -> Result<(), AgentError>;
     async fn get_block_lite(block_hash: String) -> Result<Option<SnarkOSLiteBlock>, AgentError>;
     async fn find_transaction(tx_id: String) -> Result<Option<String>, AgentError>;
+    async fn get_ledger_digest() -> Result<LedgerDigest, AgentError>;
 }
@@ -2,7 +2,9 @@ use std::net::IpAddr;
 
 use serde::{Deserialize, Serialize};
 
+use crate::action_models::FeeBreakdown;
 use crate::rpc::error::*;
+use crate::state::ledger_digest::LedgerDigest;
 use crate::state::snarkos_status::SnarkOSLiteBlock;
 use crate::state::{AgentId, ReconcileOptions};
 use crate::{
@@ -54,6 +56,21 @@ pub trait AgentService {
         auth: String,
     ) -> Result<String, AgentError>;
 
+    /// Locally authorize a program execution, using the given query
+    async fn authorize_program(
+        env_id: EnvId,
+        network: NetworkId,
+        request: AuthorizeRequest,
+    ) -> Result<String, AgentError>;
+
+    /// Locally estimate the fee of a program execution or deployment, using
+    /// the given query
+    async fn estimate_fee(
+        env_id: EnvId,
+        network: NetworkId,
+        request: EstimateFeeRequest,
+    ) -> Result<FeeBreakdown, AgentError>;
+
     async fn get_metric(metric: AgentMetric) -> f64;
 
     async fn set_log_level(level: String) -> Result<(), AgentError>;
@@ -66,6 +83,21 @@ pub trait AgentService {
         block_hash: String,
     ) -> Result<Option<SnarkOSLiteBlock>, AgentError>;
 
+    /// Summarize the running node's ledger, to compare it with other nodes
+    async fn get_ledger_digest() -> Result<LedgerDigest, AgentError>;
+
+    /// List the heights of the checkpoints next to the running node's ledger
+    async fn get_checkpoints() -> Result<Vec<u32>, AgentError>;
+
+    /// Read a checkpoint of the running node's ledger
+    async fn get_checkpoint(height: u32) -> Result<Vec<u8>, AgentError>;
+
+    /// Store a checkpoint transferred from another agent's ledger, to be
+    /// applied when the node's height request cannot be met by its own
+    /// checkpoints
+    async fn put_checkpoint(env_id: EnvId, height: u32, checkpoint: Vec<u8>)
+    -> Result<(), AgentError>;
+
     async fn set_aot_log_level(verbosity: u8) -> Result<(), AgentError>;
 
     async fn get_status() -> Result<AgentStatus, AgentError>;
@@ -77,6 +109,33 @@ pub struct AgentStatus {
     pub version: String,
 }
 
+/// A program execution for a compute agent to authorize
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuthorizeRequest {
+    pub private_key: String,
+    pub fee_private_key: Option<String>,
+    pub program: String,
+    pub function: String,
+    pub inputs: Vec<String>,
+    /// Query path on the control plane
+    pub query: String,
+    pub priority_fee: Option<u64>,
+    pub cost_v1: bool,
+}
+
+/// A transaction for a compute agent to estimate the fee of
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum EstimateFeeRequest {
+    Program(AuthorizeRequest),
+    Deploy {
+        private_key: String,
+        program: String,
+        /// Query path on the control plane
+        query: String,
+        priority_fee: Option<u64>,
+    },
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum AgentMetric {
     Tps,
@@ -38,4 +38,8 @@ pub trait ControlService {
 
     /// Emit an agent reconcile status update.
     async fn post_reconcile_status(status: Result<ReconcileStatus<bool>, ReconcileError>);
+
+    /// Emit the sha256 hashes of the cached files the agent can serve to other
+    /// agents. Will overwrite the old list.
+    async fn post_cached_files(hashes: Vec<String>);
 }
@@ -91,6 +91,12 @@ pub enum AgentError {
     InvalidBlockHash,
     #[error("invalid transaction id")]
     InvalidTransactionId,
+    #[error("checkpoint not found")]
+    CheckpointNotFound,
+    #[error("failed to read checkpoint: {0}")]
+    FailedToReadCheckpoint(String),
+    #[error("failed to write checkpoint: {0}")]
+    FailedToWriteCheckpoint(String),
 }
 
 #[derive(Debug, Error, Serialize, Deserialize, AsRefStr)]
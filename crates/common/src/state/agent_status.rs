@@ -1,4 +1,4 @@
-use std::time::Instant;
+use std::{collections::HashSet, time::Instant};
 
 use chrono::{DateTime, Utc};
 use indexmap::IndexMap;
@@ -172,6 +172,8 @@ pub struct AgentStatus {
     pub transfers: IndexMap<TransferId, TransferStatus>,
     /// Latest reconcile status of the agent
     pub reconcile: Option<(Instant, Result<ReconcileStatus<bool>, ReconcileError>)>,
+    /// sha256 hashes of cached files the agent can serve to other agents
+    pub cached_files: HashSet<String>,
 }
 
 impl DataFormat for LatestBlockInfo {
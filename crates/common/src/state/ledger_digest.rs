@@ -0,0 +1,300 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// Mappings with more entries than this are summarized by a checksum in
+/// digests requested from nodes, instead of listing every entry.
+pub const MAX_DIGEST_MAPPING_ENTRIES: usize = 10_000;
+
+/// A summary of a ledger, used to compare ledgers across nodes
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LedgerDigest {
+    /// Block hashes, indexed by height
+    pub block_hashes: Vec<String>,
+    /// Transaction ids of blocks with transactions, by height. Only included
+    /// when requested, as it can be large.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub transactions: BTreeMap<u32, Vec<String>>,
+    /// Finalize mapping entries by `program/mapping`, then by key
+    pub mappings: BTreeMap<String, BTreeMap<String, String>>,
+    /// Checksums of mappings with too many entries to list, by
+    /// `program/mapping`. See [`LedgerDigest::bound_mappings`].
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub mapping_checksums: BTreeMap<String, String>,
+}
+
+/// The differences between two ledgers, `a` and `b`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LedgerDiff {
+    /// The latest height of each ledger
+    pub heights: (u32, u32),
+    /// The first height at which the block hashes differ
+    pub diverged_at: Option<u32>,
+    /// The block hashes at the diverging height
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub block_hashes: Option<(String, String)>,
+    /// Transactions only found in one of the blocks at the diverging height
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transactions: Option<(Vec<String>, Vec<String>)>,
+    /// Finalize mappings with differing keys, by `program/mapping`
+    pub mappings: BTreeMap<String, MappingDiff>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MappingDiff {
+    /// Keys only present in `a`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub only_a: Vec<String>,
+    /// Keys only present in `b`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub only_b: Vec<String>,
+    /// Keys present in both with different values
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub changed: Vec<String>,
+    /// The mapping was too large to list its keys, and its entries differ
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub summarized: bool,
+}
+
+impl LedgerDigest {
+    pub fn height(&self) -> u32 {
+        self.block_hashes.len().saturating_sub(1) as u32
+    }
+
+    /// Replace the entries of mappings with more than `max_entries` entries
+    /// with a checksum of their entries, bounding the size of the digest.
+    pub fn bound_mappings(&mut self, max_entries: usize) {
+        let large: Vec<_> = self
+            .mappings
+            .iter()
+            .filter(|(_, entries)| entries.len() > max_entries)
+            .map(|(name, _)| name.clone())
+            .collect();
+
+        for name in large {
+            if let Some(entries) = self.mappings.remove(&name) {
+                self.mapping_checksums
+                    .insert(name, mapping_checksum(&entries));
+            }
+        }
+    }
+
+    /// The checksum of a mapping, whether its entries are listed or not.
+    fn checksum(&self, name: &str) -> Option<String> {
+        match self.mappings.get(name) {
+            Some(entries) => Some(mapping_checksum(entries)),
+            None => self.mapping_checksums.get(name).cloned(),
+        }
+    }
+
+    /// Compare this ledger (`a`) to another (`b`). Transactions at the
+    /// diverging height are only compared when the digests include them.
+    pub fn diff(&self, other: &LedgerDigest) -> LedgerDiff {
+        let diverged_at = self
+            .block_hashes
+            .iter()
+            .zip(&other.block_hashes)
+            .position(|(a, b)| a != b)
+            .map(|height| height as u32);
+
+        let block_hashes = diverged_at.map(|height| {
+            (
+                self.block_hashes[height as usize].clone(),
+                other.block_hashes[height as usize].clone(),
+            )
+        });
+
+        let transactions = diverged_at
+            .filter(|_| !self.transactions.is_empty() || !other.transactions.is_empty())
+            .map(|height| {
+                let empty = vec![];
+                let a = self.transactions.get(&height).unwrap_or(&empty);
+                let b = other.transactions.get(&height).unwrap_or(&empty);
+                diff_transactions(a, b)
+            });
+
+        let mut mappings = BTreeMap::new();
+        let empty = BTreeMap::new();
+        let names: BTreeSet<_> = self
+            .mappings
+            .keys()
+            .chain(other.mappings.keys())
+            .chain(self.mapping_checksums.keys())
+            .chain(other.mapping_checksums.keys())
+            .collect();
+        for name in names {
+            // summarized mappings can only be compared by their checksums
+            if self.mapping_checksums.contains_key(name)
+                || other.mapping_checksums.contains_key(name)
+            {
+                if self.checksum(name) != other.checksum(name) {
+                    let diff = MappingDiff {
+                        summarized: true,
+                        ..Default::default()
+                    };
+                    mappings.insert(name.clone(), diff);
+                }
+                continue;
+            }
+
+            let a = self.mappings.get(name).unwrap_or(&empty);
+            let b = other.mappings.get(name).unwrap_or(&empty);
+
+            let mut diff = MappingDiff::default();
+            for (key, value) in a {
+                match b.get(key) {
+                    None => diff.only_a.push(key.clone()),
+                    Some(other) if other != value => diff.changed.push(key.clone()),
+                    Some(_) => {}
+                }
+            }
+            diff.only_b = b.keys().filter(|k| !a.contains_key(*k)).cloned().collect();
+
+            if !diff.is_empty() {
+                mappings.insert(name.clone(), diff);
+            }
+        }
+
+        LedgerDiff {
+            heights: (self.height(), other.height()),
+            diverged_at,
+            block_hashes,
+            transactions,
+            mappings,
+        }
+    }
+}
+
+/// A checksum of a mapping's entries.
+pub fn mapping_checksum(entries: &BTreeMap<String, String>) -> String {
+    let mut hasher = Sha256::new();
+    for (key, value) in entries {
+        hasher.update(key.as_bytes());
+        hasher.update([0]);
+        hasher.update(value.as_bytes());
+        hasher.update([0]);
+    }
+    hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+/// Transactions only in `a`, and only in `b`
+pub fn diff_transactions(a: &[String], b: &[String]) -> (Vec<String>, Vec<String>) {
+    (
+        a.iter().filter(|tx| !b.contains(tx)).cloned().collect(),
+        b.iter().filter(|tx| !a.contains(tx)).cloned().collect(),
+    )
+}
+
+impl LedgerDiff {
+    /// Whether the ledgers agree on every common block and mapping entry
+    pub fn is_empty(&self) -> bool {
+        self.diverged_at.is_none() && self.mappings.is_empty()
+    }
+}
+
+impl MappingDiff {
+    pub fn is_empty(&self) -> bool {
+        self.only_a.is_empty()
+            && self.only_b.is_empty()
+            && self.changed.is_empty()
+            && !self.summarized
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn digest(hashes: &[&str], mappings: &[(&str, &str, &str)]) -> LedgerDigest {
+        let mut digest = LedgerDigest {
+            block_hashes: hashes.iter().map(|h| h.to_string()).collect(),
+            ..Default::default()
+        };
+        for (mapping, key, value) in mappings {
+            digest
+                .mappings
+                .entry(mapping.to_string())
+                .or_default()
+                .insert(key.to_string(), value.to_string());
+        }
+        digest
+    }
+
+    #[test]
+    fn diff_finds_divergence_and_mapping_changes() {
+        let a = digest(
+            &["g", "a1", "a2"],
+            &[
+                ("credits.aleo/account", "x", "1"),
+                ("credits.aleo/account", "y", "2"),
+            ],
+        );
+        let b = digest(
+            &["g", "b1"],
+            &[
+                ("credits.aleo/account", "x", "3"),
+                ("credits.aleo/bonded", "z", "4"),
+            ],
+        );
+
+        let diff = a.diff(&b);
+        assert_eq!(diff.heights, (2, 1));
+        assert_eq!(diff.diverged_at, Some(1));
+        assert_eq!(diff.block_hashes, Some(("a1".into(), "b1".into())));
+        assert_eq!(diff.transactions, None);
+
+        let account = &diff.mappings["credits.aleo/account"];
+        assert_eq!(account.changed, vec!["x".to_owned()]);
+        assert_eq!(account.only_a, vec!["y".to_owned()]);
+        assert_eq!(
+            diff.mappings["credits.aleo/bonded"].only_b,
+            vec!["z".to_owned()]
+        );
+
+        assert!(a.diff(&a).is_empty());
+        // a ledger that is behind has not diverged
+        assert_eq!(a.diff(&digest(&["g", "a1"], &[])).diverged_at, None);
+    }
+
+    #[test]
+    fn bounded_mappings_compare_by_checksum() {
+        let entries = [
+            ("credits.aleo/account", "x", "1"),
+            ("credits.aleo/account", "y", "2"),
+            ("credits.aleo/bonded", "z", "3"),
+        ];
+        let a = digest(&["g"], &entries);
+        let mut bounded = a.clone();
+        bounded.bound_mappings(1);
+
+        // only the mapping over the limit is summarized
+        assert!(!bounded.mappings.contains_key("credits.aleo/account"));
+        assert!(bounded.mappings.contains_key("credits.aleo/bonded"));
+        assert_eq!(
+            bounded.mapping_checksums["credits.aleo/account"],
+            mapping_checksum(&a.mappings["credits.aleo/account"])
+        );
+
+        // a summarized mapping matches the same listed entries
+        assert!(a.diff(&bounded).is_empty());
+        assert!(bounded.diff(&bounded).is_empty());
+
+        let mut changed = digest(
+            &["g"],
+            &[
+                ("credits.aleo/account", "x", "1"),
+                ("credits.aleo/account", "y", "4"),
+                ("credits.aleo/bonded", "z", "3"),
+            ],
+        );
+        changed.bound_mappings(1);
+        let diff = a.diff(&changed);
+        assert!(diff.mappings["credits.aleo/account"].summarized);
+        assert!(!diff.mappings.contains_key("credits.aleo/bonded"));
+    }
+}
@@ -13,6 +13,7 @@ mod node_state;
 mod node_type;
 mod port_config;
 mod reconcile;
+pub mod ledger_digest;
 pub mod snarkos_status;
 pub mod strings;
 mod transaction_status;
@@ -17,6 +17,11 @@ pub struct PortConfig {
     /// Specify the port for the metrics
     #[clap(long = "metrics", default_value_t = 9000)]
     pub metrics: u16,
+
+    /// Specify the port to serve cached storage files to other agents on, or
+    /// 0 to disable
+    #[clap(long = "content", default_value_t = 0)]
+    pub content: u16,
 }
 
 impl std::fmt::Display for PortConfig {
@@ -31,7 +36,7 @@ impl std::fmt::Display for PortConfig {
 
 impl DataFormat for PortConfig {
     type Header = u8;
-    const LATEST_HEADER: Self::Header = 1;
+    const LATEST_HEADER: Self::Header = 2;
 
     fn write_data<W: std::io::prelude::Write>(
         &self,
@@ -42,6 +47,7 @@ impl DataFormat for PortConfig {
         written += self.bft.write_data(writer)?;
         written += self.rest.write_data(writer)?;
         written += self.metrics.write_data(writer)?;
+        written += self.content.write_data(writer)?;
         Ok(written)
     }
 
@@ -49,10 +55,10 @@ impl DataFormat for PortConfig {
         reader: &mut R,
         header: &Self::Header,
     ) -> Result<Self, crate::format::DataReadError> {
-        if *header != Self::LATEST_HEADER {
+        if *header == 0 || *header > Self::LATEST_HEADER {
             return Err(crate::format::DataReadError::unsupported(
                 "PortConfig",
-                Self::LATEST_HEADER,
+                format!("1 or {}", Self::LATEST_HEADER),
                 *header,
             ));
         }
@@ -62,6 +68,11 @@ impl DataFormat for PortConfig {
             bft: reader.read_data(&())?,
             rest: reader.read_data(&())?,
             metrics: reader.read_data(&())?,
+            content: if *header > 1 {
+                reader.read_data(&())?
+            } else {
+                0
+            },
         })
     }
 }
@@ -100,7 +111,8 @@ mod test {
             bft: 1,
             rest: 2,
             metrics: 3,
+            content: 4,
         },
-        [0, 0, 1, 0, 2, 0, 3, 0]
+        [0, 0, 1, 0, 2, 0, 3, 0, 4, 0]
     );
 }
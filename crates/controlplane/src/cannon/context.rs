@@ -1,4 +1,8 @@
-use std::sync::{Arc, atomic::AtomicUsize};
+use std::{
+    collections::HashSet,
+    sync::{Arc, atomic::AtomicUsize},
+    time::{Duration, Instant},
+};
 
 use chrono::Utc;
 use dashmap::DashMap;
@@ -13,16 +17,25 @@ use tracing::{error, trace, warn};
 use super::{
     CannonReceivers,
     error::{CannonError, ExecutionContextError, SourceError},
+    fee_records::FeeRecordReservations,
     file::TransactionSink,
+    history::TransactionOutcome,
+    intake::IntakeTask,
+    limit::{CannonLoad, TokenBucket},
+    queue::{QueueOrder, WorkQueue},
     sink::TxSink,
     source::TxSource,
-    tracker::TransactionTracker,
+    status::{CannonStatus, SharedStatus},
+    tracker::{DependencyState, TransactionTracker},
 };
 use crate::{
     cannon::source::ComputeTarget,
     state::{EmitEvent, GetGlobalState, GlobalState, REST_CLIENT},
 };
 
+/// How often a cannon with queued work checks whether it can start it
+const THROTTLE_POLL: Duration = Duration::from_secs(1);
+
 /// Information a transaction cannon needs for execution via spawned task
 pub struct ExecutionContext {
     pub(crate) state: Arc<GlobalState>,
@@ -33,8 +46,13 @@ pub struct ExecutionContext {
     pub(crate) network: NetworkId,
     pub(crate) source: TxSource,
     pub(crate) sink: TxSink,
+    /// Port of the cannon's local ledger query service, if it has one
+    pub(crate) query_port: Option<u16>,
     pub(crate) fired_txs: Arc<AtomicUsize>,
     pub(crate) transactions: Arc<DashMap<Arc<String>, TransactionTracker>>,
+    pub(crate) status: Arc<SharedStatus>,
+    pub(crate) load: Arc<CannonLoad>,
+    pub(crate) fee_records: Arc<FeeRecordReservations>,
 }
 
 impl ExecutionContext {
@@ -82,51 +100,131 @@ impl ExecutionContext {
             })
             .transpose()?;
 
+        // read authorizations from the source's intake alongside the cannon's work
+        let intake = source.intake.clone().map(|intake| IntakeTask {
+            state: Arc::clone(state),
+            env_id,
+            cannon_id: *cannon_id,
+            intake,
+            storage_path: env.storage.path(state),
+        });
+        let intake = async move {
+            match intake {
+                Some(intake) => intake.run().await,
+                None => std::future::pending().await,
+            }
+        };
+        tokio::pin!(intake);
+
         let mut auth_execs = FuturesUnordered::new();
         let mut tx_shots = FuturesUnordered::new();
 
+        // work received from the channels that is waiting for the cannon's limits
+        let mut auth_queue = WorkQueue::default();
+        let mut tx_queue = WorkQueue::default();
+        // when queued work waiting for its dependencies was last checked
+        let mut unblocked_at = Instant::now();
+        // authorizations waiting for compute or executing, which the transaction
+        // task may queue again in the meantime
+        let mut auth_in_flight = HashSet::<Arc<String>>::new();
+        let mut broadcast_bucket = sink.broadcast_rate.map(TokenBucket::new);
+
         loop {
-            tokio::select! {
-                // ------------------------
-                // Work generation
-                // ------------------------
+            // check work waiting for its dependencies again once per poll
+            if unblocked_at.elapsed() >= THROTTLE_POLL {
+                auth_queue.unblock();
+                tx_queue.unblock();
+                unblocked_at = Instant::now();
+            }
 
-                // receive authorizations and forward the executions to the compute target
-                Some(tx_id) = rx.authorizations.recv() => {
-                    // ensure the transaction tracker exists
-                    let Some(tracker) = self.transactions.get(&tx_id) else {
-                        error!("cannon {env_id}.{cannon_id} missing transaction tracker for {tx_id}");
-                        TransactionEvent::ExecuteAborted(TransactionAbortReason::MissingTracker).with_cannon_ctx(&self, tx_id).emit(&self);
-                        continue;
+            // start as much queued work as the cannon's limits allow. paused cannons
+            // hold their queued work until resumed
+            if self.status.get() != CannonStatus::Paused {
+                while sink
+                    .max_executing
+                    .is_none_or(|max| auth_execs.len() < max as usize)
+                {
+                    let Some(tx_id) = auth_queue.pop_ready(|tx_id| self.is_ready(tx_id)) else {
+                        break;
                     };
-                    // ensure the transaction is in the correct state
-                    if tracker.status != TransactionSendState::Authorized {
-                        error!("cannon {env_id}.{cannon_id} unexpected status for {tx_id}: {:?}", tracker.status);
-                        // TODO: remove this auth and log it somewhere
-                        TransactionEvent::ExecuteAborted(TransactionAbortReason::UnexpectedStatus{ transaction_status: tracker.status}).with_cannon_ctx(&self, tx_id).emit(&self);
+                    if auth_in_flight.contains(&tx_id) || self.is_archived(&tx_id) {
                         continue;
                     }
-                    // ensure the transaction has an authorization (more than likely unreachable)
-                    let Some(auth) = &tracker.authorization else {
-                        error!("cannon {env_id}.{cannon_id} missing authorization for {tx_id}");
-                        // TODO: remove the auth anyway
-                        TransactionEvent::ExecuteAborted(TransactionAbortReason::MissingAuthorization).with_cannon_ctx(&self, tx_id).emit(&self);
+                    if let Some(auth) = self.queued_auth(&tx_id) {
+                        auth_in_flight.insert(Arc::clone(&tx_id));
+                        auth_execs.push(self.execute_auth(tx_id, auth, &query_path));
+                    }
+                }
+
+                // number of new transactions the unconfirmed limit has room for
+                let mut broadcast_room = sink.max_unconfirmed.map(|max| {
+                    (max as usize).saturating_sub(self.load.unconfirmed() + tx_shots.len())
+                });
+                while let Some(tx_id) = tx_queue.peek_ready(|tx_id| self.is_ready(tx_id)) {
+                    if self.is_archived(&tx_id) {
+                        tx_queue.pop();
                         continue;
-                    };
+                    }
+                    // re-broadcasts are already counted as unconfirmed
+                    let rebroadcast = self.transactions.get(&tx_id).is_some_and(|tx| {
+                        matches!(tx.status, TransactionSendState::Broadcasted(_, _))
+                    });
+                    if !rebroadcast && broadcast_room == Some(0) {
+                        break;
+                    }
+                    if !broadcast_bucket.as_mut().is_none_or(|b| b.try_take()) {
+                        break;
+                    }
+
+                    tx_queue.pop();
+                    if let (false, Some(room)) = (rebroadcast, broadcast_room.as_mut()) {
+                        *room -= 1;
+                    }
+                    tx_shots.push(self.fire_tx(sink_pipe.clone(), tx_id));
+                }
+            }
+            self.load.update(
+                auth_execs.len(),
+                tx_shots.len(),
+                auth_queue.len(),
+                tx_queue.len(),
+            );
+
+            // check the queues again once a broadcast token is available, or the
+            // cannon may have been resumed or had transactions confirmed
+            let throttle = broadcast_bucket
+                .as_mut()
+                .map(|b| b.wait_time())
+                .filter(|wait| !wait.is_zero())
+                .unwrap_or(THROTTLE_POLL)
+                .min(THROTTLE_POLL);
+            let throttled = !auth_queue.is_empty() || !tx_queue.is_empty();
+
+            tokio::select! {
+                // ------------------------
+                // Work generation
+                // ------------------------
 
-                    auth_execs.push(self.execute_auth(tx_id, Arc::clone(auth), &query_path));
+                // queue authorizations to be forwarded to the compute target
+                Some(tx_id) = rx.authorizations.recv() => {
+                    auth_queue.push(Arc::clone(&tx_id), self.queue_order(&tx_id));
                 }
-                // receive transaction ids and forward them to the sink target
+                // queue transaction ids to be forwarded to the sink target
                 Some(tx) = rx.transactions.recv() => {
-                    tx_shots.push(self.fire_tx(sink_pipe.clone(), tx));
+                    tx_queue.push(Arc::clone(&tx), self.queue_order(&tx));
                 }
+                _ = tokio::time::sleep(throttle), if throttled => {}
+
+                // the intake only finishes when the cannon is dropped
+                _ = &mut intake => {}
 
                 // ------------------------
                 // Work results
                 // ------------------------
 
-                Some(res) = auth_execs.next() => {
-                    if let Err((tx_id, e)) = res {
+                Some((tx_id, res)) = auth_execs.next() => {
+                    auth_in_flight.remove(&tx_id);
+                    if let Err(e) = res {
                         warn!("cannon {env_id}.{cannon_id} auth execute task {tx_id} failed: {e}");
                     }
                 },
@@ -156,34 +254,135 @@ impl ExecutionContext {
                 );
             }
             tx.status = status;
+            self.load.set_unconfirmed(
+                tx_id,
+                matches!(status, TransactionSendState::Broadcasted(_, _)),
+            );
         }
     }
 
-    pub fn remove_tx_tracker(&self, tx_id: Arc<String>) {
-        let _ = self.transactions.remove(&tx_id);
-        if let Err(e) =
-            TransactionTracker::delete(&self.state, &(self.env_id, self.id, tx_id.clone()))
-        {
+    /// Stop tracking a transaction and move it into the cannon's history
+    pub fn archive_tx_tracker(&self, tx_id: Arc<String>, outcome: TransactionOutcome) {
+        let Some((_, tracker)) = self.transactions.remove(&tx_id) else {
+            return;
+        };
+        self.fee_records.release_transaction(&tx_id);
+        self.load.set_unconfirmed(&tx_id, false);
+        if let Err(e) = tracker.archive(
+            &self.state,
+            &(self.env_id, self.id, tx_id.clone()),
+            outcome,
+            None,
+        ) {
             error!(
-                "cannon {}.{} failed to delete transaction {tx_id}: {e:?}",
+                "cannon {}.{} failed to archive transaction {tx_id}: {e:?}",
                 self.env_id, self.id
             );
         }
     }
 
+    /// Get the authorization of a queued transaction, emitting an abort event
+    /// if it can no longer be executed
+    fn queued_auth(&self, tx_id: &Arc<String>) -> Option<Arc<Authorization>> {
+        let (env_id, cannon_id) = (self.env_id, self.id);
+
+        // ensure the transaction tracker exists
+        let Some(tracker) = self.transactions.get(tx_id) else {
+            error!("cannon {env_id}.{cannon_id} missing transaction tracker for {tx_id}");
+            TransactionEvent::ExecuteAborted(TransactionAbortReason::MissingTracker)
+                .with_cannon_ctx(self, Arc::clone(tx_id))
+                .emit(self);
+            return None;
+        };
+        // ensure the transaction is in the correct state
+        if tracker.status != TransactionSendState::Authorized {
+            error!(
+                "cannon {env_id}.{cannon_id} unexpected status for {tx_id}: {:?}",
+                tracker.status
+            );
+            // TODO: remove this auth and log it somewhere
+            TransactionEvent::ExecuteAborted(TransactionAbortReason::UnexpectedStatus {
+                transaction_status: tracker.status,
+            })
+            .with_cannon_ctx(self, Arc::clone(tx_id))
+            .emit(self);
+            return None;
+        }
+        // abort the authorization if it was not executed in time
+        if let Some(deadline) = tracker.schedule.deadline.filter(|d| *d <= Utc::now()) {
+            drop(tracker);
+            trace!("cannon {env_id}.{cannon_id} aborted {tx_id} (deadline exceeded)");
+            TransactionEvent::ExecuteAborted(TransactionAbortReason::DeadlineExceeded { deadline })
+                .with_cannon_ctx(self, Arc::clone(tx_id))
+                .emit(self);
+            self.archive_tx_tracker(Arc::clone(tx_id), TransactionOutcome::Expired);
+            return None;
+        }
+        // ensure the transaction has an authorization (more than likely unreachable)
+        let Some(auth) = &tracker.authorization else {
+            error!("cannon {env_id}.{cannon_id} missing authorization for {tx_id}");
+            // TODO: remove the auth anyway
+            TransactionEvent::ExecuteAborted(TransactionAbortReason::MissingAuthorization)
+                .with_cannon_ctx(self, Arc::clone(tx_id))
+                .emit(self);
+            return None;
+        };
+
+        Some(Arc::clone(auth))
+    }
+
+    /// Whether a queued transaction stopped being tracked since it was queued,
+    /// eg. because it was flushed or its dependencies failed. Its work is
+    /// dropped from the queue without an abort event, as it was already
+    /// archived with its outcome.
+    fn is_archived(&self, tx_id: &Arc<String>) -> bool {
+        !self.transactions.contains_key(tx_id)
+            && self
+                .state
+                .db
+                .tx_history
+                .restore(&(self.env_id, self.id, Arc::clone(tx_id)))
+                .is_ok_and(|history| history.is_some())
+    }
+
+    /// Order in which queued work for a transaction is started
+    fn queue_order(&self, tx_id: &Arc<String>) -> QueueOrder {
+        self.transactions
+            .get(tx_id)
+            .map(|tx| QueueOrder::new(&tx.schedule, tx.index))
+            .unwrap_or_default()
+    }
+
+    /// Whether queued work for a transaction can start. Authorizations wait
+    /// until the transactions they depend on are confirmed.
+    fn is_ready(&self, tx_id: &Arc<String>) -> bool {
+        let after = match self.transactions.get(tx_id) {
+            Some(tx) if tx.status == TransactionSendState::Authorized => tx.schedule.after.clone(),
+            _ => return true,
+        };
+        after.is_empty()
+            || TransactionTracker::dependency_state(
+                &self.state.db,
+                self.state.env_network_cache.get(&self.env_id).as_deref(),
+                &self.transactions,
+                (self.env_id, self.id),
+                &after,
+            ) == DependencyState::Ready
+    }
+
     /// Execute an authorization on the source's compute target
     async fn execute_auth(
         &self,
         tx_id: Arc<String>,
         auth: Arc<Authorization>,
         query_path: &str,
-    ) -> Result<(), (Arc<String>, CannonError)> {
+    ) -> (Arc<String>, Result<(), CannonError>) {
         TransactionEvent::AuthorizationReceived {
             authorization: Arc::clone(&auth),
         }
         .with_cannon_ctx(self, tx_id.clone())
         .emit(self);
-        match self
+        let res = match self
             .source
             .compute
             .execute(self, query_path, &tx_id, &auth)
@@ -203,10 +402,11 @@ impl ExecutionContext {
                 TransactionEvent::ExecuteFailed(e.to_string())
                     .with_cannon_ctx(self, tx_id.clone())
                     .emit(self);
-                Err((tx_id, e))
+                Err(e)
             }
-            res => res.map_err(|e| (tx_id, e)),
-        }
+            res => res,
+        };
+        (tx_id, res)
     }
 
     /// Fire a transaction to the sink
@@ -289,14 +489,20 @@ impl ExecutionContext {
                 ev.agent = agent;
                 ev.emit(self);
 
-                if let Err(e) = TransactionTracker::inc_attempts(
-                    &self.state,
-                    &(env_id, cannon_id, tx_id.to_owned()),
-                ) {
+                let key = (env_id, cannon_id, tx_id.to_owned());
+                if let Err(e) = TransactionTracker::inc_attempts(&self.state, &key) {
                     error!(
                         "cannon {env_id}.{cannon_id} failed to increment broadcast attempts for {tx_id}: {e}",
                     );
                 }
+                if let Err(e) = TransactionTracker::update_progress(&self.state, &key, |p| {
+                    p.broadcast_agent = agent;
+                    p.broadcast_at = Some(Utc::now());
+                }) {
+                    error!(
+                        "cannon {env_id}.{cannon_id} failed to record broadcast for {tx_id}: {e}",
+                    );
+                }
             };
 
             // broadcast to the first responding node
@@ -375,9 +581,9 @@ impl ExecutionContext {
                 "to broadcast transactions",
             ))?
         } else {
-            // remove the transaction from the store as there is no need to
-            // confirm the broadcast
-            self.remove_tx_tracker(tx_id.clone());
+            // archive the transaction as there is no need to confirm the
+            // broadcast
+            self.archive_tx_tracker(tx_id.clone(), TransactionOutcome::Recorded);
         }
         Ok(tx_id)
     }
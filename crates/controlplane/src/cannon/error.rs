@@ -12,6 +12,7 @@ use snops_common::{
 use strum_macros::AsRefStr;
 use thiserror::Error;
 
+use super::history::TransactionOutcome;
 use crate::{env::error::EnvRequestError, error::StateError};
 
 #[derive(Debug, Error, AsRefStr)]
@@ -26,10 +27,13 @@ pub enum AuthorizeError {
     InvalidProgramInputs(String, String),
     #[error("execution {0} requires a valid private key: {1}")]
     MissingPrivateKey(String, String),
+    #[error("no unspent fee record with at least {0} microcredits is available")]
+    NoFeeRecord(u64),
 }
 
 impl_into_status_code!(AuthorizeError, |value| match value {
     Command(e) => e.into(),
+    NoFeeRecord(_) => StatusCode::UNPROCESSABLE_ENTITY,
     _ => StatusCode::INTERNAL_SERVER_ERROR,
 });
 
@@ -95,13 +99,29 @@ pub enum CannonInstanceError {
     MissingQueryPort(CannonId),
     #[error("cannon `{0}` is not configured to playback txs")]
     NotConfiguredToPlayback(CannonId),
+    #[error("cannon `{0}` needs a local query service to select fee records")]
+    RecordsUnavailable(CannonId),
     #[error("no target node found for cannon `{0}`: {1}")]
     TargetNodeNotFound(CannonId, NodeTargets),
+    #[error("cannon `{0}` is draining and not accepting transactions")]
+    Draining(CannonId),
+    #[error("unknown transaction status `{0}`")]
+    UnknownTransactionStatus(String),
+    #[error("intake path {1:#?} of cannon `{0}` must be relative to the storage directory")]
+    InvalidIntakePath(CannonId, PathBuf),
+    #[error("`{1}` of cannon `{0}` must be greater than 0")]
+    InvalidLimit(CannonId, &'static str),
 }
 
 impl_into_status_code!(CannonInstanceError, |value| match value {
-    MissingQueryPort(_) | NotConfiguredToPlayback(_) => StatusCode::BAD_REQUEST,
+    MissingQueryPort(_)
+    | NotConfiguredToPlayback(_)
+    | RecordsUnavailable(_)
+    | UnknownTransactionStatus(_)
+    | InvalidIntakePath(_, _)
+    | InvalidLimit(_, _) => StatusCode::BAD_REQUEST,
     TargetNodeNotFound(_, _) => StatusCode::NOT_FOUND,
+    Draining(_) => StatusCode::SERVICE_UNAVAILABLE,
 });
 
 impl Serialize for CannonInstanceError {
@@ -188,6 +208,12 @@ pub enum CannonError {
     RequestError(#[from] EnvRequestError),
     #[error("transaction already exists for cannon `{0}`: {1}")]
     TransactionAlreadyExists(CannonId, String),
+    #[error("transaction {1} for cannon `{0}` already finished as {2}")]
+    TransactionAlreadyFinished(CannonId, String, TransactionOutcome),
+    #[error("transaction {1} for cannon `{0}` depends on unknown transaction {2}")]
+    UnknownDependency(CannonId, String, Arc<String>),
+    #[error("transaction {1} for cannon `{0}` depends on transaction {2}, which finished as {3}")]
+    DependencyFailed(CannonId, String, Arc<String>, TransactionOutcome),
     #[error("transaction lost for cannon `{0}`: {1}")]
     TransactionLost(CannonId, String),
     #[error("invalid transaction state for transaction {1} for cannon `{0}`: {2}")]
@@ -205,7 +231,9 @@ impl_into_status_code!(CannonError, |value| match value {
     TransactionSink(e) => e.into(),
     Source(e) => e.into(),
     State(e) => e.into(),
-    TransactionAlreadyExists(_, _) => StatusCode::CONFLICT,
+    TransactionAlreadyExists(_, _) | TransactionAlreadyFinished(_, _, _) => StatusCode::CONFLICT,
+    UnknownDependency(_, _, _) => StatusCode::UNPROCESSABLE_ENTITY,
+    DependencyFailed(_, _, _, _) => StatusCode::FAILED_DEPENDENCY,
     _ => StatusCode::INTERNAL_SERVER_ERROR,
 });
 
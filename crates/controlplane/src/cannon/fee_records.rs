@@ -0,0 +1,183 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+    time::{Duration, Instant},
+};
+
+/// How long a fee record stays reserved before the authorization spending it
+/// reaches the cannon. Once the cannon tracks the transaction, the record stays
+/// reserved until the transaction is archived.
+pub const PENDING_RESERVATION: Duration = Duration::from_secs(600);
+
+/// Fee records reserved by authorizations, by commitment, so concurrent
+/// authorizations don't spend the same record
+#[derive(Debug, Default)]
+pub struct FeeRecordReservations(Mutex<Reservations>);
+
+#[derive(Debug, Default)]
+struct Reservations {
+    records: HashMap<String, Reservation>,
+    /// The commitment reserved by each tracked transaction
+    transactions: HashMap<Arc<String>, String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Reservation {
+    /// Reserved at this time, and not yet spent by a tracked transaction
+    Pending(Instant),
+    /// Spent by a transaction the cannon is tracking
+    Transaction(Arc<String>),
+}
+
+impl FeeRecordReservations {
+    fn lock(&self) -> MutexGuard<'_, Reservations> {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Reserve a record, returning false if it is already reserved
+    pub fn reserve(&self, commitment: &str, now: Instant) -> bool {
+        let mut reservations = self.lock();
+        if reservations.records.contains_key(commitment) {
+            return false;
+        }
+        reservations
+            .records
+            .insert(commitment.to_owned(), Reservation::Pending(now));
+        true
+    }
+
+    /// Hold a reserved record until the transaction spending it is released
+    pub fn bind(&self, commitment: &str, tx_id: Arc<String>) {
+        let mut reservations = self.lock();
+        let Some(reservation) = reservations.records.get_mut(commitment) else {
+            return;
+        };
+        *reservation = Reservation::Transaction(Arc::clone(&tx_id));
+        reservations
+            .transactions
+            .insert(tx_id, commitment.to_owned());
+    }
+
+    /// Release a reserved record
+    pub fn release(&self, commitment: &str) {
+        let mut reservations = self.lock();
+        if let Some(Reservation::Transaction(tx_id)) = reservations.records.remove(commitment) {
+            reservations.transactions.remove(&tx_id);
+        }
+    }
+
+    /// Release the record reserved by a transaction, if any
+    pub fn release_transaction(&self, tx_id: &Arc<String>) {
+        let mut reservations = self.lock();
+        if let Some(commitment) = reservations.transactions.remove(tx_id) {
+            reservations.records.remove(&commitment);
+        }
+    }
+
+    /// Release pending reservations older than [`PENDING_RESERVATION`]
+    pub fn expire(&self, now: Instant) {
+        self.lock()
+            .records
+            .retain(|_, reservation| match reservation {
+                Reservation::Pending(at) => {
+                    now.saturating_duration_since(*at) < PENDING_RESERVATION
+                }
+                Reservation::Transaction(_) => true,
+            });
+    }
+
+    pub fn is_reserved(&self, commitment: &str) -> bool {
+        self.lock().records.contains_key(commitment)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn records_are_reserved_once() {
+        let reservations = FeeRecordReservations::default();
+        let now = Instant::now();
+
+        assert!(reservations.reserve("a", now));
+        assert!(!reservations.reserve("a", now));
+        assert!(reservations.reserve("b", now));
+
+        reservations.release("a");
+        assert!(!reservations.is_reserved("a"));
+        assert!(reservations.reserve("a", now));
+    }
+
+    #[test]
+    fn pending_reservations_expire() {
+        let reservations = FeeRecordReservations::default();
+        let now = Instant::now();
+        reservations.reserve("a", now);
+        reservations.reserve("b", now + Duration::from_secs(1));
+
+        reservations.expire(now + PENDING_RESERVATION - Duration::from_secs(1));
+        assert!(reservations.is_reserved("a"));
+
+        reservations.expire(now + PENDING_RESERVATION);
+        assert!(!reservations.is_reserved("a"));
+        assert!(reservations.is_reserved("b"));
+    }
+
+    #[test]
+    fn transaction_reservations_last_until_released() {
+        let reservations = FeeRecordReservations::default();
+        let now = Instant::now();
+        let tx_id = Arc::new("at1".to_owned());
+
+        reservations.reserve("a", now);
+        reservations.bind("a", Arc::clone(&tx_id));
+
+        // a record spent by a tracked transaction never expires
+        reservations.expire(now + PENDING_RESERVATION * 10);
+        assert!(reservations.is_reserved("a"));
+        assert!(!reservations.reserve("a", now));
+
+        reservations.release_transaction(&tx_id);
+        assert!(!reservations.is_reserved("a"));
+        assert!(reservations.lock().transactions.is_empty());
+
+        // releasing by commitment also forgets the transaction
+        reservations.reserve("a", now);
+        reservations.bind("a", Arc::clone(&tx_id));
+        reservations.release("a");
+        assert!(reservations.lock().transactions.is_empty());
+    }
+
+    #[test]
+    fn releasing_flushed_transactions_keeps_the_others() {
+        let reservations = FeeRecordReservations::default();
+        let now = Instant::now();
+        let tx_ids = ["at1", "at2", "at3"].map(|id| Arc::new(id.to_owned()));
+
+        for (commitment, tx_id) in ["a", "b", "c"].iter().zip(&tx_ids) {
+            reservations.reserve(commitment, now);
+            reservations.bind(commitment, Arc::clone(tx_id));
+        }
+
+        // flushing some transactions only frees their records
+        reservations.release_transaction(&tx_ids[0]);
+        reservations.release_transaction(&tx_ids[2]);
+        assert!(reservations.reserve("a", now));
+        assert!(reservations.is_reserved("b"));
+        assert!(reservations.reserve("c", now));
+        assert_eq!(reservations.lock().transactions.len(), 1);
+
+        // releasing a transaction twice is harmless
+        reservations.release_transaction(&tx_ids[0]);
+        assert!(reservations.is_reserved("a"));
+    }
+
+    #[test]
+    fn binding_an_unreserved_record_does_nothing() {
+        let reservations = FeeRecordReservations::default();
+        reservations.bind("a", Arc::new("at1".to_owned()));
+        assert!(!reservations.is_reserved("a"));
+        assert!(reservations.lock().transactions.is_empty());
+    }
+}
@@ -0,0 +1,557 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    fmt::Display,
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+};
+
+use chrono::{DateTime, TimeDelta, Utc};
+use serde::{Deserialize, Serialize};
+use snops_common::{
+    db::error::DatabaseError,
+    state::{AgentId, CannonId, EnvId},
+};
+
+use crate::{
+    db::{Database, TxEntry},
+    state::GlobalState,
+};
+
+/// How a cannon finished with a transaction
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TransactionOutcome {
+    /// The transaction was included in a block and executed successfully
+    Accepted,
+    /// The transaction was included in a block, but its execution failed and
+    /// only its fee was consumed
+    Rejected,
+    /// The transaction was aborted by the block that included it
+    Aborted,
+    /// The transaction was written to the cannon's sink file without being
+    /// broadcasted
+    Recorded,
+    /// The authorization exceeded the maximum number of execution attempts
+    ExecuteExceeded,
+    /// The transaction exceeded the maximum number of broadcast attempts
+    BroadcastExceeded,
+    /// The transaction was removed by flushing the cannon
+    Flushed,
+    /// The authorization was not executed before its deadline
+    Expired,
+    /// The authorization was cancelled because a transaction it depends on
+    /// was not confirmed
+    DependencyFailed,
+}
+
+impl TransactionOutcome {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Accepted => "accepted",
+            Self::Rejected => "rejected",
+            Self::Aborted => "aborted",
+            Self::Recorded => "recorded",
+            Self::ExecuteExceeded => "execute_exceeded",
+            Self::BroadcastExceeded => "broadcast_exceeded",
+            Self::Flushed => "flushed",
+            Self::Expired => "expired",
+            Self::DependencyFailed => "dependency_failed",
+        }
+    }
+
+    /// Whether the transaction is final, so transactions that depend on it
+    /// can be executed
+    pub fn is_confirmed(&self) -> bool {
+        matches!(self, Self::Accepted | Self::Recorded)
+    }
+
+    /// Whether the transaction reached the network or the cannon's sink, so
+    /// sending it again would be a duplicate
+    pub fn is_delivered(&self) -> bool {
+        matches!(
+            self,
+            Self::Accepted | Self::Rejected | Self::Aborted | Self::Recorded
+        )
+    }
+}
+
+impl Display for TransactionOutcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.label())
+    }
+}
+
+/// What happened to a transaction while a cannon was tracking it
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct TransactionProgress {
+    /// When the cannon received the authorization or transaction
+    pub received_at: Option<DateTime<Utc>>,
+    /// The agent that most recently executed the authorization
+    pub compute_agent: Option<AgentId>,
+    /// When the authorization finished executing
+    pub executed_at: Option<DateTime<Utc>>,
+    /// Number of attempts it took to execute the authorization
+    pub execute_attempts: u32,
+    /// The agent that most recently accepted the broadcast, when broadcasted
+    /// through an agent
+    pub broadcast_agent: Option<AgentId>,
+    /// When the transaction was most recently broadcasted
+    pub broadcast_at: Option<DateTime<Utc>>,
+}
+
+/// A transaction a cannon has finished with
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TransactionHistory {
+    /// Index of the transaction in the order the cannon received it
+    pub index: u64,
+    pub outcome: TransactionOutcome,
+    #[serde(flatten)]
+    pub progress: TransactionProgress,
+    /// Number of attempts made to broadcast the transaction
+    pub broadcast_attempts: u32,
+    pub block_hash: Option<String>,
+    pub block_height: Option<u32>,
+    pub finished_at: DateTime<Utc>,
+}
+
+impl TransactionHistory {
+    /// Whether the same transaction must be rejected at `now`, given a
+    /// cannon's dedupe window in seconds. Without a window, delivered
+    /// transactions are rejected for as long as they are in the history.
+    /// Transactions are sent again once exactly `dedupe_window` seconds have
+    /// passed.
+    pub fn rejects_duplicate(&self, dedupe_window: Option<u32>, now: DateTime<Utc>) -> bool {
+        let within_window = dedupe_window
+            .is_none_or(|secs| now - self.finished_at < TimeDelta::seconds(secs.into()));
+        self.outcome.is_delivered() && within_window
+    }
+}
+
+/// A transaction history entry along with its ids
+#[derive(Debug, Serialize)]
+pub struct HistoryEntry {
+    pub env: EnvId,
+    pub cannon: CannonId,
+    pub transaction: Arc<String>,
+    #[serde(flatten)]
+    pub history: TransactionHistory,
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HistoryFormat {
+    #[default]
+    Json,
+    Jsonl,
+    Csv,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct HistoryQuery {
+    /// Only include transactions from this cannon
+    pub cannon: Option<CannonId>,
+    /// Only include transactions with this outcome
+    pub outcome: Option<TransactionOutcome>,
+    #[serde(default)]
+    pub format: HistoryFormat,
+}
+
+const CSV_HEADER: &str = "env,cannon,transaction,index,outcome,received_at,compute_agent,executed_at,execute_attempts,broadcast_agent,broadcast_at,broadcast_attempts,block_hash,block_height,finished_at";
+
+/// Format history entries as CSV
+pub fn history_csv(entries: &[HistoryEntry]) -> String {
+    fn opt<T: Display>(value: &Option<T>) -> String {
+        value.as_ref().map(|v| v.to_string()).unwrap_or_default()
+    }
+
+    let mut out = format!("{CSV_HEADER}\n");
+    for HistoryEntry {
+        env,
+        cannon,
+        transaction,
+        history: h,
+    } in entries
+    {
+        let p = &h.progress;
+        out.push_str(&format!(
+            "{env},{cannon},{transaction},{},{},{},{},{},{},{},{},{},{},{},{}\n",
+            h.index,
+            h.outcome,
+            opt(&p.received_at.map(|t| t.to_rfc3339())),
+            opt(&p.compute_agent),
+            opt(&p.executed_at.map(|t| t.to_rfc3339())),
+            p.execute_attempts,
+            opt(&p.broadcast_agent),
+            opt(&p.broadcast_at.map(|t| t.to_rfc3339())),
+            h.broadcast_attempts,
+            opt(&h.block_hash),
+            opt(&h.block_height),
+            h.finished_at.to_rfc3339(),
+        ));
+    }
+    out
+}
+
+/// Format history entries as newline delimited JSON
+pub fn history_jsonl(entries: &[HistoryEntry]) -> Result<String, serde_json::Error> {
+    let mut out = String::new();
+    for entry in entries {
+        out.push_str(&serde_json::to_string(entry)?);
+        out.push('\n');
+    }
+    Ok(out)
+}
+
+/// Read an env's transaction history in the order transactions finished
+pub fn read_history(
+    state: &GlobalState,
+    env_id: EnvId,
+    query: &HistoryQuery,
+) -> Result<Vec<HistoryEntry>, DatabaseError> {
+    let rows: Box<dyn Iterator<Item = (TxEntry, TransactionHistory)>> = match query.cannon {
+        Some(cannon_id) => Box::new(state.db.tx_history.read_with_prefix(&(env_id, cannon_id))?),
+        None => Box::new(state.db.tx_history.read_with_prefix(&env_id)?),
+    };
+
+    let mut entries = rows
+        .filter(|(_, history)| query.outcome.is_none_or(|o| history.outcome == o))
+        .map(|((env, cannon, transaction), history)| HistoryEntry {
+            env,
+            cannon,
+            transaction,
+            history,
+        })
+        .collect::<Vec<_>>();
+    entries.sort_by_key(|e| e.history.finished_at);
+    Ok(entries)
+}
+
+/// Finish times of the transactions in each cannon's history, so the history
+/// can be pruned without reading it
+#[derive(Default)]
+pub struct HistoryIndex(Mutex<HashMap<EnvId, HashMap<CannonId, CannonHistory>>>);
+
+#[derive(Default)]
+struct CannonHistory {
+    /// Transactions ordered by when they finished
+    by_time: BTreeSet<(DateTime<Utc>, Arc<String>)>,
+    finished_at: HashMap<Arc<String>, DateTime<Utc>>,
+}
+
+impl CannonHistory {
+    fn insert(&mut self, tx_id: Arc<String>, finished_at: DateTime<Utc>) {
+        // a transaction sent again replaces its earlier entry
+        if let Some(old) = self.finished_at.insert(Arc::clone(&tx_id), finished_at) {
+            self.by_time.remove(&(old, Arc::clone(&tx_id)));
+        }
+        self.by_time.insert((finished_at, tx_id));
+    }
+
+    fn latest(&self) -> Option<DateTime<Utc>> {
+        self.by_time.last().map(|(finished_at, _)| *finished_at)
+    }
+}
+
+impl HistoryIndex {
+    /// Index the transaction history in the database
+    pub fn load(db: &Database) -> Self {
+        let index = Self::default();
+        for ((env_id, cannon_id, tx_id), history) in db.tx_history.read_all() {
+            index.insert(&(env_id, cannon_id, tx_id), history.finished_at);
+        }
+        index
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<EnvId, HashMap<CannonId, CannonHistory>>> {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Index a transaction that was moved into the history
+    pub fn insert(&self, (env_id, cannon_id, tx_id): &TxEntry, finished_at: DateTime<Utc>) {
+        self.lock()
+            .entry(*env_id)
+            .or_default()
+            .entry(*cannon_id)
+            .or_default()
+            .insert(Arc::clone(tx_id), finished_at);
+    }
+
+    /// Remove the oldest finished transactions from a cannon's history so it
+    /// holds at most `limit` transactions. Returns the number removed.
+    pub fn prune(
+        &self,
+        db: &Database,
+        env_id: EnvId,
+        cannon_id: CannonId,
+        limit: usize,
+    ) -> Result<usize, DatabaseError> {
+        let mut index = self.lock();
+        let Some(history) = index
+            .get_mut(&env_id)
+            .and_then(|cannons| cannons.get_mut(&cannon_id))
+        else {
+            return Ok(0);
+        };
+
+        let mut removed = 0;
+        while history.by_time.len() > limit {
+            let Some((_, tx_id)) = history.by_time.pop_first() else {
+                break;
+            };
+            history.finished_at.remove(&tx_id);
+            db.tx_history.delete(&(env_id, cannon_id, tx_id))?;
+            removed += 1;
+        }
+        Ok(removed)
+    }
+
+    /// Remove the history of deleted envs whose last transaction finished
+    /// more than `retention` before `now`. Returns the envs removed.
+    pub fn prune_deleted(
+        &self,
+        db: &Database,
+        is_deleted: impl Fn(EnvId) -> bool,
+        retention: TimeDelta,
+        now: DateTime<Utc>,
+    ) -> Result<Vec<EnvId>, DatabaseError> {
+        let mut index = self.lock();
+        let expired = index
+            .iter()
+            .filter(|(env_id, cannons)| {
+                is_deleted(**env_id)
+                    && cannons
+                        .values()
+                        .filter_map(CannonHistory::latest)
+                        .max()
+                        .is_none_or(|latest| latest + retention <= now)
+            })
+            .map(|(env_id, _)| *env_id)
+            .collect::<Vec<_>>();
+
+        for env_id in &expired {
+            db.tx_history.delete_with_prefix(env_id)?;
+            index.remove(env_id);
+        }
+        Ok(expired)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use snops_common::db::Database as _;
+
+    use super::*;
+
+    fn id(s: &str) -> EnvId {
+        EnvId::from_str(s).unwrap()
+    }
+
+    fn at(secs: i64) -> DateTime<Utc> {
+        DateTime::from_timestamp(secs, 0).unwrap()
+    }
+
+    fn history(index: u64, finished_at: DateTime<Utc>) -> TransactionHistory {
+        TransactionHistory {
+            index,
+            outcome: TransactionOutcome::Accepted,
+            progress: TransactionProgress::default(),
+            broadcast_attempts: 1,
+            block_hash: None,
+            block_height: None,
+            finished_at,
+        }
+    }
+
+    /// Save a history entry the way a tracker archives it
+    fn archive(db: &Database, index: &HistoryIndex, key: TxEntry, finished_at: DateTime<Utc>) {
+        db.tx_history.save(&key, &history(0, finished_at)).unwrap();
+        index.insert(&key, finished_at);
+    }
+
+    fn stored(db: &Database, env_id: EnvId) -> Vec<String> {
+        let mut ids = db
+            .tx_history
+            .read_with_prefix(&env_id)
+            .unwrap()
+            .map(|((_, _, tx_id), _)| tx_id.to_string())
+            .collect::<Vec<_>>();
+        ids.sort();
+        ids
+    }
+
+    #[test]
+    fn prune_removes_the_oldest_transactions() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Database::open(dir.path()).unwrap();
+        let index = HistoryIndex::default();
+        let (env, cannon, other) = (id("alpha"), id("one"), id("two"));
+
+        for (tx, secs) in [("at3", 3), ("at1", 1), ("at2", 2)] {
+            archive(
+                &db,
+                &index,
+                (env, cannon, Arc::new(tx.to_owned())),
+                at(secs),
+            );
+        }
+        archive(&db, &index, (env, other, Arc::new("at0".to_owned())), at(0));
+
+        assert_eq!(index.prune(&db, env, cannon, 3).unwrap(), 0);
+        assert_eq!(index.prune(&db, env, cannon, 1).unwrap(), 2);
+        // other cannons keep their history
+        assert_eq!(stored(&db, env), ["at0", "at3"]);
+
+        // the index is rebuilt from the database
+        let index = HistoryIndex::load(&db);
+        assert_eq!(index.prune(&db, env, other, 0).unwrap(), 1);
+        assert_eq!(stored(&db, env), ["at3"]);
+    }
+
+    #[test]
+    fn transactions_sent_again_replace_their_entry() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Database::open(dir.path()).unwrap();
+        let index = HistoryIndex::default();
+        let (env, cannon) = (id("alpha"), id("one"));
+        let tx = |s: &str| (env, cannon, Arc::new(s.to_owned()));
+
+        archive(&db, &index, tx("at1"), at(1));
+        archive(&db, &index, tx("at2"), at(2));
+        archive(&db, &index, tx("at1"), at(3));
+
+        // the old entry of at1 is not pruned in place of at2
+        assert_eq!(index.prune(&db, env, cannon, 1).unwrap(), 1);
+        assert_eq!(stored(&db, env), ["at1"]);
+    }
+
+    #[test]
+    fn prune_deleted_waits_for_the_retention() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Database::open(dir.path()).unwrap();
+        let index = HistoryIndex::default();
+        let (deleted, running, cannon) = (id("alpha"), id("beta"), id("one"));
+        let retention = TimeDelta::seconds(10);
+
+        archive(
+            &db,
+            &index,
+            (deleted, cannon, Arc::new("at1".to_owned())),
+            at(0),
+        );
+        archive(
+            &db,
+            &index,
+            (deleted, id("two"), Arc::new("at2".to_owned())),
+            at(5),
+        );
+        archive(
+            &db,
+            &index,
+            (running, cannon, Arc::new("at3".to_owned())),
+            at(0),
+        );
+        let is_deleted = |env_id| env_id == deleted;
+
+        // the latest transaction of the env finished too recently
+        let removed = index
+            .prune_deleted(&db, is_deleted, retention, at(14))
+            .unwrap();
+        assert!(removed.is_empty());
+
+        let removed = index
+            .prune_deleted(&db, is_deleted, retention, at(15))
+            .unwrap();
+        assert_eq!(removed, [deleted]);
+        assert!(stored(&db, deleted).is_empty());
+        assert_eq!(stored(&db, running), ["at3"]);
+    }
+
+    #[test]
+    fn dedupe_window_boundaries() {
+        let entry = history(0, at(1_000));
+
+        // without a window, the transaction is rejected while it is in history
+        assert!(entry.rejects_duplicate(None, at(1_000)));
+        assert!(entry.rejects_duplicate(None, at(1_000_000)));
+
+        // a zero window never rejects a finished transaction
+        assert!(!entry.rejects_duplicate(Some(0), at(1_000)));
+
+        // the window rejects up to, but not including, its end
+        assert!(entry.rejects_duplicate(Some(60), at(1_000)));
+        assert!(entry.rejects_duplicate(Some(60), at(1_059)));
+        assert!(!entry.rejects_duplicate(Some(60), at(1_060)));
+        assert!(!entry.rejects_duplicate(Some(60), at(1_061)));
+    }
+
+    #[test]
+    fn undelivered_transactions_are_never_duplicates() {
+        for outcome in [
+            TransactionOutcome::ExecuteExceeded,
+            TransactionOutcome::BroadcastExceeded,
+            TransactionOutcome::Flushed,
+            TransactionOutcome::Expired,
+            TransactionOutcome::DependencyFailed,
+        ] {
+            let entry = TransactionHistory {
+                outcome,
+                ..history(0, at(1_000))
+            };
+            assert!(!entry.rejects_duplicate(None, at(1_000)), "{outcome}");
+            assert!(!entry.rejects_duplicate(Some(60), at(1_000)), "{outcome}");
+        }
+
+        for outcome in [
+            TransactionOutcome::Rejected,
+            TransactionOutcome::Aborted,
+            TransactionOutcome::Recorded,
+        ] {
+            let entry = TransactionHistory {
+                outcome,
+                ..history(0, at(1_000))
+            };
+            assert!(entry.rejects_duplicate(None, at(1_000)), "{outcome}");
+        }
+    }
+
+    #[test]
+    fn history_csv_has_a_row_per_entry() {
+        let entry = HistoryEntry {
+            env: id("alpha"),
+            cannon: id("one"),
+            transaction: Arc::new("at1".to_owned()),
+            history: TransactionHistory {
+                index: 4,
+                outcome: TransactionOutcome::Rejected,
+                progress: TransactionProgress {
+                    received_at: Some(at(1)),
+                    compute_agent: Some(id("compute-1")),
+                    executed_at: Some(at(2)),
+                    execute_attempts: 1,
+                    broadcast_agent: None,
+                    broadcast_at: Some(at(3)),
+                },
+                broadcast_attempts: 2,
+                block_hash: Some("ab1".to_owned()),
+                block_height: Some(7),
+                finished_at: at(4),
+            },
+        };
+
+        let csv = history_csv(&[entry]);
+        let lines = csv.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0], CSV_HEADER);
+        assert_eq!(
+            lines[1],
+            "alpha,one,at1,4,rejected,1970-01-01T00:00:01+00:00,compute-1,\
+             1970-01-01T00:00:02+00:00,1,,1970-01-01T00:00:03+00:00,2,ab1,7,\
+             1970-01-01T00:00:04+00:00"
+        );
+        // every row has a column for each header
+        assert_eq!(lines[1].split(',').count(), CSV_HEADER.split(',').count());
+
+        assert_eq!(history_csv(&[]), format!("{CSV_HEADER}\n"));
+    }
+}
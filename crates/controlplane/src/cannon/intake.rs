@@ -0,0 +1,616 @@
+use std::{
+    io::{self, SeekFrom},
+    path::{Component, Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+
+use futures_util::{StreamExt, stream::FuturesUnordered};
+use serde::{Deserialize, Serialize};
+use snops_common::{
+    db::error::DatabaseError,
+    format::PackedUint,
+    state::{Authorization, CannonId, EnvId},
+};
+use tokio::{
+    fs::File,
+    io::{AsyncBufReadExt, AsyncReadExt, AsyncSeekExt, AsyncWriteExt, BufReader},
+    net::{
+        TcpStream, UnixListener, UnixStream,
+        tcp::{OwnedReadHalf, OwnedWriteHalf},
+    },
+};
+use tracing::{error, info, trace, warn};
+
+use super::{
+    CannonInstance,
+    error::{CannonError, CannonInstanceError},
+    status::CannonStatus,
+    tracker::TransactionSchedule,
+};
+use crate::{
+    db::{Database, TxEntry},
+    state::GlobalState,
+};
+
+/// How long to wait before polling a file again, or before submitting to a
+/// cannon that is full
+const INTAKE_POLL: Duration = Duration::from_secs(1);
+/// How long to wait before restarting an intake that failed
+const INTAKE_RETRY: Duration = Duration::from_secs(5);
+
+/// Where a cannon reads authorizations from, in addition to its HTTP routes
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum IntakeSource {
+    /// Follow a JSONL file of authorizations in the env's storage directory
+    File(PathBuf),
+    /// Follow every `.jsonl` file in a directory of the env's storage, in name
+    /// order
+    Dir(PathBuf),
+    /// Accept newline delimited authorizations from clients of a Unix socket
+    /// in the env's storage directory
+    Socket(PathBuf),
+    /// Subscribe to a subject on a NATS server
+    Nats { url: String, subject: String },
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct TxIntake {
+    #[serde(flatten)]
+    pub source: IntakeSource,
+    /// Stop reading authorizations while the cannon tracks at least this many
+    /// transactions
+    #[serde(default = "TxIntake::default_max_queue")]
+    pub max_queue: u32,
+}
+
+impl TxIntake {
+    pub fn default_max_queue() -> u32 {
+        1000
+    }
+
+    /// Check that file and socket intakes stay inside the env's storage
+    /// directory
+    pub fn validate(&self, cannon_id: CannonId) -> Result<(), CannonInstanceError> {
+        match self.source.storage_path() {
+            Some(path) if !is_storage_relative(path) => Err(
+                CannonInstanceError::InvalidIntakePath(cannon_id, path.to_owned()),
+            ),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl IntakeSource {
+    /// The path of the intake in the env's storage directory
+    pub fn storage_path(&self) -> Option<&Path> {
+        match self {
+            Self::File(path) | Self::Dir(path) | Self::Socket(path) => Some(path),
+            Self::Nats { .. } => None,
+        }
+    }
+}
+
+/// Whether a path is relative and has no `..` components, so joining it to a
+/// directory stays inside that directory
+pub fn is_storage_relative(path: &Path) -> bool {
+    path.is_relative()
+        && path
+            .components()
+            .all(|c| matches!(c, Component::Normal(_) | Component::CurDir))
+}
+
+fn load_offset(db: &Database, key: &TxEntry) -> Result<u64, DatabaseError> {
+    Ok(db
+        .intake_offsets
+        .restore(key)?
+        .map(|o| o.0)
+        .unwrap_or_default())
+}
+
+fn save_offset(db: &Database, key: &TxEntry, offset: u64) -> Result<(), DatabaseError> {
+    db.intake_offsets.save(key, &PackedUint(offset))
+}
+
+/// Reads the complete lines of a file after an offset
+struct LineReader {
+    reader: BufReader<File>,
+    /// Offset of the end of the last complete line read
+    offset: u64,
+    /// Whether the file was shorter than the offset, and is read from the
+    /// start
+    truncated: bool,
+    line: String,
+}
+
+impl LineReader {
+    /// Open a file at an offset, or return `None` when there is nothing after
+    /// the offset
+    async fn open(path: &Path, offset: u64) -> io::Result<Option<Self>> {
+        let mut file = File::open(path).await?;
+
+        let len = file.metadata().await?.len();
+        let truncated = len < offset;
+        let offset = if truncated { 0 } else { offset };
+        if len == offset {
+            return Ok(None);
+        }
+
+        file.seek(SeekFrom::Start(offset)).await?;
+        Ok(Some(Self {
+            reader: BufReader::new(file),
+            offset,
+            truncated,
+            line: String::new(),
+        }))
+    }
+
+    /// The next complete line, or `None` at the end of the file or at a line
+    /// that is still being written
+    async fn next_line(&mut self) -> io::Result<Option<&str>> {
+        self.line.clear();
+        let read = self.reader.read_line(&mut self.line).await?;
+        if read == 0 || !self.line.ends_with('\n') {
+            return Ok(None);
+        }
+
+        self.offset += read as u64;
+        Ok(Some(&self.line))
+    }
+}
+
+/// A subscription to a subject using the core NATS protocol
+struct NatsSubscription {
+    reader: BufReader<OwnedReadHalf>,
+    write: OwnedWriteHalf,
+    line: String,
+}
+
+impl NatsSubscription {
+    async fn connect(url: &str, subject: &str, name: &str) -> io::Result<Self> {
+        let addr = url.strip_prefix("nats://").unwrap_or(url);
+        let (read, mut write) = TcpStream::connect(addr).await?.into_split();
+
+        write
+            .write_all(
+                format!(
+                    "CONNECT {{\"verbose\":false,\"pedantic\":false,\"name\":\"{name}\"}}\r\nSUB {subject} 1\r\n"
+                )
+                .as_bytes(),
+            )
+            .await?;
+
+        Ok(Self {
+            reader: BufReader::new(read),
+            write,
+            line: String::new(),
+        })
+    }
+
+    /// The payload of the next message published to the subject, answering
+    /// the server's pings in the meantime
+    async fn next_message(&mut self) -> io::Result<Vec<u8>> {
+        loop {
+            self.line.clear();
+            if self.reader.read_line(&mut self.line).await? == 0 {
+                return Err(io::ErrorKind::UnexpectedEof.into());
+            }
+
+            let mut parts = self.line.split_whitespace();
+            match parts.next() {
+                Some("PING") => self.write.write_all(b"PONG\r\n").await?,
+                // MSG <subject> <sid> [reply-to] <#bytes>
+                Some("MSG") => {
+                    let len = parts
+                        .last()
+                        .and_then(|n| n.parse::<usize>().ok())
+                        .ok_or_else(|| {
+                            io::Error::other(format!("invalid message: {}", self.line))
+                        })?;
+                    // read the payload and its trailing CRLF
+                    let mut payload = vec![0; len + 2];
+                    self.reader.read_exact(&mut payload).await?;
+                    payload.truncate(len);
+                    return Ok(payload);
+                }
+                Some("-ERR") => return Err(io::Error::other(self.line.trim().to_owned())),
+                // INFO, +OK, and PONG need no response
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Reads authorizations from a cannon's intake and submits them to the cannon
+pub struct IntakeTask {
+    pub(crate) state: Arc<GlobalState>,
+    pub(crate) env_id: EnvId,
+    pub(crate) cannon_id: CannonId,
+    pub(crate) intake: TxIntake,
+    pub(crate) storage_path: PathBuf,
+}
+
+impl IntakeTask {
+    /// Read from the intake until the cannon is dropped, restarting the intake
+    /// when it fails
+    pub async fn run(self) {
+        let (env_id, cannon_id) = (self.env_id, self.cannon_id);
+
+        loop {
+            let res = match &self.intake.source {
+                IntakeSource::File(path) => self.follow_file(path).await,
+                IntakeSource::Dir(path) => self.follow_dir(path).await,
+                IntakeSource::Socket(path) => self.listen_socket(path).await,
+                IntakeSource::Nats { url, subject } => self.subscribe_nats(url, subject).await,
+            };
+
+            if let Err(e) = res {
+                error!("cannon {env_id}.{cannon_id} intake failed: {e}");
+            }
+            tokio::time::sleep(INTAKE_RETRY).await;
+        }
+    }
+
+    fn cannon(&self) -> Option<Arc<CannonInstance>> {
+        self.state
+            .get_env(self.env_id)
+            .and_then(|env| env.get_cannon(self.cannon_id))
+    }
+
+    /// Wait until the cannon has room for another transaction, then submit an
+    /// authorization to it
+    async fn submit(&self, line: &str) {
+        let (env_id, cannon_id) = (self.env_id, self.cannon_id);
+
+        let line = line.trim();
+        if line.is_empty() {
+            return;
+        }
+        let auth: Authorization = match serde_json::from_str(line) {
+            Ok(auth) => auth,
+            Err(e) => {
+                warn!("cannon {env_id}.{cannon_id} intake skipped an invalid authorization: {e}");
+                return;
+            }
+        };
+
+        loop {
+            let Some(cannon) = self.cannon() else {
+                tokio::time::sleep(INTAKE_POLL).await;
+                continue;
+            };
+
+            // hold the authorization until the cannon has room for it
+            if cannon.status() == CannonStatus::Draining
+                || cannon.transactions.len() >= self.intake.max_queue as usize
+            {
+                drop(cannon);
+                tokio::time::sleep(INTAKE_POLL).await;
+                continue;
+            }
+
+            match cannon
+                .proxy_auth(auth.clone(), TransactionSchedule::default())
+                .await
+            {
+                Ok(tx_id) => trace!("cannon {env_id}.{cannon_id} intake received auth {tx_id}"),
+                Err(
+                    CannonError::TransactionAlreadyExists(_, tx_id)
+                    | CannonError::TransactionAlreadyFinished(_, tx_id, _),
+                ) => {
+                    trace!("cannon {env_id}.{cannon_id} intake skipped duplicate auth {tx_id}")
+                }
+                Err(CannonError::CannonInstance(CannonInstanceError::Draining(_))) => {
+                    drop(cannon);
+                    tokio::time::sleep(INTAKE_POLL).await;
+                    continue;
+                }
+                Err(e) => warn!("cannon {env_id}.{cannon_id} intake failed to submit auth: {e}"),
+            }
+            return;
+        }
+    }
+
+    fn offset_key(&self, name: &str) -> TxEntry {
+        (self.env_id, self.cannon_id, Arc::new(name.to_owned()))
+    }
+
+    /// Resolve a path in the env's storage directory, refusing paths that
+    /// would leave it
+    fn storage_file(&self, path: &Path) -> io::Result<PathBuf> {
+        if !is_storage_relative(path) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{} is outside of the storage directory", path.display()),
+            ));
+        }
+        Ok(self.storage_path.join(path))
+    }
+
+    /// Submit the complete lines of a file after its saved offset
+    async fn read_file(&self, path: &Path, name: &str) -> io::Result<()> {
+        let (env_id, cannon_id) = (self.env_id, self.cannon_id);
+        let key = self.offset_key(name);
+
+        let offset = load_offset(&self.state.db, &key).unwrap_or_else(|e| {
+            error!("cannon {env_id}.{cannon_id} failed to restore intake offset for {name}: {e}");
+            0
+        });
+        let Some(mut reader) = LineReader::open(&self.storage_file(path)?, offset).await? else {
+            return Ok(());
+        };
+        if reader.truncated {
+            warn!(
+                "cannon {env_id}.{cannon_id} intake file {name} was truncated, reading from the start"
+            );
+        }
+
+        while let Some(line) = reader.next_line().await? {
+            self.submit(line).await;
+            if let Err(e) = save_offset(&self.state.db, &key, reader.offset) {
+                error!("cannon {env_id}.{cannon_id} failed to save intake offset for {name}: {e}");
+            }
+        }
+        Ok(())
+    }
+
+    async fn follow_file(&self, path: &Path) -> io::Result<()> {
+        let name = path.display().to_string();
+        loop {
+            self.read_file(path, &name).await?;
+            tokio::time::sleep(INTAKE_POLL).await;
+        }
+    }
+
+    async fn follow_dir(&self, path: &Path) -> io::Result<()> {
+        let dir = self.storage_file(path)?;
+        loop {
+            let mut files = vec![];
+            let mut entries = tokio::fs::read_dir(&dir).await?;
+            while let Some(entry) = entries.next_entry().await? {
+                let file_name = PathBuf::from(entry.file_name());
+                if file_name.extension().is_some_and(|ext| ext == "jsonl") {
+                    files.push(path.join(file_name));
+                }
+            }
+            files.sort();
+
+            for file in files {
+                self.read_file(&file, &file.display().to_string()).await?;
+            }
+            tokio::time::sleep(INTAKE_POLL).await;
+        }
+    }
+
+    async fn listen_socket(&self, path: &Path) -> io::Result<()> {
+        let path = self.storage_file(path)?;
+        // remove the socket left behind by a previous run
+        if tokio::fs::try_exists(&path).await? {
+            tokio::fs::remove_file(&path).await?;
+        }
+        let listener = UnixListener::bind(&path)?;
+        info!(
+            "cannon {}.{} intake listening on {}",
+            self.env_id,
+            self.cannon_id,
+            path.display()
+        );
+
+        let mut clients = FuturesUnordered::new();
+        loop {
+            tokio::select! {
+                res = listener.accept() => {
+                    let (stream, _) = res?;
+                    clients.push(self.read_socket(stream));
+                }
+                Some(res) = clients.next() => {
+                    if let Err(e) = res {
+                        warn!("cannon {}.{} intake client failed: {e}", self.env_id, self.cannon_id);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Submit authorizations from a socket client until it disconnects. The
+    /// client is not read from while the cannon is full.
+    async fn read_socket(&self, stream: UnixStream) -> io::Result<()> {
+        let mut lines = BufReader::new(stream).lines();
+        while let Some(line) = lines.next_line().await? {
+            self.submit(&line).await;
+        }
+        Ok(())
+    }
+
+    /// Submit the messages published to a NATS subject. The subscription is
+    /// not read from while the cannon is full.
+    async fn subscribe_nats(&self, url: &str, subject: &str) -> io::Result<()> {
+        let name = format!("snops-cannon-{}", self.cannon_id);
+        let mut subscription = NatsSubscription::connect(url, subject, &name).await?;
+        info!(
+            "cannon {}.{} intake subscribed to {subject} on {url}",
+            self.env_id, self.cannon_id
+        );
+
+        loop {
+            let payload = subscription.next_message().await?;
+            self.submit(&String::from_utf8_lossy(&payload)).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use snops_common::db::Database as _;
+    use tokio::net::TcpListener;
+
+    use super::*;
+
+    async fn read_lines(path: &Path, offset: u64) -> (Vec<String>, u64) {
+        let Some(mut reader) = LineReader::open(path, offset).await.unwrap() else {
+            return (vec![], offset);
+        };
+        let mut lines = vec![];
+        while let Some(line) = reader.next_line().await.unwrap() {
+            lines.push(line.trim().to_owned());
+        }
+        (lines, reader.offset)
+    }
+
+    #[test]
+    fn intake_paths_stay_in_storage() {
+        assert!(is_storage_relative(Path::new("auths.jsonl")));
+        assert!(is_storage_relative(Path::new("./intake/auths.jsonl")));
+        assert!(!is_storage_relative(Path::new("/tmp/auths.jsonl")));
+        assert!(!is_storage_relative(Path::new("../auths.jsonl")));
+        assert!(!is_storage_relative(Path::new("intake/../../auths.jsonl")));
+
+        let cannon_id = CannonId::from_str("one").unwrap();
+        let intake = |source| TxIntake {
+            source,
+            max_queue: 1,
+        };
+        assert!(
+            intake(IntakeSource::Dir("auths".into()))
+                .validate(cannon_id)
+                .is_ok()
+        );
+        assert!(
+            intake(IntakeSource::Socket("../intake.sock".into()))
+                .validate(cannon_id)
+                .is_err()
+        );
+        assert!(
+            intake(IntakeSource::Nats {
+                url: "127.0.0.1:4222".to_owned(),
+                subject: "auths".to_owned(),
+            })
+            .validate(cannon_id)
+            .is_ok()
+        );
+    }
+
+    #[tokio::test]
+    async fn partial_lines_are_read_once_complete() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("auths.jsonl");
+        tokio::fs::write(&path, "a\nb\npart").await.unwrap();
+
+        let (lines, offset) = read_lines(&path, 0).await;
+        assert_eq!(lines, ["a", "b"]);
+        assert_eq!(offset, 4);
+
+        // nothing new is read until the line is finished
+        let (lines, offset) = read_lines(&path, offset).await;
+        assert!(lines.is_empty());
+        assert_eq!(offset, 4);
+
+        let mut file = tokio::fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .await
+            .unwrap();
+        file.write_all(b"ial\n").await.unwrap();
+
+        let (lines, offset) = read_lines(&path, offset).await;
+        assert_eq!(lines, ["partial"]);
+        assert!(LineReader::open(&path, offset).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn truncated_files_are_read_from_the_start() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("auths.jsonl");
+        tokio::fs::write(&path, "a\n").await.unwrap();
+
+        let reader = LineReader::open(&path, 10).await.unwrap().unwrap();
+        assert!(reader.truncated);
+        assert_eq!(reader.offset, 0);
+        assert_eq!(read_lines(&path, 10).await, (vec!["a".to_owned()], 2));
+
+        let reader = LineReader::open(&path, 1).await.unwrap().unwrap();
+        assert!(!reader.truncated);
+    }
+
+    #[tokio::test]
+    async fn offsets_resume_after_a_restart() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("auths.jsonl");
+        let db = Database::open(&dir.path().join("store")).unwrap();
+        let key = (
+            EnvId::from_str("alpha").unwrap(),
+            CannonId::from_str("one").unwrap(),
+            Arc::new("auths.jsonl".to_owned()),
+        );
+        tokio::fs::write(&path, "a\nb\n").await.unwrap();
+
+        assert_eq!(load_offset(&db, &key).unwrap(), 0);
+        let (lines, offset) = read_lines(&path, 0).await;
+        assert_eq!(lines, ["a", "b"]);
+        save_offset(&db, &key, offset).unwrap();
+
+        let mut file = tokio::fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .await
+            .unwrap();
+        file.write_all(b"c\n").await.unwrap();
+
+        // a restarted intake does not read the submitted lines again
+        let offset = load_offset(&db, &key).unwrap();
+        assert_eq!(offset, 4);
+        assert_eq!(read_lines(&path, offset).await.0, ["c"]);
+
+        // offsets are kept per file
+        let other = (key.0, key.1, Arc::new("other.jsonl".to_owned()));
+        assert_eq!(load_offset(&db, &other).unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn nats_subscription_with_a_local_broker() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("nats://{}", listener.local_addr().unwrap());
+
+        // a broker that publishes two messages and answers with an error
+        let broker = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let (read, mut write) = stream.into_split();
+            let mut lines = BufReader::new(read).lines();
+
+            write
+                .write_all(b"INFO {\"server_id\":\"test\"}\r\n")
+                .await
+                .unwrap();
+            let connect = lines.next_line().await.unwrap().unwrap();
+            assert!(connect.starts_with("CONNECT "));
+            assert!(connect.contains("\"name\":\"snops-cannon-one\""));
+            assert_eq!(lines.next_line().await.unwrap().unwrap(), "SUB auths 1");
+
+            write
+                .write_all(b"PING\r\nMSG auths 1 5\r\nfirst\r\n")
+                .await
+                .unwrap();
+            assert_eq!(lines.next_line().await.unwrap().unwrap(), "PONG");
+
+            // payloads may contain newlines, and messages may have a reply subject
+            write
+                .write_all(b"MSG auths 1 _INBOX.1 8\r\nsec\r\nond\r\n-ERR 'Stale Connection'\r\n")
+                .await
+                .unwrap();
+        });
+
+        let mut subscription = NatsSubscription::connect(&url, "auths", "snops-cannon-one")
+            .await
+            .unwrap();
+        assert_eq!(subscription.next_message().await.unwrap(), b"first");
+        assert_eq!(subscription.next_message().await.unwrap(), b"sec\r\nond");
+        let err = subscription.next_message().await.unwrap_err();
+        assert!(err.to_string().contains("Stale Connection"));
+
+        broker.await.unwrap();
+    }
+}
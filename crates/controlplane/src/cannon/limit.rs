@@ -0,0 +1,199 @@
+use std::{
+    collections::HashSet,
+    sync::{
+        Arc, Mutex, MutexGuard, PoisonError,
+        atomic::{AtomicUsize, Ordering},
+    },
+    time::{Duration, Instant},
+};
+
+use serde::Serialize;
+
+/// Limits how often a cannon broadcasts, allowing up to one second of
+/// broadcasts in a burst
+#[derive(Debug)]
+pub struct TokenBucket {
+    rate: f64,
+    tokens: f64,
+    updated: Instant,
+}
+
+impl TokenBucket {
+    /// Create a full bucket that refills `per_second` tokens per second. The
+    /// rate must be greater than 0, which the cannon's sink validates.
+    pub fn new(per_second: u32) -> Self {
+        let rate = per_second as f64;
+        Self {
+            rate,
+            tokens: rate,
+            updated: Instant::now(),
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.rate);
+        self.updated = now;
+    }
+
+    /// Take a token if one is available
+    pub fn try_take(&mut self) -> bool {
+        self.try_take_at(Instant::now())
+    }
+
+    fn try_take_at(&mut self, now: Instant) -> bool {
+        self.refill(now);
+        if self.tokens < 1.0 {
+            return false;
+        }
+        self.tokens -= 1.0;
+        true
+    }
+
+    /// How long until the next token is available
+    pub fn wait_time(&mut self) -> Duration {
+        self.wait_time_at(Instant::now())
+    }
+
+    fn wait_time_at(&mut self, now: Instant) -> Duration {
+        self.refill(now);
+        Duration::from_secs_f64(((1.0 - self.tokens) / self.rate).max(0.0))
+    }
+}
+
+/// Work a cannon's execution context has started or is holding back, shared
+/// between the instance and its execution context
+#[derive(Debug, Default)]
+pub struct CannonLoad {
+    executing: AtomicUsize,
+    broadcasting: AtomicUsize,
+    queued_executions: AtomicUsize,
+    queued_broadcasts: AtomicUsize,
+    /// Broadcasted transactions waiting to be confirmed
+    unconfirmed: Mutex<HashSet<Arc<String>>>,
+}
+
+impl CannonLoad {
+    /// Create the load of a cannon with restored broadcasted transactions
+    pub fn with_unconfirmed(unconfirmed: impl IntoIterator<Item = Arc<String>>) -> Self {
+        Self {
+            unconfirmed: Mutex::new(unconfirmed.into_iter().collect()),
+            ..Default::default()
+        }
+    }
+
+    fn lock_unconfirmed(&self) -> MutexGuard<'_, HashSet<Arc<String>>> {
+        self.unconfirmed
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Track whether a transaction is broadcasted and waiting to be confirmed
+    pub fn set_unconfirmed(&self, tx_id: &Arc<String>, unconfirmed: bool) {
+        let mut set = self.lock_unconfirmed();
+        if unconfirmed {
+            set.insert(Arc::clone(tx_id));
+        } else {
+            set.remove(tx_id);
+        }
+    }
+
+    /// Number of broadcasted transactions waiting to be confirmed
+    pub fn unconfirmed(&self) -> usize {
+        self.lock_unconfirmed().len()
+    }
+
+    pub fn update(
+        &self,
+        executing: usize,
+        broadcasting: usize,
+        queued_executions: usize,
+        queued_broadcasts: usize,
+    ) {
+        self.executing.store(executing, Ordering::Relaxed);
+        self.broadcasting.store(broadcasting, Ordering::Relaxed);
+        self.queued_executions
+            .store(queued_executions, Ordering::Relaxed);
+        self.queued_broadcasts
+            .store(queued_broadcasts, Ordering::Relaxed);
+    }
+
+    pub fn info(&self) -> CannonLoadInfo {
+        CannonLoadInfo {
+            executing: self.executing.load(Ordering::Relaxed),
+            broadcasting: self.broadcasting.load(Ordering::Relaxed),
+            queued_executions: self.queued_executions.load(Ordering::Relaxed),
+            queued_broadcasts: self.queued_broadcasts.load(Ordering::Relaxed),
+            unconfirmed: self.unconfirmed(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct CannonLoadInfo {
+    /// Number of authorizations being executed
+    pub executing: usize,
+    /// Number of transactions being broadcasted
+    pub broadcasting: usize,
+    /// Number of authorizations waiting for the cannon to be resumed or for
+    /// an execution to finish
+    pub queued_executions: usize,
+    /// Number of transactions waiting for the cannon to be resumed or for the
+    /// broadcast limits
+    pub queued_broadcasts: usize,
+    /// Number of broadcasted transactions waiting to be confirmed
+    pub unconfirmed: usize,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn bucket_allows_a_burst_of_one_second() {
+        let mut bucket = TokenBucket::new(3);
+        let now = bucket.updated;
+        assert!(bucket.try_take_at(now));
+        assert!(bucket.try_take_at(now));
+        assert!(bucket.try_take_at(now));
+        assert!(!bucket.try_take_at(now));
+    }
+
+    #[test]
+    fn bucket_refills_at_its_rate() {
+        let mut bucket = TokenBucket::new(4);
+        let start = bucket.updated;
+        for _ in 0..4 {
+            assert!(bucket.try_take_at(start));
+        }
+        assert_eq!(bucket.wait_time_at(start), Duration::from_millis(250));
+
+        let later = start + Duration::from_millis(250);
+        assert_eq!(bucket.wait_time_at(later), Duration::ZERO);
+        assert!(bucket.try_take_at(later));
+        assert!(!bucket.try_take_at(later));
+
+        // a long pause only refills up to one second of tokens
+        let much_later = later + Duration::from_secs(10);
+        for _ in 0..4 {
+            assert!(bucket.try_take_at(much_later));
+        }
+        assert!(!bucket.try_take_at(much_later));
+    }
+
+    #[test]
+    fn unconfirmed_transactions_are_counted_once() {
+        let tx = |id: &str| Arc::new(id.to_owned());
+        let load = CannonLoad::with_unconfirmed([tx("a")]);
+        assert_eq!(load.unconfirmed(), 1);
+
+        // re-broadcasts don't count twice
+        load.set_unconfirmed(&tx("a"), true);
+        load.set_unconfirmed(&tx("b"), true);
+        assert_eq!(load.info().unconfirmed, 2);
+
+        load.set_unconfirmed(&tx("a"), false);
+        load.set_unconfirmed(&tx("c"), false);
+        assert_eq!(load.unconfirmed(), 1);
+    }
+}
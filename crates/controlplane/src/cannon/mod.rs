@@ -1,24 +1,35 @@
 pub mod context;
 pub mod error;
+pub mod fee_records;
 pub mod file;
+pub mod history;
+pub mod intake;
+pub mod limit;
 mod net;
+pub mod proof_cache;
+pub mod queue;
 pub mod router;
 pub mod sink;
 pub mod source;
+pub mod status;
 pub mod tracker;
 
 use std::{
+    collections::BTreeMap,
     path::PathBuf,
     sync::{
         Arc,
         atomic::{AtomicU64, AtomicUsize},
     },
+    time::Instant,
 };
 
+use chrono::Utc;
 use context::ExecutionContext;
-use dashmap::DashMap;
+use dashmap::{DashMap, mapref::entry::Entry};
 use snops_common::{
     aot_cmds::AotCmd,
+    events::{EventHelpers, TransactionAbortReason, TransactionEvent},
     format::PackedUint,
     state::{Authorization, CannonId, EnvId, NetworkId, StorageId, TransactionSendState},
 };
@@ -30,14 +41,24 @@ use tokio::{
     task::AbortHandle,
 };
 use tracing::{error, trace, warn};
-use tracker::TransactionTracker;
+use tracker::{TransactionSchedule, TransactionTracker};
 
 use self::{
-    error::{CannonError, CannonInstanceError},
+    error::{AuthorizeError, CannonError, CannonInstanceError},
+    fee_records::FeeRecordReservations,
+    history::TransactionOutcome,
+    limit::CannonLoad,
     sink::TxSink,
     source::TxSource,
+    status::{
+        BlockOutcomes, CannonDetails, CannonInfo, CannonStatus, SEND_STATE_LABELS, SharedStatus,
+        TransactionInfo,
+    },
+};
+use crate::{
+    cannon::source::QueryTarget,
+    state::{EmitEvent, GlobalState},
 };
-use crate::{cannon::source::QueryTarget, state::GlobalState};
 
 /*
 
@@ -107,6 +128,17 @@ pub struct CannonInstance {
 
     pub(crate) received_txs: Arc<AtomicU64>,
     pub(crate) fired_txs: Arc<AtomicUsize>,
+
+    /// Whether the cannon is running, paused, or draining
+    pub(crate) status: Arc<SharedStatus>,
+    /// Number of transactions accepted, rejected, or aborted by the network
+    pub(crate) outcomes: BlockOutcomes,
+    /// Work the execution context has started or is holding back
+    pub(crate) load: Arc<CannonLoad>,
+
+    /// Fee records reserved by authorizations, held until the transactions
+    /// spending them are archived
+    pub(crate) fee_records: Arc<FeeRecordReservations>,
 }
 
 pub struct CannonReceivers {
@@ -220,10 +252,23 @@ impl CannonInstance {
                 }
             };
 
+            // Transactions received before schedules were tracked use the default
+            let schedule = match state.db.tx_schedule.restore(&key) {
+                Ok(schedule) => schedule.unwrap_or_default(),
+                Err(e) => {
+                    warn!(
+                        "cannon {env_id}.{cannon_id} failed to restore schedule for transaction {}: {e}",
+                        key.2
+                    );
+                    TransactionSchedule::default()
+                }
+            };
+
             transactions.insert(
                 key.2,
                 TransactionTracker {
                     index,
+                    schedule,
                     authorization,
                     transaction,
                     status,
@@ -245,6 +290,11 @@ impl CannonInstance {
         source: TxSource,
         sink: TxSink,
     ) -> Result<(Self, CannonReceivers), CannonError> {
+        if let Some(intake) = &source.intake {
+            intake.validate(id)?;
+        }
+        sink.validate(id)?;
+
         let (tx_sender, tx_receiver) = tokio::sync::mpsc::unbounded_channel();
         let query_port = source.get_query_port()?;
         let fired_txs = Arc::new(AtomicUsize::new(0));
@@ -259,6 +309,12 @@ impl CannonInstance {
 
         let (auth_sender, auth_receiver) = tokio::sync::mpsc::unbounded_channel();
         let (transactions, received_txs) = Self::restore_transactions(&global_state, env_id, id);
+        let load = CannonLoad::with_unconfirmed(
+            transactions
+                .iter()
+                .filter(|tx| matches!(tx.status, TransactionSendState::Broadcasted(_, _)))
+                .map(|tx| Arc::clone(tx.key())),
+        );
 
         Ok((
             Self {
@@ -276,6 +332,10 @@ impl CannonInstance {
                 fired_txs,
                 received_txs: Arc::new(received_txs),
                 transactions: Arc::new(transactions),
+                status: Default::default(),
+                outcomes: Default::default(),
+                load: Arc::new(load),
+                fee_records: Default::default(),
             },
             CannonReceivers {
                 transactions: tx_receiver,
@@ -292,9 +352,13 @@ impl CannonInstance {
             network: self.network,
             source: self.source.clone(),
             sink: self.sink.clone(),
+            query_port: self.query_port,
             fired_txs: Arc::clone(&self.fired_txs),
             state: Arc::clone(&self.global_state),
             transactions: Arc::clone(&self.transactions),
+            status: Arc::clone(&self.status),
+            load: Arc::clone(&self.load),
+            fee_records: Arc::clone(&self.fee_records),
         }
     }
 
@@ -323,6 +387,134 @@ impl CannonInstance {
         self.ctx().spawn(rx).await
     }
 
+    pub fn status(&self) -> CannonStatus {
+        self.status.get()
+    }
+
+    /// Stop executing and broadcasting transactions. New transactions are
+    /// still accepted and queued until the cannon is resumed.
+    pub fn pause(&self) {
+        self.status.set(CannonStatus::Paused);
+    }
+
+    /// Resume a paused or draining cannon. Queued transactions are picked up
+    /// by the next transaction tracking cycle.
+    pub fn resume(&self) {
+        self.status.set(CannonStatus::Running);
+    }
+
+    /// Finish the tracked transactions while rejecting new ones
+    pub fn drain(&self) {
+        self.status.set(CannonStatus::Draining);
+    }
+
+    /// Stop tracking transactions, optionally only those with a specific send
+    /// state label. Returns the number of removed transactions.
+    pub fn flush(&self, status: Option<&str>) -> Result<usize, CannonError> {
+        let status = status.map(parse_status_label).transpose()?;
+
+        let tx_ids = self
+            .transactions
+            .iter()
+            .filter(|tx| status.is_none_or(|s| tx.status.label() == s))
+            .map(|tx| Arc::clone(tx.key()))
+            .collect::<Vec<_>>();
+
+        for tx_id in &tx_ids {
+            let Some((_, tracker)) = self.transactions.remove(tx_id) else {
+                continue;
+            };
+            self.fee_records.release_transaction(tx_id);
+            self.load.set_unconfirmed(tx_id, false);
+            tracker.archive(
+                &self.global_state,
+                &(self.env_id, self.id, Arc::clone(tx_id)),
+                TransactionOutcome::Flushed,
+                None,
+            )?;
+            TransactionEvent::ExecuteAborted(TransactionAbortReason::Flushed)
+                .with_cannon(self.id)
+                .with_env_id(self.env_id)
+                .with_transaction(Arc::clone(tx_id))
+                .emit(self.global_state.as_ref());
+        }
+
+        Ok(tx_ids.len())
+    }
+
+    /// Overview of the cannon's status and transaction queue
+    pub fn info(&self) -> CannonInfo {
+        let mut transactions: BTreeMap<_, _> = SEND_STATE_LABELS
+            .map(|label| (label, 0))
+            .into_iter()
+            .collect();
+        for tx in self.transactions.iter() {
+            *transactions.entry(tx.status.label()).or_default() += 1;
+        }
+
+        let status = self.status();
+        CannonInfo {
+            id: self.id,
+            status,
+            drained: status == CannonStatus::Draining && self.transactions.is_empty(),
+            received_txs: self.received_txs.load(std::sync::atomic::Ordering::Relaxed),
+            fired_txs: self.fired_txs.load(std::sync::atomic::Ordering::Relaxed),
+            transactions,
+            outcomes: self.outcomes.counts(),
+            load: self.load.info(),
+        }
+    }
+
+    /// Overview of the cannon along with its source and sink
+    pub fn details(&self) -> CannonDetails {
+        CannonDetails {
+            info: self.info(),
+            source: self.source.clone(),
+            sink: self.sink.clone(),
+        }
+    }
+
+    /// Page through tracked transactions in the order they were received,
+    /// optionally only those with a specific send state label. Returns the
+    /// total number of matching transactions and the requested page.
+    pub fn list_transactions(
+        &self,
+        status: Option<&str>,
+        offset: usize,
+        limit: usize,
+    ) -> Result<(usize, Vec<TransactionInfo>), CannonError> {
+        let status = status.map(parse_status_label).transpose()?;
+
+        let mut txs = self
+            .transactions
+            .iter()
+            .filter(|tx| status.is_none_or(|s| tx.status.label() == s))
+            .map(|tx| (Arc::clone(tx.key()), tx.value().clone()))
+            .collect::<Vec<_>>();
+        txs.sort_by_key(|(_, tx)| tx.index);
+
+        let total = txs.len();
+        let page = txs
+            .into_iter()
+            .skip(offset)
+            .take(limit)
+            .map(|(id, tx)| TransactionInfo {
+                attempts: TransactionTracker::get_attempts(
+                    &self.global_state,
+                    &(self.env_id, self.id, Arc::clone(&id)),
+                ),
+                id,
+                index: tx.index,
+                status: tx.status,
+                schedule: tx.schedule.clone(),
+                has_authorization: tx.authorization.is_some(),
+                has_transaction: tx.transaction.is_some(),
+            })
+            .collect();
+
+        Ok((total, page))
+    }
+
     /// Get an expected local query address for this cannon
     pub fn get_local_query(&self) -> String {
         format!(
@@ -333,65 +525,148 @@ impl CannonInstance {
         )
     }
 
-    /// Called by axum to forward /cannon/<id>/<network>/latest/stateRoot
-    /// to the ledger query service's /<network>/latest/stateRoot
-    pub async fn proxy_state_root(&self) -> Result<String, CannonError> {
-        let cannon_id = self.id;
-        let env_id = self.env_id;
-        let network = self.network;
-
-        match &self.source.query {
-            QueryTarget::Local(qs) => {
-                if let Some(port) = self.query_port {
-                    qs.get_state_root(network, port).await
-                } else {
-                    Err(CannonInstanceError::MissingQueryPort(cannon_id).into())
-                }
-            }
-            QueryTarget::Node(target) => {
-                // shortcut to cached state root if the target is all nodes
-                if target.is_all() {
-                    if let Some(info) = self.global_state.get_env_block_info(env_id) {
-                        return Ok(info.state_root);
-                    }
-                }
+    /// Select an unspent credits record owned by `private_key` with at least
+    /// `microcredits` from the local query service, reserving it so
+    /// concurrent authorizations don't spend the same record. Returns the
+    /// record's commitment and plaintext.
+    pub async fn reserve_fee_record(
+        &self,
+        aot: &AotCmd,
+        private_key: &str,
+        microcredits: u64,
+    ) -> Result<(String, String), CannonError> {
+        let (QueryTarget::Local(_), Some(port)) = (&self.source.query, self.query_port) else {
+            return Err(CannonInstanceError::RecordsUnavailable(self.id).into());
+        };
+
+        let records = aot
+            .find_records(
+                private_key,
+                &format!("http://127.0.0.1:{port}"),
+                microcredits,
+            )
+            .await
+            .map_err(AuthorizeError::from)?;
 
-                Ok(self
-                    .global_state
-                    .snarkos_get::<String>(env_id, "/stateRoot/latest", target)
-                    .await?)
+        let now = Instant::now();
+        self.fee_records.expire(now);
+
+        for record in records {
+            if self.fee_records.reserve(&record.commitment, now) {
+                return Ok((record.commitment, record.record));
             }
         }
+
+        Err(AuthorizeError::NoFeeRecord(microcredits).into())
+    }
+
+    /// Hold a reserved fee record until the transaction spending it is
+    /// archived
+    pub fn bind_fee_record(&self, commitment: &str, tx_id: &Arc<String>) {
+        self.fee_records.bind(commitment, Arc::clone(tx_id));
+        // the transaction may have been archived before it was bound
+        if !self.transactions.contains_key(tx_id) {
+            self.fee_records.release_transaction(tx_id);
+        }
+    }
+
+    /// Release a fee record reserved by an authorization that failed
+    pub fn release_fee_record(&self, commitment: &str) {
+        self.fee_records.release(commitment);
+    }
+
+    /// Called by axum to forward /cannon/<id>/<network>/latest/stateRoot
+    /// to the ledger query service's /<network>/latest/stateRoot
+    pub async fn proxy_state_root(&self) -> Result<String, CannonError> {
+        self.source
+            .state_root(
+                &self.global_state,
+                (self.env_id, self.id),
+                self.network,
+                self.query_port,
+            )
+            .await
     }
 
     /// Called by axum to forward /cannon/<id>/<network>/block/height/latest
     pub async fn proxy_latest_height(&self) -> Result<u32, CannonError> {
-        let cannon_id = self.id;
-        let env_id = self.env_id;
-        let network = self.network;
-
-        match &self.source.query {
-            QueryTarget::Local(qs) => {
-                if let Some(port) = self.query_port {
-                    qs.get_latest_height(network, port).await
-                } else {
-                    Err(CannonInstanceError::MissingQueryPort(cannon_id).into())
-                }
+        self.source
+            .latest_height(
+                &self.global_state,
+                (self.env_id, self.id),
+                self.network,
+                self.query_port,
+            )
+            .await
+    }
+
+    /// Reject a transaction the cannon already delivered within its dedupe
+    /// window. Without a window, delivered transactions are rejected until
+    /// they are pruned from the cannon's history.
+    fn check_history(&self, tx_id: &Arc<String>) -> Result<(), CannonError> {
+        if self.source.dedupe_window == Some(0) {
+            return Ok(());
+        }
+
+        let key = (self.env_id, self.id, Arc::clone(tx_id));
+        let Some(history) = self.global_state.db.tx_history.restore(&key)? else {
+            return Ok(());
+        };
+
+        if history.rejects_duplicate(self.source.dedupe_window, Utc::now()) {
+            return Err(CannonError::TransactionAlreadyFinished(
+                self.id,
+                tx_id.to_string(),
+                history.outcome,
+            ));
+        }
+        Ok(())
+    }
+
+    /// Ensure the transactions an authorization depends on were not already
+    /// failed, and are known to the cannon or the network so the
+    /// authorization does not wait forever. Returns the dependencies the
+    /// cannon is still tracking, as the others are already confirmed.
+    fn check_dependencies(
+        &self,
+        tx_id: &str,
+        after: Vec<Arc<String>>,
+    ) -> Result<Vec<Arc<String>>, CannonError> {
+        let mut pending = Vec::with_capacity(after.len());
+        for dep in after {
+            if self.transactions.contains_key(&dep) {
+                pending.push(dep);
+                continue;
             }
-            QueryTarget::Node(target) => {
-                // shortcut to cached state root if the target is all nodes
-                if target.is_all() {
-                    if let Some(info) = self.global_state.get_env_block_info(env_id) {
-                        return Ok(info.height);
-                    }
+
+            let key = (self.env_id, self.id, Arc::clone(&dep));
+            match self.global_state.db.tx_history.restore(&key)? {
+                Some(history) if history.outcome.is_confirmed() => continue,
+                Some(history) => {
+                    return Err(CannonError::DependencyFailed(
+                        self.id,
+                        tx_id.to_owned(),
+                        dep,
+                        history.outcome,
+                    ));
                 }
+                None => {}
+            }
 
-                Ok(self
-                    .global_state
-                    .snarkos_get::<u32>(env_id, "/block/height/latest", target)
-                    .await?)
+            let confirmed = self
+                .global_state
+                .env_network_cache
+                .get(&self.env_id)
+                .is_some_and(|cache| cache.has_transaction(&dep));
+            if !confirmed {
+                return Err(CannonError::UnknownDependency(
+                    self.id,
+                    tx_id.to_owned(),
+                    dep,
+                ));
             }
         }
+        Ok(pending)
     }
 
     /// Called by axum to forward /cannon/<id>/<network>/transaction/broadcast
@@ -403,6 +678,11 @@ impl CannonInstance {
     ) -> Result<(), CannonError> {
         let key = (self.env_id, self.id, Arc::clone(&tx_id));
 
+        // draining cannons only accept executions of their tracked authorizations
+        if self.status() == CannonStatus::Draining && !self.transactions.contains_key(&tx_id) {
+            return Err(CannonInstanceError::Draining(self.id).into());
+        }
+
         // if the transaction is in the cache, it has already been broadcasted
         if let Some(cache) = self.global_state.env_network_cache.get(&self.env_id) {
             if cache.has_transaction(&tx_id) {
@@ -443,6 +723,7 @@ impl CannonInstance {
                 tx
             }
             _ => {
+                self.check_history(&tx_id)?;
                 trace!(
                     "cannon {}.{} received broadcast {tx_id}",
                     self.env_id, self.id
@@ -454,6 +735,7 @@ impl CannonInstance {
                         self.id,
                         &self.received_txs,
                     ),
+                    schedule: TransactionSchedule::default(),
                     authorization: None,
                     transaction: Some(Arc::new(body)),
                     status: TransactionSendState::Unsent,
@@ -463,6 +745,9 @@ impl CannonInstance {
 
         // write the transaction to the store to prevent data loss
         tracker.write(&self.global_state, &key)?;
+        TransactionTracker::update_progress(&self.global_state, &key, |p| {
+            p.received_at.get_or_insert_with(Utc::now);
+        })?;
         self.transactions.insert(tx_id.to_owned(), tracker);
 
         // forward the transaction to the task, which will broadcast it
@@ -475,7 +760,15 @@ impl CannonInstance {
     }
 
     /// Called by axum to forward /cannon/<id>/auth to a listen source
-    pub async fn proxy_auth(&self, body: Authorization) -> Result<Arc<String>, CannonError> {
+    pub async fn proxy_auth(
+        &self,
+        body: Authorization,
+        schedule: TransactionSchedule,
+    ) -> Result<Arc<String>, CannonError> {
+        if self.status() == CannonStatus::Draining {
+            return Err(CannonInstanceError::Draining(self.id).into());
+        }
+
         let Some(storage) = self
             .global_state
             .get_env(self.env_id)
@@ -501,10 +794,24 @@ impl CannonInstance {
             .await
             .map_err(|e| CannonError::BinaryError(self.id, format!("derive tx id: {e}")))?;
 
-        // prevent already queued transactions from being re-computed
-        if self.transactions.contains_key(&tx_id) {
-            return Err(CannonError::TransactionAlreadyExists(self.id, tx_id));
-        }
+        let tx_id = Arc::new(tx_id);
+        self.check_history(&tx_id)?;
+        let schedule = TransactionSchedule {
+            after: self.check_dependencies(&tx_id, schedule.after)?,
+            ..schedule
+        };
+
+        // prevent already queued transactions from being re-computed, claiming
+        // the id so concurrent submissions of the same authorization collapse
+        let entry = match self.transactions.entry(Arc::clone(&tx_id)) {
+            Entry::Occupied(_) => {
+                return Err(CannonError::TransactionAlreadyExists(
+                    self.id,
+                    tx_id.to_string(),
+                ));
+            }
+            Entry::Vacant(entry) => entry,
+        };
 
         let tracker = TransactionTracker {
             index: Self::inc_received_txs(
@@ -513,19 +820,19 @@ impl CannonInstance {
                 self.id,
                 &self.received_txs,
             ),
+            schedule,
             authorization: Some(Arc::new(body)),
             transaction: None,
             status: TransactionSendState::Authorized,
         };
 
-        let tx_id = Arc::new(tx_id);
-
         // write the transaction to the store to prevent data loss
-        tracker.write(
-            &self.global_state,
-            &(self.env_id, self.id, Arc::clone(&tx_id)),
-        )?;
-        self.transactions.insert(Arc::clone(&tx_id), tracker);
+        let key = (self.env_id, self.id, Arc::clone(&tx_id));
+        tracker.write(&self.global_state, &key)?;
+        TransactionTracker::update_progress(&self.global_state, &key, |p| {
+            p.received_at = Some(Utc::now());
+        })?;
+        entry.insert(tracker);
 
         trace!("cannon {}.{} received auth {tx_id}", self.env_id, self.id);
         self.auth_sender
@@ -536,6 +843,14 @@ impl CannonInstance {
     }
 }
 
+/// Validate a transaction send state label
+fn parse_status_label(label: &str) -> Result<&'static str, CannonInstanceError> {
+    SEND_STATE_LABELS
+        .into_iter()
+        .find(|l| *l == label)
+        .ok_or_else(|| CannonInstanceError::UnknownTransactionStatus(label.to_owned()))
+}
+
 impl Drop for CannonInstance {
     fn drop(&mut self) {
         // cancel the task on drop
@@ -0,0 +1,236 @@
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+use indexmap::IndexMap;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use snops_common::state::{Authorization, NetworkId};
+use tracing::{error, info};
+
+use crate::db::Database;
+
+const MIB: u64 = 1024 * 1024;
+
+/// Transactions proven by compute agents, addressed by a hash of their
+/// authorization and the ledger state they were proven against.
+///
+/// Cannons with the proof cache enabled reuse a cached transaction instead of
+/// proving an identical authorization again, which makes re-running a seeded
+/// workload much cheaper.
+#[derive(Default)]
+pub struct ProofCache {
+    /// Maximum total size of the cached transactions in bytes
+    max_size: u64,
+    index: Mutex<ProofCacheIndex>,
+}
+
+#[derive(Default)]
+struct ProofCacheIndex {
+    /// Sizes of the cached transactions, from least to most recently used
+    entries: IndexMap<String, u64>,
+    /// Total size of the cached transactions
+    size: u64,
+}
+
+impl ProofCache {
+    /// Index the transactions cached in the database, evicting transactions
+    /// past the size limit
+    pub fn load(db: &Database, max_size_mib: u64) -> Self {
+        Self::load_bytes(db, max_size_mib * MIB)
+    }
+
+    fn load_bytes(db: &Database, max_size: u64) -> Self {
+        let cache = Self {
+            max_size,
+            index: Default::default(),
+        };
+
+        {
+            let mut index = cache.index();
+            for (key, transaction) in db.proof_cache.read_all() {
+                let size = transaction.to_string().len() as u64;
+                index.size += size;
+                index.entries.insert(key, size);
+            }
+            cache.evict(db, &mut index);
+
+            if !index.entries.is_empty() {
+                info!(
+                    "loaded {} cached proofs ({} bytes)",
+                    index.entries.len(),
+                    index.size
+                );
+            }
+        }
+
+        cache
+    }
+
+    fn index(&self) -> MutexGuard<'_, ProofCacheIndex> {
+        self.index.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Check if the control plane caches proofs at all
+    pub fn is_enabled(&self) -> bool {
+        self.max_size > 0
+    }
+
+    /// The address of an authorization's transaction when it is proven
+    /// against the ledger at the given height and state root. `None` if the
+    /// authorization can't be serialized, so it is never cached.
+    pub fn key(
+        network: NetworkId,
+        height: u32,
+        state_root: &str,
+        auth: &Authorization,
+    ) -> Option<String> {
+        // the authorization's json is deterministic for the same authorization
+        let auth = match serde_json::to_vec(auth) {
+            Ok(auth) => auth,
+            Err(e) => {
+                error!("failed to serialize authorization for the proof cache: {e}");
+                return None;
+            }
+        };
+
+        let mut digest = Sha256::new();
+        digest.update(network.to_string());
+        digest.update(height.to_le_bytes());
+        digest.update(state_root);
+        digest.update(auth);
+        Some(format!("{:x}", digest.finalize()))
+    }
+
+    /// Get a cached transaction, marking it as recently used
+    pub fn get(&self, db: &Database, key: &str) -> Option<Arc<Value>> {
+        let mut index = self.index();
+        let size = index.entries.shift_remove(key)?;
+
+        match db.proof_cache.restore(&key.to_owned()) {
+            Ok(Some(transaction)) => {
+                index.entries.insert(key.to_owned(), size);
+                Some(Arc::new(transaction))
+            }
+            Ok(None) => {
+                index.size -= size;
+                None
+            }
+            Err(e) => {
+                error!("failed to restore cached proof {key}: {e}");
+                index.size -= size;
+                None
+            }
+        }
+    }
+
+    /// Cache a proven transaction, evicting the least recently used
+    /// transactions past the size limit
+    pub fn insert(&self, db: &Database, key: String, transaction: &Value) {
+        let size = transaction.to_string().len() as u64;
+        if size > self.max_size {
+            return;
+        }
+
+        if let Err(e) = db.proof_cache.save(&key, transaction) {
+            error!("failed to cache proof {key}: {e}");
+            return;
+        }
+
+        let mut index = self.index();
+        if let Some(old_size) = index.entries.shift_remove(&key) {
+            index.size -= old_size;
+        }
+        index.entries.insert(key, size);
+        index.size += size;
+        self.evict(db, &mut index);
+    }
+
+    fn evict(&self, db: &Database, index: &mut ProofCacheIndex) {
+        while index.size > self.max_size {
+            let Some((key, size)) = index.entries.shift_remove_index(0) else {
+                break;
+            };
+            index.size -= size;
+            if let Err(e) = db.proof_cache.delete(&key) {
+                error!("failed to evict cached proof {key}: {e}");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use snops_common::db::Database as _;
+
+    use super::*;
+
+    /// A transaction that takes `size` bytes in the cache
+    fn transaction(size: usize) -> Value {
+        json!("x".repeat(size - 2))
+    }
+
+    fn cached(cache: &ProofCache) -> Vec<String> {
+        cache.index().entries.keys().cloned().collect()
+    }
+
+    #[test]
+    fn least_recently_used_proofs_are_evicted() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Database::open(dir.path()).unwrap();
+        let cache = ProofCache::load_bytes(&db, 30);
+
+        for key in ["a", "b", "c"] {
+            cache.insert(&db, key.to_owned(), &transaction(10));
+        }
+        assert_eq!(cache.index().size, 30);
+
+        // reading a proof makes it the most recently used
+        assert_eq!(cache.get(&db, "a").as_deref(), Some(&transaction(10)));
+        cache.insert(&db, "d".to_owned(), &transaction(10));
+        assert_eq!(cached(&cache), ["c", "a", "d"]);
+        assert_eq!(cache.index().size, 30);
+        assert!(cache.get(&db, "b").is_none());
+        assert!(db.proof_cache.restore(&"b".to_owned()).unwrap().is_none());
+
+        // a large proof evicts as many proofs as it needs room for
+        cache.insert(&db, "e".to_owned(), &transaction(20));
+        assert_eq!(cached(&cache), ["d", "e"]);
+        assert_eq!(cache.index().size, 30);
+    }
+
+    #[test]
+    fn cache_sizes_are_accounted() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Database::open(dir.path()).unwrap();
+        let cache = ProofCache::load_bytes(&db, 30);
+
+        // proofs larger than the cache are not kept
+        cache.insert(&db, "huge".to_owned(), &transaction(31));
+        assert!(cached(&cache).is_empty());
+        assert!(
+            db.proof_cache
+                .restore(&"huge".to_owned())
+                .unwrap()
+                .is_none()
+        );
+
+        // replacing a proof replaces its size
+        cache.insert(&db, "a".to_owned(), &transaction(10));
+        cache.insert(&db, "a".to_owned(), &transaction(15));
+        cache.insert(&db, "b".to_owned(), &transaction(5));
+        assert_eq!(cache.index().size, 20);
+
+        // a proof missing from the store gives its size back
+        db.proof_cache.delete(&"b".to_owned()).unwrap();
+        assert!(cache.get(&db, "b").is_none());
+        assert_eq!(cache.index().size, 15);
+        assert_eq!(cached(&cache), ["a"]);
+
+        // loading the cache with a smaller limit evicts the stored proofs
+        cache.insert(&db, "c".to_owned(), &transaction(10));
+        let smaller = ProofCache::load_bytes(&db, 12);
+        assert_eq!(smaller.index().size, 10);
+        assert_eq!(cached(&smaller).len(), 1);
+        assert_eq!(db.proof_cache.read_all().count(), 1);
+    }
+}
@@ -0,0 +1,194 @@
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashSet},
+    sync::Arc,
+};
+
+use chrono::{DateTime, Utc};
+
+use super::tracker::{TransactionPriority, TransactionSchedule};
+
+/// Order in which a cannon starts its queued work: the highest priority, then
+/// the earliest deadline, then the first received
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct QueueOrder {
+    priority: Reverse<TransactionPriority>,
+    /// Work without a deadline goes after work with one
+    no_deadline: bool,
+    deadline: Option<DateTime<Utc>>,
+    index: u64,
+}
+
+impl QueueOrder {
+    pub fn new(schedule: &TransactionSchedule, index: u64) -> Self {
+        Self {
+            priority: Reverse(schedule.priority),
+            no_deadline: schedule.deadline.is_none(),
+            deadline: schedule.deadline,
+            index,
+        }
+    }
+}
+
+/// Work a cannon's limits are holding back, in the order it is started
+#[derive(Debug, Default)]
+pub struct WorkQueue {
+    heap: BinaryHeap<Reverse<(QueueOrder, Arc<String>)>>,
+    /// Work that was not ready when it reached the front of the queue, which
+    /// is left out until [`WorkQueue::unblock`]
+    blocked: Vec<(QueueOrder, Arc<String>)>,
+    queued: HashSet<Arc<String>>,
+}
+
+impl WorkQueue {
+    /// Queue work, returning false if it is already queued
+    pub fn push(&mut self, tx_id: Arc<String>, order: QueueOrder) -> bool {
+        if !self.queued.insert(Arc::clone(&tx_id)) {
+            return false;
+        }
+        self.heap.push(Reverse((order, tx_id)));
+        true
+    }
+
+    pub fn len(&self) -> usize {
+        self.queued.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queued.is_empty()
+    }
+
+    /// The next work that is ready to start, without removing it. Work that
+    /// is not ready is set aside until the queue is unblocked.
+    pub fn peek_ready(
+        &mut self,
+        mut is_ready: impl FnMut(&Arc<String>) -> bool,
+    ) -> Option<Arc<String>> {
+        while let Some(Reverse((_, tx_id))) = self.heap.peek() {
+            if is_ready(tx_id) {
+                break;
+            }
+            if let Some(Reverse(work)) = self.heap.pop() {
+                self.blocked.push(work);
+            }
+        }
+        self.heap
+            .peek()
+            .map(|Reverse((_, tx_id))| Arc::clone(tx_id))
+    }
+
+    /// Remove the work at the front of the queue
+    pub fn pop(&mut self) -> Option<Arc<String>> {
+        let Reverse((_, tx_id)) = self.heap.pop()?;
+        self.queued.remove(&tx_id);
+        Some(tx_id)
+    }
+
+    /// Remove the next work that is ready to start
+    pub fn pop_ready(&mut self, is_ready: impl FnMut(&Arc<String>) -> bool) -> Option<Arc<String>> {
+        self.peek_ready(is_ready)?;
+        self.pop()
+    }
+
+    /// Put the work that was not ready back in the queue so it is checked
+    /// again
+    pub fn unblock(&mut self) {
+        self.heap.extend(self.blocked.drain(..).map(Reverse));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn order(priority: TransactionPriority, deadline: Option<i64>, index: u64) -> QueueOrder {
+        QueueOrder::new(
+            &TransactionSchedule {
+                priority,
+                deadline: deadline.map(|secs| DateTime::from_timestamp(secs, 0).unwrap()),
+                after: vec![],
+            },
+            index,
+        )
+    }
+
+    fn drain(queue: &mut WorkQueue) -> Vec<String> {
+        std::iter::from_fn(|| queue.pop_ready(|_| true))
+            .map(|tx_id| tx_id.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn work_is_started_in_order() {
+        use TransactionPriority::*;
+
+        let mut queue = WorkQueue::default();
+        for (tx_id, order) in [
+            ("low", order(Low, None, 0)),
+            ("normal-2", order(Normal, None, 2)),
+            ("normal-1", order(Normal, None, 1)),
+            ("normal-late", order(Normal, Some(200), 3)),
+            ("normal-soon", order(Normal, Some(100), 4)),
+            ("high", order(High, None, 5)),
+        ] {
+            assert!(queue.push(Arc::new(tx_id.to_owned()), order));
+        }
+        assert_eq!(queue.len(), 6);
+
+        assert_eq!(
+            drain(&mut queue),
+            [
+                "high",
+                "normal-soon",
+                "normal-late",
+                "normal-1",
+                "normal-2",
+                "low"
+            ]
+        );
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn work_is_queued_once() {
+        let mut queue = WorkQueue::default();
+        let tx_id = Arc::new("a".to_owned());
+        assert!(queue.push(Arc::clone(&tx_id), QueueOrder::default()));
+        assert!(!queue.push(Arc::clone(&tx_id), QueueOrder::default()));
+        assert_eq!(queue.len(), 1);
+
+        assert_eq!(queue.pop(), Some(Arc::clone(&tx_id)));
+        assert!(queue.push(tx_id, QueueOrder::default()));
+    }
+
+    #[test]
+    fn blocked_work_waits_for_unblock() {
+        let mut queue = WorkQueue::default();
+        for (i, tx_id) in ["a", "b", "c"].into_iter().enumerate() {
+            queue.push(
+                Arc::new(tx_id.to_owned()),
+                order(TransactionPriority::Normal, None, i as u64),
+            );
+        }
+
+        // a blocked transaction is checked once, then skipped
+        let mut checked = vec![];
+        let next = queue.peek_ready(|tx_id| {
+            checked.push(tx_id.to_string());
+            tx_id.as_str() != "a"
+        });
+        assert_eq!(next.as_deref().map(String::as_str), Some("b"));
+        assert_eq!(queue.pop().as_deref().map(String::as_str), Some("b"));
+        assert_eq!(drain(&mut queue), ["c"]);
+        assert_eq!(checked, ["a", "b"]);
+
+        // blocked work is still queued, and keeps its place once unblocked
+        assert_eq!(queue.len(), 1);
+        queue.push(
+            Arc::new("d".to_owned()),
+            order(TransactionPriority::Normal, None, 3),
+        );
+        queue.unblock();
+        assert_eq!(drain(&mut queue), ["a", "d"]);
+    }
+}
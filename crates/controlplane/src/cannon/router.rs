@@ -6,6 +6,7 @@ use axum::{
     response::{IntoResponse, Response},
     routing::{get, post},
 };
+use chrono::{DateTime, Utc};
 use reqwest::StatusCode;
 use serde::Deserialize;
 use serde_json::json;
@@ -14,7 +15,12 @@ use snops_common::{
     state::{Authorization, KeyState, NetworkId, id_or_none},
 };
 
-use super::source::QueryTarget;
+use super::{
+    CannonInstance,
+    source::QueryTarget,
+    status::{FlushQuery, TransactionsQuery},
+    tracker::{TransactionPriority, TransactionSchedule},
+};
 use crate::{
     server::{actions::execute::execute_status, error::ServerError},
     state::AppState,
@@ -22,6 +28,13 @@ use crate::{
 
 pub(crate) fn redirect_cannon_routes() -> Router<AppState> {
     Router::new()
+        .route("/", get(list_cannons))
+        .route("/:cannon", get(get_cannon))
+        .route("/:cannon/transactions", get(get_transactions))
+        .route("/:cannon/pause", post(pause_cannon))
+        .route("/:cannon/resume", post(resume_cannon))
+        .route("/:cannon/drain", post(drain_cannon))
+        .route("/:cannon/flush", post(flush_cannon))
         .route("/:cannon/:network/latest/stateRoot", get(state_root))
         .route("/:cannon/:network/stateRoot/latest", get(state_root))
         .route("/:cannon/:network/transaction/broadcast", post(transaction))
@@ -46,6 +59,126 @@ pub(crate) fn redirect_cannon_routes() -> Router<AppState> {
         .route("/:cannon/auth", post(authorization))
 }
 
+/// Find a cannon by its environment and cannon ids
+fn find_cannon(
+    state: &AppState,
+    env_id: &str,
+    cannon_id: &str,
+) -> Result<Arc<CannonInstance>, ServerError> {
+    let (Some(env_id), Some(cannon_id)) = (id_or_none(env_id), id_or_none(cannon_id)) else {
+        return Err(ServerError::NotFound(
+            "unknown cannon or environment".to_owned(),
+        ));
+    };
+
+    let Some(env) = state.get_env(env_id) else {
+        return Err(ServerError::NotFound("environment not found".to_owned()));
+    };
+
+    env.get_cannon(cannon_id)
+        .ok_or_else(|| ServerError::NotFound("cannon not found".to_owned()))
+}
+
+async fn list_cannons(Path(env_id): Path<String>, state: State<AppState>) -> Response {
+    let Some(env_id) = id_or_none(&env_id) else {
+        return ServerError::NotFound("unknown environment".to_owned()).into_response();
+    };
+
+    let Some(env) = state.get_env(env_id) else {
+        return ServerError::NotFound("environment not found".to_owned()).into_response();
+    };
+
+    let mut cannons = env.cannons.values().map(|c| c.info()).collect::<Vec<_>>();
+    cannons.sort_by_key(|c| c.id.to_string());
+    Json(cannons).into_response()
+}
+
+async fn get_cannon(
+    Path((env_id, cannon_id)): Path<(String, String)>,
+    state: State<AppState>,
+) -> Response {
+    match find_cannon(&state, &env_id, &cannon_id) {
+        Ok(cannon) => Json(cannon.details()).into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+async fn get_transactions(
+    Path((env_id, cannon_id)): Path<(String, String)>,
+    state: State<AppState>,
+    Query(query): Query<TransactionsQuery>,
+) -> Response {
+    let cannon = match find_cannon(&state, &env_id, &cannon_id) {
+        Ok(cannon) => cannon,
+        Err(e) => return e.into_response(),
+    };
+
+    match cannon.list_transactions(query.status.as_deref(), query.offset, query.limit) {
+        Ok((total, transactions)) => Json(json!({
+            "total": total,
+            "offset": query.offset,
+            "transactions": transactions,
+        }))
+        .into_response(),
+        Err(e) => ServerError::from(e).into_response(),
+    }
+}
+
+async fn pause_cannon(
+    Path((env_id, cannon_id)): Path<(String, String)>,
+    state: State<AppState>,
+) -> Response {
+    match find_cannon(&state, &env_id, &cannon_id) {
+        Ok(cannon) => {
+            cannon.pause();
+            Json(cannon.info()).into_response()
+        }
+        Err(e) => e.into_response(),
+    }
+}
+
+async fn resume_cannon(
+    Path((env_id, cannon_id)): Path<(String, String)>,
+    state: State<AppState>,
+) -> Response {
+    match find_cannon(&state, &env_id, &cannon_id) {
+        Ok(cannon) => {
+            cannon.resume();
+            Json(cannon.info()).into_response()
+        }
+        Err(e) => e.into_response(),
+    }
+}
+
+async fn drain_cannon(
+    Path((env_id, cannon_id)): Path<(String, String)>,
+    state: State<AppState>,
+) -> Response {
+    match find_cannon(&state, &env_id, &cannon_id) {
+        Ok(cannon) => {
+            cannon.drain();
+            Json(cannon.info()).into_response()
+        }
+        Err(e) => e.into_response(),
+    }
+}
+
+async fn flush_cannon(
+    Path((env_id, cannon_id)): Path<(String, String)>,
+    state: State<AppState>,
+    Query(query): Query<FlushQuery>,
+) -> Response {
+    let cannon = match find_cannon(&state, &env_id, &cannon_id) {
+        Ok(cannon) => cannon,
+        Err(e) => return e.into_response(),
+    };
+
+    match cannon.flush(query.status.as_deref()) {
+        Ok(flushed) => Json(json!({ "flushed": flushed })).into_response(),
+        Err(e) => ServerError::from(e).into_response(),
+    }
+}
+
 async fn state_root(
     Path((env_id, cannon_id, network)): Path<(String, String, NetworkId)>,
     state: State<AppState>,
@@ -380,12 +513,34 @@ pub struct AuthQuery {
     /// When present, the response will contain only the transaction ID
     #[serde(rename = "async")]
     async_mode: Option<bool>,
+    /// Priority of the transaction in the cannon's queues
+    priority: Option<TransactionPriority>,
+    /// Abort the authorization if it has not been executed by this time
+    deadline: Option<DateTime<Utc>>,
+    /// Comma separated ids of transactions that must be confirmed before the
+    /// authorization is executed
+    after: Option<String>,
 }
 
 impl AuthQuery {
     pub fn is_async(&self) -> bool {
         self.async_mode.unwrap_or_default()
     }
+
+    pub fn schedule(&self) -> TransactionSchedule {
+        TransactionSchedule {
+            priority: self.priority.unwrap_or_default(),
+            deadline: self.deadline,
+            after: self
+                .after
+                .iter()
+                .flat_map(|after| after.split(','))
+                .map(str::trim)
+                .filter(|tx_id| !tx_id.is_empty())
+                .map(|tx_id| Arc::new(tx_id.to_owned()))
+                .collect(),
+        }
+    }
 }
 
 async fn authorization(
@@ -407,13 +562,13 @@ async fn authorization(
     };
 
     if query.is_async() {
-        return match cannon.proxy_auth(body).await {
+        return match cannon.proxy_auth(body, query.schedule()).await {
             Ok(tx_id) => (StatusCode::ACCEPTED, Json(tx_id)).into_response(),
             Err(e) => ServerError::from(e).into_response(),
         };
     }
 
-    match cannon.proxy_auth(body).await {
+    match cannon.proxy_auth(body, query.schedule()).await {
         Ok(tx_id) => {
             use snops_common::events::EventFilter::*;
             let subscriber = state
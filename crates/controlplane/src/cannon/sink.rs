@@ -1,5 +1,7 @@
 use serde::{Deserialize, Serialize};
-use snops_common::state::TxPipeId;
+use snops_common::state::{CannonId, TxPipeId};
+
+use super::error::CannonInstanceError;
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(rename_all = "kebab-case")]
@@ -31,10 +33,80 @@ pub struct TxSink {
     /// Time to wait before re-trying to authorize a transaction
     #[serde(default = "TxSink::default_retry_timeout")]
     pub authorize_timeout: u32,
+    /// Maximum number of authorizations executing at once
+    ///
+    /// None means no limit. 0 is rejected, as it would never execute.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_executing: Option<u32>,
+    /// Maximum number of broadcasts per second, including re-broadcasts.
+    /// Up to one second of broadcasts can be sent in a burst.
+    ///
+    /// None means no limit. 0 is rejected, as it would never broadcast.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub broadcast_rate: Option<u32>,
+    /// Maximum number of broadcasted transactions waiting to be confirmed
+    ///
+    /// None means no limit. 0 is rejected, as it would never broadcast.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_unconfirmed: Option<u32>,
 }
 
 impl TxSink {
     pub fn default_retry_timeout() -> u32 {
         60
     }
+
+    /// Ensure the sink's limits allow the cannon to make progress
+    pub fn validate(&self, cannon_id: CannonId) -> Result<(), CannonInstanceError> {
+        if self.max_executing == Some(0) {
+            return Err(CannonInstanceError::InvalidLimit(
+                cannon_id,
+                "max-executing",
+            ));
+        }
+        if self.broadcast_rate == Some(0) {
+            return Err(CannonInstanceError::InvalidLimit(
+                cannon_id,
+                "broadcast-rate",
+            ));
+        }
+        if self.max_unconfirmed == Some(0) {
+            return Err(CannonInstanceError::InvalidLimit(
+                cannon_id,
+                "max-unconfirmed",
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use super::*;
+
+    #[test]
+    fn zero_limits_are_rejected() {
+        let cannon_id = CannonId::from_str("default").unwrap();
+        let sink = |yaml: &str| serde_yaml::from_str::<TxSink>(yaml).unwrap();
+
+        assert!(
+            sink("max-executing: 1\nbroadcast-rate: 1\nmax-unconfirmed: 1")
+                .validate(cannon_id)
+                .is_ok()
+        );
+        assert!(matches!(
+            sink("max-executing: 0").validate(cannon_id),
+            Err(CannonInstanceError::InvalidLimit(_, "max-executing"))
+        ));
+        assert!(matches!(
+            sink("broadcast-rate: 0").validate(cannon_id),
+            Err(CannonInstanceError::InvalidLimit(_, "broadcast-rate"))
+        ));
+        assert!(matches!(
+            sink("max-unconfirmed: 0").validate(cannon_id),
+            Err(CannonInstanceError::InvalidLimit(_, "max-unconfirmed"))
+        ));
+    }
 }
@@ -1,22 +1,24 @@
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 
 use chrono::Utc;
 use serde::{Deserialize, Serialize};
 use serde_json::{Value, json};
 use snops_common::events::{EventHelpers, TransactionEvent};
-use snops_common::state::{Authorization, TransactionSendState};
+use snops_common::state::{AgentId, Authorization, CannonId, EnvId, TransactionSendState};
 use snops_common::{INTERN, lasso::Spur, node_targets::NodeTargets, state::NetworkId};
-use tracing::error;
+use tracing::{error, trace};
 
 use super::context::CtxEventHelper;
+use super::intake::TxIntake;
 use super::{
     ExecutionContext,
-    error::{CannonError, SourceError},
+    error::{CannonError, CannonInstanceError, SourceError},
     net::get_available_port,
+    proof_cache::ProofCache,
+    status::CannonStatus,
     tracker::TransactionTracker,
 };
-use crate::env::set::find_compute_agent;
-use crate::state::EmitEvent;
+use crate::state::{EmitEvent, GlobalState, compute::compute_slots_for};
 
 /// Represents an instance of a local query service.
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -153,6 +155,22 @@ pub struct TxSource {
     pub query: QueryTarget,
     #[serde(default)]
     pub compute: ComputeTarget,
+    /// Receive authorizations from a file, socket, or message queue
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub intake: Option<TxIntake>,
+    /// Seconds after the cannon finishes with a transaction during which the
+    /// same transaction is rejected. 0 only rejects transactions being
+    /// tracked.
+    ///
+    /// None (the default) rejects it for as long as it is in the cannon's
+    /// history, which is until it is pruned by the control plane's
+    /// `tx_history_limit`, or the env is deleted and its history expires.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dedupe_window: Option<u32>,
+    /// Reuse transactions the control plane has already proven for the same
+    /// authorization and ledger state
+    #[serde(default)]
+    pub proof_cache: bool,
 }
 
 impl TxSource {
@@ -165,9 +183,76 @@ impl TxSource {
             get_available_port().ok_or(SourceError::TxSourceUnavailablePort)?,
         ))
     }
+
+    /// Fetch the latest state root of the ledger the query target serves
+    pub async fn state_root(
+        &self,
+        state: &GlobalState,
+        (env_id, cannon_id): (EnvId, CannonId),
+        network: NetworkId,
+        query_port: Option<u16>,
+    ) -> Result<String, CannonError> {
+        match &self.query {
+            QueryTarget::Local(qs) => {
+                let port = query_port.ok_or(CannonInstanceError::MissingQueryPort(cannon_id))?;
+                qs.get_state_root(network, port).await
+            }
+            QueryTarget::Node(target) => {
+                // shortcut to cached state root if the target is all nodes
+                if target.is_all() {
+                    if let Some(info) = state.get_env_block_info(env_id) {
+                        return Ok(info.state_root);
+                    }
+                }
+
+                Ok(state
+                    .snarkos_get::<String>(env_id, "/stateRoot/latest", target)
+                    .await?)
+            }
+        }
+    }
+
+    /// Fetch the latest block height of the ledger the query target serves
+    pub async fn latest_height(
+        &self,
+        state: &GlobalState,
+        (env_id, cannon_id): (EnvId, CannonId),
+        network: NetworkId,
+        query_port: Option<u16>,
+    ) -> Result<u32, CannonError> {
+        match &self.query {
+            QueryTarget::Local(qs) => {
+                let port = query_port.ok_or(CannonInstanceError::MissingQueryPort(cannon_id))?;
+                qs.get_latest_height(network, port).await
+            }
+            QueryTarget::Node(target) => {
+                // shortcut to cached height if the target is all nodes
+                if target.is_all() {
+                    if let Some(info) = state.get_env_block_info(env_id) {
+                        return Ok(info.height);
+                    }
+                }
+
+                Ok(state
+                    .snarkos_get::<u32>(env_id, "/block/height/latest", target)
+                    .await?)
+            }
+        }
+    }
 }
 
 impl ComputeTarget {
+    /// Labels a compute agent must have to generate authorizations and
+    /// executions for this target
+    pub fn agent_labels(&self) -> &[Spur] {
+        match self {
+            ComputeTarget::Agent {
+                labels: Some(labels),
+            } => labels,
+            _ => &[],
+        }
+    }
+
     pub async fn execute(
         &self,
         ctx: &ExecutionContext,
@@ -176,11 +261,59 @@ impl ComputeTarget {
         auth: &Authorization,
     ) -> Result<(), CannonError> {
         match self {
-            ComputeTarget::Agent { labels } => {
-                // find a client, mark it as busy
-                let (agent_id, client, _busy) =
-                    find_compute_agent(&ctx.state, &labels.clone().unwrap_or_default())
-                        .ok_or(SourceError::NoAvailableAgents("authorization"))?;
+            ComputeTarget::Agent { .. } => {
+                // reuse a transaction already proven for this authorization and ledger state
+                if let Some(transaction) = proof_cache_key(ctx, auth)
+                    .await
+                    .and_then(|key| ctx.state.proof_cache.get(&ctx.state.db, &key))
+                {
+                    // the authorization may have been aborted, or the cannon paused,
+                    // while the ledger state was fetched
+                    if !can_execute(ctx, tx_id) {
+                        return Ok(());
+                    }
+                    trace!(
+                        "cannon {}.{} reused a cached proof for {tx_id}",
+                        ctx.env_id, ctx.id
+                    );
+                    complete_execution(ctx, tx_id, transaction, None);
+                    return Ok(());
+                }
+
+                // don't wait in line when no agent could ever grant the request. the
+                // transaction task queues the authorization again later
+                if compute_slots_for(&ctx.state.pool, self.agent_labels()) == 0 {
+                    return Err(SourceError::NoAvailableAgents("authorization").into());
+                }
+
+                // wait in line for a compute slot, which is released when the lease drops
+                let mut ticket = ctx.state.compute.request(
+                    &ctx.state.pool,
+                    (ctx.env_id, Some(ctx.id)),
+                    self.agent_labels(),
+                );
+                let lease = match ticket.try_take() {
+                    Some(lease) => lease,
+                    None => {
+                        TransactionEvent::ExecuteAwaitingCompute
+                            .with_cannon_ctx(ctx, Arc::clone(tx_id))
+                            .emit(ctx);
+                        // leave the queue if no agent frees up in time
+                        let wait = Duration::from_secs(ctx.sink.authorize_timeout.into());
+                        tokio::time::timeout(wait, ticket.granted())
+                            .await
+                            .ok()
+                            .flatten()
+                            .ok_or(SourceError::NoAvailableAgents("authorization"))?
+                    }
+                };
+                let (agent_id, client) = (lease.agent_id, &lease.client);
+
+                // the authorization may have been aborted, or the cannon paused,
+                // while waiting. the transaction task re-queues paused work
+                if !can_execute(ctx, tx_id) {
+                    return Ok(());
+                }
 
                 // emit status updates & increment attempts
                 TransactionEvent::Executing
@@ -188,15 +321,24 @@ impl ComputeTarget {
                     .with_agent_id(agent_id)
                     .emit(ctx);
                 ctx.write_tx_status(tx_id, TransactionSendState::Executing(Utc::now()));
-                if let Err(e) = TransactionTracker::inc_attempts(
-                    &ctx.state,
-                    &(ctx.env_id, ctx.id, tx_id.to_owned()),
-                ) {
+                let key = (ctx.env_id, ctx.id, tx_id.to_owned());
+                if let Err(e) = TransactionTracker::inc_attempts(&ctx.state, &key) {
                     error!(
                         "cannon {}.{} failed to increment auth attempts for {tx_id}: {e}",
                         ctx.env_id, ctx.id
                     );
                 }
+                if let Err(e) = TransactionTracker::update_progress(&ctx.state, &key, |p| {
+                    p.compute_agent = Some(agent_id);
+                }) {
+                    error!(
+                        "cannon {}.{} failed to record compute agent for {tx_id}: {e}",
+                        ctx.env_id, ctx.id
+                    );
+                }
+
+                // the proof is cached against the ledger state it is executed with
+                let cache_key = proof_cache_key(ctx, auth).await;
 
                 // execute the authorization
                 let transaction_json = client
@@ -225,46 +367,12 @@ impl ComputeTarget {
                     }
                 };
 
-                // update the transaction blob and tracker status
-                let key = (ctx.env_id, ctx.id, tx_id.to_owned());
-                if let Some(mut tx) = ctx.transactions.get_mut(tx_id) {
-                    if let Err(e) = TransactionTracker::write_status(
-                        &ctx.state,
-                        &key,
-                        TransactionSendState::Unsent,
-                    ) {
-                        error!(
-                            "cannon {}.{} failed to write status after auth for {tx_id}: {e}",
-                            ctx.env_id, ctx.id
-                        );
-                    }
-                    if let Err(e) = TransactionTracker::write_tx(&ctx.state, &key, &transaction) {
-                        error!(
-                            "cannon {}.{} failed to write tx json after auth for {tx_id}: {e}",
-                            ctx.env_id, ctx.id
-                        );
-                    }
-
-                    // clear auth attempts so the broadcast has a clean slate
-                    if let Err(e) = TransactionTracker::clear_attempts(
-                        &ctx.state,
-                        &(ctx.env_id, ctx.id, tx_id.to_owned()),
-                    ) {
-                        tracing::error!(
-                            "cannon {}.{} failed to clear auth attempts for {tx_id}: {e}",
-                            ctx.env_id,
-                            ctx.id
-                        );
-                    }
-                    tx.status = TransactionSendState::Unsent;
-                    tx.transaction = Some(Arc::clone(&transaction));
+                if let Some(key) = cache_key {
+                    ctx.state
+                        .proof_cache
+                        .insert(&ctx.state.db, key, &transaction);
                 }
-                TransactionEvent::ExecuteComplete {
-                    transaction: Arc::clone(&transaction),
-                }
-                .with_cannon_ctx(ctx, Arc::clone(tx_id))
-                .with_agent_id(agent_id)
-                .emit(ctx);
+                complete_execution(ctx, tx_id, transaction, Some(agent_id));
 
                 Ok(())
             }
@@ -295,3 +403,100 @@ impl ComputeTarget {
         }
     }
 }
+
+/// Whether an authorization can still be executed: the cannon is not paused
+/// and the authorization was not aborted or executed in the meantime. The
+/// transaction task re-queues paused work
+fn can_execute(ctx: &ExecutionContext, tx_id: &Arc<String>) -> bool {
+    ctx.status.get() != CannonStatus::Paused
+        && ctx
+            .transactions
+            .get(tx_id)
+            .is_some_and(|tx| tx.status == TransactionSendState::Authorized)
+}
+
+/// Address of an authorization's transaction in the proof cache, when the
+/// cannon caches proofs. The address uses the ledger state of the cannon's
+/// query target, which is what the compute agent proves against.
+async fn proof_cache_key(ctx: &ExecutionContext, auth: &Authorization) -> Option<String> {
+    if !ctx.source.proof_cache || !ctx.state.proof_cache.is_enabled() {
+        return None;
+    }
+
+    let ids = (ctx.env_id, ctx.id);
+    let query_state = async {
+        let height = ctx
+            .source
+            .latest_height(&ctx.state, ids, ctx.network, ctx.query_port)
+            .await?;
+        let state_root = ctx
+            .source
+            .state_root(&ctx.state, ids, ctx.network, ctx.query_port)
+            .await?;
+        Ok::<_, CannonError>((height, state_root))
+    };
+    match query_state.await {
+        Ok((height, state_root)) => ProofCache::key(ctx.network, height, &state_root, auth),
+        Err(e) => {
+            trace!(
+                "cannon {}.{} skipped the proof cache, failed to fetch the ledger state: {e}",
+                ctx.env_id, ctx.id
+            );
+            None
+        }
+    }
+}
+
+/// Store an executed transaction so it can be broadcasted
+fn complete_execution(
+    ctx: &ExecutionContext,
+    tx_id: &Arc<String>,
+    transaction: Arc<Value>,
+    agent_id: Option<AgentId>,
+) {
+    let key = (ctx.env_id, ctx.id, tx_id.to_owned());
+
+    // update the transaction blob and tracker status
+    if let Some(mut tx) = ctx.transactions.get_mut(tx_id) {
+        if let Err(e) =
+            TransactionTracker::write_status(&ctx.state, &key, TransactionSendState::Unsent)
+        {
+            error!(
+                "cannon {}.{} failed to write status after auth for {tx_id}: {e}",
+                ctx.env_id, ctx.id
+            );
+        }
+        if let Err(e) = TransactionTracker::write_tx(&ctx.state, &key, &transaction) {
+            error!(
+                "cannon {}.{} failed to write tx json after auth for {tx_id}: {e}",
+                ctx.env_id, ctx.id
+            );
+        }
+
+        let attempts = TransactionTracker::get_attempts(&ctx.state, &key);
+        if let Err(e) = TransactionTracker::update_progress(&ctx.state, &key, |p| {
+            p.executed_at = Some(Utc::now());
+            p.execute_attempts = attempts;
+        }) {
+            error!(
+                "cannon {}.{} failed to record execution for {tx_id}: {e}",
+                ctx.env_id, ctx.id
+            );
+        }
+
+        // clear auth attempts so the broadcast has a clean slate
+        if let Err(e) = TransactionTracker::clear_attempts(&ctx.state, &key) {
+            error!(
+                "cannon {}.{} failed to clear auth attempts for {tx_id}: {e}",
+                ctx.env_id, ctx.id
+            );
+        }
+        tx.status = TransactionSendState::Unsent;
+        tx.transaction = Some(Arc::clone(&transaction));
+    }
+
+    let mut ev =
+        TransactionEvent::ExecuteComplete { transaction }.with_cannon_ctx(ctx, Arc::clone(tx_id));
+    ev.agent = agent_id;
+    ev.emit(ctx);
+}
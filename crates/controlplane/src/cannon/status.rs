@@ -0,0 +1,189 @@
+use std::{
+    collections::BTreeMap,
+    fmt::Write,
+    sync::{
+        Arc,
+        atomic::{AtomicU8, AtomicU64, Ordering},
+    },
+};
+
+use serde::{Deserialize, Serialize};
+use snops_common::state::{CannonId, EnvId, TransactionSendState};
+
+use super::{
+    history::TransactionOutcome, limit::CannonLoadInfo, sink::TxSink, source::TxSource,
+    tracker::TransactionSchedule,
+};
+
+/// Labels of every `TransactionSendState`
+pub const SEND_STATE_LABELS: [&str; 4] = ["authorized", "executing", "unsent", "broadcasted"];
+
+/// Whether a cannon is processing its transactions
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CannonStatus {
+    /// Executing and broadcasting transactions
+    Running,
+    /// Accepting transactions, but not executing or broadcasting them
+    Paused,
+    /// Finishing tracked transactions, and rejecting new ones
+    Draining,
+}
+
+/// A cannon's status, shared between the instance and its execution context
+#[derive(Debug, Default)]
+pub struct SharedStatus(AtomicU8);
+
+impl SharedStatus {
+    pub fn get(&self) -> CannonStatus {
+        match self.0.load(Ordering::Relaxed) {
+            1 => CannonStatus::Paused,
+            2 => CannonStatus::Draining,
+            _ => CannonStatus::Running,
+        }
+    }
+
+    pub fn set(&self, status: CannonStatus) {
+        self.0.store(status as u8, Ordering::Relaxed);
+    }
+}
+
+/// Counts of the transactions a cannon has seen included in blocks
+#[derive(Debug, Default)]
+pub struct BlockOutcomes {
+    accepted: AtomicU64,
+    rejected: AtomicU64,
+    aborted: AtomicU64,
+}
+
+impl BlockOutcomes {
+    /// Count a finished transaction, ignoring outcomes that did not come from a
+    /// block
+    pub fn record(&self, outcome: TransactionOutcome) {
+        let counter = match outcome {
+            TransactionOutcome::Accepted => &self.accepted,
+            TransactionOutcome::Rejected => &self.rejected,
+            TransactionOutcome::Aborted => &self.aborted,
+            _ => return,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Write the counts as samples of the `snops_cannon_transactions_total`
+    /// prometheus counter
+    pub fn write_metrics(&self, out: &mut String, env_id: EnvId, cannon_id: CannonId) {
+        let counts = self.counts();
+        for (outcome, count) in [
+            (TransactionOutcome::Accepted, counts.accepted),
+            (TransactionOutcome::Rejected, counts.rejected),
+            (TransactionOutcome::Aborted, counts.aborted),
+        ] {
+            let _ = writeln!(
+                out,
+                "snops_cannon_transactions_total{{env_id=\"{env_id}\",cannon_id=\"{cannon_id}\",outcome=\"{outcome}\"}} {count}"
+            );
+        }
+    }
+
+    pub fn counts(&self) -> BlockOutcomeCounts {
+        BlockOutcomeCounts {
+            accepted: self.accepted.load(Ordering::Relaxed),
+            rejected: self.rejected.load(Ordering::Relaxed),
+            aborted: self.aborted.load(Ordering::Relaxed),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct BlockOutcomeCounts {
+    pub accepted: u64,
+    pub rejected: u64,
+    pub aborted: u64,
+}
+
+/// Overview of a cannon and its transaction queue
+#[derive(Debug, Serialize)]
+pub struct CannonInfo {
+    pub id: CannonId,
+    pub status: CannonStatus,
+    /// Whether a draining cannon has no transactions left
+    pub drained: bool,
+    pub received_txs: u64,
+    pub fired_txs: usize,
+    /// Number of tracked transactions in each send state
+    pub transactions: BTreeMap<&'static str, usize>,
+    /// Number of transactions accepted, rejected, or aborted by the network
+    /// since the control plane started
+    pub outcomes: BlockOutcomeCounts,
+    /// Work the cannon has started or is holding back for its limits
+    pub load: CannonLoadInfo,
+}
+
+/// A cannon's overview and configuration
+#[derive(Debug, Serialize)]
+pub struct CannonDetails {
+    #[serde(flatten)]
+    pub info: CannonInfo,
+    pub source: TxSource,
+    pub sink: TxSink,
+}
+
+/// A transaction tracked by a cannon
+#[derive(Debug, Serialize)]
+pub struct TransactionInfo {
+    pub id: Arc<String>,
+    pub index: u64,
+    pub status: TransactionSendState,
+    #[serde(flatten)]
+    pub schedule: TransactionSchedule,
+    pub attempts: u32,
+    pub has_authorization: bool,
+    pub has_transaction: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TransactionsQuery {
+    /// Only include transactions with this send state label
+    pub status: Option<String>,
+    #[serde(default)]
+    pub offset: usize,
+    #[serde(default = "default_page_limit")]
+    pub limit: usize,
+}
+
+fn default_page_limit() -> usize {
+    100
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FlushQuery {
+    /// Only flush transactions with this send state label
+    pub status: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    #[test]
+    fn block_outcome_metrics() {
+        let outcomes = BlockOutcomes::default();
+        outcomes.record(TransactionOutcome::Accepted);
+        outcomes.record(TransactionOutcome::Accepted);
+        outcomes.record(TransactionOutcome::Aborted);
+        // outcomes that did not come from a block are not counted
+        outcomes.record(TransactionOutcome::Flushed);
+
+        let mut out = String::new();
+        let id = |s| EnvId::from_str(s).unwrap();
+        outcomes.write_metrics(&mut out, id("alpha"), id("one"));
+        assert_eq!(
+            out,
+            "snops_cannon_transactions_total{env_id=\"alpha\",cannon_id=\"one\",outcome=\"accepted\"} 2\n\
+             snops_cannon_transactions_total{env_id=\"alpha\",cannon_id=\"one\",outcome=\"rejected\"} 0\n\
+             snops_cannon_transactions_total{env_id=\"alpha\",cannon_id=\"one\",outcome=\"aborted\"} 1\n"
+        );
+    }
+}
@@ -1,17 +1,71 @@
 use std::sync::Arc;
 
+use chrono::{DateTime, Utc};
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
 use snops_common::{
     format::PackedUint,
-    state::{Authorization, TransactionSendState},
+    state::{Authorization, CannonId, EnvId, TransactionSendState},
 };
+use tracing::error;
 
-use super::error::CannonError;
-use crate::{db::TxEntry, state::GlobalState};
+use super::{
+    error::CannonError,
+    history::{TransactionHistory, TransactionOutcome, TransactionProgress},
+};
+use crate::{
+    db::{Database, TxEntry},
+    env::cache::NetworkCache,
+    state::GlobalState,
+};
+
+/// How urgently a cannon executes and broadcasts a transaction
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TransactionPriority {
+    Low,
+    #[default]
+    Normal,
+    High,
+}
+
+/// When a cannon should get to a transaction, relative to the other
+/// transactions it tracks
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct TransactionSchedule {
+    pub priority: TransactionPriority,
+    /// The authorization is aborted if it has not been executed by this time.
+    /// Executed transactions are broadcasted even after the deadline, as the
+    /// deadline only orders their broadcasts.
+    pub deadline: Option<DateTime<Utc>>,
+    /// Transactions that must be confirmed before the authorization is
+    /// executed
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub after: Vec<Arc<String>>,
+}
+
+impl TransactionSchedule {
+    pub fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        self.deadline.is_some_and(|deadline| deadline <= now)
+    }
+}
+
+/// Whether the transactions an authorization depends on were confirmed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DependencyState {
+    Ready,
+    /// A dependency is still tracked by the cannon
+    Waiting,
+    /// A dependency finished without being confirmed
+    Failed(Arc<String>, TransactionOutcome),
+}
 
 #[derive(Debug, Clone)]
 pub struct TransactionTracker {
     /// Index of the transaction, used for ordering
     pub index: u64,
+    /// Priority and deadline of the transaction
+    pub schedule: TransactionSchedule,
     /// Optional transaction authorization. Must be present if transaction
     /// is None.
     pub authorization: Option<Arc<Authorization>>,
@@ -62,6 +116,50 @@ impl TransactionTracker {
         Ok(state.db.tx_status.save(key, &status)?)
     }
 
+    /// Check the transactions an authorization depends on. Dependencies that
+    /// are neither tracked nor in the cannon's history were pruned from the
+    /// history, and only count as confirmed while their block is in the env's
+    /// network cache. Otherwise the authorization waits for its deadline.
+    ///
+    /// This must not be called while holding a reference into `transactions`.
+    pub fn dependency_state(
+        db: &Database,
+        network_cache: Option<&NetworkCache>,
+        transactions: &DashMap<Arc<String>, TransactionTracker>,
+        (env_id, cannon_id): (EnvId, CannonId),
+        after: &[Arc<String>],
+    ) -> DependencyState {
+        // tracked dependencies are checked first, as they need no reads
+        if after.iter().any(|dep| transactions.contains_key(dep)) {
+            return DependencyState::Waiting;
+        }
+
+        for dep in after {
+            match db.tx_history.restore(&(env_id, cannon_id, Arc::clone(dep))) {
+                Ok(Some(history)) if history.outcome.is_confirmed() => {}
+                Ok(Some(history)) => {
+                    return DependencyState::Failed(Arc::clone(dep), history.outcome);
+                }
+                Ok(None) if network_cache.is_some_and(|cache| cache.has_transaction(dep)) => {}
+                Ok(None) => return DependencyState::Waiting,
+                Err(e) => {
+                    error!("cannon {env_id}.{cannon_id} failed to restore history for {dep}: {e}");
+                    return DependencyState::Waiting;
+                }
+            }
+        }
+        DependencyState::Ready
+    }
+
+    /// Write the transaction tracker's schedule to the store
+    pub fn write_schedule(
+        state: &GlobalState,
+        key: &TxEntry,
+        schedule: &TransactionSchedule,
+    ) -> Result<(), CannonError> {
+        Ok(state.db.tx_schedule.save(key, schedule)?)
+    }
+
     /// Write the transaction tracker's authorization to the store
     pub fn write_auth(
         state: &GlobalState,
@@ -84,6 +182,7 @@ impl TransactionTracker {
     pub fn write(&self, state: &GlobalState, key: &TxEntry) -> Result<(), CannonError> {
         Self::write_index(state, key, self.index)?;
         Self::write_status(state, key, self.status)?;
+        Self::write_schedule(state, key, &self.schedule)?;
         if let Some(auth) = self.authorization.as_deref() {
             Self::write_auth(state, key, auth)?;
         }
@@ -93,6 +192,62 @@ impl TransactionTracker {
         Ok(())
     }
 
+    /// Update the agents and timings recorded for the transaction
+    pub fn update_progress(
+        state: &GlobalState,
+        key: &TxEntry,
+        update: impl FnOnce(&mut TransactionProgress),
+    ) -> Result<(), CannonError> {
+        let mut progress = state.db.tx_progress.restore(key)?.unwrap_or_default();
+        update(&mut progress);
+        Ok(state.db.tx_progress.save(key, &progress)?)
+    }
+
+    /// Move the transaction tracker from the store into the cannon's history
+    pub fn archive(
+        &self,
+        state: &GlobalState,
+        key: &TxEntry,
+        outcome: TransactionOutcome,
+        block_hash: Option<String>,
+    ) -> Result<(), CannonError> {
+        let mut progress = state.db.tx_progress.restore(key)?.unwrap_or_default();
+
+        // attempts are cleared once an authorization is executed, so they only
+        // count broadcasts for executed transactions
+        let attempts = Self::get_attempts(state, key);
+        let broadcast_attempts = match self.status {
+            TransactionSendState::Authorized | TransactionSendState::Executing(_) => {
+                progress.execute_attempts = attempts;
+                0
+            }
+            _ => attempts,
+        };
+
+        let block_height = block_hash.as_deref().and_then(|hash| {
+            state
+                .env_network_cache
+                .get(&key.0)
+                .and_then(|cache| cache.height_and_hash.get_by_right(hash).copied())
+        });
+
+        let finished_at = Utc::now();
+        state.db.tx_history.save(
+            key,
+            &TransactionHistory {
+                index: self.index,
+                outcome,
+                progress,
+                broadcast_attempts,
+                block_hash,
+                block_height,
+                finished_at,
+            },
+        )?;
+        state.history.insert(key, finished_at);
+        Self::delete(state, key)
+    }
+
     /// Remove the transaction tracker from the store
     pub fn delete(state: &GlobalState, key: &TxEntry) -> Result<(), CannonError> {
         state.db.tx_index.delete(key)?;
@@ -100,6 +255,124 @@ impl TransactionTracker {
         state.db.tx_status.delete(key)?;
         state.db.tx_auths.delete(key)?;
         state.db.tx_blobs.delete(key)?;
+        state.db.tx_progress.delete(key)?;
+        state.db.tx_schedule.delete(key)?;
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use snops_common::{db::Database as _, state::LatestBlockInfo};
+
+    use super::*;
+
+    fn ids() -> (EnvId, CannonId) {
+        (
+            EnvId::from_str("env").unwrap(),
+            CannonId::from_str("cannon").unwrap(),
+        )
+    }
+
+    fn tx(id: &str) -> Arc<String> {
+        Arc::new(id.to_owned())
+    }
+
+    fn tracker(after: &[&str]) -> TransactionTracker {
+        TransactionTracker {
+            index: 0,
+            schedule: TransactionSchedule {
+                after: after.iter().map(|id| tx(id)).collect(),
+                ..Default::default()
+            },
+            authorization: None,
+            transaction: None,
+            status: TransactionSendState::Authorized,
+        }
+    }
+
+    /// Save a history entry the way a tracker archives it
+    fn archive(db: &Database, tx_id: &str, outcome: TransactionOutcome) {
+        let (env_id, cannon_id) = ids();
+        let history = TransactionHistory {
+            index: 0,
+            outcome,
+            progress: TransactionProgress::default(),
+            broadcast_attempts: 0,
+            block_hash: None,
+            block_height: None,
+            finished_at: Utc::now(),
+        };
+        db.tx_history
+            .save(&(env_id, cannon_id, tx(tx_id)), &history)
+            .unwrap();
+    }
+
+    #[test]
+    fn dependency_state_follows_the_dependencies() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Database::open(dir.path()).unwrap();
+        let transactions = DashMap::new();
+        let state = |after: &[&str], cache: Option<&NetworkCache>| {
+            let after = after.iter().map(|id| tx(id)).collect::<Vec<_>>();
+            TransactionTracker::dependency_state(&db, cache, &transactions, ids(), &after)
+        };
+
+        assert_eq!(state(&[], None), DependencyState::Ready);
+
+        // tracked dependencies are waited for
+        transactions.insert(tx("tracked"), tracker(&[]));
+        assert_eq!(state(&["tracked"], None), DependencyState::Waiting);
+
+        // confirmed dependencies are ready, the others failed
+        archive(&db, "accepted", TransactionOutcome::Accepted);
+        archive(&db, "recorded", TransactionOutcome::Recorded);
+        archive(&db, "rejected", TransactionOutcome::Rejected);
+        assert_eq!(
+            state(&["accepted", "recorded"], None),
+            DependencyState::Ready
+        );
+        assert_eq!(
+            state(&["accepted", "rejected"], None),
+            DependencyState::Failed(tx("rejected"), TransactionOutcome::Rejected)
+        );
+        // a tracked dependency is waited for before a failure is reported
+        assert_eq!(
+            state(&["rejected", "tracked"], None),
+            DependencyState::Waiting
+        );
+    }
+
+    #[test]
+    fn unknown_dependencies_need_a_block() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Database::open(dir.path()).unwrap();
+        let transactions = DashMap::new();
+        let after = [tx("pruned")];
+
+        // a dependency that left the history is not assumed to be confirmed
+        let empty = NetworkCache::default();
+        for cache in [None, Some(&empty)] {
+            assert_eq!(
+                TransactionTracker::dependency_state(&db, cache, &transactions, ids(), &after),
+                DependencyState::Waiting
+            );
+        }
+
+        let mut cache = NetworkCache::default();
+        cache.add_block(
+            LatestBlockInfo {
+                height: 1,
+                block_hash: "ab1".to_owned(),
+                ..Default::default()
+            },
+            vec![Arc::from("pruned")],
+        );
+        assert_eq!(
+            TransactionTracker::dependency_state(&db, Some(&cache), &transactions, ids(), &after),
+            DependencyState::Ready
+        );
+    }
+}
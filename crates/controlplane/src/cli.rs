@@ -7,7 +7,7 @@ use std::{
 
 #[cfg(any(feature = "clipages", feature = "mangen"))]
 use clap::CommandFactory;
-use clap::Parser;
+use clap::{Args, Parser};
 use url::Url;
 
 #[derive(Debug, Parser)]
@@ -44,6 +44,27 @@ pub struct Cli {
     /// must contain http:// or https://
     pub hostname: Option<String>,
 
+    /// Number of finished transactions to keep in each cannon's history
+    #[arg(long, env = "TX_HISTORY_LIMIT", default_value_t = 10_000)]
+    pub tx_history_limit: usize,
+
+    /// Seconds to keep the transaction history of a deleted env after its
+    /// last transaction finished
+    #[arg(long, env = "TX_HISTORY_RETENTION", default_value_t = 86_400)]
+    pub tx_history_retention: u64,
+
+    /// Maximum size in MiB of the transactions cached for cannons with the
+    /// proof cache enabled. 0 disables the cache
+    #[arg(long, env = "PROOF_CACHE_SIZE", default_value_t = 256)]
+    pub proof_cache_size: u64,
+
+    /// Where generated storage and binaries are kept for agents to download
+    #[arg(long, env = "STORAGE_BACKEND", default_value_t = StorageBackendKind::Local)]
+    pub storage_backend: StorageBackendKind,
+
+    #[clap(flatten)]
+    pub s3: S3Args,
+
     #[cfg(any(feature = "clipages", feature = "mangen"))]
     #[clap(subcommand)]
     pub command: Commands,
@@ -91,6 +112,77 @@ impl Cli {
     }
 }
 
+/// Options for the S3-compatible storage backend
+#[derive(Debug, Clone, Args)]
+pub struct S3Args {
+    /// S3-compatible endpoint, e.g. `https://s3.us-east-1.amazonaws.com` or
+    /// `http://localhost:9000` for MinIO
+    #[arg(long = "s3-endpoint", env = "S3_ENDPOINT")]
+    pub endpoint: Option<Url>,
+
+    /// Bucket storage and binaries are uploaded to
+    #[arg(long = "s3-bucket", env = "S3_BUCKET")]
+    pub bucket: Option<String>,
+
+    /// Region used when signing requests
+    #[arg(long = "s3-region", env = "S3_REGION", default_value = "us-east-1")]
+    pub region: String,
+
+    #[arg(long = "s3-access-key", env = "S3_ACCESS_KEY", hide_env_values = true)]
+    pub access_key: Option<String>,
+
+    #[arg(long = "s3-secret-key", env = "S3_SECRET_KEY", hide_env_values = true)]
+    pub secret_key: Option<String>,
+
+    /// Prefix prepended to every object key, allowing several control planes
+    /// to share a bucket
+    #[arg(long = "s3-prefix", env = "S3_PREFIX", default_value = "")]
+    pub prefix: String,
+
+    /// Use virtual-hosted style addressing (`bucket.host/key`) instead of
+    /// path style (`host/bucket/key`)
+    #[arg(long = "s3-virtual-host", env = "S3_VIRTUAL_HOST")]
+    pub virtual_host: bool,
+
+    /// How long presigned download URLs are valid for, in seconds
+    #[arg(
+        long = "s3-presign-expiry",
+        env = "S3_PRESIGN_EXPIRY",
+        default_value_t = 3600
+    )]
+    pub presign_expiry: u32,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+pub enum StorageBackendKind {
+    /// Files are kept in the control plane's data directory
+    #[default]
+    Local,
+    /// Files are uploaded to an S3-compatible bucket
+    S3,
+}
+
+impl Display for StorageBackendKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StorageBackendKind::Local => f.write_str("local"),
+            StorageBackendKind::S3 => f.write_str("s3"),
+        }
+    }
+}
+
+impl FromStr for StorageBackendKind {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "local" => StorageBackendKind::Local,
+            "s3" => StorageBackendKind::S3,
+            _ => return Err("expected one of 'local', 's3'"),
+        })
+    }
+}
+
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
 pub enum PrometheusLocation {
     Internal,
@@ -7,6 +7,10 @@ use snops_common::{
 };
 
 use crate::{
+    cannon::{
+        history::{TransactionHistory, TransactionProgress},
+        tracker::TransactionSchedule,
+    },
     persist::{PersistEnv, PersistStorage},
     state::Agent,
 };
@@ -36,8 +40,21 @@ pub struct Database {
     /// Transactions with lower indices are prioritized for execution and
     /// broadcast.
     pub(crate) tx_index: DbTree<TxEntry, PackedUint>,
+    /// Priority and deadline of transactions, used for ordering ahead of the
+    /// index
+    pub(crate) tx_schedule: DbTree<TxEntry, TransactionSchedule>,
     /// Number of attempts for the transaction's current state
     pub(crate) tx_attempts: DbTree<TxEntry, PackedUint>,
+    /// Agents and timings of transactions that are being tracked
+    pub(crate) tx_progress: DbTree<TxEntry, TransactionProgress>,
+    /// Transactions that cannons have finished with
+    pub(crate) tx_history: DbTree<TxEntry, TransactionHistory>,
+    /// Read offsets of files followed by cannon intakes, keyed by the file's
+    /// path in the storage directory
+    pub(crate) intake_offsets: DbTree<TxEntry, PackedUint>,
+    /// Transactions proven by compute agents, keyed by a hash of the
+    /// authorization and the ledger state they were proven against
+    pub(crate) proof_cache: DbTree<String, serde_json::Value>,
 }
 
 impl DatabaseTrait for Database {
@@ -50,7 +67,12 @@ impl DatabaseTrait for Database {
         let tx_blobs = DbTree::new(db.open_tree(b"v2/tx_blobs")?);
         let tx_status = DbTree::new(db.open_tree(b"v2/tx_status")?);
         let tx_index = DbTree::new(db.open_tree(b"v2/tx_index")?);
+        let tx_schedule = DbTree::new(db.open_tree(b"v2/tx_schedule")?);
         let tx_attempts = DbTree::new(db.open_tree(b"v2/tx_attempts")?);
+        let tx_progress = DbTree::new(db.open_tree(b"v2/tx_progress")?);
+        let tx_history = DbTree::new(db.open_tree(b"v2/tx_history")?);
+        let intake_offsets = DbTree::new(db.open_tree(b"v2/intake_offsets")?);
+        let proof_cache = DbTree::new(db.open_tree(b"v2/proof_cache")?);
 
         Ok(Self {
             db,
@@ -61,7 +83,12 @@ impl DatabaseTrait for Database {
             tx_blobs,
             tx_status,
             tx_index,
+            tx_schedule,
             tx_attempts,
+            tx_progress,
+            tx_history,
+            intake_offsets,
+            proof_cache,
         })
     }
 }
@@ -190,10 +190,20 @@ pub enum ReconcileError {
     EnvNotFound(EnvId),
     #[error("expected internal agent peer for node with key {key}")]
     ExpectedInternalAgentPeer { key: NodeKey },
+    #[error("agent `{0}` is not running a node in env `{1}`")]
+    AgentNotInEnv(AgentId, EnvId),
+    #[error("failed to transfer checkpoint {height} to agent `{agent}`: {error}")]
+    CheckpointTransfer {
+        agent: AgentId,
+        height: u32,
+        error: String,
+    },
 }
 
 impl_into_status_code!(ReconcileError, |value| match value {
-    EnvNotFound(_) | ExpectedInternalAgentPeer { .. } => StatusCode::NOT_FOUND,
+    EnvNotFound(_) | ExpectedInternalAgentPeer { .. } | AgentNotInEnv(_, _) =>
+        StatusCode::NOT_FOUND,
+    CheckpointTransfer { .. } => StatusCode::INTERNAL_SERVER_ERROR,
 });
 
 #[derive(Debug, Error, AsRefStr)]
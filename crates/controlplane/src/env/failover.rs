@@ -0,0 +1,172 @@
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use snops_common::{
+    events::{AgentEvent, EventHelpers},
+    state::{AgentId, EnvId, NodeKey},
+};
+use tracing::{info, warn};
+
+use super::EnvPeer;
+use crate::{
+    schema::nodes::FailoverPolicy,
+    state::{EmitEvent, GlobalState},
+};
+
+/// A task that moves nodes off of agents that have been disconnected for
+/// longer than their environment's failover grace period.
+pub async fn failover_task(state: Arc<GlobalState>) {
+    // last failed failover attempt for each agent, used to avoid re-attempting
+    // (and re-emitting errors) more than once per grace period
+    let mut failed_attempts = HashMap::<AgentId, Instant>::new();
+
+    loop {
+        for (env_id, agent_id, node_key, grace_period) in get_failover_candidates(&state) {
+            if failed_attempts
+                .get(&agent_id)
+                .is_some_and(|t| t.elapsed() < grace_period)
+            {
+                continue;
+            }
+
+            let Some(env) = state.get_env(env_id) else {
+                continue;
+            };
+
+            info!("{env_id}: agent {agent_id} has been disconnected, failing over node {node_key}");
+
+            match env.reassign_agent_node(&state, agent_id).await {
+                Ok((_, to)) => {
+                    failed_attempts.remove(&agent_id);
+                    info!("{env_id}: node {node_key} failed over from agent {agent_id} to {to}");
+                    AgentEvent::NodeFailover { to }
+                        .with_agent_id(agent_id)
+                        .with_node_key(node_key)
+                        .with_env_id(env_id)
+                        .emit(&state);
+                }
+                Err(e) => {
+                    failed_attempts.insert(agent_id, Instant::now());
+                    warn!("{env_id}: failed to fail over node {node_key}: {e}");
+                    AgentEvent::NodeFailoverFailed {
+                        reason: e.to_string(),
+                    }
+                    .with_agent_id(agent_id)
+                    .with_node_key(node_key)
+                    .with_env_id(env_id)
+                    .emit(&state);
+                }
+            }
+        }
+
+        // forget about agents that have reconnected or were moved
+        failed_attempts.retain(|id, _| {
+            state
+                .pool
+                .get(id)
+                .is_some_and(|a| !a.is_connected() && a.env().is_some())
+        });
+
+        tokio::time::sleep(Duration::from_secs(5)).await;
+    }
+}
+
+/// Get a list of env nodes whose agents have been disconnected for longer than
+/// the env's failover grace period
+fn get_failover_candidates(state: &GlobalState) -> Vec<(EnvId, AgentId, NodeKey, Duration)> {
+    let mut candidates = vec![];
+
+    for env in &state.envs {
+        let Some(policy) = env.failover else {
+            continue;
+        };
+
+        candidates.extend(
+            expired_nodes(policy, env.node_peers.iter(), |agent_id| {
+                state.pool.get(&agent_id).and_then(|a| a.disconnected_for())
+            })
+            .into_iter()
+            .map(|(agent_id, key, grace_period)| (env.id, agent_id, key, grace_period)),
+        );
+    }
+
+    candidates
+}
+
+/// Find the internal nodes whose agents have been disconnected for at least
+/// the policy's grace period
+fn expired_nodes<'a>(
+    policy: FailoverPolicy,
+    peers: impl Iterator<Item = (&'a NodeKey, &'a EnvPeer)>,
+    disconnected_for: impl Fn(AgentId) -> Option<Duration>,
+) -> Vec<(AgentId, NodeKey, Duration)> {
+    let grace_period = Duration::from_secs(policy.grace_period as u64);
+
+    peers
+        .filter_map(|(key, peer)| match peer {
+            EnvPeer::Internal(agent_id) => Some((*agent_id, key)),
+            EnvPeer::External(_) => None,
+        })
+        .filter(|(agent_id, _)| {
+            disconnected_for(*agent_id).is_some_and(|elapsed| elapsed >= grace_period)
+        })
+        .map(|(agent_id, key)| (agent_id, key.clone(), grace_period))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    fn agent(id: &str) -> AgentId {
+        AgentId::from_str(id).unwrap()
+    }
+
+    fn key(key: &str) -> NodeKey {
+        NodeKey::from_str(key).unwrap()
+    }
+
+    #[test]
+    fn expired_nodes_respects_grace_period() {
+        let policy = FailoverPolicy { grace_period: 60 };
+        let peers = [
+            (key("validator/0"), EnvPeer::Internal(agent("online"))),
+            (key("validator/1"), EnvPeer::Internal(agent("recent"))),
+            (key("validator/2"), EnvPeer::Internal(agent("expired"))),
+            (key("client/ext"), EnvPeer::External(key("client/ext"))),
+        ];
+
+        let expired = expired_nodes(policy, peers.iter().map(|(k, p)| (k, p)), |id| {
+            if id == agent("recent") {
+                Some(Duration::from_secs(59))
+            } else if id == agent("expired") {
+                Some(Duration::from_secs(60))
+            } else {
+                None
+            }
+        });
+
+        assert_eq!(
+            expired,
+            vec![(
+                agent("expired"),
+                key("validator/2"),
+                Duration::from_secs(60)
+            )]
+        );
+    }
+
+    #[test]
+    fn expired_nodes_ignores_unknown_agents() {
+        let policy = FailoverPolicy { grace_period: 0 };
+        let peers = [(key("validator/0"), EnvPeer::Internal(agent("gone")))];
+
+        let expired = expired_nodes(policy, peers.iter().map(|(k, p)| (k, p)), |_| None);
+        assert!(expired.is_empty());
+    }
+}
@@ -33,7 +33,7 @@ use crate::{
     persist::PersistEnv,
     schema::{
         ItemDocument,
-        nodes::{ExternalNode, Node},
+        nodes::{ExternalNode, FailoverPolicy, Node},
         storage::LoadedStorage,
     },
     state::{Agent, GlobalState},
@@ -41,6 +41,7 @@ use crate::{
 
 pub mod cache;
 pub mod error;
+pub mod failover;
 pub mod set;
 
 #[derive(Debug)]
@@ -57,6 +58,8 @@ pub struct Environment {
     pub sinks: HashMap<TxPipeId, Arc<TransactionSink>>,
     /// Map of cannon ids to their cannon instances
     pub cannons: HashMap<CannonId, Arc<CannonInstance>>,
+    /// Policy for moving nodes off of disconnected agents
+    pub failover: Option<FailoverPolicy>,
 }
 
 /// The effective test state of a node.
@@ -133,6 +136,7 @@ impl Environment {
         };
 
         let mut network = NetworkId::default();
+        let mut failover = None;
 
         let mut pending_cannons = HashMap::new();
         let mut agents_to_inventory = IndexSet::<AgentId>::default();
@@ -145,6 +149,9 @@ impl Environment {
                 TxSource {
                     query: QueryTarget::Node(NodeTargets::ALL),
                     compute: ComputeTarget::Agent { labels: None },
+                    intake: None,
+                    dedupe_window: None,
+                    proof_cache: false,
                 },
                 TxSink {
                     target: Some(NodeTargets::ALL),
@@ -153,6 +160,9 @@ impl Environment {
                     broadcast_timeout: TxSink::default_retry_timeout(),
                     authorize_attempts: Some(3),
                     authorize_timeout: TxSink::default_retry_timeout(),
+                    max_executing: None,
+                    broadcast_rate: None,
+                    max_unconfirmed: None,
                 },
             ),
         );
@@ -176,6 +186,11 @@ impl Environment {
                     if let Some(n) = nodes.network {
                         network = n;
                     }
+                    // documents without a failover policy keep the policy of the others
+                    failover = match (failover, nodes.failover) {
+                        (Some(a), Some(b)) => Some(a.merge(b)),
+                        (a, b) => a.or(b),
+                    };
 
                     // maps of states and peers that are new to this environment
                     let mut incoming_states = IndexMap::default();
@@ -388,6 +403,7 @@ impl Environment {
             node_states,
             sinks,
             cannons,
+            failover,
         });
 
         if let Err(e) = state.db.envs.save(&env_id, &PersistEnv::from(env.as_ref())) {
@@ -471,6 +487,183 @@ impl Environment {
         Ok(node_map)
     }
 
+    /// Move the node currently delegated to `agent_id` onto another matching
+    /// inventory agent. The node keeps its key, peers, and height request, and
+    /// the checkpoints of the previous agent's ledger are copied to the
+    /// replacement agent when the previous agent is still connected, so the
+    /// node's height requests can be met once it has synced from its peers.
+    /// The node is not moved if the checkpoints can't be copied. The previous
+    /// agent is returned to inventory.
+    ///
+    /// Returns the node key and the id of the replacement agent.
+    pub async fn reassign_agent_node(
+        &self,
+        state: &GlobalState,
+        agent_id: AgentId,
+    ) -> Result<(NodeKey, AgentId), EnvError> {
+        let key = self
+            .get_node_key_by_agent(agent_id)
+            .cloned()
+            .ok_or(ReconcileError::AgentNotInEnv(agent_id, self.id))?;
+        let node = match self.node_states.get(&key).as_deref() {
+            Some(EnvNodeState::Internal(node)) => node.clone(),
+            _ => return Err(ReconcileError::ExpectedInternalAgentPeer { key }.into()),
+        };
+
+        // find a replacement agent that satisfies the node's mode and labels
+        let labels = node.labels.iter().copied().collect::<Vec<_>>();
+        let mask = node.mask(&key, &labels);
+        let Some((new_id, busy)) = get_agent_mappings(BusyMode::Env, state, &labels)
+            .iter()
+            .find_map(|a| a.claim_if_subset(&mask).map(|c| (a.id(), c)))
+        else {
+            return Err(EnvError::Delegation(vec![
+                DelegationError::NoAvailableAgents(key),
+            ]));
+        };
+
+        info!(
+            "{}: moving node {key} from agent {agent_id} to {new_id}",
+            self.id
+        );
+
+        // carry the previous node state over so the height request (and its
+        // increment) is preserved when the env states are resolved
+        let prev_state = state.pool.get(&agent_id).and_then(|a| match a.state() {
+            AgentState::Node(env_id, s) if *env_id == self.id => Some(s.clone()),
+            _ => None,
+        });
+
+        // copy the checkpoints while the replacement agent is still claimed,
+        // before the node is moved to it
+        let checkpoints = self.read_checkpoints(state, agent_id).await;
+        self.write_checkpoints(state, new_id, checkpoints).await?;
+
+        let mut node_peers = self.node_peers.clone();
+        node_peers.insert(key.clone(), EnvPeer::Internal(new_id));
+        let env = Arc::new(self.with_node_peers(node_peers));
+
+        if let Err(e) = state
+            .db
+            .envs
+            .save(&self.id, &PersistEnv::from(env.as_ref()))
+        {
+            error!("failed to save env {} to persistence: {e}", self.id);
+        }
+        // replace the env without resetting the network cache
+        state.envs.insert(self.id, Arc::clone(&env));
+
+        if let Some(prev_state) = prev_state {
+            if let Some(mut agent) = state.pool.get_mut(&new_id) {
+                agent.set_state(AgentState::Node(self.id, prev_state));
+                if let Err(e) = state.db.agents.save(&new_id, &agent) {
+                    error!("failed to save agent {new_id} to the database: {e}");
+                }
+            }
+        }
+
+        state
+            .update_agent_states([(agent_id, AgentState::Inventory)])
+            .await;
+
+        // re-resolve peers for every node, including the replacement agent
+        env.update_all_agents(state, Default::default()).await?;
+
+        // the replacement agent stays claimed until the node has been moved
+        drop(busy);
+
+        Ok((key, new_id))
+    }
+
+    /// Read the checkpoints next to an agent's ledger. Agents that are
+    /// disconnected, or whose node has no checkpoints, have none to transfer.
+    async fn read_checkpoints(
+        &self,
+        state: &GlobalState,
+        agent_id: AgentId,
+    ) -> Vec<(u32, Vec<u8>)> {
+        let Some(client) = state
+            .pool
+            .get(&agent_id)
+            .filter(|a| a.is_connected())
+            .and_then(|a| a.client_owned())
+        else {
+            return vec![];
+        };
+
+        let heights = match client.get_checkpoints().await {
+            Ok(heights) => heights,
+            Err(e) => {
+                warn!(
+                    "{}: failed to list checkpoints of agent {agent_id}: {e}",
+                    self.id
+                );
+                return vec![];
+            }
+        };
+
+        let mut checkpoints = Vec::with_capacity(heights.len());
+        for height in heights {
+            match client.get_checkpoint(height).await {
+                Ok(bytes) => checkpoints.push((height, bytes)),
+                Err(e) => warn!(
+                    "{}: failed to read checkpoint {height} of agent {agent_id}: {e}",
+                    self.id
+                ),
+            }
+        }
+        checkpoints
+    }
+
+    /// Copy checkpoints read from another agent to an agent in this env
+    async fn write_checkpoints(
+        &self,
+        state: &GlobalState,
+        agent_id: AgentId,
+        checkpoints: Vec<(u32, Vec<u8>)>,
+    ) -> Result<(), ReconcileError> {
+        let Some((first, _)) = checkpoints.first() else {
+            return Ok(());
+        };
+        let Some(client) = state.pool.get(&agent_id).and_then(|a| a.client_owned()) else {
+            return Err(ReconcileError::CheckpointTransfer {
+                agent: agent_id,
+                height: *first,
+                error: "agent is disconnected".to_owned(),
+            });
+        };
+
+        for (height, bytes) in checkpoints {
+            client
+                .put_checkpoint(self.id, height, bytes)
+                .await
+                .map_err(|e| ReconcileError::CheckpointTransfer {
+                    agent: agent_id,
+                    height,
+                    error: e.to_string(),
+                })?;
+            trace!(
+                "{}: transferred checkpoint {height} to agent {agent_id}",
+                self.id
+            );
+        }
+        Ok(())
+    }
+
+    /// Create a copy of this environment with a different node map
+    fn with_node_peers(&self, node_peers: BiMap<NodeKey, EnvPeer>) -> Environment {
+        Environment {
+            id: self.id,
+            storage: Arc::clone(&self.storage),
+            network: self.network,
+            node_peers,
+            node_states: self.node_states.clone(),
+            sinks: self.sinks.clone(),
+            cannons: self.cannons.clone(),
+            failover: self.failover,
+        }
+    }
+
     pub async fn cleanup(id: EnvId, state: &GlobalState) -> Result<(), EnvError> {
         // clear the env state
         info!("{id}: Deleting persistence...");
@@ -499,6 +692,16 @@ impl Environment {
         if let Err(e) = state.db.tx_status.delete_with_prefix(&id) {
             error!("{id}: Failed to delete env tx_status persistence: {e}");
         }
+        if let Err(e) = state.db.tx_progress.delete_with_prefix(&id) {
+            error!("{id}: Failed to delete env tx_progress persistence: {e}");
+        }
+        if let Err(e) = state.db.tx_schedule.delete_with_prefix(&id) {
+            error!("{id}: Failed to delete env tx_schedule persistence: {e}");
+        }
+        if let Err(e) = state.db.intake_offsets.delete_with_prefix(&id) {
+            error!("{id}: Failed to delete env intake_offsets persistence: {e}");
+        }
+        // the transaction history is kept for analysis after the env is gone
 
         if let Some(storage) = state.try_unload_storage(env.network, env.storage.id) {
             info!("{id}: Unloaded storage {}", storage.id);
@@ -1,6 +1,7 @@
 use std::{
     collections::{HashMap, HashSet},
     sync::{Arc, Weak, mpsc},
+    time::Duration,
 };
 
 use fixedbitset::FixedBitSet;
@@ -13,7 +14,10 @@ use snops_common::{
 };
 
 use super::{DelegationError, EnvNodeState};
-use crate::state::{Agent, AgentClient, Busy, GlobalState};
+use crate::state::{
+    Agent, Busy, GlobalState,
+    compute::{ComputeLease, ComputeRequester},
+};
 
 pub struct AgentMapping {
     id: AgentId,
@@ -32,7 +36,7 @@ pub enum BusyMode {
 
 impl AgentMapping {
     pub fn new(mode: BusyMode, agent: &Agent, labels: &[Spur]) -> Option<Self> {
-        if !agent.is_inventory() {
+        if !agent.is_inventory() || agent.is_cordoned() {
             return None;
         }
 
@@ -72,6 +76,11 @@ impl AgentMapping {
         })
     }
 
+    /// The id of the mapped agent
+    pub fn id(&self) -> AgentId {
+        self.id
+    }
+
     /// Attempt to atomically claim the agent
     pub fn claim(&self) -> Option<Arc<Busy>> {
         // avoid needlessly upgrading the weak pointer
@@ -147,20 +156,19 @@ fn _find_compute_agent_by_mask<'a, I: Iterator<Item = &'a Agent>>(
     })
 }
 
-/// Find an agent that can compute and has the given labels by checking each
-/// label individually
-pub fn find_compute_agent(
+/// Wait in the compute queue for an agent that has the given labels, giving
+/// up after the timeout
+pub async fn wait_for_compute_agent(
     state: &GlobalState,
+    requester: ComputeRequester,
     labels: &[Spur],
-) -> Option<(AgentId, AgentClient, Arc<Busy>)> {
-    state.pool.iter().find_map(|a| {
-        if !a.can_compute() || a.is_compute_claimed() || !labels.iter().all(|l| a.has_label(*l)) {
-            return None;
-        }
-        let arc = a.make_busy();
-        a.client_owned()
-            .and_then(|c| (Arc::strong_count(&arc) == 2).then_some((a.id(), c, arc)))
-    })
+    timeout: Duration,
+) -> Option<ComputeLease> {
+    let ticket = state.compute.request(&state.pool, requester, labels);
+    tokio::time::timeout(timeout, ticket.granted())
+        .await
+        .ok()
+        .flatten()
 }
 
 /// Given a map of nodes and list of agent mappings, attempt to pair each node
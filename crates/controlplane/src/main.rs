@@ -22,6 +22,7 @@ pub mod persist;
 pub mod schema;
 pub mod server;
 pub mod state;
+pub mod storage_backend;
 
 type ReloadHandler = reload::Handle<EnvFilter, tracing_subscriber::Registry>;
 
@@ -94,6 +95,10 @@ async fn main() {
     let transaction_task = tokio::spawn(state::transactions::tracking_task(Arc::clone(&state)));
     // start the task that manages cache invalidation
     let cache_task = tokio::spawn(env::cache::invalidation_task(Arc::clone(&state)));
+    // start the task that moves nodes off of disconnected agents
+    let failover_task = tokio::spawn(env::failover::failover_task(Arc::clone(&state)));
+    // start the task that hands compute agents to queued authorizations
+    let compute_task = tokio::spawn(state::compute::compute_task(Arc::clone(&state)));
 
     info!("Starting server on {socket_addr}");
     select! {
@@ -109,5 +114,11 @@ async fn main() {
         Err(err) = cache_task => {
             error!("cache invalidation task failed: {err:?}");
         }
+        Err(err) = failover_task => {
+            error!("failover task failed: {err:?}");
+        }
+        Err(err) = compute_task => {
+            error!("compute task failed: {err:?}");
+        }
     }
 }
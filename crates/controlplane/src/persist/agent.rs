@@ -51,7 +51,7 @@ impl DataFormat for AgentFormatHeader {
 impl DataFormat for Agent {
     type Header = AgentFormatHeader;
     const LATEST_HEADER: Self::Header = AgentFormatHeader {
-        version: 1,
+        version: 2,
         addrs: AgentAddrs::LATEST_HEADER,
         node: NodeState::LATEST_HEADER,
         flags: AgentFlags::LATEST_HEADER,
@@ -76,15 +76,16 @@ impl DataFormat for Agent {
         written += self.flags.write_data(writer)?;
         written += self.ports.write_data(writer)?;
         written += self.addrs.write_data(writer)?;
+        written += self.cordoned.write_data(writer)?;
 
         Ok(written)
     }
 
     fn read_data<R: Read>(reader: &mut R, header: &Self::Header) -> Result<Self, DataReadError> {
-        if header.version != Self::LATEST_HEADER.version {
+        if header.version == 0 || header.version > Self::LATEST_HEADER.version {
             return Err(DataReadError::unsupported(
                 "Agent",
-                Self::LATEST_HEADER.version,
+                format!("1 or {}", Self::LATEST_HEADER.version),
                 header.version,
             ));
         }
@@ -107,34 +108,36 @@ impl DataFormat for Agent {
         let flags = reader.read_data(&header.flags)?;
         let ports = reader.read_data(&header.ports)?;
         let addrs = reader.read_data(&header.addrs)?;
+        let cordoned = if header.version > 1 {
+            reader.read_data(&())?
+        } else {
+            false
+        };
 
-        Ok(Agent::from_components(
-            Claims { id, nonce },
-            state,
-            flags,
-            ports,
-            addrs,
-        ))
+        let mut agent = Agent::from_components(Claims { id, nonce }, state, flags, ports, addrs);
+        agent.set_cordoned(cordoned);
+        Ok(agent)
     }
 }
 
 impl DataFormat for AgentFlags {
     type Header = u8;
-    const LATEST_HEADER: Self::Header = 1;
+    const LATEST_HEADER: Self::Header = 2;
 
     fn write_data<W: Write>(&self, writer: &mut W) -> Result<usize, DataWriteError> {
         let mut written = 0;
         written += u8::from(self.mode).write_data(writer)?;
         written += self.labels.write_data(writer)?;
         written += self.local_pk.write_data(writer)?;
+        written += self.compute_slots.write_data(writer)?;
         Ok(written)
     }
 
     fn read_data<R: Read>(reader: &mut R, header: &Self::Header) -> Result<Self, DataReadError> {
-        if *header != Self::LATEST_HEADER {
+        if *header == 0 || *header > Self::LATEST_HEADER {
             return Err(DataReadError::unsupported(
                 "AgentFlags",
-                Self::LATEST_HEADER,
+                format!("1 to {}", Self::LATEST_HEADER),
                 *header,
             ));
         }
@@ -143,6 +146,11 @@ impl DataFormat for AgentFlags {
             mode: AgentModeOptions::from(u8::read_data(reader, &())?),
             labels: reader.read_data(&())?,
             local_pk: reader.read_data(&())?,
+            compute_slots: if *header > 1 {
+                reader.read_data(&())?
+            } else {
+                1
+            },
         })
     }
 }
@@ -205,6 +213,7 @@ mod test {
             mode: AgentModeOptions::from(0u8),
             labels: [INTERN.get_or_intern("hello")].into_iter().collect(),
             local_pk: true,
+            compute_slots: 1,
         },
         [
             AgentFlags::LATEST_HEADER.to_byte_vec()?,
@@ -212,6 +221,24 @@ mod test {
             PackedUint(1).to_byte_vec()?,
             "hello".to_string().to_byte_vec()?,
             true.to_byte_vec()?,
+            1u32.to_byte_vec()?,
+        ].concat()
+    );
+
+    case!(agent_flags_slots,
+        AgentFlags,
+        AgentFlags {
+            mode: AgentModeOptions::from(8u8),
+            labels: Default::default(),
+            local_pk: false,
+            compute_slots: 4,
+        },
+        [
+            AgentFlags::LATEST_HEADER.to_byte_vec()?,
+            8u8.to_byte_vec()?,
+            PackedUint(0).to_byte_vec()?,
+            false.to_byte_vec()?,
+            4u32.to_byte_vec()?,
         ].concat()
     );
 
@@ -253,8 +280,9 @@ mod test {
                 mode: AgentModeOptions::from(0u8),
                 labels: [INTERN.get_or_intern("hello")].into_iter().collect(),
                 local_pk: true,
+                compute_slots: 1,
             },
-            Some(PortConfig { node: 0, bft: 1, rest: 2, metrics: 3 }),
+            Some(PortConfig { node: 0, bft: 1, rest: 2, metrics: 3, content: 0 }),
             Some(AgentAddrs {
                 external: Some("1.2.3.4".parse()?),
                 internal: vec!["127.0.0.1".parse()?],
@@ -270,12 +298,14 @@ mod test {
                 mode: AgentModeOptions::from(0u8),
                 labels: [INTERN.get_or_intern("hello")].into_iter().collect(),
                 local_pk: true,
+                compute_slots: 1,
             }.to_byte_vec()?,
-            Some(PortConfig { node: 0, bft: 1, rest: 2, metrics: 3 }).to_byte_vec()?,
+            Some(PortConfig { node: 0, bft: 1, rest: 2, metrics: 3, content: 0 }).to_byte_vec()?,
             Some(AgentAddrs {
                 external: Some("1.2.3.4".parse()?),
                 internal: vec!["127.0.0.1".parse()?],
             }).to_byte_vec()?,
+            false.to_byte_vec()?, // cordoned
         ].concat()
     );
 
@@ -300,8 +330,9 @@ mod test {
                 mode: AgentModeOptions::from(5u8),
                 labels: Default::default(),
                 local_pk: true,
+                compute_slots: 1,
             },
-            Some(PortConfig { node: 3, bft: 2, rest: 1, metrics: 0 }),
+            Some(PortConfig { node: 3, bft: 2, rest: 1, metrics: 0, content: 4 }),
             Some(AgentAddrs {
                 external: None,
                 internal: vec![],
@@ -328,12 +359,53 @@ mod test {
                 mode: AgentModeOptions::from(5u8),
                 labels: Default::default(),
                 local_pk: true,
+                compute_slots: 1,
             }.to_byte_vec()?,
-            Some(PortConfig { node: 3, bft: 2, rest: 1, metrics: 0 }).to_byte_vec()?,
+            Some(PortConfig { node: 3, bft: 2, rest: 1, metrics: 0, content: 4 }).to_byte_vec()?,
             Some(AgentAddrs {
                 external: None,
                 internal: vec![],
             }).to_byte_vec()?,
+            false.to_byte_vec()?, // cordoned
+        ].concat()
+    );
+
+    case!(agent_cordoned,
+        crate::state::Agent,
+        {
+            let mut agent = crate::state::Agent::from_components(
+                crate::server::jwt::Claims {
+                    id: "agent".parse()?,
+                    nonce: 2,
+                },
+                AgentState::Inventory,
+                AgentFlags {
+                    mode: AgentModeOptions::from(0u8),
+                    labels: Default::default(),
+                    local_pk: false,
+                    compute_slots: 1,
+                },
+                None,
+                None,
+            );
+            agent.set_cordoned(true);
+            agent
+        },
+        [
+            AgentFormatHeader::LATEST_HEADER.to_byte_vec()?,
+            Agent::LATEST_HEADER.to_byte_vec()?,
+            "agent".to_string().to_byte_vec()?, // agent id
+            2u16.to_byte_vec()?, // nonce
+            0u8.to_byte_vec()?, // inventory state
+            AgentFlags {
+                mode: AgentModeOptions::from(0u8),
+                labels: Default::default(),
+                local_pk: false,
+                compute_slots: 1,
+            }.to_byte_vec()?,
+            None::<PortConfig>.to_byte_vec()?,
+            None::<AgentAddrs>.to_byte_vec()?,
+            true.to_byte_vec()?, // cordoned
         ].concat()
     );
 }
@@ -15,6 +15,7 @@ use crate::{
         error::{EnvError, PrepareError},
         prepare_cannons,
     },
+    schema::nodes::FailoverPolicy,
     state::GlobalState,
 };
 
@@ -25,6 +26,7 @@ pub struct PersistEnvFormatHeader {
     tx_source: DataHeaderOf<TxSource>,
     tx_sink: DataHeaderOf<TxSink>,
     network: DataHeaderOf<NetworkId>,
+    failover: DataHeaderOf<FailoverPolicy>,
 }
 
 pub struct PersistEnv {
@@ -35,6 +37,8 @@ pub struct PersistEnv {
     pub nodes: Vec<(NodeKey, PersistNode)>,
     /// Loaded cannon configs in this env
     pub cannons: Vec<(CannonId, TxSource, TxSink)>,
+    /// Policy for moving nodes off of disconnected agents
+    pub failover: Option<FailoverPolicy>,
 }
 
 impl From<&Environment> for PersistEnv {
@@ -75,6 +79,7 @@ impl From<&Environment> for PersistEnv {
                 .iter()
                 .map(|(id, cannon)| (*id, cannon.source.clone(), cannon.sink.clone()))
                 .collect(),
+            failover: value.failover,
         }
     }
 }
@@ -145,13 +150,14 @@ impl PersistEnv {
             node_states: initial_nodes,
             sinks,
             cannons,
+            failover: self.failover,
         })
     }
 }
 
 impl DataFormat for PersistEnvFormatHeader {
     type Header = u8;
-    const LATEST_HEADER: Self::Header = 2;
+    const LATEST_HEADER: Self::Header = 3;
 
     fn write_data<W: Write>(&self, writer: &mut W) -> Result<usize, DataWriteError> {
         let mut written = 0;
@@ -160,6 +166,7 @@ impl DataFormat for PersistEnvFormatHeader {
         written += write_dataformat(writer, &self.tx_source)?;
         written += write_dataformat(writer, &self.tx_sink)?;
         written += writer.write_data(&self.network)?;
+        written += writer.write_data(&self.failover)?;
         Ok(written)
     }
 
@@ -181,6 +188,11 @@ impl DataFormat for PersistEnvFormatHeader {
         } else {
             0
         };
+        let failover = if *header > 2 {
+            reader.read_data(&())?
+        } else {
+            0
+        };
 
         Ok(PersistEnvFormatHeader {
             version,
@@ -188,6 +200,7 @@ impl DataFormat for PersistEnvFormatHeader {
             tx_source,
             tx_sink,
             network,
+            failover,
         })
     }
 }
@@ -200,6 +213,7 @@ impl DataFormat for PersistEnv {
         tx_source: TxSource::LATEST_HEADER,
         tx_sink: TxSink::LATEST_HEADER,
         network: NetworkId::LATEST_HEADER,
+        failover: FailoverPolicy::LATEST_HEADER,
     };
 
     fn write_data<W: Write>(&self, writer: &mut W) -> Result<usize, DataWriteError> {
@@ -210,6 +224,7 @@ impl DataFormat for PersistEnv {
         written += writer.write_data(&self.nodes)?;
         written += writer.write_data(&self.cannons)?;
         written += writer.write_data(&self.network)?;
+        written += writer.write_data(&self.failover)?;
 
         Ok(written)
     }
@@ -232,6 +247,11 @@ impl DataFormat for PersistEnv {
         } else {
             NetworkId::default()
         };
+        let failover = if header.failover > 0 {
+            reader.read_data(&header.failover)?
+        } else {
+            None
+        };
 
         Ok(PersistEnv {
             id,
@@ -239,6 +259,7 @@ impl DataFormat for PersistEnv {
             network,
             nodes,
             cannons,
+            failover,
         })
     }
 }
@@ -259,6 +280,7 @@ mod tests {
             PersistEnv, PersistEnvFormatHeader, PersistNode, PersistNodeFormatHeader,
             TxSinkFormatHeader, TxSourceFormatHeader,
         },
+        schema::nodes::FailoverPolicy,
     };
 
     macro_rules! case {
@@ -295,6 +317,7 @@ mod tests {
             TxSinkFormatHeader::LATEST_HEADER.to_byte_vec()?,
             TxSink::LATEST_HEADER.to_byte_vec()?,
             NetworkId::LATEST_HEADER.to_byte_vec()?,
+            FailoverPolicy::LATEST_HEADER.to_byte_vec()?,
         ]
         .concat()
     );
@@ -308,6 +331,31 @@ mod tests {
             network: Default::default(),
             nodes: Default::default(),
             cannons: Default::default(),
+            failover: None,
+        },
+        [
+            PersistEnvFormatHeader::LATEST_HEADER.to_byte_vec()?,
+            PersistEnv::LATEST_HEADER.to_byte_vec()?,
+            InternedId::from_str("foo")?.to_byte_vec()?,
+            InternedId::from_str("bar")?.to_byte_vec()?,
+            Vec::<(String, PersistNode)>::new().to_byte_vec()?,
+            Vec::<(InternedId, TxSource, TxSink)>::new().to_byte_vec()?,
+            NetworkId::default().to_byte_vec()?,
+            None::<FailoverPolicy>.to_byte_vec()?,
+        ]
+        .concat()
+    );
+
+    case!(
+        env_failover,
+        PersistEnv,
+        PersistEnv {
+            id: InternedId::from_str("foo")?,
+            storage_id: InternedId::from_str("bar")?,
+            network: Default::default(),
+            nodes: Default::default(),
+            cannons: Default::default(),
+            failover: Some(FailoverPolicy { grace_period: 30 }),
         },
         [
             PersistEnvFormatHeader::LATEST_HEADER.to_byte_vec()?,
@@ -317,6 +365,7 @@ mod tests {
             Vec::<(String, PersistNode)>::new().to_byte_vec()?,
             Vec::<(InternedId, TxSource, TxSink)>::new().to_byte_vec()?,
             NetworkId::default().to_byte_vec()?,
+            Some(FailoverPolicy { grace_period: 30 }).to_byte_vec()?,
         ]
         .concat()
     );
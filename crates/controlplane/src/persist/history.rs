@@ -0,0 +1,276 @@
+use super::prelude::*;
+use crate::cannon::history::{TransactionHistory, TransactionOutcome, TransactionProgress};
+
+impl DataFormat for TransactionOutcome {
+    type Header = u8;
+    const LATEST_HEADER: Self::Header = 1;
+
+    fn write_data<W: Write>(&self, writer: &mut W) -> Result<usize, DataWriteError> {
+        let tag: u8 = match self {
+            TransactionOutcome::Accepted => 0,
+            TransactionOutcome::Recorded => 1,
+            TransactionOutcome::ExecuteExceeded => 2,
+            TransactionOutcome::BroadcastExceeded => 3,
+            TransactionOutcome::Flushed => 4,
+            TransactionOutcome::Rejected => 5,
+            TransactionOutcome::Aborted => 6,
+            TransactionOutcome::Expired => 7,
+            TransactionOutcome::DependencyFailed => 8,
+        };
+        tag.write_data(writer)
+    }
+
+    fn read_data<R: Read>(reader: &mut R, header: &Self::Header) -> Result<Self, DataReadError> {
+        if *header != Self::LATEST_HEADER {
+            return Err(DataReadError::unsupported(
+                "TransactionOutcome",
+                Self::LATEST_HEADER,
+                *header,
+            ));
+        }
+
+        Ok(match reader.read_data(&())? {
+            0u8 => TransactionOutcome::Accepted,
+            1 => TransactionOutcome::Recorded,
+            2 => TransactionOutcome::ExecuteExceeded,
+            3 => TransactionOutcome::BroadcastExceeded,
+            4 => TransactionOutcome::Flushed,
+            5 => TransactionOutcome::Rejected,
+            6 => TransactionOutcome::Aborted,
+            7 => TransactionOutcome::Expired,
+            8 => TransactionOutcome::DependencyFailed,
+            n => {
+                return Err(DataReadError::Custom(format!(
+                    "invalid TransactionOutcome discriminant: {n}"
+                )));
+            }
+        })
+    }
+}
+
+impl DataFormat for TransactionProgress {
+    type Header = u8;
+    const LATEST_HEADER: Self::Header = 1;
+
+    fn write_data<W: Write>(&self, writer: &mut W) -> Result<usize, DataWriteError> {
+        let mut written = 0;
+        written += self.received_at.write_data(writer)?;
+        written += self.compute_agent.write_data(writer)?;
+        written += self.executed_at.write_data(writer)?;
+        written += self.execute_attempts.write_data(writer)?;
+        written += self.broadcast_agent.write_data(writer)?;
+        written += self.broadcast_at.write_data(writer)?;
+        Ok(written)
+    }
+
+    fn read_data<R: Read>(reader: &mut R, header: &Self::Header) -> Result<Self, DataReadError> {
+        if *header != Self::LATEST_HEADER {
+            return Err(DataReadError::unsupported(
+                "TransactionProgress",
+                Self::LATEST_HEADER,
+                *header,
+            ));
+        }
+
+        Ok(TransactionProgress {
+            received_at: reader.read_data(&())?,
+            compute_agent: reader.read_data(&())?,
+            executed_at: reader.read_data(&())?,
+            execute_attempts: reader.read_data(&())?,
+            broadcast_agent: reader.read_data(&())?,
+            broadcast_at: reader.read_data(&())?,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TransactionHistoryFormatHeader {
+    pub version: u8,
+    pub outcome: DataHeaderOf<TransactionOutcome>,
+    pub progress: DataHeaderOf<TransactionProgress>,
+}
+
+impl DataFormat for TransactionHistoryFormatHeader {
+    type Header = u8;
+    const LATEST_HEADER: Self::Header = 1;
+
+    fn write_data<W: Write>(&self, writer: &mut W) -> Result<usize, DataWriteError> {
+        Ok(self.version.write_data(writer)?
+            + self.outcome.write_data(writer)?
+            + self.progress.write_data(writer)?)
+    }
+
+    fn read_data<R: Read>(reader: &mut R, header: &Self::Header) -> Result<Self, DataReadError> {
+        if *header != Self::LATEST_HEADER {
+            return Err(DataReadError::unsupported(
+                "TransactionHistoryFormatHeader",
+                Self::LATEST_HEADER,
+                *header,
+            ));
+        }
+
+        Ok(Self {
+            version: reader.read_data(&())?,
+            outcome: reader.read_data(&())?,
+            progress: reader.read_data(&())?,
+        })
+    }
+}
+
+impl DataFormat for TransactionHistory {
+    type Header = TransactionHistoryFormatHeader;
+    const LATEST_HEADER: Self::Header = TransactionHistoryFormatHeader {
+        version: 1,
+        outcome: TransactionOutcome::LATEST_HEADER,
+        progress: TransactionProgress::LATEST_HEADER,
+    };
+
+    fn write_data<W: Write>(&self, writer: &mut W) -> Result<usize, DataWriteError> {
+        let mut written = 0;
+        written += self.index.write_data(writer)?;
+        written += self.outcome.write_data(writer)?;
+        written += self.progress.write_data(writer)?;
+        written += self.broadcast_attempts.write_data(writer)?;
+        written += self.block_hash.write_data(writer)?;
+        written += self.block_height.write_data(writer)?;
+        written += self.finished_at.write_data(writer)?;
+        Ok(written)
+    }
+
+    fn read_data<R: Read>(reader: &mut R, header: &Self::Header) -> Result<Self, DataReadError> {
+        if header.version != Self::LATEST_HEADER.version {
+            return Err(DataReadError::unsupported(
+                "TransactionHistory",
+                Self::LATEST_HEADER.version,
+                header.version,
+            ));
+        }
+
+        Ok(TransactionHistory {
+            index: reader.read_data(&())?,
+            outcome: reader.read_data(&header.outcome)?,
+            progress: reader.read_data(&header.progress)?,
+            broadcast_attempts: reader.read_data(&())?,
+            block_hash: reader.read_data(&())?,
+            block_height: reader.read_data(&())?,
+            finished_at: reader.read_data(&())?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use chrono::{TimeZone, Utc};
+    use snops_common::state::InternedId;
+
+    use crate::{
+        cannon::history::{TransactionHistory, TransactionOutcome, TransactionProgress},
+        persist::{TransactionHistoryFormatHeader, prelude::*},
+    };
+
+    macro_rules! case {
+        ($name:ident, $ty:ty, $a:expr_2021, $b:expr_2021) => {
+            #[test]
+            fn $name() -> Result<(), Box<dyn std::error::Error>> {
+                let mut data = Vec::new();
+                write_dataformat(&mut data, &$a)?;
+                assert_eq!(data, $b);
+
+                let mut reader = &data[..];
+                let read_value = read_dataformat::<_, $ty>(&mut reader)?;
+                assert_eq!(read_value, $a);
+                Ok(())
+            }
+        };
+    }
+
+    case!(
+        history_header,
+        TransactionHistoryFormatHeader,
+        TransactionHistory::LATEST_HEADER,
+        [
+            TransactionHistoryFormatHeader::LATEST_HEADER.to_byte_vec()?,
+            TransactionHistory::LATEST_HEADER.version.to_byte_vec()?,
+            TransactionOutcome::LATEST_HEADER.to_byte_vec()?,
+            TransactionProgress::LATEST_HEADER.to_byte_vec()?,
+        ]
+        .concat()
+    );
+
+    case!(
+        outcome_flushed,
+        TransactionOutcome,
+        TransactionOutcome::Flushed,
+        [
+            TransactionOutcome::LATEST_HEADER.to_byte_vec()?,
+            4u8.to_byte_vec()?,
+        ]
+        .concat()
+    );
+
+    case!(
+        outcome_rejected,
+        TransactionOutcome,
+        TransactionOutcome::Rejected,
+        [
+            TransactionOutcome::LATEST_HEADER.to_byte_vec()?,
+            5u8.to_byte_vec()?,
+        ]
+        .concat()
+    );
+
+    case!(
+        progress_default,
+        TransactionProgress,
+        TransactionProgress::default(),
+        [
+            TransactionProgress::LATEST_HEADER.to_byte_vec()?,
+            None::<chrono::DateTime<Utc>>.to_byte_vec()?,
+            None::<InternedId>.to_byte_vec()?,
+            None::<chrono::DateTime<Utc>>.to_byte_vec()?,
+            0u32.to_byte_vec()?,
+            None::<InternedId>.to_byte_vec()?,
+            None::<chrono::DateTime<Utc>>.to_byte_vec()?,
+        ]
+        .concat()
+    );
+
+    case!(
+        history_accepted,
+        TransactionHistory,
+        TransactionHistory {
+            index: 3,
+            outcome: TransactionOutcome::Accepted,
+            progress: TransactionProgress {
+                received_at: Some(Utc.timestamp_opt(1_700_000_000, 0).unwrap()),
+                compute_agent: Some(InternedId::from_str("agent")?),
+                execute_attempts: 1,
+                ..Default::default()
+            },
+            broadcast_attempts: 2,
+            block_hash: Some("ab1hash".to_owned()),
+            block_height: Some(10),
+            finished_at: Utc.timestamp_opt(1_700_000_100, 0).unwrap(),
+        },
+        [
+            TransactionHistoryFormatHeader::LATEST_HEADER.to_byte_vec()?,
+            TransactionHistory::LATEST_HEADER.to_byte_vec()?,
+            3u64.to_byte_vec()?,
+            TransactionOutcome::Accepted.to_byte_vec()?,
+            TransactionProgress {
+                received_at: Some(Utc.timestamp_opt(1_700_000_000, 0).unwrap()),
+                compute_agent: Some(InternedId::from_str("agent")?),
+                execute_attempts: 1,
+                ..Default::default()
+            }
+            .to_byte_vec()?,
+            2u32.to_byte_vec()?,
+            Some("ab1hash".to_owned()).to_byte_vec()?,
+            Some(10u32).to_byte_vec()?,
+            Utc.timestamp_opt(1_700_000_100, 0).unwrap().to_byte_vec()?,
+        ]
+        .concat()
+    );
+}
@@ -1,12 +1,15 @@
 mod agent;
 mod env;
+mod history;
 mod node;
+mod schedule;
 mod sink;
 mod source;
 mod storage;
 
 pub use agent::*;
 pub use env::*;
+pub use history::*;
 pub use node::*;
 pub use sink::*;
 pub use source::*;
@@ -0,0 +1,152 @@
+use super::prelude::*;
+use crate::cannon::tracker::{TransactionPriority, TransactionSchedule};
+
+impl DataFormat for TransactionSchedule {
+    type Header = u8;
+    const LATEST_HEADER: Self::Header = 2;
+
+    fn write_data<W: Write>(&self, writer: &mut W) -> Result<usize, DataWriteError> {
+        let priority: u8 = match self.priority {
+            TransactionPriority::Low => 0,
+            TransactionPriority::Normal => 1,
+            TransactionPriority::High => 2,
+        };
+        Ok(priority.write_data(writer)?
+            + self.deadline.write_data(writer)?
+            + self.after.write_data(writer)?)
+    }
+
+    fn read_data<R: Read>(reader: &mut R, header: &Self::Header) -> Result<Self, DataReadError> {
+        if *header == 0 || *header > Self::LATEST_HEADER {
+            return Err(DataReadError::unsupported(
+                "TransactionSchedule",
+                format!("1 to {}", Self::LATEST_HEADER),
+                *header,
+            ));
+        }
+
+        let priority = match reader.read_data(&())? {
+            0u8 => TransactionPriority::Low,
+            1 => TransactionPriority::Normal,
+            2 => TransactionPriority::High,
+            n => {
+                return Err(DataReadError::Custom(format!(
+                    "invalid TransactionPriority discriminant: {n}"
+                )));
+            }
+        };
+
+        let deadline = reader.read_data(&())?;
+        // dependencies were added in version 2
+        let after = if *header < 2 {
+            Vec::new()
+        } else {
+            reader.read_data(&())?
+        };
+
+        Ok(TransactionSchedule {
+            priority,
+            deadline,
+            after,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use chrono::{DateTime, TimeZone, Utc};
+
+    use crate::{
+        cannon::tracker::{TransactionPriority, TransactionSchedule},
+        persist::prelude::*,
+    };
+
+    macro_rules! case {
+        ($name:ident, $ty:ty, $a:expr_2021, $b:expr_2021) => {
+            #[test]
+            fn $name() -> Result<(), Box<dyn std::error::Error>> {
+                let mut data = Vec::new();
+                write_dataformat(&mut data, &$a)?;
+                assert_eq!(data, $b);
+
+                let mut reader = &data[..];
+                let read_value = read_dataformat::<_, $ty>(&mut reader)?;
+                assert_eq!(read_value, $a);
+                Ok(())
+            }
+        };
+    }
+
+    case!(
+        schedule_default,
+        TransactionSchedule,
+        TransactionSchedule::default(),
+        [
+            TransactionSchedule::LATEST_HEADER.to_byte_vec()?,
+            1u8.to_byte_vec()?,
+            None::<DateTime<Utc>>.to_byte_vec()?,
+            Vec::<Arc<String>>::new().to_byte_vec()?,
+        ]
+        .concat()
+    );
+
+    case!(
+        schedule_deadline,
+        TransactionSchedule,
+        TransactionSchedule {
+            priority: TransactionPriority::High,
+            deadline: Some(Utc.timestamp_opt(1_700_000_000, 0).unwrap()),
+            after: vec![],
+        },
+        [
+            TransactionSchedule::LATEST_HEADER.to_byte_vec()?,
+            2u8.to_byte_vec()?,
+            Some(Utc.timestamp_opt(1_700_000_000, 0).unwrap()).to_byte_vec()?,
+            Vec::<Arc<String>>::new().to_byte_vec()?,
+        ]
+        .concat()
+    );
+
+    case!(
+        schedule_after,
+        TransactionSchedule,
+        TransactionSchedule {
+            priority: TransactionPriority::Low,
+            deadline: None,
+            after: vec![Arc::new("at1dep".to_owned())],
+        },
+        [
+            TransactionSchedule::LATEST_HEADER.to_byte_vec()?,
+            0u8.to_byte_vec()?,
+            None::<DateTime<Utc>>.to_byte_vec()?,
+            vec![Arc::new("at1dep".to_owned())].to_byte_vec()?,
+        ]
+        .concat()
+    );
+
+    #[test]
+    fn schedule_v1_has_no_dependencies() -> Result<(), Box<dyn std::error::Error>> {
+        let deadline = Utc.timestamp_opt(1_700_000_000, 0).unwrap();
+        let data = [
+            1u8.to_byte_vec()?,
+            2u8.to_byte_vec()?,
+            Some(deadline).to_byte_vec()?,
+        ]
+        .concat();
+
+        let mut reader = &data[..];
+        let schedule = read_dataformat::<_, TransactionSchedule>(&mut reader)?;
+        assert_eq!(
+            schedule,
+            TransactionSchedule {
+                priority: TransactionPriority::High,
+                deadline: Some(deadline),
+                after: vec![],
+            }
+        );
+        assert!(reader.is_empty());
+        Ok(())
+    }
+}
@@ -39,7 +39,7 @@ impl DataFormat for TxSinkFormatHeader {
 impl DataFormat for TxSink {
     type Header = TxSinkFormatHeader;
     const LATEST_HEADER: Self::Header = TxSinkFormatHeader {
-        version: 2,
+        version: 3,
         node_targets: NodeTargets::LATEST_HEADER,
     };
 
@@ -51,6 +51,9 @@ impl DataFormat for TxSink {
         written += self.authorize_attempts.write_data(writer)?;
         written += self.broadcast_timeout.write_data(writer)?;
         written += self.authorize_timeout.write_data(writer)?;
+        written += self.max_executing.write_data(writer)?;
+        written += self.broadcast_rate.write_data(writer)?;
+        written += self.max_unconfirmed.write_data(writer)?;
         Ok(written)
     }
 
@@ -66,6 +69,9 @@ impl DataFormat for TxSink {
                         authorize_attempts: None,
                         broadcast_timeout: TxSink::default_retry_timeout(),
                         authorize_timeout: TxSink::default_retry_timeout(),
+                        max_executing: None,
+                        broadcast_rate: None,
+                        max_unconfirmed: None,
                     })
                 }
                 1u8 => {
@@ -77,19 +83,32 @@ impl DataFormat for TxSink {
                         authorize_attempts: None,
                         broadcast_timeout: TxSink::default_retry_timeout(),
                         authorize_timeout: TxSink::default_retry_timeout(),
+                        max_executing: None,
+                        broadcast_rate: None,
+                        max_unconfirmed: None,
                     })
                 }
                 n => Err(DataReadError::Custom(format!(
                     "invalid TxSink discriminant: {n}"
                 ))),
             },
-            n if n == Self::LATEST_HEADER.version => {
+            n @ 2..=3 => {
                 let file_name: Option<TxPipeId> = reader.read_data(&())?;
                 let target: Option<NodeTargets> = reader.read_data(&header.node_targets)?;
                 let broadcast_attempts: Option<u32> = reader.read_data(&())?;
                 let authorize_attempts: Option<u32> = reader.read_data(&())?;
                 let broadcast_timeout: u32 = reader.read_data(&())?;
                 let authorize_timeout: u32 = reader.read_data(&())?;
+                // limits were added in version 3
+                let (max_executing, broadcast_rate, max_unconfirmed) = if n < 3 {
+                    (None, None, None)
+                } else {
+                    (
+                        reader.read_data(&())?,
+                        reader.read_data(&())?,
+                        reader.read_data(&())?,
+                    )
+                };
                 Ok(TxSink {
                     file_name,
                     target,
@@ -97,11 +116,14 @@ impl DataFormat for TxSink {
                     authorize_attempts,
                     broadcast_timeout,
                     authorize_timeout,
+                    max_executing,
+                    broadcast_rate,
+                    max_unconfirmed,
                 })
             }
             n => Err(DataReadError::unsupported(
                 "TxSink",
-                format!("1 or {}", Self::LATEST_HEADER.version),
+                format!("1 to {}", Self::LATEST_HEADER.version),
                 n,
             )),
         }
@@ -1,7 +1,10 @@
 use snops_common::node_targets::NodeTargets;
 
 use super::prelude::*;
-use crate::cannon::source::{ComputeTarget, LocalService, QueryTarget, TxSource};
+use crate::cannon::{
+    intake::{IntakeSource, TxIntake},
+    source::{ComputeTarget, LocalService, QueryTarget, TxSource},
+};
 
 #[derive(Debug, Clone)]
 pub struct TxSourceFormatHeader {
@@ -38,7 +41,7 @@ impl DataFormat for TxSourceFormatHeader {
 impl DataFormat for TxSource {
     type Header = TxSourceFormatHeader;
     const LATEST_HEADER: Self::Header = TxSourceFormatHeader {
-        version: 1,
+        version: 5,
         node_targets: NodeTargets::LATEST_HEADER,
     };
 
@@ -67,14 +70,43 @@ impl DataFormat for TxSource {
             }
         }
 
+        match &self.intake {
+            None => written += 0u8.write_data(writer)?,
+            Some(intake) => {
+                match &intake.source {
+                    IntakeSource::File(path) => {
+                        written += 1u8.write_data(writer)?;
+                        written += path.to_string_lossy().into_owned().write_data(writer)?;
+                    }
+                    IntakeSource::Dir(path) => {
+                        written += 2u8.write_data(writer)?;
+                        written += path.to_string_lossy().into_owned().write_data(writer)?;
+                    }
+                    IntakeSource::Socket(path) => {
+                        written += 3u8.write_data(writer)?;
+                        written += path.to_string_lossy().into_owned().write_data(writer)?;
+                    }
+                    IntakeSource::Nats { url, subject } => {
+                        written += 4u8.write_data(writer)?;
+                        written += url.write_data(writer)?;
+                        written += subject.write_data(writer)?;
+                    }
+                }
+                written += intake.max_queue.write_data(writer)?;
+            }
+        }
+
+        written += self.dedupe_window.write_data(writer)?;
+        written += self.proof_cache.write_data(writer)?;
+
         Ok(written)
     }
 
     fn read_data<R: Read>(reader: &mut R, header: &Self::Header) -> Result<Self, DataReadError> {
-        if header.version != Self::LATEST_HEADER.version {
+        if header.version == 0 || header.version > Self::LATEST_HEADER.version {
             return Err(DataReadError::unsupported(
                 "TxSource",
-                Self::LATEST_HEADER.version,
+                format!("1 to {}", Self::LATEST_HEADER.version),
                 header.version,
             ));
         }
@@ -105,7 +137,62 @@ impl DataFormat for TxSource {
             }
         };
 
-        Ok(TxSource { query, compute })
+        // intakes were added in version 2
+        let intake = if header.version < 2 {
+            None
+        } else {
+            let source = match reader.read_data(&())? {
+                0u8 => None,
+                1u8 => Some(IntakeSource::File(reader.read_data::<String>(&())?.into())),
+                2u8 => Some(IntakeSource::Dir(reader.read_data::<String>(&())?.into())),
+                3u8 => Some(IntakeSource::Socket(
+                    reader.read_data::<String>(&())?.into(),
+                )),
+                4u8 => Some(IntakeSource::Nats {
+                    url: reader.read_data(&())?,
+                    subject: reader.read_data(&())?,
+                }),
+                n => {
+                    return Err(DataReadError::Custom(format!(
+                        "invalid IntakeSource discriminant: {n}"
+                    )));
+                }
+            };
+            match source {
+                // queue sizes were written as packed usizes before version 5
+                Some(source) if header.version < 5 => Some(TxIntake {
+                    source,
+                    max_queue: u32::try_from(reader.read_data::<usize>(&())?).unwrap_or(u32::MAX),
+                }),
+                Some(source) => Some(TxIntake {
+                    source,
+                    max_queue: reader.read_data(&())?,
+                }),
+                None => None,
+            }
+        };
+
+        // dedupe windows were added in version 3
+        let dedupe_window = if header.version < 3 {
+            None
+        } else {
+            reader.read_data(&())?
+        };
+
+        // proof caching was added in version 4
+        let proof_cache = if header.version < 4 {
+            false
+        } else {
+            reader.read_data(&())?
+        };
+
+        Ok(TxSource {
+            query,
+            compute,
+            intake,
+            dedupe_window,
+            proof_cache,
+        })
     }
 }
 
@@ -115,7 +202,10 @@ mod tests {
     use snops_common::{INTERN, node_targets::NodeTargets};
 
     use crate::{
-        cannon::source::{ComputeTarget, LocalService, QueryTarget, TxSource},
+        cannon::{
+            intake::{IntakeSource, TxIntake},
+            source::{ComputeTarget, LocalService, QueryTarget, TxSource},
+        },
         persist::{TxSourceFormatHeader, prelude::*},
     };
 
@@ -156,7 +246,10 @@ mod tests {
         TxSource,
         TxSource {
             query: QueryTarget::Local(LocalService { sync_from: None }),
-            compute: ComputeTarget::Agent { labels: None }
+            compute: ComputeTarget::Agent { labels: None },
+            intake: None,
+            dedupe_window: None,
+            proof_cache: false,
         },
         [
             TxSourceFormatHeader::LATEST_HEADER.to_byte_vec()?,
@@ -165,6 +258,9 @@ mod tests {
             0u8.to_byte_vec()?, // sync from empty option
             0u8.to_byte_vec()?, // computetarget agent discriminant
             0u8.to_byte_vec()?, // labels empty option
+            0u8.to_byte_vec()?, // intake none discriminant
+            None::<u32>.to_byte_vec()?,
+            false.to_byte_vec()?,
         ]
         .concat()
     );
@@ -178,7 +274,13 @@ mod tests {
             }),
             compute: ComputeTarget::Agent {
                 labels: Some(vec![INTERN.get_or_intern("foo")])
-            }
+            },
+            intake: Some(TxIntake {
+                source: IntakeSource::Dir("auths".into()),
+                max_queue: 100,
+            }),
+            dedupe_window: Some(0),
+            proof_cache: true,
         },
         [
             TxSourceFormatHeader::LATEST_HEADER.to_byte_vec()?,
@@ -187,6 +289,11 @@ mod tests {
             Some(NodeTargets::One("client/*".parse()?)).to_byte_vec()?,
             0u8.to_byte_vec()?, // computetarget agent discriminant
             Some(vec!["foo".to_owned()]).to_byte_vec()?,
+            2u8.to_byte_vec()?, // intake dir discriminant
+            "auths".to_owned().to_byte_vec()?,
+            100u32.to_byte_vec()?,
+            Some(0u32).to_byte_vec()?,
+            true.to_byte_vec()?,
         ]
         .concat()
     );
@@ -198,7 +305,16 @@ mod tests {
             query: QueryTarget::Node(NodeTargets::One("client/*".parse()?)),
             compute: ComputeTarget::Demox {
                 demox_api: "foo".to_owned()
-            }
+            },
+            intake: Some(TxIntake {
+                source: IntakeSource::Nats {
+                    url: "127.0.0.1:4222".to_owned(),
+                    subject: "auths".to_owned(),
+                },
+                max_queue: 1000,
+            }),
+            dedupe_window: Some(3600),
+            proof_cache: false,
         },
         [
             TxSourceFormatHeader::LATEST_HEADER.to_byte_vec()?,
@@ -207,7 +323,41 @@ mod tests {
             NodeTargets::One("client/*".parse()?).to_byte_vec()?,
             1u8.to_byte_vec()?, // computetarget demox discriminant
             "foo".to_owned().to_byte_vec()?,
+            4u8.to_byte_vec()?, // intake nats discriminant
+            "127.0.0.1:4222".to_owned().to_byte_vec()?,
+            "auths".to_owned().to_byte_vec()?,
+            1000u32.to_byte_vec()?,
+            Some(3600u32).to_byte_vec()?,
+            false.to_byte_vec()?,
         ]
         .concat()
     );
+
+    #[test]
+    fn source_v4_packed_max_queue() -> Result<(), Box<dyn std::error::Error>> {
+        let header = TxSourceFormatHeader {
+            version: 4,
+            ..TxSource::LATEST_HEADER
+        };
+        let data = [
+            0u8.to_byte_vec()?, // querytarget local discriminant
+            0u8.to_byte_vec()?, // sync from empty option
+            0u8.to_byte_vec()?, // computetarget agent discriminant
+            0u8.to_byte_vec()?, // labels empty option
+            1u8.to_byte_vec()?, // intake file discriminant
+            "auths.jsonl".to_owned().to_byte_vec()?,
+            300usize.to_byte_vec()?,
+            None::<u32>.to_byte_vec()?,
+            false.to_byte_vec()?,
+        ]
+        .concat();
+
+        let source = TxSource::read_data(&mut &data[..], &header)?;
+        let intake = source.intake.expect("intake");
+        assert!(
+            matches!(intake.source, IntakeSource::File(path) if path == std::path::Path::new("auths.jsonl"))
+        );
+        assert_eq!(intake.max_queue, 300);
+        Ok(())
+    }
 }
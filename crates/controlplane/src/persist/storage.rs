@@ -121,6 +121,10 @@ impl PersistStorage {
             }
         }
 
+        let genesis_sha256 = (!self.native_genesis)
+            .then(|| LoadedStorage::hash_genesis(id, &storage_path))
+            .flatten();
+
         Ok(LoadedStorage {
             id,
             network: self.network,
@@ -129,6 +133,7 @@ impl PersistStorage {
             committee: read_to_addrs(pick_commitee_addr, &committee_file).await?,
             retention_policy: self.retention_policy,
             native_genesis: self.native_genesis,
+            genesis_sha256,
             accounts,
             binaries: self.binaries,
         })
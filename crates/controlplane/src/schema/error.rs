@@ -14,6 +14,7 @@ use thiserror::Error;
 use url::Url;
 
 use super::storage::BinarySourceError;
+use crate::storage_backend::StorageBackendError;
 
 #[derive(Debug, Error, AsRefStr)]
 pub enum StorageError {
@@ -37,8 +38,18 @@ pub enum StorageError {
     FailedToTarLedger(StorageId, #[source] std::io::Error),
     #[error("the specified storage ID {0} doesn't exist, and no generation params were specified")]
     NoGenerationParams(StorageId),
+    #[error("storage `{0}` keyset `{1}` file {2:#?} does not exist")]
+    KeysetFileMissing(StorageId, InternedId, PathBuf),
+    #[error(
+        "storage `{0}` keysets have balances but the storage does not generate a genesis block"
+    )]
+    KeysetBalancesWithoutGenesis(StorageId),
+    #[error("storage `{0}` genesis programs {1:#?} do not exist")]
+    GenesisProgramsMissing(StorageId, PathBuf),
     #[error("reading balances {0:#?}: {1}")]
     ReadBalances(PathBuf, #[source] std::io::Error),
+    #[error("reading storage {0:#?}: {1}")]
+    ReadStorage(PathBuf, #[source] std::io::Error),
     #[error("reading version {0:#?}: {1}")]
     ReadVersion(PathBuf, #[source] std::io::Error),
     #[error("writing version {0:#?}: {1}")]
@@ -71,12 +82,16 @@ pub enum StorageError {
     PermissionError(PathBuf, std::io::Error),
     #[error("failed to parse binary `{0}`: {1}")]
     BinaryParse(InternedId, BinarySourceError),
+    #[error("storage backend: {0}")]
+    Backend(#[from] StorageBackendError),
 }
 
 impl_into_status_code!(StorageError, |value| match value {
     Command(e, _) => e.into(),
     FailedToFetchGenesis(_, _, _) => StatusCode::MISDIRECTED_REQUEST,
     NoGenerationParams(_) => StatusCode::BAD_REQUEST,
+    KeysetFileMissing(_, _, _) | KeysetBalancesWithoutGenesis(_) | GenesisProgramsMissing(_, _) =>
+        StatusCode::BAD_REQUEST,
     BinaryDoesNotExist(_, _) => StatusCode::NOT_FOUND,
     BinaryFileMissing(_, _) => StatusCode::NOT_FOUND,
     _ => StatusCode::INTERNAL_SERVER_ERROR,
@@ -32,6 +32,64 @@ pub struct Document {
 
     #[serde(default)]
     pub nodes: IndexMap<NodeKey, Node>,
+
+    /// When specified, nodes on agents that stay disconnected are moved to
+    /// spare inventory agents.
+    #[serde(default)]
+    pub failover: Option<FailoverPolicy>,
+}
+
+/// A policy for reassigning nodes whose agents have disconnected.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub struct FailoverPolicy {
+    /// Number of seconds an agent can be disconnected before its node is
+    /// reassigned to a matching inventory agent.
+    #[serde(default = "FailoverPolicy::default_grace_period")]
+    pub grace_period: u32,
+}
+
+impl FailoverPolicy {
+    pub fn default_grace_period() -> u32 {
+        300
+    }
+
+    /// Combine the failover policies of two nodes documents. Failover applies
+    /// to every node in the environment, so the shorter grace period is kept.
+    pub fn merge(self, other: Self) -> Self {
+        Self {
+            grace_period: self.grace_period.min(other.grace_period),
+        }
+    }
+}
+
+impl DataFormat for FailoverPolicy {
+    type Header = u8;
+    const LATEST_HEADER: Self::Header = 1;
+
+    fn write_data<W: std::io::prelude::Write>(
+        &self,
+        writer: &mut W,
+    ) -> Result<usize, DataWriteError> {
+        self.grace_period.write_data(writer)
+    }
+
+    fn read_data<R: std::io::prelude::Read>(
+        reader: &mut R,
+        header: &Self::Header,
+    ) -> Result<Self, DataReadError> {
+        if *header != Self::LATEST_HEADER {
+            return Err(DataReadError::unsupported(
+                "FailoverPolicy",
+                Self::LATEST_HEADER,
+                *header,
+            ));
+        }
+
+        Ok(FailoverPolicy {
+            grace_period: reader.read_data(&())?,
+        })
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize)]
@@ -412,4 +470,16 @@ mod tests {
         assert!(serde_yaml::from_str::<KeySource>("accounts._").is_err(),);
         assert!(serde_yaml::from_str::<KeySource>("accounts.*").is_err(),);
     }
+
+    #[test]
+    fn test_failover_policy_merge() {
+        let short = FailoverPolicy { grace_period: 30 };
+        let long = FailoverPolicy { grace_period: 600 };
+        assert_eq!(short.merge(long), short);
+        assert_eq!(long.merge(short), short);
+        assert_eq!(long.merge(long), long);
+
+        let policy = serde_yaml::from_str::<FailoverPolicy>("{}").unwrap();
+        assert_eq!(policy.grace_period, FailoverPolicy::default_grace_period());
+    }
 }
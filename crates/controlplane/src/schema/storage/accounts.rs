@@ -1,10 +1,29 @@
+use std::path::{Path, PathBuf};
+
 use serde::{Deserialize, Deserializer, Serialize, de::Visitor};
 
+/// A keyset of accounts, either generated or imported from a key file.
 #[derive(Debug, Clone, Serialize)]
 pub struct Accounts {
+    /// The number of accounts to generate. Not used when importing a file.
     pub count: u16,
     #[serde(default)]
     pub seed: Option<u64>,
+    /// A JSON file of existing private keys to import instead of generating
+    /// accounts. Relative paths are resolved against the storage directory.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file: Option<PathBuf>,
+    /// The public balance in microcredits each account receives at genesis
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub balance: Option<u64>,
+}
+
+impl Accounts {
+    /// The key file to import, resolved against a storage directory
+    pub fn import_path(&self, storage_dir: &Path) -> Option<PathBuf> {
+        // joining an absolute path replaces the storage directory
+        self.file.as_ref().map(|file| storage_dir.join(file))
+    }
 }
 
 impl<'de> Deserialize<'de> for Accounts {
@@ -14,11 +33,13 @@ impl<'de> Deserialize<'de> for Accounts {
     {
         struct AccountsVisitor;
 
+        const FIELDS: &[&str] = &["count", "seed", "file", "balance"];
+
         impl<'de> Visitor<'de> for AccountsVisitor {
             type Value = Accounts;
 
             fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
-                formatter.write_str("a number or an object with a count and seed")
+                formatter.write_str("a number or an object with a count or a file")
             }
 
             fn visit_u64<E>(self, v: u64) -> Result<Self::Value, E>
@@ -28,6 +49,8 @@ impl<'de> Deserialize<'de> for Accounts {
                 Ok(Accounts {
                     count: v.min(u16::MAX as u64) as u16,
                     seed: None,
+                    file: None,
+                    balance: None,
                 })
             }
 
@@ -37,6 +60,8 @@ impl<'de> Deserialize<'de> for Accounts {
             {
                 let mut count = None;
                 let mut seed = None;
+                let mut file = None;
+                let mut balance = None;
 
                 while let Some(key) = map.next_key()? {
                     match key {
@@ -52,13 +77,34 @@ impl<'de> Deserialize<'de> for Accounts {
                             }
                             seed = Some(map.next_value()?);
                         }
-                        _ => return Err(serde::de::Error::unknown_field(key, &["count", "seed"])),
+                        "file" => {
+                            if file.is_some() {
+                                return Err(serde::de::Error::duplicate_field("file"));
+                            }
+                            file = Some(map.next_value()?);
+                        }
+                        "balance" => {
+                            if balance.is_some() {
+                                return Err(serde::de::Error::duplicate_field("balance"));
+                            }
+                            balance = Some(map.next_value()?);
+                        }
+                        _ => return Err(serde::de::Error::unknown_field(key, FIELDS)),
                     }
                 }
 
+                // imported keysets don't need a count
+                let count = match (count, &file) {
+                    (Some(count), _) => count,
+                    (None, Some(_)) => 0,
+                    (None, None) => return Err(serde::de::Error::missing_field("count")),
+                };
+
                 Ok(Accounts {
-                    count: count.ok_or_else(|| serde::de::Error::missing_field("count"))?,
+                    count,
                     seed,
+                    file,
+                    balance,
                 })
             }
         }
@@ -66,3 +112,60 @@ impl<'de> Deserialize<'de> for Accounts {
         deserializer.deserialize_any(AccountsVisitor)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(yaml: &str) -> Result<Accounts, serde_yaml::Error> {
+        serde_yaml::from_str(yaml)
+    }
+
+    #[test]
+    fn accounts_from_a_count() {
+        let accounts = parse("5").unwrap();
+        assert_eq!(accounts.count, 5);
+        assert_eq!(accounts.seed, None);
+        assert_eq!(accounts.file, None);
+
+        let accounts = parse("{ count: 3, seed: 1, balance: 100 }").unwrap();
+        assert_eq!(accounts.count, 3);
+        assert_eq!(accounts.seed, Some(1));
+        assert_eq!(accounts.balance, Some(100));
+    }
+
+    #[test]
+    fn imported_accounts_do_not_need_a_count() {
+        let accounts = parse("{ file: keys.json }").unwrap();
+        assert_eq!(accounts.count, 0);
+        assert_eq!(accounts.file, Some(PathBuf::from("keys.json")));
+
+        let accounts = parse("{ file: keys.json, balance: 10 }").unwrap();
+        assert_eq!(accounts.balance, Some(10));
+    }
+
+    #[test]
+    fn rejects_invalid_accounts() {
+        assert!(parse("{ seed: 1 }").is_err());
+        assert!(parse("{ count: 1, count: 2 }").is_err());
+        assert!(parse("{ count: 1, keys: 2 }").is_err());
+    }
+
+    #[test]
+    fn import_paths_resolve_against_the_storage_dir() {
+        let storage = Path::new("/data/storage/mainnet/example");
+        let accounts = parse("{ file: keys/accounts.json }").unwrap();
+        assert_eq!(
+            accounts.import_path(storage),
+            Some(storage.join("keys/accounts.json"))
+        );
+
+        let accounts = parse("{ file: /keys/accounts.json }").unwrap();
+        assert_eq!(
+            accounts.import_path(storage),
+            Some(PathBuf::from("/keys/accounts.json"))
+        );
+
+        assert_eq!(parse("5").unwrap().import_path(storage), None);
+    }
+}
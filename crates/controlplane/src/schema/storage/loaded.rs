@@ -1,4 +1,9 @@
-use std::{fs, io::Write, os::unix::fs::PermissionsExt, path::PathBuf};
+use std::{
+    fs,
+    io::Write,
+    os::unix::fs::PermissionsExt,
+    path::{Path, PathBuf},
+};
 
 use futures_util::StreamExt;
 use indexmap::IndexMap;
@@ -8,13 +13,20 @@ use snops_checkpoint::RetentionPolicy;
 use snops_common::{
     api::StorageInfo,
     binaries::{BinaryEntry, BinarySource},
+    constant::SNARKOS_GENESIS_FILE,
     key_source::KeySource,
     state::{InternedId, KeyState, NetworkId, StorageId},
+    util::sha256_file,
 };
-use tracing::{info, trace};
+use tracing::{info, trace, warn};
 
 use super::{DEFAULT_AOT_BINARY, STORAGE_DIR};
-use crate::{cli::Cli, schema::error::StorageError, state::GlobalState};
+use crate::{
+    cli::Cli,
+    schema::error::StorageError,
+    state::GlobalState,
+    storage_backend::{StorageBackend, binary_key, list_files, storage_key},
+};
 
 // IndexMap<addr, private_key>
 pub type AleoAddrMap = IndexMap<String, String>;
@@ -38,6 +50,8 @@ pub struct LoadedStorage {
     pub persist: bool,
     /// whether to use the network's native genesis block
     pub native_genesis: bool,
+    /// sha256 of the genesis block, when it is not the native genesis
+    pub genesis_sha256: Option<String>,
     /// binaries available for this storage
     pub binaries: IndexMap<InternedId, BinaryEntry>,
 }
@@ -168,10 +182,24 @@ impl LoadedStorage {
             retention_policy: self.retention_policy.clone(),
             persist: self.persist,
             native_genesis: self.native_genesis,
+            genesis_sha256: self.genesis_sha256.clone(),
             binaries,
         }
     }
 
+    /// Hash the genesis block in a storage directory so agents can find it in
+    /// their download caches
+    pub fn hash_genesis(storage_id: StorageId, base: &Path) -> Option<String> {
+        let path = base.join(SNARKOS_GENESIS_FILE);
+        if !path.exists() {
+            return None;
+        }
+
+        sha256_file(&path)
+            .inspect_err(|e| warn!("failed to hash genesis block for storage {storage_id}: {e}"))
+            .ok()
+    }
+
     pub fn path(&self, state: &GlobalState) -> PathBuf {
         self.path_cli(&state.cli)
     }
@@ -183,6 +211,72 @@ impl LoadedStorage {
         path
     }
 
+    /// Upload the generated storage files (genesis block, ledgers,
+    /// checkpoints, and account files) and local binaries to the storage
+    /// backend. Files already in the backend are only replaced when `force` is
+    /// set.
+    pub async fn upload_to_backend(
+        &self,
+        state: &GlobalState,
+        force: bool,
+    ) -> Result<(), StorageError> {
+        let backend = &state.storage_backend;
+        let base = self.path(state);
+
+        let mut uploads = list_files(&base)
+            .await
+            .map_err(|e| StorageError::ReadStorage(base.clone(), e))?
+            .into_iter()
+            .filter(|(file, _)| file != SNARKOS_GENESIS_FILE || !self.native_genesis)
+            .map(|(file, path)| (storage_key(self.network, self.id, &file), path))
+            .collect::<Vec<_>>();
+
+        // local binaries are served from their own paths unless the backend is
+        // remote. the default aot binary is served when the storage does not
+        // override it
+        if backend.is_remote() {
+            let default_id = InternedId::default();
+            let default_bin = (!self.binaries.contains_key(&default_id))
+                .then_some((&default_id, &*DEFAULT_AOT_BINARY));
+            for (id, entry) in self.binaries.iter().chain(default_bin) {
+                if let BinarySource::Path(path) = &entry.source {
+                    uploads.push((binary_key(self.network, self.id, *id), path.clone()));
+                }
+            }
+        }
+
+        for (key, path) in uploads {
+            if !force && backend.exists(&key).await? {
+                continue;
+            }
+            info!("uploading {} to storage backend as {key}", path.display());
+            backend.put_file(&key, &path).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Remove the files of a storage directory from the storage backend,
+    /// before the directory is regenerated
+    pub async fn remove_from_backend(
+        state: &GlobalState,
+        network: NetworkId,
+        id: StorageId,
+        base: &Path,
+    ) -> Result<(), StorageError> {
+        let files = list_files(base)
+            .await
+            .map_err(|e| StorageError::ReadStorage(base.to_owned(), e))?;
+
+        for (file, _) in files {
+            state
+                .storage_backend
+                .remove(&storage_key(network, id, &file))
+                .await?;
+        }
+        Ok(())
+    }
+
     /// Resolve the default binary for this storage
     pub async fn resolve_default_binary(
         &self,
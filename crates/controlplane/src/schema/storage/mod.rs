@@ -1,4 +1,9 @@
-use std::{ops::Deref, path::PathBuf, process::Stdio, sync::Arc};
+use std::{
+    ops::Deref,
+    path::{Path, PathBuf},
+    process::Stdio,
+    sync::Arc,
+};
 
 use indexmap::IndexMap;
 use serde::{Deserialize, Serialize};
@@ -92,6 +97,13 @@ pub struct GenesisGeneration {
     #[serde(flatten)]
     pub commissions: GenesisCommissions,
     pub bonded_withdrawal: Option<IndexMap<String, String>>,
+    /// Public balances in microcredits given to addresses at genesis
+    #[serde(default)]
+    pub public_balances: IndexMap<String, u64>,
+    /// Programs to deploy in the genesis block. Can be a `.aleo` file, a
+    /// directory of `.aleo` files, or a JSON bundle manifest.
+    #[serde(default)]
+    pub programs: Option<PathBuf>,
 }
 
 #[derive(Deserialize, Debug, Clone, Serialize)]
@@ -134,6 +146,8 @@ impl Default for GenesisGeneration {
                 bonded_commission: None,
             },
             bonded_withdrawal: None,
+            public_balances: IndexMap::new(),
+            programs: None,
         }
     }
 }
@@ -178,10 +192,24 @@ impl Document {
         // wipe old storage when the version changes
         if old_version != Some(self.regen) && exists {
             info!("Storage {id} version changed, removing old storage");
+            // key files imported from the storage directory outlive it
+            let imports = read_keyset_imports(self.generate.as_ref(), &base).await;
+            LoadedStorage::remove_from_backend(state, network, id, &base).await?;
             tokio::fs::remove_dir_all(&base)
                 .await
                 .map_err(|e| StorageError::RemoveStorage(version_file.clone(), e))?;
             exists = false;
+
+            for (path, contents) in imports {
+                if let Some(parent) = path.parent() {
+                    tokio::fs::create_dir_all(parent)
+                        .await
+                        .map_err(|e| StorageError::GenerateStorage(id, e))?;
+                }
+                tokio::fs::write(&path, contents)
+                    .await
+                    .map_err(|e| StorageError::GenerateStorage(id, e))?;
+            }
         }
 
         // gather the binaries
@@ -221,12 +249,34 @@ impl Document {
             .await
             .map_err(|e| StorageError::GenerateStorage(id, e))?;
 
+        // generate or import keysets before the genesis block, which may fund them
+        let mut keysets = IndexMap::new();
+        let mut keyset_balances = IndexMap::new();
+        if let Some(generation) = &self.generate {
+            for (name, account) in &generation.accounts {
+                let addrs = prepare_keyset(&aot_bin, network, id, &base, *name, account).await?;
+                if let Some(balance) = account.balance {
+                    for addr in addrs.keys() {
+                        *keyset_balances.entry(addr.clone()).or_default() += balance;
+                    }
+                }
+                keysets.insert(*name, addrs);
+            }
+        }
+
         // generate the block and ledger if we have generation params
         if let (Some(generation), false) = (self.generate.as_ref(), exists) {
             tracing::debug!("Generating storage for {id}");
             // generate the genesis block using the aot cli
             let output = base.join(SNARKOS_GENESIS_FILE);
 
+            // only a generated genesis block can fund keysets
+            if !keyset_balances.is_empty()
+                && (self.connect.is_some() || generation.genesis.is_none())
+            {
+                return Err(StorageError::KeysetBalancesWithoutGenesis(id).into());
+            }
+
             match (self.connect, generation.genesis.as_ref()) {
                 (None, None) => {
                     native_genesis = true;
@@ -338,6 +388,26 @@ impl Document {
                             .arg(balance.to_string());
                     }
 
+                    // fund the keysets and any other requested addresses
+                    let mut public_balances = keyset_balances.clone();
+                    for (addr, balance) in &genesis.public_balances {
+                        *public_balances.entry(addr.clone()).or_default() += balance;
+                    }
+                    if !public_balances.is_empty() {
+                        command
+                            .arg("--public-balances")
+                            .arg(serde_json::to_string(&public_balances).unwrap());
+                    }
+
+                    if let Some(programs) = &genesis.programs {
+                        if !programs.exists() {
+                            return Err(
+                                StorageError::GenesisProgramsMissing(id, programs.clone()).into()
+                            );
+                        }
+                        command.arg("--programs").arg(programs);
+                    }
+
                     info!("Generating genesis for {id} with command: {command:?}");
 
                     let res = command
@@ -374,52 +444,9 @@ impl Document {
             *ACCOUNTS_KEY_ID,
             read_to_addrs(pick_additional_addr, &base.join("accounts.json")).await?,
         );
+        write_manifest(&aot_bin, network, id, &base, *ACCOUNTS_KEY_ID).await?;
 
-        if let Some(generation) = &self.generate {
-            for (name, account) in &generation.accounts {
-                let path = base.join(format!("{}.json", name));
-
-                if !path.exists() {
-                    info!("generating accounts for {name}");
-
-                    let mut command = Command::new(&aot_bin);
-                    command
-                        .stdout(Stdio::inherit())
-                        .stderr(Stdio::inherit())
-                        .env("NETWORK", network.to_string())
-                        .arg("accounts")
-                        .arg(account.count.to_string())
-                        .arg("--output")
-                        .arg(&path);
-                    if let Some(seed) = account.seed {
-                        command.arg("--seed").arg(seed.to_string());
-                    }
-
-                    let res = command
-                        .spawn()
-                        .map_err(|e| {
-                            StorageError::Command(
-                                CommandError::action("spawning", "aot accounts", e),
-                                id,
-                            )
-                        })?
-                        .wait()
-                        .await
-                        .map_err(|e| {
-                            StorageError::Command(
-                                CommandError::action("waiting", "aot accounts", e),
-                                id,
-                            )
-                        })?;
-
-                    if !res.success() {
-                        warn!("failed to run account generation command for {name}...");
-                    }
-                }
-
-                accounts.insert(*name, read_to_addrs(pick_account_addr, &path).await?);
-            }
-        }
+        accounts.extend(keysets);
 
         // write the regen version to a "version" file
         tokio::fs::write(&version_file, self.regen.to_string())
@@ -461,6 +488,10 @@ impl Document {
         // otherwise read the committee from the committee.json file
         let committee = read_to_addrs(pick_commitee_addr, &committee_file).await?;
 
+        let genesis_sha256 = (!native_genesis)
+            .then(|| LoadedStorage::hash_genesis(id, &base))
+            .flatten();
+
         let storage = Arc::new(LoadedStorage {
             version: self.regen,
             id,
@@ -470,8 +501,13 @@ impl Document {
             retention_policy: self.retention_policy,
             persist: self.persist,
             native_genesis,
+            genesis_sha256,
             binaries,
         });
+
+        // a freshly generated storage replaces anything already in the backend
+        storage.upload_to_backend(state, !exists).await?;
+
         if let Err(e) = state
             .db
             .storage
@@ -484,3 +520,132 @@ impl Document {
         Ok(storage)
     }
 }
+
+/// Path to a keyset's private keys in a storage directory
+pub fn keyset_path(base: &Path, name: InternedId) -> PathBuf {
+    base.join(format!("{name}.json"))
+}
+
+/// Path to a keyset's shareable address and view key manifest in a storage
+/// directory
+pub fn keyset_manifest_path(base: &Path, name: InternedId) -> PathBuf {
+    base.join(format!("{name}.manifest.json"))
+}
+
+/// Read the keyset files imported from within a storage directory
+async fn read_keyset_imports(
+    generation: Option<&StorageGeneration>,
+    base: &Path,
+) -> Vec<(PathBuf, Vec<u8>)> {
+    let mut imports = vec![];
+    for account in generation.iter().flat_map(|g| g.accounts.values()) {
+        let Some(path) = account.import_path(base).filter(|p| p.starts_with(base)) else {
+            continue;
+        };
+        if let Ok(contents) = tokio::fs::read(&path).await {
+            imports.push((path, contents));
+        }
+    }
+    imports
+}
+
+/// Generate or import a keyset's accounts, returning the keyset's addresses
+async fn prepare_keyset(
+    aot_bin: &Path,
+    network: NetworkId,
+    id: StorageId,
+    base: &Path,
+    name: InternedId,
+    account: &Accounts,
+) -> Result<AleoAddrMap, StorageError> {
+    let path = keyset_path(base, name);
+
+    if !path.exists() {
+        info!(
+            "{} accounts for {name}",
+            match account.file {
+                Some(_) => "importing",
+                None => "generating",
+            }
+        );
+
+        let mut command = Command::new(aot_bin);
+        command
+            .stdout(Stdio::inherit())
+            .stderr(Stdio::inherit())
+            .env("NETWORK", network.to_string())
+            .arg("accounts")
+            .arg(account.count.to_string())
+            .arg("--output")
+            .arg(&path)
+            .arg("--manifest-output")
+            .arg(keyset_manifest_path(base, name));
+        match account.import_path(base) {
+            Some(file) => {
+                if !file.exists() {
+                    return Err(StorageError::KeysetFileMissing(id, name, file));
+                }
+                command.arg("--import").arg(file);
+            }
+            None => {
+                if let Some(seed) = account.seed {
+                    command.arg("--seed").arg(seed.to_string());
+                }
+            }
+        }
+
+        let res = command
+            .spawn()
+            .map_err(|e| {
+                StorageError::Command(CommandError::action("spawning", "aot accounts", e), id)
+            })?
+            .wait()
+            .await
+            .map_err(|e| {
+                StorageError::Command(CommandError::action("waiting", "aot accounts", e), id)
+            })?;
+
+        if !res.success() {
+            warn!("failed to run account generation command for {name}...");
+        }
+    }
+
+    // keysets generated before manifests existed
+    write_manifest(aot_bin, network, id, base, name).await?;
+
+    read_to_addrs(pick_account_addr, &path).await
+}
+
+/// Write the address and view key manifest for a keyset if it is missing
+async fn write_manifest(
+    aot_bin: &Path,
+    network: NetworkId,
+    id: StorageId,
+    base: &Path,
+    name: InternedId,
+) -> Result<(), StorageError> {
+    let (path, manifest) = (keyset_path(base, name), keyset_manifest_path(base, name));
+    if !path.exists() || manifest.exists() {
+        return Ok(());
+    }
+
+    let res = Command::new(aot_bin)
+        .stdout(Stdio::null())
+        .stderr(Stdio::inherit())
+        .env("NETWORK", network.to_string())
+        .arg("accounts")
+        .arg("--import")
+        .arg(&path)
+        .arg("--manifest-output")
+        .arg(&manifest)
+        .status()
+        .await
+        .map_err(|e| {
+            StorageError::Command(CommandError::action("running", "aot accounts", e), id)
+        })?;
+
+    if !res.success() {
+        warn!("failed to write the account manifest for {name}...");
+    }
+    Ok(())
+}
@@ -0,0 +1,258 @@
+use std::{convert::Infallible, sync::Arc, time::Duration};
+
+use axum::{
+    Json,
+    body::Body,
+    extract::{Query, State},
+    response::{IntoResponse, Response},
+};
+use futures_util::{StreamExt, stream};
+use http::StatusCode;
+use serde::Serialize;
+use snops_common::{
+    action_models::{AleoValue, BatchExecuteAction},
+    lasso::Spur,
+    rpc::control::agent::AuthorizeRequest,
+    state::{Authorization, KeyState, id_or_none},
+};
+use tokio::sync::mpsc;
+use tracing::{info, warn};
+
+use super::Env;
+use crate::{
+    cannon::{
+        CannonInstance, error::AuthorizeError, router::AuthQuery, tracker::TransactionSchedule,
+    },
+    env::{Environment, error::ExecutionError, set::wait_for_compute_agent},
+    json_response,
+    server::error::ServerError,
+    state::{GlobalState, compute::compute_slots_for},
+};
+
+/// How long an authorization waits for a compute agent to become available
+const AGENT_WAIT: Duration = Duration::from_secs(300);
+
+/// The outcome of one transaction in a batch, streamed as a line of JSON
+#[derive(Debug, Serialize)]
+struct BatchProgress {
+    index: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tx_id: Option<Arc<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// The last line of a batch response
+#[derive(Debug, Serialize)]
+struct BatchSummary {
+    /// Transaction ids of the authorizations sent to the cannon, by index
+    tx_ids: Vec<Option<Arc<String>>>,
+    failed: u32,
+}
+
+/// Render and authorize a batch of executions of a function on compute agents
+/// in parallel, sending each authorization to the cannon. The response streams
+/// a line of JSON per transaction as it is sent, followed by a summary with
+/// every transaction id. In async mode the response is sent once the batch is
+/// started.
+pub async fn batch_execute(
+    State(state): State<Arc<GlobalState>>,
+    Env { env, .. }: Env,
+    Query(query): Query<AuthQuery>,
+    Json(action): Json<BatchExecuteAction>,
+) -> Response {
+    let Some(cannon_id) = id_or_none(&action.cannon) else {
+        return ServerError::from(ExecutionError::UnknownCannon(action.cannon)).into_response();
+    };
+    let Some(cannon) = env.cannons.get(&cannon_id).cloned() else {
+        return ServerError::from(ExecutionError::UnknownCannon(action.cannon)).into_response();
+    };
+    if action.count == 0 {
+        return json_response!(BAD_REQUEST, { "error": "count must be greater than 0" });
+    }
+    if action.count > BatchExecuteAction::MAX_COUNT {
+        return json_response!(BAD_REQUEST, {
+            "error": format!("count must be at most {}", BatchExecuteAction::MAX_COUNT)
+        });
+    }
+
+    // render every request up front so template errors are reported before
+    // anything is sent
+    let requests = match render_requests(&env, &action) {
+        Ok(requests) => requests,
+        Err(e) => return ServerError::from(ExecutionError::from(e)).into_response(),
+    };
+
+    let labels = cannon.source.compute.agent_labels().to_vec();
+    let concurrency = action
+        .concurrency
+        .unwrap_or_else(|| compute_slots_for(&state.pool, &labels))
+        .max(1);
+
+    info!(
+        "env {} invoked batch execute action for {} {}/{} on {concurrency} compute slots",
+        env.id, action.count, action.program, action.function
+    );
+
+    let schedule = query.schedule();
+    let (tx, mut rx) = mpsc::unbounded_channel::<String>();
+    tokio::spawn(async move {
+        let mut tx_ids = vec![None; requests.len()];
+        let mut failed = 0;
+
+        let mut results = stream::iter(requests.into_iter().enumerate())
+            .map(|(index, request)| {
+                let (state, env, cannon, labels) = (&state, &env, &cannon, &labels);
+                let schedule = schedule.clone();
+                async move {
+                    let res =
+                        authorize_and_send(state, env, cannon, labels, request, schedule).await;
+                    (index, res)
+                }
+            })
+            .buffer_unordered(concurrency);
+
+        while let Some((index, res)) = results.next().await {
+            let progress = match res {
+                Ok(tx_id) => {
+                    tx_ids[index] = Some(Arc::clone(&tx_id));
+                    BatchProgress {
+                        index: index as u32,
+                        tx_id: Some(tx_id),
+                        error: None,
+                    }
+                }
+                Err(error) => {
+                    warn!("env {} batch execute {index} failed: {error}", env.id);
+                    failed += 1;
+                    BatchProgress {
+                        index: index as u32,
+                        tx_id: None,
+                        error: Some(error),
+                    }
+                }
+            };
+            // the client may have disconnected, the batch continues regardless
+            let _ = tx.send(json_line(&progress));
+        }
+
+        let _ = tx.send(json_line(&BatchSummary { tx_ids, failed }));
+    });
+
+    // async requests respond as soon as the batch is started. Its progress
+    // can be followed with the cannon's events and transactions
+    if query.is_async() {
+        return StatusCode::ACCEPTED.into_response();
+    }
+
+    let body = stream::poll_fn(move |cx| rx.poll_recv(cx)).map(Ok::<_, Infallible>);
+    Response::builder()
+        .header(http::header::CONTENT_TYPE, "application/x-ndjson")
+        .body(Body::from_stream(body))
+        .unwrap_or_else(|_| StatusCode::INTERNAL_SERVER_ERROR.into_response())
+}
+
+fn json_line(value: &impl Serialize) -> String {
+    let mut line = serde_json::to_string(value).unwrap_or_default();
+    line.push('\n');
+    line
+}
+
+/// Render the private key and input templates for each transaction
+fn render_requests(
+    env: &Environment,
+    action: &BatchExecuteAction,
+) -> Result<Vec<AuthorizeRequest>, AuthorizeError> {
+    let locator = format!("{}/{}", action.program, action.function);
+    let invalid = |msg: String| AuthorizeError::InvalidProgramInputs(locator.clone(), msg);
+    let mut rng = rand::thread_rng();
+
+    let fee_private_key = match &action.fee_private_key {
+        Some(key) => match env.storage.sample_keysource_pk(key) {
+            KeyState::Literal(pk) => Some(pk),
+            _ => {
+                return Err(AuthorizeError::MissingPrivateKey(
+                    format!("{}.{} {locator}", env.id, action.cannon),
+                    key.to_string(),
+                ));
+            }
+        },
+        None => None,
+    };
+
+    (0..action.count)
+        .map(|index| {
+            let private_key = match action.private_key.render(index, &mut rng) {
+                Ok(AleoValue::Key(key)) => match env.storage.sample_keysource_pk(&key) {
+                    KeyState::Literal(pk) => pk,
+                    _ => {
+                        return Err(AuthorizeError::MissingPrivateKey(
+                            format!("{}.{} {locator}", env.id, action.cannon),
+                            key.to_string(),
+                        ));
+                    }
+                },
+                Ok(AleoValue::Other(value)) => {
+                    return Err(AuthorizeError::MissingPrivateKey(
+                        format!("{}.{} {locator}", env.id, action.cannon),
+                        value,
+                    ));
+                }
+                Err(e) => return Err(invalid(format!("private key: {e}"))),
+            };
+
+            let inputs = action
+                .inputs
+                .iter()
+                .map(|input| match input.render(index, &mut rng) {
+                    Ok(AleoValue::Key(key)) => match env.storage.sample_keysource_addr(&key) {
+                        KeyState::Literal(addr) => Ok(addr),
+                        _ => Err(invalid(format!("key {key} does not resolve a valid addr"))),
+                    },
+                    Ok(AleoValue::Other(value)) => Ok(value),
+                    Err(e) => Err(invalid(format!("input `{input}`: {e}"))),
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+
+            Ok(AuthorizeRequest {
+                private_key,
+                fee_private_key: fee_private_key.clone(),
+                program: action.program.clone(),
+                function: action.function.clone(),
+                inputs,
+                query: format!("/api/v1/env/{}/cannons/{}", env.id, action.cannon),
+                priority_fee: action.priority_fee,
+                // use cost_v1 when we are not using the native genesis
+                cost_v1: !env.storage.native_genesis,
+            })
+        })
+        .collect()
+}
+
+/// Authorize a request on a compute agent and send it to the cannon
+async fn authorize_and_send(
+    state: &GlobalState,
+    env: &Environment,
+    cannon: &CannonInstance,
+    labels: &[Spur],
+    request: AuthorizeRequest,
+    schedule: TransactionSchedule,
+) -> Result<Arc<String>, String> {
+    // the agent's slot stays claimed until the authorization is done
+    let lease = wait_for_compute_agent(state, (env.id, Some(cannon.id)), labels, AGENT_WAIT)
+        .await
+        .ok_or_else(|| "no compute agents available".to_owned())?;
+
+    let auth_str = lease
+        .client
+        .authorize_program(env.id, env.network, request)
+        .await
+        .map_err(|e| e.to_string())?;
+    let authorization: Authorization =
+        serde_json::from_str(&auth_str).map_err(|e| AuthorizeError::Json(e).to_string())?;
+
+    cannon
+        .proxy_auth(authorization, schedule)
+        .await
+        .map_err(|e| e.to_string())
+}
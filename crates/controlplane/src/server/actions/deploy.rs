@@ -6,15 +6,20 @@ use axum::{
     response::{IntoResponse, Response},
 };
 use http::StatusCode;
+use serde_json::json;
 use snops_common::{
-    action_models::DeployAction,
+    action_models::{AUTO_FEE_RECORD, DeployAction, FeeBreakdown},
     aot_cmds::AotCmd,
+    rpc::control::agent::EstimateFeeRequest,
     state::{Authorization, KeyState, id_or_none},
 };
 
-use super::{Env, execute::execute_status};
+use super::{
+    Env,
+    execute::{estimate_fee, execute_status},
+};
 use crate::{
-    cannon::{error::AuthorizeError, router::AuthQuery},
+    cannon::{error::AuthorizeError, router::AuthQuery, tracker::TransactionSchedule},
     env::{Environment, error::ExecutionError},
     server::error::ServerError,
     state::GlobalState,
@@ -30,14 +35,21 @@ pub async fn deploy(
     let cannon_id = unwrap_or_not_found!("unknown cannon id", id_or_none(&action.cannon));
     let query_addr = env.cannons.get(&cannon_id).map(|c| c.get_local_query());
 
+    if action.dry_run {
+        return match estimate_deploy_fee(&state, action, &env).await {
+            Ok(fee) => Json(json!({ "fee": fee, "total": fee.total() })).into_response(),
+            Err(e) => ServerError::from(e).into_response(),
+        };
+    }
+
     if query.is_async() {
-        return match deploy_inner(&state, action, &env, query_addr).await {
+        return match deploy_inner(&state, action, &env, query_addr, query.schedule()).await {
             Ok(tx_id) => (StatusCode::ACCEPTED, Json(tx_id)).into_response(),
             Err(e) => ServerError::from(e).into_response(),
         };
     }
 
-    match deploy_inner(&state, action, &env, query_addr).await {
+    match deploy_inner(&state, action, &env, query_addr, query.schedule()).await {
         Ok(tx_id) => {
             use snops_common::events::EventFilter::*;
             let subscriber = state
@@ -54,6 +66,7 @@ pub async fn deploy_inner(
     action: DeployAction,
     env: &Environment,
     query: Option<String>,
+    schedule: TransactionSchedule,
 ) -> Result<Arc<String>, ExecutionError> {
     let DeployAction {
         cannon: cannon_id,
@@ -62,6 +75,7 @@ pub async fn deploy_inner(
         program,
         priority_fee,
         fee_record,
+        dry_run: _,
     } = action;
 
     let Some(cannon_id) = id_or_none(&cannon_id) else {
@@ -94,38 +108,106 @@ pub async fn deploy_inner(
     };
 
     let compute_bin = env.storage.resolve_compute_binary(state).await?;
-    // authorize the transaction
     let aot = AotCmd::new(compute_bin, env.network);
-    let mut auth_str = aot
-        .authorize_deploy(
-            &resolved_pk,
-            resolved_fee_pk.as_ref(),
-            &program,
-            query.as_ref(),
-            priority_fee,
-            fee_record.as_ref(),
-            // use cost_v1 when we are not using the native genesis
-            !env.storage.native_genesis,
-        )
-        .await?;
+    // use cost_v1 when we are not using the native genesis
+    let cost_v1 = !env.storage.native_genesis;
+
+    // select a fee record that can pay for the deployment
+    let reserved = match fee_record.as_deref() {
+        Some(AUTO_FEE_RECORD) => {
+            let fee = aot
+                .estimate_deploy_fee(&resolved_pk, &program, query.as_ref(), priority_fee)
+                .await?;
+            let fee_pk = resolved_fee_pk.as_ref().unwrap_or(&resolved_pk);
+            Some(cannon.reserve_fee_record(&aot, fee_pk, fee.total()).await?)
+        }
+        _ => None,
+    };
+    let fee_record = match &reserved {
+        Some((_, record)) => Some(record),
+        None => fee_record.as_ref(),
+    };
+
+    // authorize the transaction
+    let res = async {
+        let mut auth_str = aot
+            .authorize_deploy(
+                &resolved_pk,
+                resolved_fee_pk.as_ref(),
+                &program,
+                query.as_ref(),
+                priority_fee,
+                fee_record,
+                cost_v1,
+            )
+            .await?;
+
+        // Truncate the output to the first {
+        // because Aleo decided to print execute
+        // status to stdout...
+        if let Some(index) = auth_str.find("{") {
+            auth_str = auth_str.split_off(index);
+        }
+
+        // parse the json and bundle it up
+        let authorization: Authorization = serde_json::from_str(&auth_str)
+            .inspect_err(|e| {
+                tracing::error!("failed to parse authorization json: {e}");
+                tracing::error!("authorization json: {auth_str}");
+            })
+            .map_err(AuthorizeError::Json)?;
+
+        // proxy it to a listen cannon
+        Ok::<_, ExecutionError>(cannon.proxy_auth(authorization, schedule).await?)
+    }
+    .await;
 
-    // Truncate the output to the first {
-    // because Aleo decided to print execute
-    // status to stdout...
-    if let Some(index) = auth_str.find("{") {
-        auth_str = auth_str.split_off(index);
+    // the record stays reserved until the transaction is archived, or is free
+    // to use again if it was never authorized
+    match (&res, &reserved) {
+        (Ok(tx_id), Some((commitment, _))) => cannon.bind_fee_record(commitment, tx_id),
+        (Err(_), Some((commitment, _))) => cannon.release_fee_record(commitment),
+        _ => {}
     }
 
-    // parse the json and bundle it up
-    let authorization: Authorization = serde_json::from_str(&auth_str)
-        .inspect_err(|e| {
-            tracing::error!("failed to parse authorization json: {e}");
-            tracing::error!("authorization json: {auth_str}");
-        })
-        .map_err(AuthorizeError::Json)?;
+    res
+}
 
-    // proxy it to a listen cannon
-    let tx_id = cannon.proxy_auth(authorization).await?;
+/// Estimate the fee of a deployment on a compute agent without authorizing or
+/// broadcasting it
+pub async fn estimate_deploy_fee(
+    state: &GlobalState,
+    action: DeployAction,
+    env: &Environment,
+) -> Result<FeeBreakdown, ExecutionError> {
+    let DeployAction {
+        cannon: cannon_id,
+        private_key,
+        program,
+        priority_fee,
+        ..
+    } = action;
+    let Some(cannon_id) = id_or_none(&cannon_id) else {
+        return Err(ExecutionError::UnknownCannon(cannon_id));
+    };
+    let Some(cannon) = env.cannons.get(&cannon_id) else {
+        return Err(ExecutionError::UnknownCannon(cannon_id.to_string()));
+    };
+
+    let KeyState::Literal(resolved_pk) = env.storage.sample_keysource_pk(&private_key) else {
+        return Err(AuthorizeError::MissingPrivateKey(
+            format!("{}.{cannon_id} deployed program", env.id),
+            private_key.to_string(),
+        )
+        .into());
+    };
+
+    let request = EstimateFeeRequest::Deploy {
+        private_key: resolved_pk,
+        program,
+        query: format!("/api/v1/env/{}/cannons/{cannon_id}", env.id),
+        priority_fee,
+    };
 
-    Ok(tx_id)
+    estimate_fee(state, env, cannon.source.compute.agent_labels(), request).await
 }
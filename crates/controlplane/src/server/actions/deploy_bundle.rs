@@ -0,0 +1,260 @@
+use std::{sync::Arc, time::Duration};
+
+use axum::{
+    Json,
+    extract::{Query, State},
+    response::{IntoResponse, Response},
+};
+use http::StatusCode;
+use serde::Serialize;
+use snops_common::{
+    action_models::{DeployAction, DeployBundleAction},
+    aot_cmds::AotCmd,
+    events::{Event, EventKind},
+    program_bundle::BundleProgram,
+    state::{CannonId, id_or_none},
+};
+use tokio::select;
+use tracing::{info, warn};
+
+use super::{Env, deploy::deploy_inner};
+use crate::{
+    cannon::{router::AuthQuery, tracker::TransactionSchedule},
+    env::{Environment, error::ExecutionError},
+    events::EventSubscriber,
+    json_response,
+    server::error::{ActionError, ServerError},
+    state::GlobalState,
+    unwrap_or_not_found,
+};
+
+/// How long to wait for each deployment in a bundle to be confirmed
+const CONFIRMATION_TIMEOUT: Duration = Duration::from_secs(300);
+
+#[derive(Debug, Serialize)]
+struct BundleDeployment {
+    id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cost: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tx_id: Option<Arc<String>>,
+    /// The hash of the block the deployment was confirmed in
+    #[serde(skip_serializing_if = "Option::is_none")]
+    block_hash: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<serde_json::Value>,
+}
+
+/// Deploy a bundle of programs, ordered by their imports. Each deployment
+/// waits for the previous one to be confirmed, and the remaining programs are
+/// skipped once a deployment fails.
+pub async fn deploy_bundle(
+    State(state): State<Arc<GlobalState>>,
+    Env { env, .. }: Env,
+    Query(query): Query<AuthQuery>,
+    Json(action): Json<DeployBundleAction>,
+) -> Response {
+    let cannon_id = unwrap_or_not_found!("unknown cannon id", id_or_none(&action.cannon));
+    let Some(query_addr) = env.cannons.get(&cannon_id).map(|c| c.get_local_query()) else {
+        return ServerError::from(ExecutionError::UnknownCannon(cannon_id.to_string()))
+            .into_response();
+    };
+
+    info!(
+        "env {} invoked deploy bundle action with {} programs",
+        env.id,
+        action.programs.len()
+    );
+
+    // sort the programs and estimate their costs
+    let bundle = match env.storage.resolve_compute_binary(&state).await {
+        Ok(bin) => AotCmd::new(bin, env.network)
+            .program_bundle(&action.programs, Some(&query_addr))
+            .await
+            .map_err(ExecutionError::from),
+        Err(e) => Err(e.into()),
+    };
+    let bundle = match bundle {
+        Ok(bundle) => bundle,
+        Err(e) => return ServerError::from(e).into_response(),
+    };
+
+    let schedule = query.schedule();
+    if query.is_async() {
+        let plan = bundle
+            .iter()
+            .map(|program| BundleDeployment {
+                id: program.id.clone(),
+                cost: program.cost,
+                tx_id: None,
+                block_hash: None,
+                error: None,
+            })
+            .collect::<Vec<_>>();
+
+        tokio::spawn(async move {
+            for res in deploy_programs(
+                &state, &env, cannon_id, query_addr, schedule, action, bundle,
+            )
+            .await
+            {
+                if let Some(error) = res.error {
+                    warn!("env {} failed to deploy {}: {error}", env.id, res.id);
+                }
+            }
+        });
+
+        return (StatusCode::ACCEPTED, Json(plan)).into_response();
+    }
+
+    let deployments = deploy_programs(
+        &state, &env, cannon_id, query_addr, schedule, action, bundle,
+    )
+    .await;
+    if deployments.iter().all(|d| d.block_hash.is_some()) {
+        json_response!(OK, { "programs": deployments })
+    } else {
+        json_response!(INTERNAL_SERVER_ERROR, { "programs": deployments })
+    }
+}
+
+async fn deploy_programs(
+    state: &GlobalState,
+    env: &Environment,
+    cannon_id: CannonId,
+    query_addr: String,
+    schedule: TransactionSchedule,
+    action: DeployBundleAction,
+    bundle: Vec<BundleProgram>,
+) -> Vec<BundleDeployment> {
+    let mut failed = false;
+    let mut deployments = Vec::with_capacity(bundle.len());
+
+    for program in bundle {
+        let mut deployment = BundleDeployment {
+            id: program.id,
+            cost: program.cost,
+            tx_id: None,
+            block_hash: None,
+            error: None,
+        };
+
+        // programs after a failed deployment may depend on it
+        if failed {
+            deployment.error = Some("skipped after a previous deployment failed".into());
+            deployments.push(deployment);
+            continue;
+        }
+
+        let deploy = DeployAction {
+            private_key: action.private_key.clone(),
+            fee_private_key: action.fee_private_key.clone(),
+            program: program.program,
+            cannon: cannon_id.to_string(),
+            priority_fee: action.priority_fee,
+            fee_record: None,
+            dry_run: false,
+        };
+
+        match deploy_inner(
+            state,
+            deploy,
+            env,
+            Some(query_addr.clone()),
+            schedule.clone(),
+        )
+        .await
+        {
+            Ok(tx_id) => {
+                use snops_common::events::EventFilter::*;
+                let subscriber = state.events.subscribe_on(
+                    TransactionIs(tx_id.clone()) & EnvIs(env.id) & CannonIs(cannon_id),
+                );
+                deployment.tx_id = Some(Arc::clone(&tx_id));
+
+                match wait_for_confirmation(tx_id, subscriber).await {
+                    Ok(hash) => deployment.block_hash = Some(hash),
+                    Err(e) => {
+                        let mut json = serde_json::json!(e);
+                        json["error"] = e.to_string().into();
+                        deployment.error = Some(json);
+                    }
+                }
+            }
+            Err(e) => deployment.error = Some(e.to_string().into()),
+        }
+
+        failed = deployment.block_hash.is_none();
+        deployments.push(deployment);
+    }
+
+    deployments
+}
+
+/// Wait for a transaction to be confirmed, returning the hash of the block it
+/// was confirmed in.
+async fn wait_for_confirmation(
+    tx_id: Arc<String>,
+    mut rx: EventSubscriber,
+) -> Result<String, ActionError> {
+    use snops_common::events::TransactionEvent::*;
+
+    let mut timeout = Box::pin(tokio::time::sleep(CONFIRMATION_TIMEOUT));
+    let mut retries = 0;
+
+    loop {
+        select! {
+            _ = &mut timeout => {
+                return Err(ActionError::ConfirmationTimeout { tx_id: tx_id.to_string() });
+            },
+            Ok(ev) = rx.next() => {
+                let Event{ content: EventKind::Transaction(ev), .. } = ev.as_ref() else {
+                    continue;
+                };
+
+                match ev {
+                    ExecuteAborted(reason) => {
+                        return Err(ActionError::ExecuteStatusAborted {
+                            tx_id: tx_id.to_string(),
+                            retries,
+                            reason: reason.clone(),
+                        });
+                    },
+                    ExecuteFailed(message) => {
+                        return Err(ActionError::ExecuteStatusFailed {
+                            message: message.to_string(),
+                            tx_id: tx_id.to_string(),
+                            retries,
+                        });
+                    },
+                    ExecuteExceeded { attempts } | BroadcastExceeded { attempts } => {
+                        return Err(ActionError::ExecuteStatusFailed {
+                            message: format!("gave up after {attempts} attempts"),
+                            tx_id: tx_id.to_string(),
+                            retries,
+                        });
+                    },
+                    ExecuteAwaitingCompute => {
+                        retries += 1;
+                    },
+                    Accepted { hash } => return Ok(hash.clone()),
+                    Rejected { hash } => {
+                        return Err(ActionError::ExecuteStatusFailed {
+                            message: format!("rejected in block {hash}"),
+                            tx_id: tx_id.to_string(),
+                            retries,
+                        });
+                    },
+                    Aborted { hash } => {
+                        return Err(ActionError::ExecuteStatusFailed {
+                            message: format!("aborted in block {hash}"),
+                            tx_id: tx_id.to_string(),
+                            retries,
+                        });
+                    },
+                    _ => (),
+                }
+            },
+        }
+    }
+}
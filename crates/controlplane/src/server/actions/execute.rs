@@ -1,4 +1,4 @@
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 
 use axum::{
     Json,
@@ -8,17 +8,24 @@ use axum::{
 use http::StatusCode;
 use serde_json::json;
 use snops_common::{
-    action_models::{AleoValue, ExecuteAction},
+    action_models::{AUTO_FEE_RECORD, AleoValue, ExecuteAction, FeeBreakdown},
     aot_cmds::AotCmd,
     events::{Event, EventKind},
-    state::{Authorization, KeyState, id_or_none},
+    key_source::KeySource,
+    lasso::Spur,
+    rpc::control::agent::{AuthorizeRequest, EstimateFeeRequest},
+    state::{Authorization, CannonId, KeyState, id_or_none},
 };
 use tokio::select;
 
 use super::Env;
 use crate::{
-    cannon::{error::AuthorizeError, router::AuthQuery},
-    env::{Environment, error::ExecutionError},
+    cannon::{
+        error::{AuthorizeError, CannonError, SourceError},
+        router::AuthQuery,
+        tracker::TransactionSchedule,
+    },
+    env::{Environment, error::ExecutionError, set::wait_for_compute_agent},
     events::EventSubscriber,
     server::error::{ActionError, ServerError},
     state::GlobalState,
@@ -90,14 +97,21 @@ pub async fn execute(
     };
     let query_addr = env.cannons.get(&cannon_id).map(|c| c.get_local_query());
 
+    if action.dry_run {
+        return match estimate_execute_fee(&state, action, &env).await {
+            Ok(fee) => Json(json!({ "fee": fee, "total": fee.total() })).into_response(),
+            Err(e) => ServerError::from(e).into_response(),
+        };
+    }
+
     if query.is_async() {
-        return match execute_inner(&state, action, &env, query_addr).await {
+        return match execute_inner(&state, action, &env, query_addr, query.schedule()).await {
             Ok(tx_id) => (StatusCode::ACCEPTED, Json(tx_id)).into_response(),
             Err(e) => ServerError::from(e).into_response(),
         };
     }
 
-    match execute_inner(&state, action, &env, query_addr).await {
+    match execute_inner(&state, action, &env, query_addr, query.schedule()).await {
         Ok(tx_id) => {
             use snops_common::events::EventFilter::*;
             let subscriber = state
@@ -114,6 +128,7 @@ pub async fn execute_inner(
     action: ExecuteAction,
     env: &Environment,
     query: Option<String>,
+    schedule: TransactionSchedule,
 ) -> Result<Arc<String>, ExecutionError> {
     let ExecuteAction {
         cannon: cannon_id,
@@ -124,6 +139,7 @@ pub async fn execute_inner(
         inputs,
         priority_fee,
         fee_record,
+        dry_run: _,
     } = action;
     let Some(cannon_id) = id_or_none(&cannon_id) else {
         return Err(ExecutionError::UnknownCannon(cannon_id));
@@ -133,21 +149,116 @@ pub async fn execute_inner(
         return Err(ExecutionError::UnknownCannon(cannon_id.to_string()));
     };
 
-    let KeyState::Literal(resolved_pk) = env.storage.sample_keysource_pk(&private_key) else {
+    let (resolved_pk, resolved_fee_pk, resolved_inputs) = resolve_keys_and_inputs(
+        env,
+        cannon_id,
+        &program,
+        &function,
+        &private_key,
+        fee_private_key.as_ref(),
+        &inputs,
+    )?;
+
+    let compute_bin = env.storage.resolve_compute_binary(state).await?;
+    let aot = AotCmd::new(compute_bin, env.network);
+    // use cost_v1 when we are not using the native genesis
+    let cost_v1 = !env.storage.native_genesis;
+
+    // select a fee record that can pay for the execution
+    let reserved = match fee_record.as_deref() {
+        Some(AUTO_FEE_RECORD) => {
+            let fee = aot
+                .estimate_program_fee(
+                    &resolved_pk,
+                    &program,
+                    &function,
+                    &resolved_inputs,
+                    query.as_ref(),
+                    priority_fee,
+                    cost_v1,
+                )
+                .await?;
+            let fee_pk = resolved_fee_pk.as_ref().unwrap_or(&resolved_pk);
+            Some(cannon.reserve_fee_record(&aot, fee_pk, fee.total()).await?)
+        }
+        _ => None,
+    };
+    let fee_record = match &reserved {
+        Some((_, record)) => Some(record),
+        None => fee_record.as_ref(),
+    };
+
+    // authorize the transaction
+    let res = async {
+        let mut auth_str = aot
+            .authorize_program(
+                &resolved_pk,
+                resolved_fee_pk.as_ref(),
+                &program,
+                &function,
+                &resolved_inputs,
+                query.as_ref(),
+                priority_fee,
+                fee_record,
+                cost_v1,
+            )
+            .await?;
+
+        // Truncate the output to the first {
+        // because Aleo decided to print execute
+        // status to stdout...
+        if let Some(index) = auth_str.find("{") {
+            auth_str = auth_str.split_off(index);
+        }
+
+        // parse the json and bundle it up
+        let authorization: Authorization = serde_json::from_str(&auth_str)
+            .inspect_err(|e| {
+                tracing::error!("failed to parse authorization json: {e}");
+                tracing::error!("authorization json: {auth_str}");
+            })
+            .map_err(AuthorizeError::Json)?;
+
+        // proxy it to a listen cannon
+        Ok::<_, ExecutionError>(cannon.proxy_auth(authorization, schedule).await?)
+    }
+    .await;
+
+    // the record stays reserved until the transaction is archived, or is free
+    // to use again if it was never authorized
+    match (&res, &reserved) {
+        (Ok(tx_id), Some((commitment, _))) => cannon.bind_fee_record(commitment, tx_id),
+        (Err(_), Some((commitment, _))) => cannon.release_fee_record(commitment),
+        _ => {}
+    }
+
+    res
+}
+
+/// Resolve the private keys and key inputs of an execution from the env's
+/// storage
+fn resolve_keys_and_inputs(
+    env: &Environment,
+    cannon_id: CannonId,
+    program: &str,
+    function: &str,
+    private_key: &KeySource,
+    fee_private_key: Option<&KeySource>,
+    inputs: &[AleoValue],
+) -> Result<(String, Option<String>, Vec<String>), AuthorizeError> {
+    let KeyState::Literal(resolved_pk) = env.storage.sample_keysource_pk(private_key) else {
         return Err(AuthorizeError::MissingPrivateKey(
             format!("{}.{cannon_id} {program}/{function}", env.id),
             private_key.to_string(),
-        )
-        .into());
+        ));
     };
 
     let resolved_fee_pk = if let Some(fee_key) = fee_private_key {
-        let KeyState::Literal(pk) = env.storage.sample_keysource_pk(&fee_key) else {
+        let KeyState::Literal(pk) = env.storage.sample_keysource_pk(fee_key) else {
             return Err(AuthorizeError::MissingPrivateKey(
                 format!("{}.{cannon_id} {program}/{function}", env.id),
                 fee_key.to_string(),
-            )
-            .into());
+            ));
         };
         Some(pk)
     } else {
@@ -168,41 +279,74 @@ pub async fn execute_inner(
         })
         .collect::<Result<Vec<String>, AuthorizeError>>()?;
 
-    // authorize the transaction
-    let compute_bin = env.storage.resolve_compute_binary(state).await?;
-    let aot = AotCmd::new(compute_bin, env.network);
-    let mut auth_str = aot
-        .authorize_program(
-            &resolved_pk,
-            resolved_fee_pk.as_ref(),
-            &program,
-            &function,
-            &resolved_inputs,
-            query.as_ref(),
-            priority_fee,
-            fee_record.as_ref(),
-            // use cost_v1 when we are not using the native genesis
-            !env.storage.native_genesis,
-        )
-        .await?;
-
-    // Truncate the output to the first {
-    // because Aleo decided to print execute
-    // status to stdout...
-    if let Some(index) = auth_str.find("{") {
-        auth_str = auth_str.split_off(index);
-    }
+    Ok((resolved_pk, resolved_fee_pk, resolved_inputs))
+}
 
-    // parse the json and bundle it up
-    let authorization: Authorization = serde_json::from_str(&auth_str)
-        .inspect_err(|e| {
-            tracing::error!("failed to parse authorization json: {e}");
-            tracing::error!("authorization json: {auth_str}");
-        })
-        .map_err(AuthorizeError::Json)?;
+/// Estimate the fee of an execution on a compute agent without authorizing or
+/// broadcasting it
+pub async fn estimate_execute_fee(
+    state: &GlobalState,
+    action: ExecuteAction,
+    env: &Environment,
+) -> Result<FeeBreakdown, ExecutionError> {
+    let ExecuteAction {
+        cannon: cannon_id,
+        private_key,
+        program,
+        function,
+        inputs,
+        priority_fee,
+        ..
+    } = action;
+    let Some(cannon_id) = id_or_none(&cannon_id) else {
+        return Err(ExecutionError::UnknownCannon(cannon_id));
+    };
+    let Some(cannon) = env.cannons.get(&cannon_id) else {
+        return Err(ExecutionError::UnknownCannon(cannon_id.to_string()));
+    };
 
-    // proxy it to a listen cannon
-    let tx_id = cannon.proxy_auth(authorization).await?;
+    // the fee is paid by the fee key, which doesn't change the estimate
+    let (private_key, _, inputs) = resolve_keys_and_inputs(
+        env,
+        cannon_id,
+        &program,
+        &function,
+        &private_key,
+        None,
+        &inputs,
+    )?;
+
+    let request = EstimateFeeRequest::Program(AuthorizeRequest {
+        private_key,
+        fee_private_key: None,
+        program,
+        function,
+        inputs,
+        query: format!("/api/v1/env/{}/cannons/{cannon_id}", env.id),
+        priority_fee,
+        // use cost_v1 when we are not using the native genesis
+        cost_v1: !env.storage.native_genesis,
+    });
+
+    estimate_fee(state, env, cannon.source.compute.agent_labels(), request).await
+}
+
+/// Estimate a fee on an available compute agent
+pub async fn estimate_fee(
+    state: &GlobalState,
+    env: &Environment,
+    labels: &[Spur],
+    request: EstimateFeeRequest,
+) -> Result<FeeBreakdown, ExecutionError> {
+    let lease = wait_for_compute_agent(state, (env.id, None), labels, Duration::from_secs(30))
+        .await
+        .ok_or(CannonError::from(SourceError::NoAvailableAgents(
+            "fee estimate",
+        )))?;
 
-    Ok(tx_id)
+    Ok(lease
+        .client
+        .estimate_fee(env.id, env.network, request)
+        .await
+        .map_err(CannonError::from)?)
 }
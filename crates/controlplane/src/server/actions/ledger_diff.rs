@@ -0,0 +1,125 @@
+use axum::{
+    Json,
+    response::{IntoResponse, Response},
+};
+use futures_util::future::join_all;
+use indexmap::IndexMap;
+use snops_common::{
+    action_models::{LedgerDiffAction, WithTargets},
+    state::{
+        NodeKey,
+        ledger_digest::{LedgerDiff, diff_transactions},
+    },
+};
+use tracing::info;
+
+use super::Env;
+use crate::{json_response, state::AgentClient};
+
+/// Compare the ledgers of the target nodes against a reference node, reporting
+/// the first diverging height and differing finalize mapping keys per node.
+pub async fn ledger_diff(
+    Env { env, state, .. }: Env,
+    Json(WithTargets {
+        nodes,
+        data: LedgerDiffAction { reference },
+    }): Json<WithTargets<LedgerDiffAction>>,
+) -> Response {
+    info!("env {} invoked ledger diff action for {nodes}", env.id);
+
+    let clients = env
+        .matching_agents(&nodes, &state.pool)
+        .filter_map(|agent| Some((agent.node_key()?.clone(), agent.client_owned()?)))
+        .collect::<IndexMap<NodeKey, AgentClient>>();
+
+    if clients.is_empty() {
+        return json_response!(NOT_FOUND, { "error": "no connected nodes matched" });
+    }
+
+    let mut digests = IndexMap::new();
+    let mut errors = IndexMap::new();
+    let results = join_all(
+        clients
+            .iter()
+            .map(|(key, client)| async move { (key.clone(), client.get_ledger_digest().await) }),
+    )
+    .await;
+    for (key, res) in results {
+        match res {
+            Ok(digest) => {
+                digests.insert(key, digest);
+            }
+            Err(e) => {
+                errors.insert(key, e.to_string());
+            }
+        }
+    }
+
+    // default to the node that is furthest ahead
+    let reference = match reference {
+        Some(key) if digests.contains_key(&key) => key,
+        Some(key) => {
+            return json_response!(BAD_REQUEST, {
+                "error": format!("no ledger digest for reference node {key}"),
+                "errors": errors,
+            });
+        }
+        None => match digests.iter().max_by_key(|(_, d)| d.height()) {
+            Some((key, _)) => key.clone(),
+            None => return json_response!(INTERNAL_SERVER_ERROR, { "errors": errors }),
+        },
+    };
+
+    let reference_digest = &digests[&reference];
+    let mut diffs = IndexMap::new();
+    for (key, digest) in &digests {
+        if *key == reference {
+            continue;
+        }
+
+        let mut diff = reference_digest.diff(digest);
+        if let Some((a, b)) = &diff.block_hashes {
+            diff.transactions = block_transactions(&clients[&reference], &clients[key], a, b).await;
+        }
+        diffs.insert(key.clone(), diff);
+    }
+
+    let heights = digests
+        .iter()
+        .map(|(key, digest)| (key.clone(), digest.height()))
+        .collect::<IndexMap<_, _>>();
+
+    Json(LedgerDiffResponse {
+        reference,
+        heights,
+        diffs,
+        errors,
+    })
+    .into_response()
+}
+
+#[derive(serde::Serialize)]
+struct LedgerDiffResponse {
+    reference: NodeKey,
+    heights: IndexMap<NodeKey, u32>,
+    diffs: IndexMap<NodeKey, LedgerDiff>,
+    errors: IndexMap<NodeKey, String>,
+}
+
+/// Transactions only found in one of the two diverging blocks. Digests from
+/// nodes don't include transactions, so the blocks are looked up separately.
+async fn block_transactions(
+    a: &AgentClient,
+    b: &AgentClient,
+    a_hash: &str,
+    b_hash: &str,
+) -> Option<(Vec<String>, Vec<String>)> {
+    let (a, b) = tokio::join!(
+        a.get_snarkos_block_lite(a_hash.to_owned()),
+        b.get_snarkos_block_lite(b_hash.to_owned())
+    );
+    let (Ok(Some(a)), Ok(Some(b))) = (a, b) else {
+        return None;
+    };
+    Some(diff_transactions(&a.transactions, &b.transactions))
+}
@@ -12,9 +12,12 @@ use snops_common::state::{EnvId, id_or_none};
 use super::error::ServerError;
 use crate::{env::Environment, state::AppState};
 
+mod batch_execute;
 mod config;
 pub mod deploy;
+mod deploy_bundle;
 pub mod execute;
+mod ledger_diff;
 mod power;
 
 #[macro_export]
@@ -97,5 +100,8 @@ pub(super) fn routes() -> Router<AppState> {
         .route("/reboot", post(power::reboot))
         .route("/config", post(config::config))
         .route("/execute", post(execute::execute))
+        .route("/batch_execute", post(batch_execute::batch_execute))
         .route("/deploy", post(deploy::deploy))
+        .route("/deploy_bundle", post(deploy_bundle::deploy_bundle))
+        .route("/ledger_diff", post(ledger_diff::ledger_diff))
 }
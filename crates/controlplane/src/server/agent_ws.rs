@@ -228,7 +228,11 @@ async fn handle_socket(
         drop(agent);
 
         match client2.handshake(context::current(), handshake).await {
-            Ok(()) => event.emit(&state2),
+            Ok(()) => {
+                event.emit(&state2);
+                // queued authorizations may be waiting for this agent
+                state2.compute.wake();
+            }
             Err(e) => error!("failed to perform agent {id} handshake: {e}"),
         }
 
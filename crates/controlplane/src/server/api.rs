@@ -3,7 +3,7 @@ use std::{collections::HashMap, str::FromStr};
 use axum::{
     Json, Router,
     extract::{self, Path, Query, State},
-    http::StatusCode,
+    http::{StatusCode, header},
     response::{IntoResponse, Response},
     routing::{delete, get, post},
 };
@@ -21,8 +21,14 @@ use tarpc::context;
 
 use super::{actions, error::ServerError, event_ws, models::AgentStatusResponse};
 use crate::{
-    cannon::{router::redirect_cannon_routes, source::QueryTarget},
+    cannon::{
+        error::CannonError,
+        history::{HistoryFormat, HistoryQuery, history_csv, history_jsonl, read_history},
+        router::redirect_cannon_routes,
+        source::QueryTarget,
+    },
     make_env_filter,
+    schema::storage::keyset_manifest_path,
     state::AppState,
 };
 use crate::{
@@ -58,6 +64,9 @@ pub(super) fn routes() -> Router<AppState> {
         .route("/agents/:id", get(get_agent))
         .route("/agents/:id/status", get(get_agent_status))
         .route("/agents/:id/kill", post(kill_agent))
+        .route("/agents/:id/cordon", post(cordon_agent))
+        .route("/agents/:id/uncordon", post(uncordon_agent))
+        .route("/agents/:id/drain", post(drain_agent))
         .route("/agents/:id/tps", get(get_agent_tps))
         .route("/agents/:id/log/:level", post(set_agent_log_level))
         .route("/agents/:id/aot/log/:verbosity", post(set_aot_log_level))
@@ -80,6 +89,7 @@ pub(super) fn routes() -> Router<AppState> {
         // .route("/env/:env_id/metric/:prom_ql", get())
         .route("/env/:env_id/apply", post(post_env_apply))
         .route("/env/:env_id/info", get(get_env_info))
+        .route("/env/:env_id/keysets/:keyset", get(get_env_keyset))
         .route("/env/:env_id/height", get(get_latest_height))
         .route("/env/:env_id/block_info", get(get_env_block_info))
         .route("/env/:env_id/balance/:key", get(get_env_balance))
@@ -95,6 +105,7 @@ pub(super) fn routes() -> Router<AppState> {
             get(get_mapping_value),
         )
         .route("/env/:env_id/program/:program/mappings", get(get_mappings))
+        .route("/env/:env_id/history", get(get_env_history))
         .nest("/env/:env_id/cannons", redirect_cannon_routes())
         .route("/env/:id", delete(delete_env))
         .nest("/env/:env_id/action", actions::routes())
@@ -166,6 +177,71 @@ async fn get_env_info(Path(env_id): Path<String>, state: State<AppState>) -> Res
     Json(env.info(&state)).into_response()
 }
 
+/// Get an env's transaction history, filtered by cannon and outcome, as JSON,
+/// JSONL, or CSV
+async fn get_env_history(
+    Path(env_id): Path<String>,
+    state: State<AppState>,
+    Query(query): Query<HistoryQuery>,
+) -> Response {
+    // the history outlives the env for a while, so the env doesn't need to
+    // exist
+    let env_id = unwrap_or_not_found!("unknown environment id", id_or_none(&env_id));
+
+    let entries = match read_history(&state, env_id, &query) {
+        Ok(entries) => entries,
+        Err(e) => return ServerError::from(CannonError::from(e)).into_response(),
+    };
+
+    match query.format {
+        HistoryFormat::Json => Json(entries).into_response(),
+        HistoryFormat::Jsonl => match history_jsonl(&entries) {
+            Ok(body) => ([(header::CONTENT_TYPE, "application/x-ndjson")], body).into_response(),
+            Err(e) => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({ "error": format!("failed to serialize history: {e}") })),
+            )
+                .into_response(),
+        },
+        HistoryFormat::Csv => {
+            ([(header::CONTENT_TYPE, "text/csv")], history_csv(&entries)).into_response()
+        }
+    }
+}
+
+/// Get a keyset's manifest of addresses and view keys, which excludes the
+/// private keys
+async fn get_env_keyset(
+    Path((env_id, keyset)): Path<(String, String)>,
+    state: State<AppState>,
+) -> Response {
+    let env_id = unwrap_or_not_found!("unknown environment id", id_or_none(&env_id));
+    let env = unwrap_or_not_found!("environment not found", state.get_env(env_id));
+    let keyset = unwrap_or_not_found!("unknown keyset", id_or_none(&keyset));
+
+    // only keysets in the storage have files to read
+    if !env.storage.accounts.contains_key(&keyset) {
+        return ServerError::NotFound("keyset not found".to_owned()).into_response();
+    }
+
+    let path = keyset_manifest_path(&env.storage.path(&state), keyset);
+    let manifest = match tokio::fs::read_to_string(&path).await {
+        Ok(manifest) => manifest,
+        Err(_) => {
+            return ServerError::NotFound("keyset manifest not found".to_owned()).into_response();
+        }
+    };
+
+    match serde_json::from_str::<serde_json::Value>(&manifest) {
+        Ok(manifest) => Json(manifest).into_response(),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": format!("invalid keyset manifest: {e}") })),
+        )
+            .into_response(),
+    }
+}
+
 async fn get_latest_height(Path(env_id): Path<String>, state: State<AppState>) -> Response {
     let env_id = unwrap_or_not_found!("unknown environment id", id_or_none(&env_id));
     let env = unwrap_or_not_found!("environment not found", state.get_env(env_id));
@@ -384,6 +460,55 @@ async fn kill_agent(state: State<AppState>, Path(id): Path<String>) -> Response
     Json("ok").into_response()
 }
 
+async fn cordon_agent(state: State<AppState>, Path(id): Path<String>) -> Response {
+    set_agent_cordoned(&state, id, true)
+}
+
+async fn uncordon_agent(state: State<AppState>, Path(id): Path<String>) -> Response {
+    set_agent_cordoned(&state, id, false)
+}
+
+fn set_agent_cordoned(state: &AppState, id: String, cordoned: bool) -> Response {
+    let id = unwrap_or_not_found!("unknown agent id", id_or_none(&id));
+    let mut agent = unwrap_or_not_found!("agent not found", state.pool.get_mut(&id));
+
+    agent.set_cordoned(cordoned);
+    if let Err(e) = state.db.agents.save(&id, &agent) {
+        tracing::error!("failed to save agent {id} to the database: {e}");
+    }
+
+    Json(AgentStatusResponse::from(agent.value())).into_response()
+}
+
+/// Cordon an agent and move its env node (if any) to another matching
+/// inventory agent, returning the drained agent to inventory.
+async fn drain_agent(state: State<AppState>, Path(id): Path<String>) -> Response {
+    let id = unwrap_or_not_found!("unknown agent id", id_or_none(&id));
+    let env_id = {
+        let mut agent = unwrap_or_not_found!("agent not found", state.pool.get_mut(&id));
+        agent.set_cordoned(true);
+        if let Err(e) = state.db.agents.save(&id, &agent) {
+            tracing::error!("failed to save agent {id} to the database: {e}");
+        }
+        agent.env()
+    };
+
+    // agents that are not running a node have nothing to drain
+    let Some(env_id) = env_id else {
+        return Json(json!({ "agent": id, "moved": null })).into_response();
+    };
+    let env = unwrap_or_not_found!("environment not found", state.get_env(env_id));
+
+    match env.reassign_agent_node(&state, id).await {
+        Ok((node_key, to)) => Json(json!({
+            "agent": id,
+            "moved": { "env": env_id, "node_key": node_key, "to": to },
+        }))
+        .into_response(),
+        Err(e) => ServerError::from(e).into_response(),
+    }
+}
+
 async fn get_agent_tps(state: State<AppState>, Path(id): Path<String>) -> Response {
     let id = unwrap_or_not_found!("unknown agent id", id_or_none(&id));
     let agent = unwrap_or_not_found!("agent not found", state.pool.get(&id));
@@ -510,6 +635,7 @@ async fn find_agents(
         mode: payload.mode,
         labels: payload.labels,
         local_pk: payload.local_pk,
+        compute_slots: 1,
     }
     .mask(&labels_vec);
     let agents = state
@@ -1,27 +1,31 @@
-use std::str::FromStr;
+use std::{net::SocketAddr, str::FromStr, time::Duration};
 
 use axum::{
     Router,
-    extract::{Path, Request, State},
+    extract::{Path, Query, Request, State},
     middleware,
     response::{IntoResponse, Redirect, Response},
     routing::get,
 };
-use http::{StatusCode, Uri};
+use http::{Method, StatusCode, Uri};
+use rand::{Rng, seq::IteratorRandom};
+use serde::Deserialize;
 use snops_common::{
     binaries::{BinaryEntry, BinarySource},
-    state::{InternedId, NetworkId, id_or_none},
+    state::{AgentId, InternedId, NetworkId, id_or_none},
 };
 use tower::Service;
 use tower_http::services::ServeFile;
+use tracing::warn;
 
 use crate::{
     schema::{
         error::StorageError,
         storage::{DEFAULT_AGENT_BINARY, DEFAULT_AOT_BINARY},
     },
-    server::error::ServerError,
-    state::{AppState, GlobalState},
+    server::{error::ServerError, rpc::resolve_one_addr},
+    state::{AppState, GlobalState, REST_CLIENT},
+    storage_backend::{StorageBackend, binary_key, storage_key},
     unwrap_or_bad_request, unwrap_or_not_found,
 };
 
@@ -73,9 +77,23 @@ pub(super) async fn init_routes(state: &GlobalState) -> Router<AppState> {
         .layer(middleware::map_response(not_found))
 }
 
+/// How long a peer has to respond to a health check before the control plane
+/// serves a file itself
+const PEER_HEALTH_TIMEOUT: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Default, Deserialize)]
+struct ContentQuery {
+    /// The agent requesting the file, used to find peers that can serve it
+    agent: Option<AgentId>,
+    /// Serve the file from the control plane rather than a peer
+    #[serde(default)]
+    direct: bool,
+}
+
 /// Serve a binary from the storage or a redirect to the binary
 async fn serve_binary(
     Path((network, storage_id, binary_id)): Path<(NetworkId, String, String)>,
+    Query(query): Query<ContentQuery>,
     State(state): State<AppState>,
     req: Request,
 ) -> Response {
@@ -89,7 +107,22 @@ async fn serve_binary(
     .clone();
 
     match storage.resolve_binary_entry(binary_id) {
-        Ok((id, entry)) => respond_from_entry(id, entry, req).await,
+        Ok((id, entry)) => {
+            // local binaries may have been uploaded to a remote storage backend
+            if entry.is_api_file() {
+                if let Some(sha256) = entry.sha256.as_deref() {
+                    if let Some(res) = redirect_to_peer(&state, &query, sha256).await {
+                        return res;
+                    }
+                }
+
+                let key = binary_key(network, storage_id, id);
+                if let Some(res) = redirect_to_backend(&state, req.method(), &key) {
+                    return res;
+                }
+            }
+            respond_from_entry(id, entry, req).await
+        }
         Err(e) => ServerError::from(e).into_response(),
     }
 }
@@ -105,8 +138,79 @@ async fn respond_from_entry(id: InternedId, entry: &BinaryEntry, req: Request) -
     }
 }
 
+/// Redirect to an object in the storage backend, or return `None` if the
+/// object is served by the control plane
+fn redirect_to_backend(state: &GlobalState, method: &Method, key: &str) -> Option<Response> {
+    match state.storage_backend.presign(method, key) {
+        Ok(Some(url)) => Some(Redirect::temporary(url.as_str()).into_response()),
+        Ok(None) => None,
+        Err(e) => Some(ServerError::from(StorageError::from(e)).into_response()),
+    }
+}
+
+/// Redirect to an agent that has a verified copy of a file, or return `None`
+/// to serve the file from the control plane. The control plane is included
+/// when picking a source so it keeps sharing the load, and is used when the
+/// picked agent does not respond to a health check.
+async fn redirect_to_peer(
+    state: &GlobalState,
+    query: &ContentQuery,
+    sha256: &str,
+) -> Option<Response> {
+    // the agent could not download the file from a peer
+    if query.direct {
+        return None;
+    }
+
+    let requester = query.agent;
+    let src_addrs = requester.and_then(|id| state.pool.get(&id)?.addrs().cloned());
+
+    let peers = state
+        .pool
+        .iter()
+        .filter(|a| Some(a.id()) != requester && a.can_serve_file(sha256))
+        .filter_map(|a| {
+            let addrs = a.addrs()?;
+            let ip = match &src_addrs {
+                Some(src) => resolve_one_addr(src, addrs)?,
+                None => addrs.usable()?,
+            };
+            Some(SocketAddr::new(ip, a.content_port()))
+        })
+        .collect::<Vec<_>>();
+
+    let addr = pick_source(peers, &mut rand::thread_rng())?;
+    let url = format!(
+        "http://{addr}/content/sha256/{}",
+        sha256.to_ascii_lowercase()
+    );
+
+    if !peer_is_serving(&url).await {
+        warn!("peer {addr} failed a health check for {sha256}, serving from the control plane");
+        return None;
+    }
+
+    Some(Redirect::temporary(&url).into_response())
+}
+
+/// Pick a peer to serve a file, or `None` for the control plane
+fn pick_source(peers: Vec<SocketAddr>, rng: &mut impl Rng) -> Option<SocketAddr> {
+    peers.into_iter().map(Some).chain([None]).choose(rng)?
+}
+
+/// Check that a peer's content server responds with the file
+async fn peer_is_serving(url: &str) -> bool {
+    REST_CLIENT
+        .head(url)
+        .timeout(PEER_HEALTH_TIMEOUT)
+        .send()
+        .await
+        .is_ok_and(|res| res.status().is_success())
+}
+
 async fn serve_file(
     Path((network, storage_id, file)): Path<(NetworkId, String, String)>,
+    Query(query): Query<ContentQuery>,
     State(state): State<AppState>,
     req: Request,
 ) -> Response {
@@ -129,6 +233,17 @@ async fn serve_file(
         _ => return StatusCode::NOT_FOUND.into_response(),
     }
 
+    if let Some(sha256) = storage.genesis_sha256.as_deref() {
+        if let Some(res) = redirect_to_peer(&state, &query, sha256).await {
+            return res;
+        }
+    }
+
+    let key = storage_key(network, storage_id, &file);
+    if let Some(res) = redirect_to_backend(&state, req.method(), &key) {
+        return res;
+    }
+
     let file_path = storage.path(&state).join(&file);
 
     // ensure the file exists
@@ -139,3 +254,64 @@ async fn serve_file(
     // serve the file
     ServeFile::new(file_path).call(req).await.into_response()
 }
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand_chacha::ChaChaRng;
+
+    use super::*;
+
+    #[test]
+    fn pick_source_without_peers_is_control_plane() {
+        let mut rng = ChaChaRng::seed_from_u64(0);
+        for _ in 0..10 {
+            assert_eq!(pick_source(vec![], &mut rng), None);
+        }
+    }
+
+    #[test]
+    fn pick_source_shares_load_with_control_plane() {
+        let peers: Vec<SocketAddr> = vec![
+            "10.0.0.1:8080".parse().unwrap(),
+            "10.0.0.2:8080".parse().unwrap(),
+        ];
+        let mut rng = ChaChaRng::seed_from_u64(0);
+
+        let picks = (0..300)
+            .map(|_| pick_source(peers.clone(), &mut rng))
+            .collect::<Vec<_>>();
+        assert!(picks.contains(&None));
+        for peer in &peers {
+            assert!(picks.contains(&Some(*peer)));
+        }
+    }
+
+    #[test]
+    fn content_query_direct_defaults_to_false() {
+        let query: ContentQuery = serde_json::from_str("{}").unwrap();
+        assert!(!query.direct && query.agent.is_none());
+
+        let query: ContentQuery =
+            serde_json::from_str(r#"{"agent":"agent-1","direct":true}"#).unwrap();
+        assert!(query.direct);
+        assert_eq!(query.agent, Some(AgentId::from_str("agent-1").unwrap()));
+    }
+
+    #[tokio::test]
+    async fn unresponsive_peer_fails_health_check() {
+        // bind then drop a listener to get a port nothing listens on
+        let addr = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        assert!(!peer_is_serving(&format!("http://{addr}/content/sha256/00")).await);
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let app = Router::new().route("/content/sha256/:sha256", get(|| async { "ok" }));
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        assert!(peer_is_serving(&format!("http://{addr}/content/sha256/00")).await);
+        assert!(!peer_is_serving(&format!("http://{addr}/missing")).await);
+    }
+}
@@ -13,6 +13,7 @@ use crate::{
     env::error::{EnvError, EnvRequestError, ExecutionError},
     error::DeserializeError,
     schema::error::{SchemaError, StorageError},
+    storage_backend::StorageBackendError,
 };
 
 #[derive(Debug, Error, strum_macros::AsRefStr)]
@@ -105,6 +106,8 @@ pub enum StartError {
     Serve(#[source] std::io::Error),
     #[error("failed to bind to tcp: {0}")]
     TcpBind(#[source] std::io::Error),
+    #[error("failed to configure storage backend: {0}")]
+    StorageBackend(#[from] StorageBackendError),
 }
 
 #[derive(Debug, Error, Serialize)]
@@ -128,10 +131,12 @@ pub enum ActionError {
         tx_id: String,
         retries: i32,
     },
+    #[error("transaction was not confirmed in time")]
+    ConfirmationTimeout { tx_id: String },
 }
 
 impl_into_status_code!(ActionError, |value| match value {
-    ExecuteStatusTimeout { .. } => StatusCode::REQUEST_TIMEOUT,
+    ExecuteStatusTimeout { .. } | ConfirmationTimeout { .. } => StatusCode::REQUEST_TIMEOUT,
     ExecuteStatusAborted { .. } | ExecuteStatusFailed { .. } => StatusCode::INTERNAL_SERVER_ERROR,
 });
 
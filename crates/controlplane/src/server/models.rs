@@ -9,6 +9,7 @@ pub struct AgentStatusResponse {
     pub agent_id: InternedId,
     pub is_connected: bool,
     pub is_computing: bool,
+    pub is_cordoned: bool,
     pub external_ip: Option<IpAddr>,
     pub internal_ip: Option<IpAddr>,
     pub state: AgentState,
@@ -19,7 +20,8 @@ impl From<&Agent> for AgentStatusResponse {
         Self {
             agent_id: agent.id(),
             is_connected: agent.is_connected(),
-            is_computing: agent.is_compute_claimed(),
+            is_computing: agent.compute_load() > 0,
+            is_cordoned: agent.is_cordoned(),
             external_ip: agent.addrs().and_then(|a| a.external),
             internal_ip: agent.addrs().and_then(|a| a.internal.first().cloned()),
             state: agent.state().clone(),
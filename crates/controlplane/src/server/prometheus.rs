@@ -1,13 +1,15 @@
 use std::collections::HashMap;
 
-use axum::{Json, Router, extract::State, response::IntoResponse, routing::get};
+use axum::{Json, Router, extract::State, http::header, response::IntoResponse, routing::get};
 use rayon::iter::{ParallelBridge, ParallelIterator};
 use serde::Serialize;
 use snops_common::state::AgentState;
 
 use crate::{cli::PrometheusLocation, state::AppState};
 pub(super) fn routes() -> Router<AppState> {
-    Router::new().route("/httpsd", get(get_httpsd))
+    Router::new()
+        .route("/httpsd", get(get_httpsd))
+        .route("/metrics", get(get_metrics))
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -61,3 +63,20 @@ async fn get_httpsd(State(state): State<AppState>) -> impl IntoResponse {
 
     Json(static_configs)
 }
+
+/// Control plane metrics in the prometheus text format
+async fn get_metrics(State(state): State<AppState>) -> impl IntoResponse {
+    let mut out = state.compute.render_metrics(&state.pool);
+
+    out.push_str(
+        "# HELP snops_cannon_transactions_total Transactions included in blocks by outcome\n",
+    );
+    out.push_str("# TYPE snops_cannon_transactions_total counter\n");
+    for env in state.envs.iter() {
+        for (cannon_id, cannon) in &env.cannons {
+            cannon.outcomes.write_metrics(&mut out, env.id, *cannon_id);
+        }
+    }
+
+    ([(header::CONTENT_TYPE, "text/plain; version=0.0.4")], out)
+}
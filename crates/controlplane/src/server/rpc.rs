@@ -241,6 +241,14 @@ impl ControlService for ControlRpcServer {
             ev.emit(&self);
         }
     }
+
+    async fn post_cached_files(self, _: context::Context, hashes: Vec<String>) {
+        let Some(mut agent) = self.state.pool.get_mut(&self.agent) else {
+            return;
+        };
+
+        agent.status.cached_files = hashes.into_iter().collect();
+    }
 }
 
 pub fn resolve_one_addr(src_addrs: &AgentAddrs, target_addrs: &AgentAddrs) -> Option<IpAddr> {
@@ -1,7 +1,7 @@
 use std::{
     net::{IpAddr, SocketAddr},
     sync::{Arc, Weak},
-    time::Instant,
+    time::{Duration, Instant},
 };
 
 use fixedbitset::FixedBitSet;
@@ -38,6 +38,8 @@ pub struct Agent {
 
     /// CLI provided information (mode, labels, local private key)
     pub(crate) flags: AgentFlags,
+    /// When true, the agent is excluded from env delegation and compute
+    pub(crate) cordoned: bool,
 
     /// Count of how many executions this agent is currently working on
     pub(crate) compute_claim: Arc<Busy>,
@@ -54,6 +56,7 @@ impl Agent {
         Self {
             id,
             flags,
+            cordoned: false,
             compute_claim: Arc::new(Busy),
             env_claim: Arc::new(Busy),
             claims: Claims {
@@ -78,6 +81,7 @@ impl Agent {
         Self {
             id: claims.id,
             flags,
+            cordoned: false,
             compute_claim: Arc::new(Busy),
             env_claim: Arc::new(Busy),
             claims,
@@ -95,6 +99,14 @@ impl Agent {
         matches!(self.connection, AgentConnection::Online(_))
     }
 
+    /// How long the agent has been disconnected, or `None` if it is online.
+    pub fn disconnected_for(&self) -> Option<Duration> {
+        match self.connection {
+            AgentConnection::Online(_) => None,
+            AgentConnection::Offline { since } => Some(since.elapsed()),
+        }
+    }
+
     /// Whether this agent is capable of being a node in the network.
     pub fn is_node_capable(&self) -> bool {
         if !self.is_connected() {
@@ -142,17 +154,39 @@ impl Agent {
         matches!(self.state, AgentState::Inventory)
     }
 
+    /// Check if an agent has been taken out of rotation
+    pub fn is_cordoned(&self) -> bool {
+        self.cordoned
+    }
+
+    /// Mark an agent as cordoned (or uncordoned). This does **not** move any
+    /// nodes the agent is currently running.
+    pub fn set_cordoned(&mut self, cordoned: bool) {
+        self.cordoned = cordoned;
+    }
+
     /// Check if an agent is available for compute tasks
     pub fn can_compute(&self) -> bool {
         self.is_inventory() && self.flags.mode.compute && !self.is_compute_claimed()
     }
 
-    /// Check if an agent is working on an authorization
+    /// Check if every compute slot of an agent is working on an authorization
     pub fn is_compute_claimed(&self) -> bool {
-        Arc::strong_count(&self.compute_claim) > 1
+        self.compute_load() >= self.compute_slots()
     }
 
-    /// Mark an agent as busy. This is used to prevent multiple authorizations
+    /// Number of authorizations an agent can work on at once
+    pub fn compute_slots(&self) -> usize {
+        self.flags.compute_slots.max(1) as usize
+    }
+
+    /// Number of authorizations an agent is working on
+    pub fn compute_load(&self) -> usize {
+        Arc::strong_count(&self.compute_claim) - 1
+    }
+
+    /// Claim a compute slot of an agent. This is used to prevent more
+    /// authorizations than the agent has slots for
     pub fn make_busy(&self) -> Arc<Busy> {
         Arc::clone(&self.compute_claim)
     }
@@ -279,6 +313,23 @@ impl Agent {
         self.ports.as_ref().map(|p| p.metrics).unwrap_or_default()
     }
 
+    /// Gets the port the agent serves cached files on, returns 0 if the agent
+    /// does not serve files.
+    pub fn content_port(&self) -> u16 {
+        self.ports.as_ref().map(|p| p.content).unwrap_or_default()
+    }
+
+    /// True when the agent is online and can serve a cached file with the
+    /// given sha256 to other agents
+    pub fn can_serve_file(&self, sha256: &str) -> bool {
+        self.is_connected()
+            && self.content_port() != 0
+            && self
+                .status
+                .cached_files
+                .contains(&sha256.to_ascii_lowercase())
+    }
+
     /// True when the agent is configured to provide its own local private key
     pub fn has_local_pk(&self) -> bool {
         self.flags.local_pk
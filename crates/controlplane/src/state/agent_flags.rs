@@ -16,6 +16,13 @@ pub struct AgentFlags {
     pub labels: IndexSet<Spur>,
     #[serde(deserialize_with = "deser_pk", default, serialize_with = "ser_pk")]
     pub local_pk: bool,
+    /// Number of authorizations the agent can execute at once
+    #[serde(
+        deserialize_with = "deser_slots",
+        default = "default_slots",
+        serialize_with = "ser_slots"
+    )]
+    pub compute_slots: u32,
 }
 
 fn deser_mode<'de, D>(deser: D) -> Result<AgentModeOptions, D::Error>
@@ -87,6 +94,31 @@ where
     }
 }
 
+fn default_slots() -> u32 {
+    1
+}
+
+fn deser_slots<'de, D>(deser: D) -> Result<u32, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    // axum's querystring visitor marks all values as string
+    Option::<String>::deserialize(deser)?
+        .map(|s| {
+            s.parse::<u32>()
+                .map_err(|e| serde::de::Error::custom(format!("error parsing u32: {e}")))
+        })
+        .transpose()
+        .map(|slots| slots.unwrap_or_else(default_slots).max(1))
+}
+
+fn ser_slots<S>(slots: &u32, ser: S) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    ser.serialize_str(&slots.to_string())
+}
+
 impl AgentFlags {
     pub fn mask(&self, labels: &[Spur]) -> FixedBitSet {
         let mut mask = FixedBitSet::with_capacity(labels.len() + MASK_PREFIX_LEN);
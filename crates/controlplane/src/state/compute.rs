@@ -0,0 +1,562 @@
+use std::{
+    collections::{HashSet, VecDeque},
+    fmt::Write,
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+    time::{Duration, Instant},
+};
+
+use indexmap::IndexMap;
+use snops_common::{
+    lasso::Spur,
+    state::{AgentId, CannonId, EnvId},
+};
+use tokio::sync::{Notify, oneshot};
+use tracing::trace;
+
+use super::{Agent, AgentClient, AgentPool, Busy, GlobalState};
+
+/// How often queued requests are re-checked without a compute slot being
+/// released, to pick up agents that connected or were uncordoned
+const DISPATCH_POLL: Duration = Duration::from_secs(1);
+
+/// Upper bounds (in seconds) of the compute queue wait histogram buckets
+const WAIT_BUCKETS: [f64; 8] = [0.1, 0.5, 1.0, 5.0, 10.0, 30.0, 60.0, 300.0];
+
+/// The environment and cannon waiting for a compute agent. Environment actions
+/// that are not run by a cannon have no cannon id.
+pub type ComputeRequester = (EnvId, Option<CannonId>);
+
+/// Requests waiting for a compute agent, grouped by environment and then by
+/// requester in the order they are next served
+type ComputeQueues = IndexMap<EnvId, IndexMap<Option<CannonId>, VecDeque<PendingCompute>>>;
+
+struct PendingCompute {
+    labels: Vec<Spur>,
+    queued_at: Instant,
+    grant: oneshot::Sender<ComputeLease>,
+}
+
+/// Cumulative counts of how long granted requests waited for an agent
+#[derive(Default)]
+struct WaitHistogram {
+    buckets: [u64; WAIT_BUCKETS.len()],
+    sum: f64,
+    count: u64,
+}
+
+impl WaitHistogram {
+    fn observe(&mut self, wait: Duration) {
+        let secs = wait.as_secs_f64();
+        for (bucket, bound) in self.buckets.iter_mut().zip(WAIT_BUCKETS) {
+            if secs <= bound {
+                *bucket += 1;
+            }
+        }
+        self.sum += secs;
+        self.count += 1;
+    }
+}
+
+/// Hands out the slots of compute agents to the requests waiting for them.
+///
+/// Environments take turns being served, as do the requesters within an
+/// environment, so a cannon with a deep backlog cannot starve other cannons
+/// or environments of compute agents.
+#[derive(Default)]
+pub struct ComputeScheduler {
+    queues: Mutex<ComputeQueues>,
+    wait_times: Mutex<WaitHistogram>,
+    /// Notified when a compute slot is released
+    released: Arc<Notify>,
+}
+
+/// A claimed compute slot on an agent. The slot is released, and handed to
+/// the next queued request, when the lease is dropped.
+pub struct ComputeLease {
+    pub agent_id: AgentId,
+    pub client: AgentClient,
+    busy: Option<Arc<Busy>>,
+    released: Arc<Notify>,
+}
+
+impl Drop for ComputeLease {
+    fn drop(&mut self) {
+        // free the slot before waking the scheduler so it can be handed out
+        self.busy.take();
+        self.released.notify_one();
+    }
+}
+
+/// A queued request for a compute agent. Dropping the ticket leaves the queue.
+pub struct ComputeTicket(oneshot::Receiver<ComputeLease>);
+
+impl ComputeTicket {
+    /// Take the lease if the request has already been granted
+    pub fn try_take(&mut self) -> Option<ComputeLease> {
+        self.0.try_recv().ok()
+    }
+
+    /// Wait for the request to be granted. Returns `None` if the scheduler
+    /// stopped
+    pub async fn granted(self) -> Option<ComputeLease> {
+        self.0.await.ok()
+    }
+}
+
+impl ComputeScheduler {
+    fn queues(&self) -> MutexGuard<'_, ComputeQueues> {
+        self.queues.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn wait_times(&self) -> MutexGuard<'_, WaitHistogram> {
+        self.wait_times
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Queue a request for a compute agent with the given labels. The request
+    /// is granted right away if it is next in line and an agent is free.
+    pub fn request(
+        &self,
+        pool: &AgentPool,
+        (env_id, cannon_id): ComputeRequester,
+        labels: &[Spur],
+    ) -> ComputeTicket {
+        let (grant, rx) = oneshot::channel();
+        self.queues()
+            .entry(env_id)
+            .or_default()
+            .entry(cannon_id)
+            .or_default()
+            .push_back(PendingCompute {
+                labels: labels.to_vec(),
+                queued_at: Instant::now(),
+                grant,
+            });
+        self.dispatch(pool);
+        ComputeTicket(rx)
+    }
+
+    /// Wake the scheduler to check if queued requests can be granted
+    pub fn wake(&self) {
+        self.released.notify_one();
+    }
+
+    /// Grant queued requests until no more can be granted, one request per
+    /// environment each round. Returns the number of granted requests.
+    pub fn dispatch(&self, pool: &AgentPool) -> usize {
+        let mut queues = self.queues();
+
+        // forget the requests that are no longer waiting
+        queues.retain(|_, requesters| {
+            requesters.retain(|_, pending| {
+                pending.retain(|p| !p.grant.is_closed());
+                !pending.is_empty()
+            });
+            !requesters.is_empty()
+        });
+
+        let mut granted = 0;
+        loop {
+            let mut progress = false;
+            let env_ids = queues.keys().copied().collect::<Vec<_>>();
+
+            for env_id in env_ids {
+                let Some(requesters) = queues.get_mut(&env_id) else {
+                    continue;
+                };
+                let Some((requester, pending, lease)) = self.grant_next(pool, requesters) else {
+                    continue;
+                };
+                progress = true;
+
+                // the served requester and environment go to the back of the line
+                if let Some(rest) = requesters.shift_remove(&requester) {
+                    if !rest.is_empty() {
+                        requesters.insert(requester, rest);
+                    }
+                }
+                if let Some(rest) = queues.shift_remove(&env_id) {
+                    if !rest.is_empty() {
+                        queues.insert(env_id, rest);
+                    }
+                }
+
+                let agent_id = lease.agent_id;
+                let wait = pending.queued_at.elapsed();
+                // a request dropped since the queue was pruned releases the lease
+                if pending.grant.send(lease).is_ok() {
+                    trace!(
+                        "granted compute agent {agent_id} to {env_id}.{} after {wait:?}",
+                        requester.map_or_else(|| "action".to_owned(), |id| id.to_string())
+                    );
+                    self.wait_times().observe(wait);
+                    granted += 1;
+                }
+            }
+
+            if !progress {
+                break granted;
+            }
+        }
+    }
+
+    /// Remove and grant the first request of an environment's requesters that
+    /// an agent is free for
+    fn grant_next(
+        &self,
+        pool: &AgentPool,
+        requesters: &mut IndexMap<Option<CannonId>, VecDeque<PendingCompute>>,
+    ) -> Option<(Option<CannonId>, PendingCompute, ComputeLease)> {
+        // labels no agent is free for, to avoid checking them again
+        let mut unavailable = HashSet::<Vec<Spur>>::new();
+
+        for (requester, pending) in requesters.iter_mut() {
+            for i in 0..pending.len() {
+                if unavailable.contains(&pending[i].labels) {
+                    continue;
+                }
+                match self.claim_agent(pool, &pending[i].labels) {
+                    Some(lease) => {
+                        let pending = pending.remove(i)?;
+                        return Some((*requester, pending, lease));
+                    }
+                    None => {
+                        unavailable.insert(pending[i].labels.clone());
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    /// Claim a slot on the least busy compute agent with the given labels
+    fn claim_agent(&self, pool: &AgentPool, labels: &[Spur]) -> Option<ComputeLease> {
+        let (agent_id, ..) = pool
+            .iter()
+            .filter(|a| a.can_compute() && is_compute_candidate(a, labels))
+            .map(|a| (a.id(), a.compute_load(), a.compute_slots()))
+            // compare the fraction of used slots, then prefer more free slots
+            .min_by(|(_, load_a, slots_a), (_, load_b, slots_b)| {
+                (load_a * slots_b)
+                    .cmp(&(load_b * slots_a))
+                    .then((slots_b - load_b).cmp(&(slots_a - load_a)))
+            })?;
+
+        let agent = pool.get(&agent_id)?;
+        let busy = agent.make_busy();
+        // another slot may have been claimed since the agent was picked
+        if Arc::strong_count(&busy) - 1 > agent.compute_slots() {
+            return None;
+        }
+
+        Some(ComputeLease {
+            agent_id,
+            client: agent.client_owned()?,
+            busy: Some(busy),
+            released: Arc::clone(&self.released),
+        })
+    }
+
+    /// Render the compute queue and agent slot metrics in the prometheus text
+    /// format
+    pub fn render_metrics(&self, pool: &AgentPool) -> String {
+        let mut out = String::new();
+
+        out.push_str("# HELP snops_compute_queue_depth Requests waiting for a compute agent\n");
+        out.push_str("# TYPE snops_compute_queue_depth gauge\n");
+        for (env_id, requesters) in self.queues().iter() {
+            for (cannon_id, pending) in requesters {
+                let depth = pending.iter().filter(|p| !p.grant.is_closed()).count();
+                let _ = match cannon_id {
+                    Some(cannon_id) => writeln!(
+                        out,
+                        "snops_compute_queue_depth{{env_id=\"{env_id}\",cannon_id=\"{cannon_id}\"}} {depth}"
+                    ),
+                    None => writeln!(
+                        out,
+                        "snops_compute_queue_depth{{env_id=\"{env_id}\"}} {depth}"
+                    ),
+                };
+            }
+        }
+
+        out.push_str(
+            "# HELP snops_compute_queue_wait_seconds Time requests waited for a compute agent\n",
+        );
+        out.push_str("# TYPE snops_compute_queue_wait_seconds histogram\n");
+        {
+            let wait_times = self.wait_times();
+            for (count, bound) in wait_times.buckets.iter().zip(WAIT_BUCKETS) {
+                let _ = writeln!(
+                    out,
+                    "snops_compute_queue_wait_seconds_bucket{{le=\"{bound}\"}} {count}"
+                );
+            }
+            let _ = writeln!(
+                out,
+                "snops_compute_queue_wait_seconds_bucket{{le=\"+Inf\"}} {}",
+                wait_times.count
+            );
+            let _ = writeln!(
+                out,
+                "snops_compute_queue_wait_seconds_sum {}",
+                wait_times.sum
+            );
+            let _ = writeln!(
+                out,
+                "snops_compute_queue_wait_seconds_count {}",
+                wait_times.count
+            );
+        }
+
+        let agents = pool
+            .iter()
+            .filter(|a| a.modes().compute)
+            .map(|a| (a.id(), a.compute_slots(), a.compute_load()))
+            .collect::<Vec<_>>();
+
+        out.push_str(
+            "# HELP snops_compute_agent_slots Authorizations a compute agent can execute at once\n",
+        );
+        out.push_str("# TYPE snops_compute_agent_slots gauge\n");
+        for (agent_id, slots, _) in &agents {
+            let _ = writeln!(
+                out,
+                "snops_compute_agent_slots{{agent_id=\"{agent_id}\"}} {slots}"
+            );
+        }
+        out.push_str(
+            "# HELP snops_compute_agent_slots_used Authorizations a compute agent is executing\n",
+        );
+        out.push_str("# TYPE snops_compute_agent_slots_used gauge\n");
+        for (agent_id, _, load) in &agents {
+            let _ = writeln!(
+                out,
+                "snops_compute_agent_slots_used{{agent_id=\"{agent_id}\"}} {load}"
+            );
+        }
+
+        out
+    }
+}
+
+/// Hands out compute agents to queued requests whenever a compute slot is
+/// released, or periodically for agents that became available
+pub async fn compute_task(state: Arc<GlobalState>) {
+    loop {
+        let _ = tokio::time::timeout(DISPATCH_POLL, state.compute.released.notified()).await;
+        state.compute.dispatch(&state.pool);
+    }
+}
+
+/// Whether an agent can generate authorizations for a requester with the
+/// given labels, ignoring how busy it is.
+fn is_compute_candidate(agent: &Agent, labels: &[Spur]) -> bool {
+    agent.is_inventory()
+        && agent.modes().compute
+        && !agent.is_cordoned()
+        && agent.is_connected()
+        && labels.iter().all(|l| agent.has_label(*l))
+}
+
+/// The total compute slots of the agents that can generate authorizations
+/// for a requester with the given labels.
+pub fn compute_slots_for(pool: &AgentPool, labels: &[Spur]) -> usize {
+    pool.iter()
+        .filter(|a| is_compute_candidate(a, labels))
+        .map(|a| a.compute_slots())
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use snops_common::{
+        INTERN,
+        rpc::control::agent::AgentServiceClient,
+        state::{AgentModeOptions, AgentState},
+    };
+
+    use super::*;
+    use crate::{server::jwt::Claims, state::AgentFlags};
+
+    const NO_LABELS: &[Spur] = &[];
+
+    /// An inventoried compute agent, connected to a client nothing answers
+    fn agent(id: &str, labels: &[&str], slots: u32, connected: bool) -> Agent {
+        let flags = AgentFlags {
+            // compute mode
+            mode: AgentModeOptions::from(8u8),
+            labels: labels.iter().map(|l| INTERN.get_or_intern(l)).collect(),
+            local_pk: false,
+            compute_slots: slots,
+        };
+        let mut agent = Agent::from_components(
+            Claims {
+                id: AgentId::from_str(id).unwrap(),
+                nonce: 0,
+            },
+            AgentState::Inventory,
+            flags.clone(),
+            None,
+            None,
+        );
+        if connected {
+            let (transport, _) = tarpc::transport::channel::unbounded();
+            let client =
+                AgentServiceClient::new(tarpc::client::Config::default(), transport).spawn();
+            agent.mark_connected(client, flags);
+        }
+        agent
+    }
+
+    fn pool(agents: impl IntoIterator<Item = Agent>) -> AgentPool {
+        agents.into_iter().map(|a| (a.id(), a)).collect()
+    }
+
+    fn requester(env: &str, cannon: Option<&str>) -> ComputeRequester {
+        (
+            EnvId::from_str(env).unwrap(),
+            cannon.map(|c| CannonId::from_str(c).unwrap()),
+        )
+    }
+
+    /// Queue requests while no agents are available, so they are all waiting
+    /// before the first dispatch
+    fn queue(
+        scheduler: &ComputeScheduler,
+        requests: &[(ComputeRequester, &[Spur])],
+    ) -> Vec<ComputeTicket> {
+        let empty = AgentPool::default();
+        requests
+            .iter()
+            .map(|(requester, labels)| scheduler.request(&empty, *requester, labels))
+            .collect()
+    }
+
+    fn granted(tickets: &mut [ComputeTicket]) -> Vec<Option<ComputeLease>> {
+        tickets.iter_mut().map(ComputeTicket::try_take).collect()
+    }
+
+    #[tokio::test]
+    async fn envs_take_turns() {
+        let scheduler = ComputeScheduler::default();
+        let (alpha, beta) = (requester("alpha", Some("a")), requester("beta", Some("b")));
+        let mut tickets = queue(
+            &scheduler,
+            &[
+                (alpha, NO_LABELS),
+                (alpha, NO_LABELS),
+                (alpha, NO_LABELS),
+                (beta, NO_LABELS),
+            ],
+        );
+
+        let pool = pool([agent("agent", &[], 2, true)]);
+        assert_eq!(scheduler.dispatch(&pool), 2);
+        let leases = granted(&mut tickets);
+        let served = leases.iter().map(Option::is_some).collect::<Vec<_>>();
+        assert_eq!(served, [true, false, false, true]);
+
+        // releasing a slot serves the next request of the waiting env
+        drop(leases);
+        assert_eq!(scheduler.dispatch(&pool), 2);
+        let served = granted(&mut tickets)
+            .iter()
+            .map(Option::is_some)
+            .collect::<Vec<_>>();
+        assert_eq!(served, [false, true, true, false]);
+    }
+
+    #[tokio::test]
+    async fn cannons_take_turns() {
+        let scheduler = ComputeScheduler::default();
+        let (one, two, action) = (
+            requester("env", Some("one")),
+            requester("env", Some("two")),
+            requester("env", None),
+        );
+        let mut tickets = queue(
+            &scheduler,
+            &[
+                (one, NO_LABELS),
+                (one, NO_LABELS),
+                (two, NO_LABELS),
+                (action, NO_LABELS),
+            ],
+        );
+
+        let pool = pool([agent("agent", &[], 3, true)]);
+        assert_eq!(scheduler.dispatch(&pool), 3);
+        let served = granted(&mut tickets)
+            .iter()
+            .map(Option::is_some)
+            .collect::<Vec<_>>();
+        assert_eq!(served, [true, false, true, true]);
+    }
+
+    #[tokio::test]
+    async fn leases_hold_agent_slots() {
+        let scheduler = ComputeScheduler::default();
+        let env = requester("env", Some("cannon"));
+        let mut tickets = queue(
+            &scheduler,
+            &[(env, NO_LABELS), (env, NO_LABELS), (env, NO_LABELS)],
+        );
+
+        let pool = pool([agent("small", &[], 1, true), agent("large", &[], 2, true)]);
+        assert_eq!(scheduler.dispatch(&pool), 3);
+        let mut leases = granted(&mut tickets)
+            .into_iter()
+            .collect::<Option<Vec<_>>>()
+            .unwrap();
+        for agent in pool.iter() {
+            assert_eq!(agent.compute_load(), agent.compute_slots());
+            assert!(!agent.can_compute());
+        }
+
+        // no slot is free for another request
+        let mut waiting = scheduler.request(&pool, env, NO_LABELS);
+        assert!(waiting.try_take().is_none());
+
+        // a released slot is handed to the waiting request
+        let released = leases.pop().unwrap().agent_id;
+        assert_eq!(scheduler.dispatch(&pool), 1);
+        assert_eq!(waiting.try_take().map(|l| l.agent_id), Some(released));
+    }
+
+    #[tokio::test]
+    async fn unavailable_labels_are_skipped() {
+        let scheduler = ComputeScheduler::default();
+        let env = requester("env", Some("cannon"));
+        let gpu = [INTERN.get_or_intern("gpu")];
+        let mut tickets = queue(
+            &scheduler,
+            &[(env, &gpu[..]), (env, &gpu[..]), (env, NO_LABELS)],
+        );
+
+        // the only agent with the label is offline
+        let pool = pool([agent("cpu", &[], 1, true), agent("gpu", &["gpu"], 1, false)]);
+        assert_eq!(compute_slots_for(&pool, &gpu), 0);
+        assert_eq!(scheduler.dispatch(&pool), 1);
+        let served = granted(&mut tickets)
+            .iter()
+            .map(|lease| lease.as_ref().map(|l| l.agent_id.to_string()))
+            .collect::<Vec<_>>();
+        assert_eq!(served, [None, None, Some("cpu".to_owned())]);
+    }
+
+    #[tokio::test]
+    async fn dropped_tickets_leave_the_queue() {
+        let scheduler = ComputeScheduler::default();
+        let env = requester("env", Some("cannon"));
+        let mut tickets = queue(&scheduler, &[(env, NO_LABELS), (env, NO_LABELS)]);
+        tickets.remove(0);
+
+        let pool = pool([agent("agent", &[], 1, true)]);
+        assert_eq!(scheduler.dispatch(&pool), 1);
+        assert!(tickets[0].try_take().is_some());
+    }
+}
@@ -19,10 +19,12 @@ use tracing::info;
 
 use super::{
     AddrMap, AgentClient, AgentPool, EnvMap, StorageMap,
+    compute::ComputeScheduler,
     snarkos_request::{self, reparse_json_env},
 };
 use crate::{
     ReloadHandler,
+    cannon::{history::HistoryIndex, proof_cache::ProofCache},
     cli::Cli,
     db::Database,
     env::{Environment, PortType, cache::NetworkCache, error::EnvRequestError},
@@ -30,6 +32,7 @@ use crate::{
     events::Events,
     schema::storage::{LoadedStorage, STORAGE_DIR},
     server::error::StartError,
+    storage_backend::Backend,
 };
 
 lazy_static::lazy_static! {
@@ -44,9 +47,16 @@ pub struct GlobalState {
     pub agent_key: Option<String>,
     pub pool: AgentPool,
     pub storage: StorageMap,
+    pub storage_backend: Backend,
     pub envs: EnvMap,
     pub env_network_cache: OpaqueDebug<DashMap<EnvId, NetworkCache>>,
     pub events: Events,
+    /// Queued requests for compute agents
+    pub compute: OpaqueDebug<ComputeScheduler>,
+    /// Transactions proven for cannons, reused for identical authorizations
+    pub proof_cache: OpaqueDebug<ProofCache>,
+    /// Finish times of the transactions in cannon histories
+    pub history: OpaqueDebug<HistoryIndex>,
 
     pub prometheus: OpaqueDebug<Option<PrometheusClient>>,
 
@@ -90,20 +100,43 @@ impl GlobalState {
         }
 
         let pool: DashMap<_, _> = db.agents.read_all().collect();
+        let storage_backend = Backend::from_cli(&cli)?;
+        let proof_cache = ProofCache::load(&db, cli.proof_cache_size);
+        let history = HistoryIndex::load(&db);
 
         let state = Arc::new(Self {
             cli,
             agent_key: std::env::var(ENV_AGENT_KEY).ok(),
             pool,
             storage,
+            storage_backend,
             envs: EnvMap::default(),
             events: Default::default(),
+            compute: Default::default(),
+            proof_cache: OpaqueDebug(proof_cache),
+            history: OpaqueDebug(history),
             prometheus: OpaqueDebug(prometheus),
             db: OpaqueDebug(db),
             env_network_cache: Default::default(),
             log_level_handler,
         });
 
+        // storage generated by a previous run may not be in the backend yet
+        let loaded_storage = state
+            .storage
+            .iter()
+            .map(|s| Arc::clone(s.value()))
+            .collect::<Vec<_>>();
+        for storage in loaded_storage {
+            if let Err(e) = storage.upload_to_backend(&state, false).await {
+                tracing::error!(
+                    "Error uploading storage {}/{} to the storage backend: {e}",
+                    storage.network,
+                    storage.id
+                );
+            }
+        }
+
         let env_meta = state.db.envs.read_all().collect::<Vec<_>>();
 
         let num_cannons = env_meta.iter().map(|(_, e)| e.cannons.len()).sum();
@@ -5,6 +5,7 @@ use snops_common::state::{AgentId, EnvId, NetworkId, StorageId};
 
 mod agent;
 mod agent_flags;
+pub mod compute;
 pub mod external_peers;
 mod global;
 mod reconcile;
@@ -2,9 +2,14 @@ use std::{fmt::Display, time::Duration};
 
 use serde::de::DeserializeOwned;
 use snops_common::{
-    rpc::{control::agent::AgentServiceClient, error::SnarkosRequestError},
+    action_models::FeeBreakdown,
+    rpc::{
+        control::agent::{AgentServiceClient, AuthorizeRequest, EstimateFeeRequest},
+        error::SnarkosRequestError,
+    },
     state::{
-        AgentId, AgentState, EnvId, NetworkId, ReconcileOptions, snarkos_status::SnarkOSLiteBlock,
+        AgentId, AgentState, EnvId, NetworkId, ReconcileOptions, ledger_digest::LedgerDigest,
+        snarkos_status::SnarkOSLiteBlock,
     },
 };
 use tarpc::{client::RpcError, context};
@@ -57,6 +62,32 @@ impl AgentClient {
             .await??)
     }
 
+    pub async fn authorize_program(
+        &self,
+        env_id: EnvId,
+        network: NetworkId,
+        request: AuthorizeRequest,
+    ) -> Result<String, StateError> {
+        let mut ctx = context::current();
+        ctx.deadline += Duration::from_secs(30);
+        Ok(self
+            .0
+            .authorize_program(ctx, env_id, network, request)
+            .await??)
+    }
+
+    pub async fn estimate_fee(
+        &self,
+        env_id: EnvId,
+        network: NetworkId,
+        request: EstimateFeeRequest,
+    ) -> Result<FeeBreakdown, StateError> {
+        let mut ctx = context::current();
+        // deployments synthesize every circuit in the program
+        ctx.deadline += Duration::from_secs(120);
+        Ok(self.0.estimate_fee(ctx, env_id, network, request).await??)
+    }
+
     pub async fn broadcast_tx(&self, tx: String) -> Result<(), StateError> {
         Ok(self.0.broadcast_tx(context::current(), tx).await??)
     }
@@ -74,4 +105,35 @@ impl AgentClient {
     pub async fn find_transaction(&self, tx_id: String) -> Result<Option<String>, StateError> {
         Ok(self.0.find_transaction(context::current(), tx_id).await??)
     }
+
+    pub async fn get_checkpoints(&self) -> Result<Vec<u32>, StateError> {
+        Ok(self.0.get_checkpoints(context::current()).await??)
+    }
+
+    pub async fn get_checkpoint(&self, height: u32) -> Result<Vec<u8>, StateError> {
+        let mut ctx = context::current();
+        ctx.deadline += Duration::from_secs(60);
+        Ok(self.0.get_checkpoint(ctx, height).await??)
+    }
+
+    pub async fn put_checkpoint(
+        &self,
+        env_id: EnvId,
+        height: u32,
+        checkpoint: Vec<u8>,
+    ) -> Result<(), StateError> {
+        let mut ctx = context::current();
+        ctx.deadline += Duration::from_secs(60);
+        Ok(self
+            .0
+            .put_checkpoint(ctx, env_id, height, checkpoint)
+            .await??)
+    }
+
+    pub async fn get_ledger_digest(&self) -> Result<LedgerDigest, StateError> {
+        // walking a large ledger can take a while
+        let mut ctx = context::current();
+        ctx.deadline += Duration::from_secs(60);
+        Ok(self.0.get_ledger_digest(ctx).await??)
+    }
 }
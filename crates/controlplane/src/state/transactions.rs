@@ -1,20 +1,34 @@
-use std::{sync::Arc, time::Duration};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+    time::Duration,
+};
 
 use chrono::{TimeDelta, Utc};
+use dashmap::DashMap;
 use futures_util::future;
+use serde::Deserialize;
 use snops_common::{
-    events::{EventHelpers, TransactionEvent},
+    events::{EventHelpers, TransactionAbortReason, TransactionEvent},
     state::{CannonId, EnvId, TransactionSendState},
 };
 use tokio::time::timeout;
 use tracing::{info, trace};
 
 use super::{EmitEvent, GlobalState};
-use crate::cannon::tracker::TransactionTracker;
+use crate::{
+    cannon::{
+        history::TransactionOutcome,
+        status::CannonStatus,
+        tracker::{DependencyState, TransactionTracker},
+    },
+    db::Database,
+    env::cache::NetworkCache,
+};
 
 /// This task re-sends all transactions that have not been confirmed,
-/// re-computes all transactions that have not been computed, and removes
-/// transactions that are confirmed.
+/// re-computes all transactions that have not been computed, and archives
+/// transactions that are confirmed or have exceeded their attempts.
 pub async fn tracking_task(state: Arc<GlobalState>) {
     loop {
         let pending_txs = get_pending_transactions(&state);
@@ -28,72 +42,136 @@ pub async fn tracking_task(state: Arc<GlobalState>) {
                 let Some(cannon) = env.get_cannon(cannon_id) else {
                     return
                 };
-
-                // queue up all the transactions that need to be executed
-                for tx_id in pending.to_execute {
-                    if let Err(e) = cannon
-                        .auth_sender
-                        .send(tx_id.clone())
-                    {
-                        tracing::error!(
-                            "cannon {env_id}.{cannon_id} failed to send auth {tx_id} to cannon: {e:?}"
-                        );
+                // paused cannons keep their transactions queued until resumed
+                if cannon.status() != CannonStatus::Paused {
+                    // queue up all the transactions that need to be executed
+                    for tx_id in pending.to_execute {
+                        if let Err(e) = cannon.auth_sender.send(tx_id.clone()) {
+                            tracing::error!(
+                                "cannon {env_id}.{cannon_id} failed to send auth {tx_id} to cannon: {e:?}"
+                            );
+                        }
                     }
-                }
 
-                // queue up all the transactions that need to be confirmed
-                for tx_id in pending.to_broadcast {
-                    trace!("cannon {env_id}.{cannon_id} queueing transaction {tx_id} for re-broadcast");
-                    if let Err(e) = cannon.tx_sender.send(tx_id.clone()) {
-                        tracing::error!(
-                            "cannon {env_id}.{cannon_id} failed to send broadcast {tx_id} to cannon: {e:?}"
-                        );
+                    // queue up all the transactions that need to be confirmed
+                    for tx_id in pending.to_broadcast {
+                        trace!("cannon {env_id}.{cannon_id} queueing transaction {tx_id} for re-broadcast");
+                        if let Err(e) = cannon.tx_sender.send(tx_id.clone()) {
+                            tracing::error!(
+                                "cannon {env_id}.{cannon_id} failed to send broadcast {tx_id} to cannon: {e:?}"
+                            );
+                        }
                     }
                 }
 
-                // attempt to confirm all the confirm-pending transactions by using the cache
-                // then fall back on making a request to the peers
-                let confirmed = future::join_all(pending.to_confirm.into_iter().map(|(tx_id, _height)| {
+                // attempt to find the blocks of all the confirm-pending transactions by using
+                // the cache then fall back on making a request to the peers
+                let cannon_target = cannon.sink.target.as_ref();
+                let included = future::join_all(pending.to_confirm.into_iter().map(|(tx_id, _height)| {
                     let state = state.clone();
-                    let cannon_target = cannon.sink.target.as_ref();
                     async move {
-                        let (tx_id, hash) = match state.env_network_cache.get(&env_id).and_then(|cache| cache.find_transaction(&tx_id).cloned()) { Some(hash) => {
+                        // blocks list rejected transactions under the id of their fee transaction
+                        // and aborted transactions separately, so a cache hit was accepted
+                        if let Some(hash) = state.env_network_cache.get(&env_id).and_then(|cache| cache.find_transaction(&tx_id).cloned()) {
                             trace!("cannon {env_id}.{cannon_id} confirmed transaction {tx_id} (cache hit)");
-                            (tx_id, hash.to_string())
-                        } _ => if let Some(target) = cannon_target {
-                            match timeout(Duration::from_secs(1),
-                            state.snarkos_get::<Option<String>>(env_id, format!("/find/blockHash/{tx_id}"), target)).await {
-                                Ok(Ok(Some(hash))) => {
-                                    trace!("cannon {env_id}.{cannon_id} confirmed transaction {tx_id} (get request)");
-                                    (tx_id, hash)
-                                }
-                                // the transaction is not in the cache
-                                _ => return None,
+                            return Some((tx_id, hash.to_string(), Some(TransactionOutcome::Accepted)));
+                        }
+
+                        match timeout(Duration::from_secs(1),
+                        state.snarkos_get::<Option<String>>(env_id, format!("/find/blockHash/{tx_id}"), cannon_target?)).await {
+                            Ok(Ok(Some(hash))) => {
+                                trace!("cannon {env_id}.{cannon_id} found transaction {tx_id} in block {hash} (get request)");
+                                Some((tx_id, hash, None))
                             }
-                        } else {
-                            return None;
-                        }};
+                            // the transaction is not in a block
+                            _ => None,
+                        }
+                }})).await.into_iter().flatten().collect::<Vec<_>>();
+
+                // fetch the blocks of transactions that missed the cache to find out how
+                // they were included
+                let unknown_blocks = included
+                    .iter()
+                    .filter(|(_, _, outcome)| outcome.is_none())
+                    .map(|(_, hash, _)| hash.as_str())
+                    .collect::<HashSet<_>>();
+                let blocks = future::join_all(unknown_blocks.into_iter().map(|hash| {
+                    let state = state.clone();
+                    async move {
+                        match timeout(Duration::from_secs(5),
+                        state.snarkos_get::<BlockTransactions>(env_id, format!("/block/{hash}"), cannon_target?)).await {
+                            Ok(Ok(block)) => Some((hash.to_owned(), block)),
+                            _ => {
+                                trace!("cannon {env_id}.{cannon_id} failed to fetch block {hash}");
+                                None
+                            }
+                        }
+                }})).await.into_iter().flatten().collect::<HashMap<_, _>>();
 
-                        // Emit a confirmed event
-                        TransactionEvent::Confirmed { hash }
+                let confirmed = included.into_iter().filter_map(|(tx_id, hash, outcome)| {
+                    // transactions in blocks that could not be fetched are checked again
+                    // on the next update
+                    let outcome = outcome.or_else(|| blocks.get(&hash).map(|block| block.outcome(&tx_id)))?;
+                    let event = match outcome {
+                        TransactionOutcome::Rejected => TransactionEvent::Rejected { hash: hash.clone() },
+                        TransactionOutcome::Aborted => TransactionEvent::Aborted { hash: hash.clone() },
+                        _ => TransactionEvent::Accepted { hash: hash.clone() },
+                    };
+                    // subscribers that predate the outcome events still get a confirmation
+                    for event in [event, TransactionEvent::Confirmed { hash: hash.clone() }] {
+                        event
                             .with_cannon(cannon_id)
                             .with_env_id(env_id)
                             .with_transaction(Arc::clone(&tx_id)).emit(&state);
+                    }
+                    cannon.outcomes.record(outcome);
 
-                        Some(tx_id)
-                }})).await;
+                    Some((tx_id, outcome, Some(hash)))
+                });
 
-                // remove all the transactions that are confirmed or expired
-                for tx_id in pending.to_remove.into_iter().chain(confirmed.into_iter().flatten()) {
-                    cannon.transactions.remove(&tx_id);
-                    if let Err(e) =
-                        TransactionTracker::delete(&state, &(env_id, cannon_id, tx_id.clone()))
-                    {
-                        tracing::error!("cannon {env_id}.{cannon_id} failed to delete {tx_id}: {e:?}");
+                // archive all the transactions that are confirmed or expired
+                let finished = pending
+                    .to_remove
+                    .into_iter()
+                    .map(|(tx_id, outcome)| (tx_id, outcome, None))
+                    .chain(confirmed);
+
+                let mut archived = false;
+                for (tx_id, outcome, block_hash) in finished {
+                    let Some((_, tracker)) = cannon.transactions.remove(&tx_id) else {
+                        continue;
+                    };
+                    archived = true;
+                    cannon.fee_records.release_transaction(&tx_id);
+                    cannon.load.set_unconfirmed(&tx_id, false);
+                    if let Err(e) = tracker.archive(&state, &(env_id, cannon_id, tx_id.clone()), outcome, block_hash) {
+                        tracing::error!("cannon {env_id}.{cannon_id} failed to archive {tx_id}: {e:?}");
+                    }
+                }
+
+                if archived {
+                    if let Err(e) = state.history.prune(&state.db, env_id, cannon_id, state.cli.tx_history_limit) {
+                        tracing::error!("cannon {env_id}.{cannon_id} failed to prune transaction history: {e}");
                     }
                 }
             }})).await;
 
+        // remove the history of envs that were deleted a while ago
+        let retention = TimeDelta::seconds(state.cli.tx_history_retention as i64);
+        match state.history.prune_deleted(
+            &state.db,
+            |env_id| !state.envs.contains_key(&env_id),
+            retention,
+            Utc::now(),
+        ) {
+            Ok(removed) => {
+                for env_id in removed {
+                    info!("removed transaction history of deleted env {env_id}");
+                }
+            }
+            Err(e) => tracing::error!("failed to prune transaction history of deleted envs: {e}"),
+        }
+
         // wait for the next update
         tokio::time::sleep(Duration::from_secs(5)).await;
     }
@@ -102,7 +180,7 @@ pub async fn tracking_task(state: Arc<GlobalState>) {
 struct PendingTransactions {
     to_execute: Vec<Arc<String>>,
     to_broadcast: Vec<Arc<String>>,
-    to_remove: Vec<Arc<String>>,
+    to_remove: Vec<(Arc<String>, TransactionOutcome)>,
     to_confirm: Vec<(Arc<String>, Option<u32>)>,
 }
 
@@ -122,6 +200,7 @@ fn get_pending_transactions(state: &GlobalState) -> Vec<((EnvId, CannonId), Pend
             let mut to_broadcast = vec![];
             let mut to_remove = vec![];
             let mut to_confirm = vec![];
+            let mut dependents = vec![];
 
             for tx in cannon.transactions.iter() {
                 let tx_id = tx.key().to_owned();
@@ -133,16 +212,38 @@ fn get_pending_transactions(state: &GlobalState) -> Vec<((EnvId, CannonId), Pend
                     .with_env_id(env_id)
                     .with_transaction(Arc::clone(&tx_id));
 
+                let executing = matches!(tx.status, TransactionSendState::Executing(start_time)
+                    if now - start_time <= TimeDelta::seconds(cannon.sink.authorize_timeout as i64));
+
                 match tx.status {
+                    // authorizations that were not executed before their deadline are
+                    // aborted. executions in progress are left to finish or time out
+                    TransactionSendState::Authorized | TransactionSendState::Executing(_)
+                        if tx.schedule.is_expired(now) && !executing =>
+                    {
+                        info!(
+                            "cannon {env_id}.{cannon_id} removed auth {tx_id} (deadline exceeded)"
+                        );
+                        ev.replace_content(TransactionEvent::ExecuteAborted(
+                            TransactionAbortReason::DeadlineExceeded {
+                                deadline: tx.schedule.deadline.unwrap_or(now),
+                            },
+                        ))
+                        .emit(state);
+                        to_remove.push((tx_id, TransactionOutcome::Expired));
+                    }
                     // any authorized transaction that is not started should be queued
                     TransactionSendState::Authorized => {
                         if cannon.sink.authorize_attempts.is_some_and(|a| attempts > a) {
                             info!(
                                 "cannon {env_id}.{cannon_id} removed auth {tx_id} (too many attempts)"
                             );
-                            to_remove.push(tx_id);
+                            to_remove.push((tx_id, TransactionOutcome::ExecuteExceeded));
                             ev.replace_content(TransactionEvent::ExecuteExceeded { attempts })
                                 .emit(state);
+                        } else if !tx.schedule.after.is_empty() {
+                            // queued once its dependencies are known not to have failed
+                            dependents.push((tx_id, tx.schedule.after.clone(), tx.index));
                         } else {
                             to_execute.push((tx_id, tx.index));
                         }
@@ -158,7 +259,7 @@ fn get_pending_transactions(state: &GlobalState) -> Vec<((EnvId, CannonId), Pend
                             );
                             ev.replace_content(TransactionEvent::ExecuteExceeded { attempts })
                                 .emit(state);
-                            to_remove.push(tx_id);
+                            to_remove.push((tx_id, TransactionOutcome::ExecuteExceeded));
                         } else {
                             to_execute.push((tx_id, tx.index));
                         }
@@ -171,7 +272,7 @@ fn get_pending_transactions(state: &GlobalState) -> Vec<((EnvId, CannonId), Pend
                             );
                             ev.replace_content(TransactionEvent::BroadcastExceeded { attempts })
                                 .emit(state);
-                            to_remove.push(tx_id);
+                            to_remove.push((tx_id, TransactionOutcome::BroadcastExceeded));
                         } else {
                             to_broadcast.push((tx_id, tx.index));
                         }
@@ -213,7 +314,7 @@ fn get_pending_transactions(state: &GlobalState) -> Vec<((EnvId, CannonId), Pend
                                     attempts,
                                 })
                                 .emit(state);
-                                to_remove.push(tx_id);
+                                to_remove.push((tx_id, TransactionOutcome::BroadcastExceeded));
                             } else {
                                 to_broadcast.push((tx_id, tx.index));
                             }
@@ -223,6 +324,31 @@ fn get_pending_transactions(state: &GlobalState) -> Vec<((EnvId, CannonId), Pend
                 }
             }
 
+            // cancel authorizations that depend on a transaction that was not confirmed,
+            // and queue the others. their own dependents are cancelled on the next update
+            let (queued, cancelled) = check_dependents(
+                &state.db,
+                state.env_network_cache.get(&env_id).as_deref(),
+                &cannon.transactions,
+                (env_id, cannon_id),
+                dependents,
+            );
+            to_execute.extend(queued);
+            for (tx_id, dependency, outcome) in cancelled {
+                info!(
+                    "cannon {env_id}.{cannon_id} removed auth {tx_id} (dependency {dependency} {outcome})"
+                );
+                TransactionEvent::ExecuteAborted(TransactionAbortReason::DependencyFailed {
+                    dependency,
+                    outcome: outcome.to_string(),
+                })
+                .with_cannon(cannon_id)
+                .with_env_id(env_id)
+                .with_transaction(Arc::clone(&tx_id))
+                .emit(state);
+                to_remove.push((tx_id, TransactionOutcome::DependencyFailed));
+            }
+
             pending.push((
                 (env_id, cannon_id),
                 PendingTransactions {
@@ -238,8 +364,211 @@ fn get_pending_transactions(state: &GlobalState) -> Vec<((EnvId, CannonId), Pend
     pending
 }
 
+/// An authorization waiting for other transactions, along with its index
+type Dependent = (Arc<String>, Vec<Arc<String>>, u64);
+
+/// Split authorizations that depend on other transactions into those that can
+/// be queued, and those to cancel because a dependency was not confirmed
+/// (along with that dependency and its outcome)
+fn check_dependents(
+    db: &Database,
+    network_cache: Option<&NetworkCache>,
+    transactions: &DashMap<Arc<String>, TransactionTracker>,
+    cannon: (EnvId, CannonId),
+    dependents: Vec<Dependent>,
+) -> (
+    Vec<(Arc<String>, u64)>,
+    Vec<(Arc<String>, Arc<String>, TransactionOutcome)>,
+) {
+    let mut queued = vec![];
+    let mut cancelled = vec![];
+    for (tx_id, after, index) in dependents {
+        match TransactionTracker::dependency_state(db, network_cache, transactions, cannon, &after)
+        {
+            DependencyState::Failed(dependency, outcome) => {
+                cancelled.push((tx_id, dependency, outcome))
+            }
+            DependencyState::Ready | DependencyState::Waiting => queued.push((tx_id, index)),
+        }
+    }
+    (queued, cancelled)
+}
+
+/// The transactions of a block, as returned by the snarkOS REST API
+#[derive(Clone, Deserialize)]
+struct BlockTransactions {
+    transactions: Vec<BlockTransaction>,
+    #[serde(default)]
+    aborted_transaction_ids: Vec<String>,
+}
+
+#[derive(Clone, Deserialize)]
+struct BlockTransaction {
+    status: String,
+    transaction: BlockTransactionId,
+}
+
+#[derive(Clone, Deserialize)]
+struct BlockTransactionId {
+    id: String,
+}
+
+impl BlockTransactions {
+    /// Determine how a transaction in this block was included. Rejected
+    /// executions are listed under the id of their fee transaction, so a
+    /// transaction that is neither listed nor aborted was rejected.
+    fn outcome(&self, tx_id: &str) -> TransactionOutcome {
+        if self.aborted_transaction_ids.iter().any(|id| id == tx_id) {
+            return TransactionOutcome::Aborted;
+        }
+
+        match self
+            .transactions
+            .iter()
+            .find(|tx| tx.transaction.id == tx_id)
+        {
+            Some(tx) if tx.status != "rejected" => TransactionOutcome::Accepted,
+            _ => TransactionOutcome::Rejected,
+        }
+    }
+}
+
 /// Sort a vec of values by their index
 fn sorted_by_index<T>(mut vec: Vec<(T, u64)>) -> Vec<T> {
     vec.sort_by_key(|(_, index)| *index);
     vec.into_iter().map(|(first, _)| first).collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use serde_json::json;
+    use snops_common::db::Database as _;
+
+    use super::*;
+    use crate::cannon::{
+        history::{TransactionHistory, TransactionProgress},
+        tracker::TransactionSchedule,
+    };
+
+    fn tx(id: &str) -> Arc<String> {
+        Arc::new(id.to_owned())
+    }
+
+    fn dependent(tx_id: &str, after: &str, index: u64) -> Dependent {
+        (tx(tx_id), vec![tx(after)], index)
+    }
+
+    fn track(transactions: &DashMap<Arc<String>, TransactionTracker>, tx_id: &str, after: &str) {
+        let tracker = TransactionTracker {
+            index: 0,
+            schedule: TransactionSchedule {
+                after: vec![tx(after)],
+                ..Default::default()
+            },
+            authorization: None,
+            transaction: None,
+            status: TransactionSendState::Authorized,
+        };
+        transactions.insert(tx(tx_id), tracker);
+    }
+
+    fn archive(db: &Database, cannon: (EnvId, CannonId), tx_id: &str, outcome: TransactionOutcome) {
+        let history = TransactionHistory {
+            index: 0,
+            outcome,
+            progress: TransactionProgress::default(),
+            broadcast_attempts: 0,
+            block_hash: None,
+            block_height: None,
+            finished_at: Utc::now(),
+        };
+        db.tx_history
+            .save(&(cannon.0, cannon.1, tx(tx_id)), &history)
+            .unwrap();
+    }
+
+    #[test]
+    fn failed_dependencies_cancel_their_dependents() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Database::open(dir.path()).unwrap();
+        let cannon = (
+            EnvId::from_str("env").unwrap(),
+            CannonId::from_str("cannon").unwrap(),
+        );
+        let transactions = DashMap::new();
+
+        // b depends on a, which exceeded its broadcasts, and c depends on b
+        archive(&db, cannon, "a", TransactionOutcome::BroadcastExceeded);
+        archive(&db, cannon, "ok", TransactionOutcome::Accepted);
+        track(&transactions, "b", "a");
+        track(&transactions, "c", "b");
+
+        let (queued, cancelled) = check_dependents(
+            &db,
+            None,
+            &transactions,
+            cannon,
+            vec![
+                dependent("b", "a", 1),
+                dependent("c", "b", 2),
+                dependent("d", "ok", 3),
+            ],
+        );
+        // c is still waiting for b, and is queued until b is archived
+        assert_eq!(queued, [(tx("c"), 2), (tx("d"), 3)]);
+        assert_eq!(
+            cancelled,
+            [(tx("b"), tx("a"), TransactionOutcome::BroadcastExceeded)]
+        );
+
+        // once b is archived as cancelled, its own dependents are cancelled
+        transactions.remove(&tx("b"));
+        archive(&db, cannon, "b", TransactionOutcome::DependencyFailed);
+        let (queued, cancelled) = check_dependents(
+            &db,
+            None,
+            &transactions,
+            cannon,
+            vec![dependent("c", "b", 2)],
+        );
+        assert!(queued.is_empty());
+        assert_eq!(
+            cancelled,
+            [(tx("c"), tx("b"), TransactionOutcome::DependencyFailed)]
+        );
+    }
+
+    #[test]
+    fn block_outcomes_follow_the_confirmed_status() {
+        let block: BlockTransactions = serde_json::from_value(json!({
+            "transactions": [
+                { "status": "accepted", "type": "execute", "transaction": { "id": "at1" } },
+                { "status": "rejected", "type": "execute", "transaction": { "id": "at2" } },
+                // a rejected execution listed under the id of its fee transaction
+                { "status": "rejected", "type": "execute", "transaction": { "id": "at3fee" } },
+            ],
+            "aborted_transaction_ids": ["at4"],
+        }))
+        .unwrap();
+
+        assert_eq!(block.outcome("at1"), TransactionOutcome::Accepted);
+        assert_eq!(block.outcome("at2"), TransactionOutcome::Rejected);
+        assert_eq!(block.outcome("at3"), TransactionOutcome::Rejected);
+        assert_eq!(block.outcome("at4"), TransactionOutcome::Aborted);
+    }
+
+    #[test]
+    fn blocks_without_aborted_transactions() {
+        let block: BlockTransactions = serde_json::from_value(json!({
+            "transactions": [
+                { "status": "accepted", "type": "deploy", "transaction": { "id": "at1" } },
+            ],
+        }))
+        .unwrap();
+
+        assert_eq!(block.outcome("at1"), TransactionOutcome::Accepted);
+        assert_eq!(block.outcome("at2"), TransactionOutcome::Rejected);
+    }
+}
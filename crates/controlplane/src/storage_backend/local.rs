@@ -0,0 +1,93 @@
+use std::path::{Path, PathBuf};
+
+use http::Method;
+use url::Url;
+
+use super::{StorageBackend, StorageBackendError};
+
+/// A backend that keeps objects in the control plane's data directory. Objects
+/// are served to agents by the control plane's content routes.
+#[derive(Debug, Clone)]
+pub struct LocalBackend {
+    root: PathBuf,
+}
+
+impl LocalBackend {
+    pub fn new(root: PathBuf) -> Self {
+        Self { root }
+    }
+
+    pub fn path(&self, key: &str) -> PathBuf {
+        self.root.join(key)
+    }
+}
+
+impl StorageBackend for LocalBackend {
+    async fn put_file(&self, key: &str, src: &Path) -> Result<(), StorageBackendError> {
+        let dst = self.path(key);
+
+        // generated storage is already written in place
+        if dst == src {
+            return Ok(());
+        }
+
+        if let Some(parent) = dst.parent() {
+            tokio::fs::create_dir_all(parent)
+                .await
+                .map_err(|e| StorageBackendError::WriteFile(key.to_owned(), e))?;
+        }
+        tokio::fs::copy(src, &dst)
+            .await
+            .map_err(|e| StorageBackendError::WriteFile(key.to_owned(), e))?;
+        Ok(())
+    }
+
+    async fn exists(&self, key: &str) -> Result<bool, StorageBackendError> {
+        tokio::fs::try_exists(self.path(key))
+            .await
+            .map_err(|e| StorageBackendError::ReadFile(key.to_owned(), e))
+    }
+
+    async fn remove(&self, key: &str) -> Result<(), StorageBackendError> {
+        match tokio::fs::remove_file(self.path(key)).await {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(StorageBackendError::WriteFile(key.to_owned(), e)),
+        }
+    }
+
+    fn presign(&self, _method: &Method, _key: &str) -> Result<Option<Url>, StorageBackendError> {
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn put_exists_remove() {
+        let root = tempfile::tempdir().unwrap();
+        let backend = LocalBackend::new(root.path().to_owned());
+
+        let src_dir = tempfile::tempdir().unwrap();
+        let src = src_dir.path().join("binary");
+        std::fs::write(&src, b"binary").unwrap();
+
+        let key = "storage/testnet/example/binaries/default";
+        assert!(!backend.exists(key).await.unwrap());
+
+        backend.put_file(key, &src).await.unwrap();
+        assert!(backend.exists(key).await.unwrap());
+        assert_eq!(std::fs::read(backend.path(key)).unwrap(), b"binary");
+
+        // files that are already in place are left alone
+        backend.put_file(key, &backend.path(key)).await.unwrap();
+        assert_eq!(std::fs::read(backend.path(key)).unwrap(), b"binary");
+
+        backend.remove(key).await.unwrap();
+        assert!(!backend.exists(key).await.unwrap());
+        backend.remove(key).await.unwrap();
+        assert_eq!(backend.presign(&Method::GET, key).unwrap(), None);
+    }
+}
@@ -0,0 +1,195 @@
+use std::{
+    future::Future,
+    path::{Path, PathBuf},
+};
+
+use http::Method;
+use snops_common::state::{InternedId, NetworkId, StorageId};
+use thiserror::Error;
+use url::Url;
+
+use crate::cli::{Cli, StorageBackendKind};
+
+mod local;
+pub use local::*;
+mod s3;
+pub use s3::*;
+
+/// A place where generated storage files (genesis blocks, ledgers,
+/// checkpoints) and binaries are kept for agents to download.
+///
+/// Objects are addressed by a `/` separated key, mirroring the layout of the
+/// control plane's data directory (see [`storage_key`]).
+pub trait StorageBackend {
+    /// Upload a local file to the backend under the given key, replacing any
+    /// existing object
+    fn put_file(
+        &self,
+        key: &str,
+        src: &Path,
+    ) -> impl Future<Output = Result<(), StorageBackendError>> + Send;
+
+    /// Check if an object with the given key exists in the backend
+    fn exists(&self, key: &str) -> impl Future<Output = Result<bool, StorageBackendError>> + Send;
+
+    /// Remove the object with the given key from the backend
+    fn remove(&self, key: &str) -> impl Future<Output = Result<(), StorageBackendError>> + Send;
+
+    /// Build a URL that an agent can use to perform `method` on the object
+    /// directly. Returns `None` when the object is served by the control plane.
+    fn presign(&self, method: &Method, key: &str) -> Result<Option<Url>, StorageBackendError>;
+}
+
+#[derive(Debug, Error)]
+pub enum StorageBackendError {
+    #[error("missing s3 option `{0}`")]
+    MissingOption(&'static str),
+    #[error("invalid s3 endpoint `{0}`")]
+    InvalidEndpoint(Url),
+    #[error("reading `{0}`: {1}")]
+    ReadFile(String, #[source] std::io::Error),
+    #[error("writing `{0}`: {1}")]
+    WriteFile(String, #[source] std::io::Error),
+    #[error("{method} `{key}`: {error}")]
+    Request {
+        method: Method,
+        key: String,
+        #[source]
+        error: reqwest::Error,
+    },
+    #[error("{method} `{key}`: status {status}")]
+    Status {
+        method: Method,
+        key: String,
+        status: http::StatusCode,
+    },
+}
+
+/// The storage backend selected by the control plane's CLI
+#[derive(Debug, Clone)]
+pub enum Backend {
+    Local(LocalBackend),
+    S3(S3Backend),
+}
+
+impl Backend {
+    pub fn from_cli(cli: &Cli) -> Result<Self, StorageBackendError> {
+        Ok(match cli.storage_backend {
+            StorageBackendKind::Local => Self::Local(LocalBackend::new(cli.path.clone())),
+            StorageBackendKind::S3 => Self::S3(S3Backend::from_args(&cli.s3)?),
+        })
+    }
+
+    /// Whether objects in this backend are served from somewhere other than
+    /// the control plane
+    pub fn is_remote(&self) -> bool {
+        !matches!(self, Self::Local(_))
+    }
+}
+
+impl StorageBackend for Backend {
+    async fn put_file(&self, key: &str, src: &Path) -> Result<(), StorageBackendError> {
+        match self {
+            Self::Local(b) => b.put_file(key, src).await,
+            Self::S3(b) => b.put_file(key, src).await,
+        }
+    }
+
+    async fn exists(&self, key: &str) -> Result<bool, StorageBackendError> {
+        match self {
+            Self::Local(b) => b.exists(key).await,
+            Self::S3(b) => b.exists(key).await,
+        }
+    }
+
+    async fn remove(&self, key: &str) -> Result<(), StorageBackendError> {
+        match self {
+            Self::Local(b) => b.remove(key).await,
+            Self::S3(b) => b.remove(key).await,
+        }
+    }
+
+    fn presign(&self, method: &Method, key: &str) -> Result<Option<Url>, StorageBackendError> {
+        match self {
+            Self::Local(b) => b.presign(method, key),
+            Self::S3(b) => b.presign(method, key),
+        }
+    }
+}
+
+/// The key of a file within a storage, e.g.
+/// `storage/testnet/my-storage/genesis.block`
+pub fn storage_key(network: NetworkId, storage_id: StorageId, file: &str) -> String {
+    format!("storage/{network}/{storage_id}/{file}")
+}
+
+/// List the files in a directory and its subdirectories, with their `/`
+/// separated paths relative to the directory. A missing directory has no
+/// files.
+pub async fn list_files(dir: &Path) -> std::io::Result<Vec<(String, PathBuf)>> {
+    let mut files = vec![];
+    let mut dirs = vec![(String::new(), dir.to_owned())];
+
+    while let Some((prefix, dir)) = dirs.pop() {
+        let mut entries = match tokio::fs::read_dir(&dir).await {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(e),
+        };
+
+        while let Some(entry) = entries.next_entry().await? {
+            let name = format!("{prefix}{}", entry.file_name().to_string_lossy());
+            let file_type = entry.file_type().await?;
+            if file_type.is_dir() {
+                dirs.push((format!("{name}/"), entry.path()));
+            } else if file_type.is_file() {
+                files.push((name, entry.path()));
+            }
+        }
+    }
+
+    files.sort();
+    Ok(files)
+}
+
+/// The key of a binary within a storage, e.g.
+/// `storage/testnet/my-storage/binaries/default`
+pub fn binary_key(network: NetworkId, storage_id: StorageId, binary_id: InternedId) -> String {
+    storage_key(network, storage_id, &format!("binaries/{binary_id}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn list_files_recursively() {
+        let dir = tempfile::tempdir().unwrap();
+        let base = dir.path().join("storage");
+        std::fs::create_dir_all(base.join("ledger/.aleo")).unwrap();
+        std::fs::write(base.join("genesis.block"), b"").unwrap();
+        std::fs::write(base.join("10.checkpoint"), b"").unwrap();
+        std::fs::write(base.join("ledger/.aleo/blocks"), b"").unwrap();
+
+        let files = list_files(&base)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|(name, path)| {
+                assert_eq!(path, base.join(&name));
+                name
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            files,
+            ["10.checkpoint", "genesis.block", "ledger/.aleo/blocks"]
+        );
+
+        assert!(
+            list_files(&dir.path().join("missing"))
+                .await
+                .unwrap()
+                .is_empty()
+        );
+    }
+}
@@ -120,6 +120,18 @@ class SnopsApi {
                 return this.post(`agents/${id}/kill`);
         }
 
+        async cordonAgent(id: string): Promise<any> {
+                return this.post(`agents/${id}/cordon`);
+        }
+
+        async uncordonAgent(id: string): Promise<any> {
+                return this.post(`agents/${id}/uncordon`);
+        }
+
+        async drainAgent(id: string): Promise<any> {
+                return this.post(`agents/${id}/drain`);
+        }
+
         async agentStatus(id: string): Promise<any> {
                 return this.get(`agents/${id}/status`);
         }
@@ -227,6 +239,18 @@ export class Agents {
                 return await this.api.killAgent(agent_id);
         }
 
+        async cordon(agent_id: string) {
+                return await this.api.cordonAgent(agent_id);
+        }
+
+        async uncordon(agent_id: string) {
+                return await this.api.uncordonAgent(agent_id);
+        }
+
+        async drain(agent_id: string) {
+                return await this.api.drainAgent(agent_id);
+        }
+
         async status(agent_id: string) {
                 return await this.api.agentStatus(agent_id);
         }